@@ -0,0 +1,234 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Activeledger Transaction Helper derive
+//!
+//! Provides `#[derive(PacketData)]` for the [active_tx](https://crates.io/crates/active_tx)
+//! crate. Use it through active_tx with the `derive` feature enabled.
+//!
+//! The struct is converted into a `PacketValue::Object`, one key per field:
+//!
+//! * `#[packet(rename = "name")]` uses a different key for the field
+//! * `#[packet(skip)]` leaves the field out
+//! * `Option` fields are left out when they are `None`
+//! * `Vec` fields, other than `Vec<u8>` which is binary data, become arrays
+//!
+//! Every other field type must implement `Into<PacketValue>`, which includes other structs
+//! deriving `PacketData`.
+
+extern crate proc_macro;
+
+// External imports
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Data, DeriveInput, Fields, GenericArgument, LitStr, PathArguments, Type,
+};
+
+/// # Packet data derive
+///
+/// Implement `From<T> for PacketValue` and `TryFrom<T> for PacketData` for a struct with named
+/// fields.
+#[proc_macro_derive(PacketData, attributes(packet))]
+pub fn derive_packet_data(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// The options set on a field using the packet attribute
+#[derive(Default)]
+struct FieldOptions {
+    rename: Option<String>,
+    skip: bool,
+}
+
+/// Generate the conversions for the struct
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "PacketData can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                input,
+                "PacketData can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut inserts = Vec::new();
+
+    for field in fields {
+        let options = field_options(field)?;
+
+        if options.skip {
+            continue;
+        }
+
+        let ident = match &field.ident {
+            Some(ident) => ident,
+            None => continue,
+        };
+
+        let key = match options.rename {
+            Some(rename) => rename,
+            None => ident.to_string(),
+        };
+
+        let insert = match option_inner(&field.ty) {
+            Some(inner) => {
+                let value = convert(inner, quote!(value));
+
+                quote! {
+                    if let ::std::option::Option::Some(value) = data.#ident {
+                        object.insert(::std::string::String::from(#key), #value);
+                    }
+                }
+            }
+            None => {
+                let value = convert(&field.ty, quote!(data.#ident));
+
+                quote! {
+                    object.insert(::std::string::String::from(#key), #value);
+                }
+            }
+        };
+
+        inserts.push(insert);
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::std::convert::From<#name #ty_generics> for ::active_tx::PacketValue
+            #where_clause
+        {
+            fn from(data: #name #ty_generics) -> Self {
+                let mut object = ::active_tx::PacketMap::new();
+
+                #(#inserts)*
+
+                ::active_tx::PacketValue::Object(object)
+            }
+        }
+
+        impl #impl_generics ::std::convert::TryFrom<#name #ty_generics> for ::active_tx::PacketData
+            #where_clause
+        {
+            type Error = ::active_tx::TxBuilderError;
+
+            fn try_from(data: #name #ty_generics) -> ::active_tx::TxBuilderResult<Self> {
+                ::active_tx::PacketBuilder::new(::active_tx::PacketValue::from(data)).build()
+            }
+        }
+    })
+}
+
+/// Read the packet attributes on a field
+fn field_options(field: &syn::Field) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+
+    for attr in &field.attrs {
+        if !attr.path().is_ident("packet") {
+            continue;
+        }
+
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let rename: LitStr = meta.value()?.parse()?;
+                options.rename = Some(rename.value());
+
+                Ok(())
+            } else if meta.path.is_ident("skip") {
+                options.skip = true;
+
+                Ok(())
+            } else {
+                Err(meta.error("unknown packet attribute, expected `rename` or `skip`"))
+            }
+        })?;
+    }
+
+    Ok(options)
+}
+
+/// Convert a value of the type to a PacketValue
+fn convert(ty: &Type, value: TokenStream2) -> TokenStream2 {
+    match generic_inner(ty, "Vec") {
+        Some(inner) if !is_ident(inner, "u8") => quote! {
+            #value.into_iter().collect::<::active_tx::PacketValue>()
+        },
+        _ => quote! {
+            ::active_tx::PacketValue::from(#value)
+        },
+    }
+}
+
+/// Get T if the type is Option<T>
+fn option_inner(ty: &Type) -> Option<&Type> {
+    generic_inner(ty, "Option")
+}
+
+/// Get T if the type is the named wrapper of T, such as Vec<T>
+fn generic_inner<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+
+    let segment = path.segments.last()?;
+
+    if segment.ident != wrapper {
+        return None;
+    }
+
+    let args = match &segment.arguments {
+        PathArguments::AngleBracketed(args) => &args.args,
+        _ => return None,
+    };
+
+    match args.first()? {
+        GenericArgument::Type(inner) if args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+/// Check whether the type is a single identifier, such as u8
+fn is_ident(ty: &Type, ident: &str) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path.path.is_ident(ident),
+        _ => false,
+    }
+}
@@ -0,0 +1,196 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+// STD
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::String};
+
+// External imports
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+// Internal imports
+use super::body::{add_sig, validate_identifier, Sigs, TransactionBody, TransactionEnvelope};
+use super::Signer;
+use crate::error::TxBuilderResult;
+
+/// # Transaction assembler
+///
+/// Assembles and signs a transaction using only `alloc`, for devices without the standard
+/// library. Signing is delegated to a [`Signer`], such as a key held in a secure element.
+///
+/// The packet data is given as JSON values, the packet and envelope are written exactly as the
+/// [`TransactionBuilder`] writes them.
+///
+/// ```
+/// # use active_tx::{KeyType, Signer, TxAssembler, TxBuilderResult};
+/// # use serde_json::json;
+/// struct SecureElement;
+///
+/// impl Signer for SecureElement {
+///     fn name(&self) -> &str {
+///         "device-1"
+///     }
+///
+///     fn key_type(&self) -> KeyType {
+///         KeyType::EC
+///     }
+///
+///     fn sign(&self, data: &str) -> TxBuilderResult<String> {
+///         // Pass the packet to the secure element
+///         # Ok(String::from("signature"))
+///     }
+///
+///     fn public_pem(&self) -> TxBuilderResult<String> {
+///         // Read the public key from the secure element
+///         # Ok(String::new())
+///     }
+/// }
+///
+/// let tx = TxAssembler::new("namespace", "contract", json!({"device-1": {"reading": 20}}))
+///     .entry("record")
+///     .build(&[("device-1", &SecureElement)])
+///     .unwrap();
+/// ```
+///
+/// [`Signer`]: trait.Signer.html
+/// [`TransactionBuilder`]: struct.TransactionBuilder.html
+#[derive(Debug, Clone)]
+pub struct TxAssembler {
+    namespace: Value,
+    contract: Value,
+    input: Value,
+    entry: Option<Value>,
+    output: Option<Value>,
+    readonly: Option<Value>,
+    selfsign: Option<Value>,
+}
+
+impl TxAssembler {
+    /// # New
+    ///
+    /// Create an assembler for a transaction calling the contract with the given input.
+    pub fn new(namespace: &str, contract: &str, input: Value) -> TxAssembler {
+        TxAssembler {
+            namespace: Value::from(namespace),
+            contract: Value::from(contract),
+            input,
+            entry: None,
+            output: None,
+            readonly: None,
+            selfsign: None,
+        }
+    }
+
+    /// # Entry
+    ///
+    /// Set the contract entry point.
+    pub fn entry(&mut self, entry: &str) -> &mut Self {
+        self.entry = Some(Value::from(entry));
+
+        self
+    }
+
+    /// # Output
+    ///
+    /// Set the output data.
+    pub fn output(&mut self, output: Value) -> &mut Self {
+        self.output = Some(output);
+
+        self
+    }
+
+    /// # Readonly
+    ///
+    /// Set the readonly data.
+    pub fn readonly(&mut self, readonly: Value) -> &mut Self {
+        self.readonly = Some(readonly);
+
+        self
+    }
+
+    /// # Selfsign
+    ///
+    /// Set whether the transaction is selfsigned, the signatures are then by key name rather
+    /// than stream ID.
+    pub fn selfsign(&mut self, selfsign: bool) -> &mut Self {
+        self.selfsign = Some(Value::from(selfsign));
+
+        self
+    }
+
+    /// # Packet
+    ///
+    /// Get the packet as it is signed.
+    pub fn packet(&self) -> TxBuilderResult<String> {
+        Ok(String::from(self.body()?.get()))
+    }
+
+    /// # Build
+    ///
+    /// Sign the packet with each signer for its stream ID and return the transaction.
+    pub fn build(&self, signers: &[(&str, &dyn Signer)]) -> TxBuilderResult<String> {
+        let packet = self.body()?;
+
+        let mut sigs = Sigs::new();
+
+        for (streamid, signer) in signers {
+            add_sig(&mut sigs, streamid, None, signer.sign(packet.get())?);
+        }
+
+        let envelope = TransactionEnvelope {
+            broadcast: None,
+            selfsign: self.selfsign.as_ref(),
+            sigs: &sigs,
+            territoriality: None,
+            tx: &*packet,
+            extra: &BTreeMap::new(),
+        };
+
+        envelope.build()
+    }
+}
+
+// Private functions
+impl TxAssembler {
+    /// Serialize the packet, the contract and namespace must be valid identifiers
+    fn body(&self) -> TxBuilderResult<Box<RawValue>> {
+        validate_identifier("contract", self.contract.as_str().unwrap_or_default())?;
+        validate_identifier("namespace", self.namespace.as_str().unwrap_or_default())?;
+
+        let mut body = TransactionBody::new(&self.contract, &self.namespace, &self.input);
+
+        for &(key, data) in &[
+            ("entry", &self.entry),
+            ("output", &self.output),
+            ("readonly", &self.readonly),
+        ] {
+            if let Some(data) = data {
+                body.add(key, data);
+            }
+        }
+
+        body.build()
+    }
+}
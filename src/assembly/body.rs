@@ -0,0 +1,251 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+// STD
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::String, string::ToString};
+
+// External imports
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Signatures by stream ID
+pub type Sigs = BTreeMap<String, Sig>;
+
+/// The signature entry for a stream
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Sig {
+    /// Signed by a single key
+    Single(String),
+    /// Signed by several keys, by key reference
+    Multi(BTreeMap<String, String>),
+}
+
+/// Add the signature for the stream, nesting it under the key reference if there is one
+pub fn add_sig(sigs: &mut Sigs, streamid: &str, key_ref: Option<&str>, signature: String) {
+    let key_ref = match key_ref {
+        Some(key_ref) => key_ref,
+        None => {
+            sigs.insert(String::from(streamid), Sig::Single(signature));
+            return;
+        }
+    };
+
+    let entry = sigs
+        .entry(String::from(streamid))
+        .or_insert_with(|| Sig::Multi(BTreeMap::new()));
+
+    match entry {
+        Sig::Multi(map) => {
+            map.insert(String::from(key_ref), signature);
+        }
+        Sig::Single(_) => {
+            let mut map = BTreeMap::new();
+            map.insert(String::from(key_ref), signature);
+
+            *entry = Sig::Multi(map);
+        }
+    }
+}
+
+/// Borrows the transactions data so the packet can be serialized without copying it.
+///
+/// Fields, including any extra fields, are kept in key order so the output matches a serialized
+/// serde_json object.
+pub struct TransactionBody<'a> {
+    contract: &'a Value,
+    entry: Option<&'a Value>,
+    input: &'a Value,
+    namespace: &'a Value,
+    output: Option<&'a Value>,
+    readonly: Option<&'a Value>,
+    extra: Option<&'a BTreeMap<String, Value>>,
+}
+
+/// Borrows the parts of the transaction around the packet.
+///
+/// Fields, including any extra fields, are kept in key order so the output matches a serialized
+/// serde_json object.
+pub struct TransactionEnvelope<'a, T: Serialize + ?Sized> {
+    pub broadcast: Option<&'a Value>,
+    pub selfsign: Option<&'a Value>,
+    pub sigs: &'a Sigs,
+    pub territoriality: Option<&'a Value>,
+    pub tx: &'a T,
+    pub extra: &'a BTreeMap<String, Value>,
+}
+
+/// A value in the envelope
+enum EnvelopeField<'a, T: Serialize + ?Sized> {
+    Value(&'a Value),
+    Sigs(&'a Sigs),
+    Tx(&'a T),
+}
+
+impl<'a> TransactionBody<'a> {
+    pub fn new(contract: &'a Value, namespace: &'a Value, input: &'a Value) -> TransactionBody<'a> {
+        // Init all as None to create base
+        TransactionBody {
+            entry: None,
+            contract,
+            namespace,
+            input,
+            output: None,
+            readonly: None,
+            extra: None,
+        }
+    }
+
+    /// Add fields that are not managed by the builder
+    #[cfg(feature = "std")]
+    pub fn extra(&mut self, extra: &'a BTreeMap<String, Value>) -> &mut Self {
+        self.extra = Some(extra);
+
+        self
+    }
+
+    pub fn add(&mut self, key: &str, data: &'a Value) -> &mut Self {
+        match key {
+            "entry" => self.entry = Some(data),
+            "output" => self.output = Some(data),
+            "readonly" => self.readonly = Some(data),
+            _ => unreachable!(),
+        };
+
+        self
+    }
+
+    /// Serialize the packet to the canonical string that gets signed
+    pub fn build(&self) -> TxBuilderResult<Box<RawValue>> {
+        let json = match serde_json::to_string(self) {
+            Ok(json) => json,
+            Err(_) => return Err(TxBuilderError::TxBodyError(4001)),
+        };
+
+        match RawValue::from_string(json) {
+            Ok(raw) => Ok(raw),
+            Err(_) => Err(TxBuilderError::TxBodyError(4001)),
+        }
+    }
+}
+
+impl<'a, T: Serialize + ?Sized> TransactionEnvelope<'a, T> {
+    /// Serialize the whole transaction
+    pub fn build(&self) -> TxBuilderResult<String> {
+        match serde_json::to_string(self) {
+            Ok(json) => Ok(json),
+            Err(_) => Err(TxBuilderError::TxBuildError(5011)),
+        }
+    }
+}
+
+impl Serialize for TransactionBody<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut fields = BTreeMap::new();
+
+        if let Some(extra) = self.extra {
+            for (key, value) in extra {
+                fields.insert(key.as_str(), value);
+            }
+        }
+
+        fields.insert("$contract", self.contract);
+        fields.insert("$i", self.input);
+        fields.insert("$namespace", self.namespace);
+
+        for &(key, value) in &[
+            ("$entry", self.entry),
+            ("$o", self.output),
+            ("$r", self.readonly),
+        ] {
+            if let Some(value) = value {
+                fields.insert(key, value);
+            }
+        }
+
+        serializer.collect_map(fields)
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for TransactionEnvelope<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut fields = BTreeMap::new();
+
+        for (key, value) in self.extra {
+            fields.insert(key.as_str(), EnvelopeField::Value(value));
+        }
+
+        fields.insert("$sigs", EnvelopeField::Sigs(self.sigs));
+        fields.insert("$tx", EnvelopeField::Tx(self.tx));
+
+        if let Some(broadcast) = self.broadcast {
+            fields.insert("$broadcast", EnvelopeField::Value(broadcast));
+        }
+
+        if let Some(selfsign) = self.selfsign {
+            fields.insert("$selfsign", EnvelopeField::Value(selfsign));
+        }
+
+        if let Some(territoriality) = self.territoriality {
+            fields.insert("$territoriality", EnvelopeField::Value(territoriality));
+        }
+
+        serializer.collect_map(fields)
+    }
+}
+
+impl<T: Serialize + ?Sized> Serialize for EnvelopeField<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            EnvelopeField::Value(value) => value.serialize(serializer),
+            EnvelopeField::Sigs(sigs) => sigs.serialize(serializer),
+            EnvelopeField::Tx(tx) => tx.serialize(serializer),
+        }
+    }
+}
+
+/// Check that a namespace or contract is not empty and has no leading or trailing whitespace,
+/// which copied identifiers often pick up. The ledger's own naming rules are left to the ledger.
+pub fn validate_identifier(field: &'static str, value: &str) -> TxBuilderResult<()> {
+    let reason = if value.is_empty() {
+        Some("empty")
+    } else if value.trim() != value {
+        Some("leading or trailing whitespace")
+    } else {
+        None
+    };
+
+    match reason {
+        Some(reason) => Err(TxBuilderError::InvalidIdentifier {
+            field,
+            value: value.to_string(),
+            reason,
+        }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,64 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Constant time comparison
+//!
+//! Comparing signatures, hashes or tokens with `==` returns as soon as a byte differs, so the
+//! time taken leaks how much of a forged value was right.
+
+/// # Constant time eq
+///
+/// Check whether two values, such as signatures, are equal in a time that depends only on
+/// their lengths and not on where they differ. Use this in place of `==` when one side comes
+/// from an untrusted caller, such as a signature header checked by a webhook.
+///
+/// The lengths are not secret, values of different lengths are unequal straight away.
+///
+/// ```
+/// # use active_tx::constant_time_eq;
+/// assert!(constant_time_eq("c2lnbmF0dXJl", "c2lnbmF0dXJl"));
+/// assert!(!constant_time_eq("c2lnbmF0dXJl", "c2lnbmF0dXJm"));
+/// assert!(!constant_time_eq(&b"short"[..], &b"longer"[..]));
+/// ```
+pub fn constant_time_eq<A, B>(a: &A, b: &B) -> bool
+where
+    A: AsRef<[u8]> + ?Sized,
+    B: AsRef<[u8]> + ?Sized,
+{
+    let (a, b) = (a.as_ref(), b.as_ref());
+
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut difference = 0u8;
+
+    // Hiding each byte and the running difference from the optimizer stops it from turning
+    // the loop back into an early return
+    for (x, y) in a.iter().zip(b) {
+        let (x, y) = (core::hint::black_box(*x), core::hint::black_box(*y));
+        difference = core::hint::black_box(difference | (x ^ y));
+    }
+
+    difference == 0
+}
@@ -0,0 +1,59 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Assembly
+//!
+//! The parts of the crate that don't need the standard library: the structure of the packet
+//! and envelope, key types and the [`Signer`] trait.
+//!
+//! With `default-features = false` only this module is built, using `alloc`, so devices
+//! without `std` can assemble transactions locally with [`TxAssembler`] and delegate signing,
+//! for example to a secure element, through a [`Signer`].
+//!
+//! ```toml
+//! [dependencies]
+//! active_tx = { version = "0.1", default-features = false }
+//! ```
+//!
+//! [`Signer`]: trait.Signer.html
+//! [`TxAssembler`]: struct.TxAssembler.html
+
+// External imports
+use serde::{Deserialize, Serialize};
+
+mod assembler;
+pub(crate) mod body;
+mod compare;
+mod signer;
+
+pub use assembler::TxAssembler;
+pub use compare::constant_time_eq;
+pub use signer::{FnSigner, Signer};
+
+/// Key Type for generating a key and onboarding it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyType {
+    RSA,
+    EC,
+}
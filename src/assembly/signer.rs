@@ -0,0 +1,170 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # External signers
+//!
+//! Keys that are not held by this crate, such as keys in a browser's WebCrypto store or an HSM,
+//! sign through the [`Signer`] trait. Wrap the signer in [`Key::External`] to use it anywhere
+//! a [`Key`] is accepted.
+//!
+//! [`Key::External`]: enum.Key.html#variant.External
+//! [`Key`]: enum.Key.html
+
+// STD
+#[cfg(not(feature = "std"))]
+use alloc::{
+    format,
+    string::{String, ToString},
+};
+
+// Internal imports
+use super::KeyType;
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Base64 length of an RSA 4096 signature, the longest Activeledger accepts
+const MAX_SIGNATURE_LENGTH: usize = 684;
+
+/// # Signer
+///
+/// Signs transaction packets with a key held outside of this crate.
+///
+/// External signers choose their own algorithm and encoding, so the hash, encoding and padding
+/// of the [`SignOptions`] are not applied to them. A context set in the options is: the data
+/// given to [`sign`](#tymethod.sign) is the packet wrapped in the context tag, as returned by
+/// `SignOptions::signed_data`.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// # use std::sync::Arc;
+/// # use active_tx::{signees, Key, KeyType, Signer, TxBuilderResult};
+/// struct RemoteKey;
+///
+/// impl Signer for RemoteKey {
+///     fn name(&self) -> &str {
+///         "remote"
+///     }
+///
+///     fn key_type(&self) -> KeyType {
+///         KeyType::EC
+///     }
+///
+///     fn sign(&self, data: &str) -> TxBuilderResult<String> {
+///         // Send the data to the signing service
+///         # Ok(data.to_string())
+///     }
+///
+///     fn public_pem(&self) -> TxBuilderResult<String> {
+///         // Fetch the public key from the signing service
+///         # Ok(String::new())
+///     }
+/// }
+///
+/// let signees = signees![{"streamid" => Key::External(Arc::new(RemoteKey))}];
+/// # }
+/// ```
+///
+/// [`SignOptions`]: struct.SignOptions.html
+pub trait Signer: Send + Sync {
+    /// The name of the key, used as the stream ID when selfsigning
+    fn name(&self) -> &str;
+
+    /// The type of the key
+    fn key_type(&self) -> KeyType;
+
+    /// Sign the data, returning the encoded signature
+    fn sign(&self, data: &str) -> TxBuilderResult<String>;
+
+    /// The public key as a PEM, used when onboarding the key
+    fn public_pem(&self) -> TxBuilderResult<String>;
+
+    /// The longest signature the signer returns, used to estimate transaction sizes.
+    /// Defaults to the length of a base64 encoded RSA 4096 signature.
+    fn max_signature_len(&self) -> usize {
+        MAX_SIGNATURE_LENGTH
+    }
+}
+
+/// # Function signer
+///
+/// A [`Signer`] that signs with a function, for fake signers in tests and services where only
+/// the signature is needed. It has no public key, implement [`Signer`] when the key is
+/// onboarded.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// # use active_tx::{signees, FnSigner, KeyType};
+/// let fake = FnSigner::new("fake", KeyType::EC, |data: &str| {
+///     Ok(format!("signed:{}", data.len()))
+/// });
+///
+/// let signees = signees![{"streamid" => fake}];
+/// # }
+/// ```
+///
+/// [`Signer`]: trait.Signer.html
+pub struct FnSigner<F> {
+    name: String,
+    key_type: KeyType,
+    sign: F,
+}
+
+impl<F> FnSigner<F>
+where
+    F: Fn(&str) -> TxBuilderResult<String> + Send + Sync,
+{
+    /// # New
+    ///
+    /// Create a signer with the key name and type it signs as, calling the function with the
+    /// data to sign.
+    pub fn new(name: &str, key_type: KeyType, sign: F) -> FnSigner<F> {
+        FnSigner {
+            name: name.to_string(),
+            key_type,
+            sign,
+        }
+    }
+}
+
+impl<F> Signer for FnSigner<F>
+where
+    F: Fn(&str) -> TxBuilderResult<String> + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    fn sign(&self, data: &str) -> TxBuilderResult<String> {
+        (self.sign)(data)
+    }
+
+    fn public_pem(&self) -> TxBuilderResult<String> {
+        Err(TxBuilderError::SignerError(format!(
+            "function signer '{}' has no public key",
+            self.name
+        )))
+    }
+}
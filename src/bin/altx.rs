@@ -0,0 +1,406 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # altx
+//!
+//! Command line tool for one-off Activeledger transactions.
+//!
+//! Packet data is read as JSON from a file, or from stdin when no file is given. Keys are read
+//! from PEM files and given as `streamid=path`.
+//!
+//! ```text
+//! altx build --namespace ns --contract contract --key streamid=key.pem < input.json
+//! altx sign tx.json --key streamid=key.pem
+//! altx onboard --key key.pem
+//! altx verify tx.json --key streamid=public.pem
+//! altx explain tx.json
+//! ```
+
+// STD
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+
+// External imports
+use clap::{Args, Parser, Subcommand};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use zeroize::Zeroizing;
+
+// Internal imports
+use active_tx::{
+    explain, Key, OnboardTxBuilder, PacketBuilder, PacketData, PublicKey, SignOptions, Signees,
+    TransactionBuilder,
+};
+
+type CliResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(
+    name = "altx",
+    version,
+    about = "Build and sign Activeledger transactions"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Build and sign a transaction from packet data
+    Build(BuildArgs),
+    /// Add signatures to a built transaction
+    Sign {
+        /// Transaction file, stdin if not given
+        tx: Option<PathBuf>,
+        #[command(flatten)]
+        keys: KeyArgs,
+    },
+    /// Build a transaction onboarding a key
+    Onboard {
+        /// Private key PEM file
+        #[arg(long)]
+        key: PathBuf,
+        /// Name of the key, defaults to the file name
+        #[arg(long)]
+        name: Option<String>,
+        #[arg(long, default_value = "default")]
+        namespace: String,
+        #[arg(long, default_value = "onboard")]
+        contract: String,
+    },
+    /// Check the signatures of a transaction against public keys
+    Verify {
+        /// Transaction file, stdin if not given
+        tx: Option<PathBuf>,
+        #[command(flatten)]
+        keys: KeyArgs,
+    },
+    /// Summarise a transaction
+    Explain {
+        /// Transaction file, stdin if not given
+        tx: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct BuildArgs {
+    #[arg(long)]
+    namespace: String,
+    #[arg(long)]
+    contract: String,
+    #[arg(long)]
+    entry: Option<String>,
+    #[arg(long)]
+    territoriality: Option<String>,
+    /// Sign using the key names as stream IDs
+    #[arg(long)]
+    selfsign: bool,
+    /// Input ($i) JSON file, stdin if not given
+    #[arg(long)]
+    input: Option<PathBuf>,
+    /// Output ($o) JSON file
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Readonly ($r) JSON file
+    #[arg(long)]
+    readonly: Option<PathBuf>,
+    #[command(flatten)]
+    keys: KeyArgs,
+}
+
+#[derive(Args)]
+struct KeyArgs {
+    /// Key PEM file for a stream
+    #[arg(long = "key", value_name = "STREAMID=PEM", value_parser = parse_key, required = true)]
+    keys: Vec<(String, PathBuf)>,
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Build(args) => build(args),
+        Command::Sign { tx, keys } => sign(tx.as_deref(), keys),
+        Command::Onboard {
+            key,
+            name,
+            namespace,
+            contract,
+        } => onboard(&key, name, &namespace, &contract),
+        Command::Verify { tx, keys } => verify(tx.as_deref(), keys),
+        Command::Explain { tx } => explain_tx(tx.as_deref()),
+    };
+
+    match result {
+        Ok(out) => println!("{}", out),
+        Err(e) => {
+            eprintln!("altx: {}", e.to_string().trim());
+            process::exit(1);
+        }
+    }
+}
+
+fn build(args: BuildArgs) -> CliResult<String> {
+    let mut tx_builder = TransactionBuilder::new(&args.namespace, &args.contract);
+
+    tx_builder.input(packet(&read(args.input.as_deref())?)?)?;
+
+    if let Some(path) = &args.output {
+        tx_builder.output(packet(&read(Some(path))?)?)?;
+    }
+
+    if let Some(path) = &args.readonly {
+        tx_builder.readonly(packet(&read(Some(path))?)?)?;
+    }
+
+    if let Some(entry) = &args.entry {
+        tx_builder.entry(entry);
+    }
+
+    if let Some(territoriality) = &args.territoriality {
+        tx_builder.territoriality(territoriality);
+    }
+
+    let mut signees = Signees::new();
+
+    for (streamid, path) in &args.keys.keys {
+        signees.add_from_pem_file(path, streamid)?;
+    }
+
+    Ok(tx_builder.set_selfsign(args.selfsign).build(signees)?)
+}
+
+fn sign(tx: Option<&Path>, keys: KeyArgs) -> CliResult<String> {
+    let tx = read(tx)?;
+    let mut envelope: BTreeMap<String, Box<RawValue>> = serde_json::from_str(&tx)?;
+
+    // Sign the canonical form of the packet, as the builder does, not the layout of the file
+    let packet = match envelope.get("$tx") {
+        Some(packet) => serde_json::to_string(&serde_json::from_str::<Value>(packet.get())?)?,
+        None => return Err("transaction has no $tx packet".into()),
+    };
+
+    let mut sigs: BTreeMap<String, Value> = match envelope.get("$sigs") {
+        Some(sigs) => serde_json::from_str(sigs.get())?,
+        None => BTreeMap::new(),
+    };
+
+    for (streamid, path) in &keys.keys {
+        let key = Key::from_pem(streamid, &Zeroizing::new(fs::read_to_string(path)?))?;
+
+        sigs.insert(
+            streamid.to_string(),
+            Value::from(key.sign(&packet, &SignOptions::new())?),
+        );
+    }
+
+    envelope.insert(String::from("$tx"), RawValue::from_string(packet)?);
+    envelope.insert(
+        String::from("$sigs"),
+        RawValue::from_string(serde_json::to_string(&sigs)?)?,
+    );
+
+    Ok(serde_json::to_string(&envelope)?)
+}
+
+fn onboard(
+    path: &Path,
+    name: Option<String>,
+    namespace: &str,
+    contract: &str,
+) -> CliResult<String> {
+    let name = match name {
+        Some(name) => name,
+        None => match path.file_stem() {
+            Some(stem) => stem.to_string_lossy().to_string(),
+            None => return Err("key file has no name".into()),
+        },
+    };
+
+    let key = Key::from_pem(&name, &Zeroizing::new(fs::read_to_string(path)?))?;
+
+    Ok(OnboardTxBuilder::new(key)
+        .namespace(namespace)
+        .contract(contract)
+        .build()?)
+}
+
+fn verify(tx: Option<&Path>, keys: KeyArgs) -> CliResult<String> {
+    let tx = read(tx)?;
+    let mut public_keys = HashMap::new();
+
+    for (streamid, path) in keys.keys {
+        public_keys.insert(streamid, PublicKey::from_pem(&fs::read_to_string(path)?)?);
+    }
+
+    TransactionBuilder::verify(&tx, &public_keys, &SignOptions::new())?;
+
+    Ok(String::from("valid"))
+}
+
+fn explain_tx(tx: Option<&Path>) -> CliResult<String> {
+    let tx: Value = serde_json::from_str(&read(tx)?)?;
+
+    Ok(explain(&tx).to_string())
+}
+
+/// Read the file, or stdin if no file is given
+fn read(path: Option<&Path>) -> CliResult<String> {
+    match path {
+        Some(path) => Ok(fs::read_to_string(path)?),
+        None => {
+            let mut data = String::new();
+            io::stdin().read_to_string(&mut data)?;
+
+            Ok(data)
+        }
+    }
+}
+
+/// Build packet data from a JSON string
+fn packet(json: &str) -> CliResult<PacketData> {
+    Ok(PacketBuilder::new_json(serde_json::from_str(json)?).build()?)
+}
+
+/// Parse a `streamid=path` key argument
+fn parse_key(value: &str) -> Result<(String, PathBuf), String> {
+    match value.find('=') {
+        Some(index) if index > 0 => Ok((
+            value[..index].to_string(),
+            PathBuf::from(&value[index + 1..]),
+        )),
+        _ => Err(String::from("expected STREAMID=PEM")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use activeledger::key::EllipticCurve;
+    use clap::CommandFactory;
+
+    /// Write a file to the temp directory, named for this process so test runs don't collide
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("altx-{}-{}", process::id(), name));
+        fs::write(&path, contents).unwrap();
+
+        path
+    }
+
+    fn key_args(keys: &[(&str, &Path)]) -> KeyArgs {
+        KeyArgs {
+            keys: keys
+                .iter()
+                .map(|(streamid, path)| (streamid.to_string(), path.to_path_buf()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn cli_args() {
+        Cli::command().debug_assert();
+
+        assert!(parse_key("=key.pem").is_err());
+        assert_eq!(
+            parse_key("streamid=dir/key=1.pem").unwrap(),
+            (String::from("streamid"), PathBuf::from("dir/key=1.pem"))
+        );
+    }
+
+    #[test]
+    fn cli_build_sign_verify_explain() {
+        let key = EllipticCurve::new("streamid").unwrap().get_pem().unwrap();
+        let other = EllipticCurve::new("other").unwrap().get_pem().unwrap();
+
+        let private = temp_file("build.pem", &key.private);
+        let public = temp_file("build.pub.pem", &key.public);
+        let other_private = temp_file("other.pem", &other.private);
+        let other_public = temp_file("other.pub.pem", &other.public);
+        let input = temp_file("input.json", r#"{"streamid": {"data": "data"}}"#);
+
+        let tx = build(BuildArgs {
+            namespace: String::from("namespace"),
+            contract: String::from("contract"),
+            entry: Some(String::from("entry")),
+            territoriality: None,
+            selfsign: false,
+            input: Some(input.clone()),
+            output: None,
+            readonly: None,
+            keys: key_args(&[("streamid", &private)]),
+        })
+        .unwrap();
+
+        let parsed: Value = serde_json::from_str(&tx).unwrap();
+        assert_eq!(parsed["$tx"]["$entry"], "entry");
+        assert!(parsed["$sigs"]["streamid"].is_string());
+
+        let tx_path = temp_file("tx.json", &tx);
+        assert_eq!(
+            verify(Some(&tx_path), key_args(&[("streamid", &public)])).unwrap(),
+            "valid"
+        );
+
+        // Re-laid out packets are signed, and written back, in canonical form
+        let pretty = serde_json::to_string_pretty(&parsed).unwrap();
+        let pretty_path = temp_file("pretty.json", &pretty);
+        let signed = sign(Some(&pretty_path), key_args(&[("other", &other_private)])).unwrap();
+
+        let signed_value: Value = serde_json::from_str(&signed).unwrap();
+        assert_eq!(
+            signed_value["$sigs"]["streamid"],
+            parsed["$sigs"]["streamid"]
+        );
+        assert!(signed.contains(&serde_json::to_string(&parsed["$tx"]).unwrap()));
+
+        let signed_path = temp_file("signed.json", &signed);
+        let keys = key_args(&[("streamid", &public), ("other", &other_public)]);
+        assert_eq!(verify(Some(&signed_path), keys).unwrap(), "valid");
+
+        let wrong = key_args(&[("other", &public)]);
+        assert!(verify(Some(&signed_path), wrong).is_err());
+
+        let summary = explain_tx(Some(&signed_path)).unwrap();
+        assert!(summary.contains("namespace"));
+        assert!(summary.contains("contract"));
+
+        for path in &[
+            private,
+            public,
+            other_private,
+            other_public,
+            input,
+            tx_path,
+            pretty_path,
+            signed_path,
+        ] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
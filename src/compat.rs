@@ -0,0 +1,413 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Compatibility
+//!
+//! Packet serialization matching other Activeledger SDKs, so signatures made in Rust verify
+//! against nodes and tools built around them, and the other way around.
+//!
+//! By default the packet is written with its fields in key order, see
+//! [`TransactionBuilder::signed_payload`]. The JS SDK signs the result of `JSON.stringify`,
+//! which differs in three ways, all reproduced by [`CompatMode::Js`]:
+//!
+//! * The packet fields are in the order `$namespace`, `$contract`, `$entry`, `$i`, `$o`, `$r`,
+//!   followed by any extra packet fields
+//! * Object keys that are array indexes, such as `"2"` and `"10"`, come first in numeric order
+//! * Numbers without a fraction are written without one, `2.0` is written as `2`, and very
+//!   large and small numbers use an exponent, `1e+21` and `1e-7`
+//!
+//! Other object keys keep their order in the data. This is key order by default, enable the
+//! `preserve-order` feature to keep the order they were added in as JavaScript does.
+//!
+//! [`VECTORS`] holds payloads and signatures produced by `JSON.stringify` and Node.js, for
+//! checking this and other SDKs against.
+//!
+//! ```
+//! # #[cfg(feature = "openssl-keys")] {
+//! # use active_tx::compat::CompatMode;
+//! # use active_tx::{packet_data, signees, Key, PacketBuilder, TransactionBuilder};
+//! # use activeledger::key::EllipticCurve;
+//! let key = Key::Ec(EllipticCurve::new("id").unwrap());
+//! let input = PacketBuilder::new(packet_data!({"id": {"amount": 2.0}}))
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+//! tx_builder.compat(CompatMode::Js).input(input).unwrap();
+//! tx_builder.build(signees![{"id" => key}]).unwrap();
+//!
+//! assert_eq!(
+//!     tx_builder.signed_payload().unwrap(),
+//!     r#"{"$namespace":"namespace","$contract":"contract","$i":{"id":{"amount":2}}}"#
+//! );
+//! # }
+//! ```
+//!
+//! [`TransactionBuilder::signed_payload`]: ../struct.TransactionBuilder.html#method.signed_payload
+//! [`CompatMode::Js`]: enum.CompatMode.html#variant.Js
+//! [`VECTORS`]: constant.VECTORS.html
+
+// External imports
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::{PacketBuilder, TransactionBuilder};
+
+/// Packet fields in the order the JS SDK writes them
+const JS_PACKET_FIELDS: [&str; 6] = ["$namespace", "$contract", "$entry", "$i", "$o", "$r"];
+
+/// # Compatibility mode
+///
+/// How the packet is serialized before it is signed.
+/// Set on the builder using [`TransactionBuilder::compat`].
+///
+/// [`TransactionBuilder::compat`]: ../struct.TransactionBuilder.html#method.compat
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompatMode {
+    /// Fields in key order, numbers as serde_json writes them
+    #[default]
+    Canonical,
+    /// The output of `JSON.stringify`, as signed by the JS SDK
+    Js,
+}
+
+/// # Test vector
+///
+/// A packet built and signed by the JS SDK. The namespace is always "namespace" and the contract
+/// "contract". The signature is made with the fixture key, `fixtures::KEY`, using SHA-256 and
+/// PKCS#1 v1.5.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestVector {
+    pub name: &'static str,
+    pub entry: Option<&'static str>,
+    /// Input JSON
+    pub input: &'static str,
+    /// Output JSON
+    pub output: Option<&'static str>,
+    /// Readonly JSON
+    pub readonly: Option<&'static str>,
+    /// The signed packet
+    pub payload: &'static str,
+    /// Base64 signature of the payload
+    pub signature: &'static str,
+}
+
+/// Vectors generated with `JSON.stringify` and `crypto.sign` in Node.js
+pub const VECTORS: [TestVector; 5] = [
+    TestVector {
+        name: "minimal",
+        entry: None,
+        input: r#"{"fixture":{"data":"data"}}"#,
+        output: None,
+        readonly: None,
+        payload: r#"{"$namespace":"namespace","$contract":"contract","$i":{"fixture":{"data":"data"}}}"#,
+        signature: "OAl+uXZw4IDdkDSzCnTcC8/j5OjBzqeZT+OGxqEVFaNfrHu5XM3o9kgMxl6ZYpBMryLToWX6OS9eNT0VwoGUbKZ7SvlRETegvEesWed32yHMATgFeNhkPIpyv2siBuWWZGC4qhihHUgzALhBrhR+ZqoF7PpmOA64xZAiNV4CjuVRiUKG/lKgMgpWf5iShfuGW9Wh32huLpBhGRtpExndDlHMSn8ijYPy8tPEjV4vhKeZr0JB8poCus3P9yrDf5oiS7juovKGFrnKx4VaYt7A4kN8zcAkpQl0XKcRNbW2SZ7+PdX8tBr3sksc0S6IfaMJwlWGd/r+ZfmQRWeLIUvPMg==",
+    },
+    TestVector {
+        name: "all_fields",
+        entry: Some("update"),
+        input: r#"{"fixture":{"data":"data"}}"#,
+        output: Some(r#"{"fixture":{"status":"ok"}}"#),
+        readonly: Some(r#"{"config":"ro-stream"}"#),
+        payload: r#"{"$namespace":"namespace","$contract":"contract","$entry":"update","$i":{"fixture":{"data":"data"}},"$o":{"fixture":{"status":"ok"}},"$r":{"config":"ro-stream"}}"#,
+        signature: "BO7AObxb982bDSegw5TdP3EcyCjUQb04XwMSSeYGn/0XQcveBgSU3MY4Romfvep9uXc2COI7tCxCXEZmm/UNMOmN2Xkv8EcleKhv6L2OF+FZHVYrBeeyhHYn+D7gI+zHnrZibiiUwPf1aGGN7fp82AeJ5SNoDfIlOJYAOyRsXZ/Imv9PM64VMQu82u09PC8JryiUgNtcS1aXlJoIvxTS8jH3Iutf1hSOTQiUMoB3YT1AN0dz/CQV9o69/vpO8IWjnk3gCpaZdwEzyo9hrEidPtCIQAUZXbAw40tldZVjRjkKK0Ff79cpt8Q6aq2V/scM+FNGV2e3nG95gLFoa+I7CQ==",
+    },
+    TestVector {
+        name: "numbers",
+        entry: None,
+        input: r#"{"fixture":{"float":1.5,"int":42,"integral":2.0,"large":1e21,"small":1e-7,"zero":-0.0}}"#,
+        output: None,
+        readonly: None,
+        payload: r#"{"$namespace":"namespace","$contract":"contract","$i":{"fixture":{"float":1.5,"int":42,"integral":2,"large":1e+21,"small":1e-7,"zero":0}}}"#,
+        signature: "qDiTRXoIqgUwusVgxB2PK0O+43K3cNR0iM6D9jAHAM2xu4DALY6HKkUrbKdyDZedxy1uE8TZ0TMwRyoe/JigygwU8TC/CVe/dfG+d/fqUloNXjFQrf5iYd5g4ZFZ8uf+GgvAtfj0OpzkjJxyiP/F8cf27PtPTUXS/0Jz9fuIv/Vh1kbupUOkIZEUH1WzkyQA/I/nq5tQPAnTcvXLLFFsih+4cP88e4Og8CVAXM4Q70LMw7ujLshMjrztLfAU8gwuYaKQg63OZ57N0LtfPnJ1bksvyNTO3bRUyfM+4QDvVvBi8nMrpKDre+OpoTBOE47lV8SAnQ9w1C9i2/XJdIec+w==",
+    },
+    TestVector {
+        name: "index_keys",
+        entry: None,
+        input: r#"{"fixture":{"10":"c","2":"b","a":"d"}}"#,
+        output: None,
+        readonly: None,
+        payload: r#"{"$namespace":"namespace","$contract":"contract","$i":{"fixture":{"2":"b","10":"c","a":"d"}}}"#,
+        signature: "PiXGhTISReqlfghYK2/8MGB/fxhx+MU92sE2JUCqxnwFp0zfLXlh/RmVkFRnEARfcOTRSdn+kpjGc2Vthh2vomPT3+4QwvjbFh+vmyXLZyZZRb4ydFJhSa4v77j4uUVtwhLuc+YV7KWUZAoKw9oY1TjZzQZOlp3vQDaHVu2btzCLm1JGw0ialLUtfTjOm2dvSpRTlaORRE5CCw92eDPgBU2ZX0RI9fD14Cq57d7sQPvzxhsphgQBstk7v7tYqv3rriumsEKKFIlhfAp/v71Vz8LWHcUd/G8wDi3E+qt6HqVUZmx7lJtXtQzPXanlcccAKaE8PAEveTXixsdMJTJuyg==",
+    },
+    TestVector {
+        name: "escaping",
+        entry: None,
+        input: r#"{"fixture":{"text":"line\nbreak \"quoted\" \u0001 é /"}}"#,
+        output: None,
+        readonly: None,
+        payload: r#"{"$namespace":"namespace","$contract":"contract","$i":{"fixture":{"text":"line\nbreak \"quoted\" \u0001 é /"}}}"#,
+        signature: "B3D97vXBfRGOe1xCr4Q743q+FOfucYXoa9WRtdafrAsjXl0AlxTPN2G5H8AmyrWkwJR3k0wdIyVzQYWwV5Fv+An7nUnG/JeJE7AEaeEgtWzk5Lvr36OIDUa1MqXJUrpD2g7mRtDncdku8mGR1IrGsPzo6PPthxjQs/ILQ3hjj7tWiiTe5zE93zkyDqzJ5q3rHSapMgueE4jjvcNWk/v5R9dTG2npslPuR2xaayBI/nLLwTW4D+M9tx17ffOAy+rn9jVZkXgwu93yHaIajk+UwedPjHZFvIo6EUdNy2MUtGQHpeUsNTu0WhNysJz/OhIrMuOis+pMnDGrroJadhKXpw==",
+    },
+];
+
+impl TestVector {
+    /// # Builder
+    ///
+    /// Create a transaction builder holding the vector's packet, in [`CompatMode::Js`].
+    ///
+    /// [`CompatMode::Js`]: enum.CompatMode.html#variant.Js
+    pub fn builder(&self) -> TxBuilderResult<TransactionBuilder> {
+        let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+        tx_builder.compat(CompatMode::Js);
+
+        if let Some(entry) = self.entry {
+            tx_builder.entry(entry);
+        }
+
+        tx_builder.input(TestVector::data(self.input)?)?;
+
+        if let Some(output) = self.output {
+            tx_builder.output(TestVector::data(output)?)?;
+        }
+
+        if let Some(readonly) = self.readonly {
+            tx_builder.readonly(TestVector::data(readonly)?)?;
+        }
+
+        Ok(tx_builder)
+    }
+}
+
+// Private functions
+impl TestVector {
+    /// Parse vector JSON into packet data
+    fn data(json: &str) -> TxBuilderResult<crate::PacketData> {
+        match serde_json::from_str(json) {
+            Ok(json) => PacketBuilder::new_json(json).build(),
+            Err(e) => Err(TxBuilderError::TransactionParseError(e)),
+        }
+    }
+}
+
+/// Serialize the packet using the compatibility mode
+pub(crate) fn packet<T: Serialize>(mode: CompatMode, body: &T) -> TxBuilderResult<Box<RawValue>> {
+    let json = match mode {
+        CompatMode::Canonical => serde_json::to_string(body),
+        CompatMode::Js => serde_json::to_value(body).map(|value| js_packet(&value)),
+    };
+
+    let json = match json {
+        Ok(json) => json,
+        Err(_) => return Err(TxBuilderError::TxBodyError(4001)),
+    };
+
+    match RawValue::from_string(json) {
+        Ok(raw) => Ok(raw),
+        Err(_) => Err(TxBuilderError::TxBodyError(4001)),
+    }
+}
+
+/// Write the packet with its fields in the JS SDK order
+fn js_packet(value: &Value) -> String {
+    let fields = match value {
+        Value::Object(fields) => fields,
+        _ => return js_string(value),
+    };
+
+    let mut keys: Vec<&str> = JS_PACKET_FIELDS
+        .iter()
+        .copied()
+        .filter(|key| fields.contains_key(*key))
+        .collect();
+
+    for key in fields.keys() {
+        if !JS_PACKET_FIELDS.contains(&key.as_str()) {
+            keys.push(key);
+        }
+    }
+
+    let mut out = String::new();
+    write_fields(&mut out, fields, &keys);
+
+    out
+}
+
+/// Write a value as `JSON.stringify` would
+fn js_string(value: &Value) -> String {
+    let mut out = String::new();
+    write_js(&mut out, value);
+
+    out
+}
+
+fn write_js(out: &mut String, value: &Value) {
+    match value {
+        Value::Number(number) => match number.as_f64() {
+            Some(float) if number.is_f64() => out.push_str(&js_number(float)),
+            _ => out.push_str(&number.to_string()),
+        },
+        Value::Array(values) => {
+            out.push('[');
+
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+
+                write_js(out, value);
+            }
+
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            // Array index keys come first in numeric order, then the rest in their order
+            let mut indexes: Vec<(u32, &str)> = fields
+                .keys()
+                .filter_map(|key| array_index(key).map(|index| (index, key.as_str())))
+                .collect();
+            indexes.sort_unstable();
+
+            let mut keys: Vec<&str> = indexes.into_iter().map(|(_, key)| key).collect();
+            keys.extend(
+                fields
+                    .keys()
+                    .filter(|key| array_index(key).is_none())
+                    .map(String::as_str),
+            );
+
+            write_fields(out, fields, &keys);
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+fn write_fields(out: &mut String, fields: &Map<String, Value>, keys: &[&str]) {
+    out.push('{');
+
+    for (index, key) in keys.iter().enumerate() {
+        if index > 0 {
+            out.push(',');
+        }
+
+        out.push_str(&Value::from(*key).to_string());
+        out.push(':');
+        write_js(out, &fields[*key]);
+    }
+
+    out.push('}');
+}
+
+/// Format a number as JavaScript does
+fn js_number(value: f64) -> String {
+    // Negative zero is written as 0
+    if value == 0.0 {
+        return String::from("0");
+    }
+
+    let abs = value.abs();
+
+    if !(1e-6..1e21).contains(&abs) {
+        let exp = format!("{:e}", value);
+
+        // JavaScript always signs the exponent
+        return match exp.find('e') {
+            Some(index) if !exp[index + 1..].starts_with('-') => {
+                format!("{}e+{}", &exp[..index], &exp[index + 1..])
+            }
+            _ => exp,
+        };
+    }
+
+    format!("{}", value)
+}
+
+/// The key as a JavaScript array index, which is an integer below 2^32 - 1 with no leading zeros
+fn array_index(key: &str) -> Option<u32> {
+    if key.is_empty() || (key.len() > 1 && key.starts_with('0')) {
+        return None;
+    }
+
+    if !key.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    match key.parse::<u32>() {
+        Ok(index) if index < u32::MAX => Some(index),
+        _ => None,
+    }
+}
+
+#[cfg(all(test, feature = "openssl-keys"))]
+mod tests {
+    use super::*;
+    use crate::{signees, Key};
+    use activeledger::key::EllipticCurve;
+
+    #[test]
+    fn compat_vectors() {
+        let key = Key::Ec(EllipticCurve::new("fixture").unwrap());
+
+        for vector in VECTORS.iter() {
+            let mut tx_builder = vector.builder().unwrap();
+            tx_builder
+                .build(signees![{"fixture" => key.clone()}])
+                .unwrap();
+
+            assert_eq!(
+                tx_builder.signed_payload().unwrap(),
+                vector.payload,
+                "{}",
+                vector.name
+            );
+        }
+    }
+
+    #[cfg(feature = "fixtures")]
+    #[test]
+    fn compat_vector_signatures() {
+        let key = crate::fixtures::key().unwrap();
+
+        for vector in VECTORS.iter() {
+            let tx = vector
+                .builder()
+                .unwrap()
+                .build(signees![{"fixture" => key.clone()}])
+                .unwrap();
+
+            let json: Value = serde_json::from_str(&tx).unwrap();
+            assert_eq!(
+                json["$sigs"]["fixture"], vector.signature,
+                "{}",
+                vector.name
+            );
+        }
+    }
+
+    #[test]
+    fn compat_js_numbers() {
+        assert_eq!(js_number(2.0), "2");
+        assert_eq!(js_number(-0.0), "0");
+        assert_eq!(js_number(0.1), "0.1");
+        assert_eq!(js_number(1e21), "1e+21");
+        assert_eq!(js_number(-1.5e22), "-1.5e+22");
+        assert_eq!(js_number(1e-7), "1e-7");
+        assert_eq!(js_number(0.000001), "0.000001");
+        assert_eq!(array_index("01"), None);
+        assert_eq!(array_index("4294967295"), None);
+        assert_eq!(array_index("4294967294"), Some(4294967294));
+    }
+}
@@ -0,0 +1,277 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Transaction diff
+//!
+//! Compare two built transactions and report the differences by section, for example when
+//! replaying transactions in another environment and reconciling the results.
+//!
+//! Paths are dotted, like [`PacketData::get_path`](struct.PacketData.html#method.get_path).
+//! Array elements are compared by index.
+//!
+//! ## Example
+//! ```
+//! # use active_tx::{tx_diff, Change};
+//! # use serde_json::json;
+//! let a = json!({"$tx": {"$i": {"id": {"amount": 10}}}, "$sigs": {"id": "sig1"}});
+//! let b = json!({"$tx": {"$i": {"id": {"amount": 11}}}, "$sigs": {"id": "sig2"}, "$selfsign": true});
+//!
+//! let diff = tx_diff(&a, &b);
+//!
+//! assert_eq!(
+//!     diff.tx,
+//!     vec![Change::Changed {
+//!         path: "$tx.$i.id.amount".to_string(),
+//!         from: json!(10),
+//!         to: json!(11),
+//!     }]
+//! );
+//! assert_eq!(diff.sigs.len(), 1);
+//! assert_eq!(diff.envelope.len(), 1);
+//! ```
+
+// STD
+use std::fmt;
+
+// External imports
+use serde::Serialize;
+use serde_json::Value;
+
+/// The differences between two transactions, by section
+#[derive(Serialize, Debug, Clone, PartialEq, Default)]
+pub struct TxDiff {
+    /// Changes to the $tx packet
+    pub tx: Vec<Change>,
+    /// Changes to the $sigs object
+    pub sigs: Vec<Change>,
+    /// Changes to the rest of the envelope, such as $selfsign and $territoriality
+    pub envelope: Vec<Change>,
+}
+
+/// A single difference, from the first transaction to the second
+#[derive(Serialize, Debug, Clone, PartialEq)]
+#[serde(tag = "change", rename_all = "lowercase")]
+pub enum Change {
+    /// The value is only in the second transaction
+    Added { path: String, value: Value },
+    /// The value is only in the first transaction
+    Removed { path: String, value: Value },
+    /// The value is in both transactions but differs
+    Changed {
+        path: String,
+        from: Value,
+        to: Value,
+    },
+}
+
+/// # Transaction diff
+///
+/// Compare two built transactions, returning the differences in each section.
+pub fn tx_diff(a: &Value, b: &Value) -> TxDiff {
+    let mut diff = TxDiff::default();
+
+    let (a, b) = match (a, b) {
+        (Value::Object(a), Value::Object(b)) => (a, b),
+        _ => {
+            diff_value(String::new(), a, b, &mut diff.envelope);
+            return diff;
+        }
+    };
+
+    let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    for key in keys {
+        let changes = match key.as_str() {
+            "$tx" => &mut diff.tx,
+            "$sigs" => &mut diff.sigs,
+            _ => &mut diff.envelope,
+        };
+
+        diff_entry(key.clone(), a.get(key), b.get(key), changes);
+    }
+
+    diff
+}
+
+impl TxDiff {
+    /// # Is empty
+    ///
+    /// Check whether the transactions are the same.
+    pub fn is_empty(&self) -> bool {
+        self.tx.is_empty() && self.sigs.is_empty() && self.envelope.is_empty()
+    }
+
+    /// # Changes
+    ///
+    /// Iterate over the changes in every section.
+    pub fn changes(&self) -> impl Iterator<Item = &Change> {
+        self.tx.iter().chain(&self.sigs).chain(&self.envelope)
+    }
+}
+
+impl Change {
+    /// # Path
+    ///
+    /// Get the dotted path of the changed value.
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. } => path,
+            Change::Removed { path, .. } => path,
+            Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for TxDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in self.changes() {
+            writeln!(f, "{}", change)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {}: {}", path, value),
+            Change::Removed { path, value } => write!(f, "- {}: {}", path, value),
+            Change::Changed { path, from, to } => write!(f, "~ {}: {} -> {}", path, from, to),
+        }
+    }
+}
+
+/// Compare a value that may be missing from either side
+fn diff_entry(path: String, a: Option<&Value>, b: Option<&Value>, changes: &mut Vec<Change>) {
+    match (a, b) {
+        (Some(a), Some(b)) => diff_value(path, a, b, changes),
+        (Some(a), None) => changes.push(Change::Removed {
+            path,
+            value: a.clone(),
+        }),
+        (None, Some(b)) => changes.push(Change::Added {
+            path,
+            value: b.clone(),
+        }),
+        (None, None) => {}
+    }
+}
+
+/// Recursively compare two values, descending into objects and arrays
+fn diff_value(path: String, a: &Value, b: &Value, changes: &mut Vec<Change>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                diff_entry(join(&path, key), a.get(key), b.get(key), changes);
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                diff_entry(join(&path, &i.to_string()), a.get(i), b.get(i), changes);
+            }
+        }
+        _ if a != b => changes.push(Change::Changed {
+            path,
+            from: a.clone(),
+            to: b.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// Add a key to a dotted path
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_sections() {
+        let a = json!({
+            "$tx": {"$namespace": "ns", "$i": {"id": {"list": [1, 2], "old": true}}},
+            "$sigs": {"id": "sig1", "other": "sig"},
+            "$territoriality": "node1"
+        });
+        let b = json!({
+            "$tx": {"$namespace": "ns", "$i": {"id": {"list": [1, 3, 4], "new": 1}}},
+            "$sigs": {"id": "sig2"},
+            "$selfsign": true,
+            "$territoriality": "node1"
+        });
+
+        let diff = tx_diff(&a, &b);
+
+        let paths: Vec<&str> = diff.tx.iter().map(Change::path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "$tx.$i.id.list.1",
+                "$tx.$i.id.list.2",
+                "$tx.$i.id.new",
+                "$tx.$i.id.old"
+            ]
+        );
+        assert_eq!(
+            diff.sigs,
+            vec![
+                Change::Changed {
+                    path: "$sigs.id".to_string(),
+                    from: json!("sig1"),
+                    to: json!("sig2"),
+                },
+                Change::Removed {
+                    path: "$sigs.other".to_string(),
+                    value: json!("sig"),
+                },
+            ]
+        );
+        assert_eq!(
+            diff.envelope,
+            vec![Change::Added {
+                path: "$selfsign".to_string(),
+                value: json!(true),
+            }]
+        );
+        assert_eq!(diff.changes().count(), 7);
+        assert!(diff
+            .to_string()
+            .contains("~ $sigs.id: \"sig1\" -> \"sig2\""));
+
+        assert!(tx_diff(&a, &a).is_empty());
+    }
+}
@@ -0,0 +1,129 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Binary encodings
+//!
+//! Encode built transactions as CBOR or MessagePack, for transports where JSON is too large.
+//!
+//! Requires the `cbor` and/or `msgpack` features.
+//!
+//! Only the envelope is re-encoded. The signatures are still computed over the compact JSON
+//! form of `$tx`, so the receiver must convert the transaction back to JSON before it is sent
+//! to a node.
+//!
+//! ## Example
+//! ```
+//! # use active_tx::{PacketBuilder, TransactionBuilder, Key, packet_data, signees};
+//! # use activeledger::key::EllipticCurve;
+//! let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+//! let input = PacketBuilder::new(packet_data!({"keyname": {"input": "data"}}))
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+//! tx_builder.input(input).unwrap().build(signees!(key)).unwrap();
+//!
+//! # #[cfg(feature = "cbor")]
+//! let cbor = tx_builder.get_cbor().unwrap();
+//! # #[cfg(feature = "msgpack")]
+//! let msgpack = tx_builder.get_msgpack().unwrap();
+//! ```
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::TransactionBuilder;
+
+impl TransactionBuilder {
+    /// # Transaction CBOR
+    ///
+    /// Get the built transaction encoded as CBOR.
+    /// Requires the `cbor` feature.
+    #[cfg(feature = "cbor")]
+    pub fn get_cbor(&self) -> TxBuilderResult<Vec<u8>> {
+        let tx = self.get_json()?;
+
+        match serde_cbor::to_vec(&tx) {
+            Ok(cbor) => Ok(cbor),
+            Err(e) => Err(TxBuilderError::CborError(e)),
+        }
+    }
+
+    /// # Transaction MessagePack
+    ///
+    /// Get the built transaction encoded as MessagePack.
+    /// Requires the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    pub fn get_msgpack(&self) -> TxBuilderResult<Vec<u8>> {
+        let tx = self.get_json()?;
+
+        match rmp_serde::to_vec(&tx) {
+            Ok(msgpack) => Ok(msgpack),
+            Err(e) => Err(TxBuilderError::MsgpackError(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use activeledger::key::EllipticCurve;
+    use serde_json::Value;
+
+    fn built() -> TransactionBuilder {
+        let key = EllipticCurve::new("test").unwrap();
+        let input = PacketBuilder::new(packet_data!({"test": {"count": 1, "flag": true}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .build(signees![{"test" => Key::Ec(key)}])
+            .unwrap();
+
+        transaction_builder
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn tx_cbor() {
+        let transaction_builder = built();
+
+        let cbor = transaction_builder.get_cbor().unwrap();
+        let decoded: Value = serde_cbor::from_slice(&cbor).unwrap();
+
+        assert_eq!(decoded, transaction_builder.get_json().unwrap());
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn tx_msgpack() {
+        let transaction_builder = built();
+
+        let msgpack = transaction_builder.get_msgpack().unwrap();
+        let decoded: Value = rmp_serde::from_slice(&msgpack).unwrap();
+
+        assert_eq!(decoded, transaction_builder.get_json().unwrap());
+    }
+}
@@ -22,9 +22,31 @@
  */
 
 //! # Transaction Builder Error definitions
+//!
+//! Errors carry the context needed to act on them (the missing field, the offending stream ID,
+//! the underlying key error). The numeric codes used by earlier versions are still available
+//! via [`TxBuilderError::code`], apart from calls made before the transaction is built, which
+//! return `InvalidState` (5005) in place of 5000 and 5004.
 
+#[cfg(feature = "openssl-keys")]
+use activeledger::key::KeyError;
+#[cfg(feature = "openssl-keys")]
+use openssl::error::ErrorStack;
+
+#[cfg(not(feature = "std"))]
+use alloc::{boxed::Box, string::String, string::ToString, vec::Vec};
+use core::fmt;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+#[cfg(feature = "std")]
 use std::error::Error;
-use std::fmt;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+use crate::assembly::KeyType;
+#[cfg(feature = "std")]
+use crate::TxState;
 
 /// KeyResult definition - Shorthand for: Result<T, TxBuilderError>
 pub type TxBuilderResult<T> = Result<T, TxBuilderError>;
@@ -32,107 +54,939 @@ pub type TxBuilderResult<T> = Result<T, TxBuilderError>;
 /// KeyError data holder
 #[derive(Debug)]
 pub enum TxBuilderError {
-    BuildError(u16),      // 1000
-    JsonError(u16),       // 2000
-    PacketError(u16),     // 3000
-    TxBodyError(u16),     // 4000
-    TxBuildError(u16),    // 5000
-    TxGenerateError(u16), // 6000
-    KeyError(u16),        // 7000
+    BuildError(u16),   // 1000
+    JsonError(u16),    // 2000
+    PacketError(u16),  // 3000
+    TxBodyError(u16),  // 4000
+    TxBuildError(u16), // 5000
+
+    /// The JSON Schema given to the packet builder could not be compiled - 1002
+    #[cfg(feature = "schema")]
+    InvalidSchema(String),
+
+    /// The packet data does not match its JSON Schema, listing each violation - 1003
+    #[cfg(feature = "schema")]
+    SchemaViolation(Vec<String>),
+
+    /// A decimal amount can't be written as a JSON number without rounding - 1004
+    InexactDecimal(String),
+
+    /// A YAML document given to the packet builder could not be parsed - 2003
+    #[cfg(feature = "yaml")]
+    YamlError(serde_yaml::Error),
+
+    /// A TOML document given to the packet builder could not be parsed - 2004
+    #[cfg(feature = "toml")]
+    TomlError(toml::de::Error),
+
+    /// A CSV file could not be read - 2005
+    #[cfg(feature = "csv")]
+    CsvError(csv::Error),
+
+    /// A CSV column is not in the header row - 2006
+    #[cfg(feature = "csv")]
+    UnknownColumn(String),
+
+    /// A CSV value does not match the type of its column - 2007
+    #[cfg(feature = "csv")]
+    InvalidColumnValue {
+        line: u64,
+        column: String,
+        value: String,
+    },
+
+    /// A dotted path into packet data is empty or passes through a value that is not an object - 3002
+    InvalidPath(String),
+
+    /// A placeholder in templated packet data has no variable to render - 3010
+    MissingVariable(String),
+
+    /// Two object keys in templated packet data render to the same key - 3012
+    RenderKeyCollision(String),
+
+    /// A stream ID given to a packet builder is not valid - 3004
+    InvalidStreamId {
+        value: String,
+        reason: &'static str,
+    },
+
+    /// OpenSSL failed to encrypt the packet field at a path - 3005
+    #[cfg(feature = "openssl-keys")]
+    FieldEncryptionError {
+        path: String,
+        source: ErrorStack,
+    },
+
+    /// An encrypted packet field could not be decrypted, holding the OpenSSL error if the
+    /// blob was well formed - 3006
+    #[cfg(feature = "openssl-keys")]
+    FieldDecryptionError(Option<ErrorStack>),
+
+    /// Packet data for a transaction section could not be read - 5001, 5002, 5003
+    SectionError {
+        section: &'static str,
+        source: Box<TxBuilderError>,
+    },
+
+    /// An error with the name of the field it was raised for, added with
+    /// [`context_field`](#method.context_field) - the code of the source error
+    WithField {
+        field: String,
+        source: Box<TxBuilderError>,
+    },
+
+    /// A required transaction field was not set - 5006, 5007, 5008
+    MissingField(&'static str),
+
+    /// The territoriality value is not a valid node reference - 5009
+    InvalidTerritoriality {
+        value: String,
+        reason: &'static str,
+    },
+
+    /// The territoriality does not match any of the known node references - 5010
+    UnknownNode(String),
+
+    /// Writing the transaction to a writer failed - 5012
+    WriteError(serde_json::Error),
+
+    /// Encoding the transaction as CBOR failed - 5013
+    #[cfg(feature = "cbor")]
+    CborError(serde_cbor::Error),
+
+    /// Encoding the transaction as MessagePack failed - 5014
+    #[cfg(feature = "msgpack")]
+    MsgpackError(rmp_serde::encode::Error),
+
+    /// A signature was given for a stream that is not required to sign - 5015
+    UnexpectedSigner(String),
+
+    /// Not enough of the required streams have signed - 5016
+    ThresholdNotMet {
+        signed: usize,
+        threshold: usize,
+    },
+
+    /// The built transaction is larger than the size limit set on the builder - 5018
+    SizeExceeded {
+        size: usize,
+        max: usize,
+    },
+
+    /// An extra field would replace one set by the builder - 5019
+    ReservedField(String),
+
+    /// A signed transaction could not be parsed - 5020
+    TransactionParseError(serde_json::Error),
+
+    /// The system random number generator failed - 5021
+    #[cfg(feature = "std")]
+    RandomError(getrandom::Error),
+
+    /// The entry is not one of the entries allowed by the builder - 5022
+    UnknownEntry(String),
+
+    /// The namespace, contract or entry is not a valid identifier - 5023
+    InvalidIdentifier {
+        field: &'static str,
+        value: String,
+        reason: &'static str,
+    },
+
+    /// The builder is not ready to build, holding every problem found - 5024
+    ValidationFailed(Vec<TxBuilderError>),
+
+    /// Merging into a transaction section would replace a value already set - 5025
+    MergeConflict(String),
+
+    /// The transaction linter found errors, holding each error message - 5026
+    #[cfg(feature = "std")]
+    LintFailed(Vec<String>),
+
+    /// Reading a profile file failed - 5027
+    #[cfg(feature = "std")]
+    ProfileFileError {
+        path: PathBuf,
+        source: io::Error,
+    },
+
+    /// A profile could not be parsed - 5028
+    ProfileFormatError(serde_json::Error),
+
+    /// The builder is not in the state needed for the call, such as signing before building - 5005
+    #[cfg(feature = "std")]
+    InvalidState {
+        expected: TxState,
+        actual: TxState,
+    },
+
+    /// A transaction descriptor is not in the expected form - 5029
+    DescriptorError(serde_json::Error),
+
+    /// Variants were built without any sets of signees - 5030
+    EmptyVariants,
+
+    /// Generating a key failed - 6000, 6001
+    #[cfg(feature = "openssl-keys")]
+    KeyGenerationError {
+        key_type: KeyType,
+        source: KeyError,
+    },
+
+    /// Signing the packet for a stream failed - 7000, 7001
+    #[cfg(feature = "openssl-keys")]
+    SigningError {
+        streamid: String,
+        key_type: KeyType,
+        source: KeyError,
+    },
+
+    /// Getting the PEM of a key failed - 7002
+    #[cfg(feature = "openssl-keys")]
+    PemError {
+        key_name: String,
+        source: KeyError,
+    },
+
+    /// An external signer failed to sign - 7003
+    SignerError(String),
+
+    /// The private key is held by an external signer and can't be used directly - 7004
+    PrivateKeyUnavailable(String),
+
+    /// Reading a key file failed - 8000
+    #[cfg(feature = "std")]
+    KeyFileError {
+        path: PathBuf,
+        source: io::Error,
+    },
+
+    /// The signature for a stream is not valid for its public key - 7005
+    InvalidSignature(String),
+
+    /// The transaction has no signature for a stream being verified - 7006
+    MissingSignature(String),
+
+    /// A PEM could not be parsed as a private key - 8001
+    #[cfg(feature = "openssl-keys")]
+    PemParseError(ErrorStack),
+
+    /// The key is not of the expected type - 8002
+    KeyTypeMismatch {
+        expected: KeyType,
+    },
+
+    /// The key algorithm or curve is not supported by Activeledger - 8003
+    UnsupportedKeyType,
+
+    /// DER bytes could not be parsed as a private key - 8004
+    #[cfg(feature = "openssl-keys")]
+    DerParseError(ErrorStack),
+
+    /// A JWK member is missing or invalid - 8005
+    JwkError(&'static str),
+
+    /// Exporting a key failed - 8006
+    #[cfg(feature = "openssl-keys")]
+    KeyExportError(ErrorStack),
+
+    /// A PEM could not be parsed as a public key - 8007
+    #[cfg(feature = "openssl-keys")]
+    PublicKeyParseError(ErrorStack),
+
+    /// A mnemonic phrase is not a valid BIP39 mnemonic - 8008
+    InvalidMnemonic(String),
+
+    /// A key derivation path is not valid, or leads to an invalid key - 8009
+    InvalidDerivationPath(String),
+
+    /// OpenSSL rejected the components of an imported key - 8010
+    #[cfg(feature = "openssl-keys")]
+    KeyImportError(ErrorStack),
+
+    /// OpenSSL failed to derive a key from a seed - 8011
+    #[cfg(feature = "openssl-keys")]
+    SeededKeyError(ErrorStack),
+
+    /// Reading or writing the key store file failed - 9000
+    #[cfg(feature = "std")]
+    KeyStoreFileError {
+        path: PathBuf,
+        source: io::Error,
+    },
+
+    /// The key store file could not be parsed or serialized - 9001
+    KeyStoreFormatError(serde_json::Error),
+
+    /// The named key is not held in the key store - 9002
+    KeyNotFound(String),
+
+    /// Deriving the key store encryption key or encrypting failed - 9003
+    #[cfg(feature = "openssl-keys")]
+    KeyStoreCryptoError(ErrorStack),
+
+    /// A stored key could not be decrypted, usually due to a wrong passphrase - 9004
+    DecryptionError,
+
+    /// Sending the transaction to the node failed - 10000
+    #[cfg(feature = "http")]
+    HttpError(reqwest::Error),
+
+    /// The response returned by the node could not be parsed - 10001
+    ResponseError(serde_json::Error),
+
+    /// The node returned an error status without a parsable response - 10002
+    HttpStatusError(u16),
+
+    /// Subscribing to the node's event stream failed - 10003
+    #[cfg(feature = "events")]
+    EventsError(active_sse::error::SSEError),
+
+    /// The ledger did not commit the transaction, holding the errors it reported - 10004
+    #[cfg(feature = "events")]
+    LedgerRejected(Vec<String>),
+
+    /// The streams were not confirmed before the timeout, holding the unconfirmed stream IDs - 10005
+    #[cfg(feature = "events")]
+    ConfirmationTimeout(Vec<String>),
 }
 
-impl fmt::Display for TxBuilderError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            TxBuilderError::BuildError(ref code) => {
-                let error = TxBuilderErrorHandler::get_build_error(code);
-                write!(f, " Error - {} : {}", code, error)
-            }
+impl TxBuilderError {
+    /// # Code
+    ///
+    /// Get the numeric code of the error.
+    /// These match the codes returned by earlier versions of this crate.
+    pub fn code(&self) -> u16 {
+        match self {
+            TxBuilderError::BuildError(code)
+            | TxBuilderError::JsonError(code)
+            | TxBuilderError::PacketError(code)
+            | TxBuilderError::TxBodyError(code)
+            | TxBuilderError::TxBuildError(code) => *code,
 
-            TxBuilderError::JsonError(ref code) => {
-                let error = TxBuilderErrorHandler::get_json_error(code);
-                write!(f, " Error - {} : {}", code, error)
-            }
+            TxBuilderError::SectionError { section, .. } => match *section {
+                "input" => 5001,
+                "output" => 5002,
+                "readonly" => 5003,
+                _ => 5000,
+            },
+            TxBuilderError::WithField { source, .. } => source.code(),
 
-            TxBuilderError::PacketError(ref code) => {
-                let error = TxBuilderErrorHandler::get_packet_error(code);
-                write!(f, " Error - {} : {}", code, error)
-            }
+            #[cfg(feature = "schema")]
+            TxBuilderError::InvalidSchema(_) => 1002,
+            #[cfg(feature = "schema")]
+            TxBuilderError::SchemaViolation(_) => 1003,
+            TxBuilderError::InexactDecimal(_) => 1004,
 
-            TxBuilderError::TxBodyError(ref code) => {
-                let error = TxBuilderErrorHandler::get_txbody_error(code);
-                write!(f, " Error - {} : {}", code, error)
-            }
+            #[cfg(feature = "yaml")]
+            TxBuilderError::YamlError(_) => 2003,
+            #[cfg(feature = "toml")]
+            TxBuilderError::TomlError(_) => 2004,
+            #[cfg(feature = "csv")]
+            TxBuilderError::CsvError(_) => 2005,
+            #[cfg(feature = "csv")]
+            TxBuilderError::UnknownColumn(_) => 2006,
+            #[cfg(feature = "csv")]
+            TxBuilderError::InvalidColumnValue { .. } => 2007,
+
+            TxBuilderError::InvalidPath(_) => 3002,
+            TxBuilderError::InvalidStreamId { .. } => 3004,
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::FieldEncryptionError { .. } => 3005,
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::FieldDecryptionError(_) => 3006,
+            TxBuilderError::MissingVariable(_) => 3010,
+            TxBuilderError::RenderKeyCollision(_) => 3012,
+
+            TxBuilderError::MissingField(field) => match *field {
+                "contract" => 5006,
+                "namespace" => 5007,
+                "input" => 5008,
+                _ => 5000,
+            },
 
-            TxBuilderError::TxBuildError(ref code) => {
-                let error = TxBuilderErrorHandler::get_txbuild_error(code);
-                write!(f, " Error - {} : {}", code, error)
+            TxBuilderError::InvalidTerritoriality { .. } => 5009,
+            TxBuilderError::UnknownNode(_) => 5010,
+            TxBuilderError::WriteError(_) => 5012,
+            TxBuilderError::UnexpectedSigner(_) => 5015,
+            TxBuilderError::ThresholdNotMet { .. } => 5016,
+            TxBuilderError::SizeExceeded { .. } => 5018,
+            TxBuilderError::ReservedField(_) => 5019,
+            TxBuilderError::TransactionParseError(_) => 5020,
+            #[cfg(feature = "std")]
+            TxBuilderError::RandomError(_) => 5021,
+            TxBuilderError::UnknownEntry(_) => 5022,
+            TxBuilderError::InvalidIdentifier { .. } => 5023,
+            TxBuilderError::ValidationFailed(_) => 5024,
+            TxBuilderError::MergeConflict(_) => 5025,
+            #[cfg(feature = "std")]
+            TxBuilderError::LintFailed(_) => 5026,
+            #[cfg(feature = "std")]
+            TxBuilderError::ProfileFileError { .. } => 5027,
+            TxBuilderError::ProfileFormatError(_) => 5028,
+            TxBuilderError::DescriptorError(_) => 5029,
+            TxBuilderError::EmptyVariants => 5030,
+            #[cfg(feature = "std")]
+            TxBuilderError::InvalidState { .. } => 5005,
+            #[cfg(feature = "cbor")]
+            TxBuilderError::CborError(_) => 5013,
+            #[cfg(feature = "msgpack")]
+            TxBuilderError::MsgpackError(_) => 5014,
+
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::KeyGenerationError { key_type, .. } => match key_type {
+                KeyType::RSA => 6000,
+                KeyType::EC => 6001,
+            },
+
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::SigningError { key_type, .. } => match key_type {
+                KeyType::EC => 7000,
+                KeyType::RSA => 7001,
+            },
+
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::PemError { .. } => 7002,
+            TxBuilderError::SignerError(_) => 7003,
+            TxBuilderError::PrivateKeyUnavailable(_) => 7004,
+            TxBuilderError::InvalidSignature(_) => 7005,
+            TxBuilderError::MissingSignature(_) => 7006,
+
+            #[cfg(feature = "std")]
+            TxBuilderError::KeyFileError { .. } => 8000,
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::PemParseError(_) => 8001,
+            TxBuilderError::KeyTypeMismatch { .. } => 8002,
+            TxBuilderError::UnsupportedKeyType => 8003,
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::DerParseError(_) => 8004,
+            TxBuilderError::JwkError(_) => 8005,
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::KeyExportError(_) => 8006,
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::PublicKeyParseError(_) => 8007,
+            TxBuilderError::InvalidMnemonic(_) => 8008,
+            TxBuilderError::InvalidDerivationPath(_) => 8009,
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::KeyImportError(_) => 8010,
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::SeededKeyError(_) => 8011,
+
+            #[cfg(feature = "std")]
+            TxBuilderError::KeyStoreFileError { .. } => 9000,
+            TxBuilderError::KeyStoreFormatError(_) => 9001,
+            TxBuilderError::KeyNotFound(_) => 9002,
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::KeyStoreCryptoError(_) => 9003,
+            TxBuilderError::DecryptionError => 9004,
+
+            #[cfg(feature = "http")]
+            TxBuilderError::HttpError(_) => 10000,
+            TxBuilderError::ResponseError(_) => 10001,
+            TxBuilderError::HttpStatusError(_) => 10002,
+            #[cfg(feature = "events")]
+            TxBuilderError::EventsError(_) => 10003,
+            #[cfg(feature = "events")]
+            TxBuilderError::LedgerRejected(_) => 10004,
+            #[cfg(feature = "events")]
+            TxBuilderError::ConfirmationTimeout(_) => 10005,
+        }
+    }
+
+    /// # Category
+    ///
+    /// Get the category of the error, named after the range its code falls in:
+    /// `build`, `json`, `packet`, `txbody`, `txbuild`, `txgenerate`, `key`, `keyimport`,
+    /// `keystore` or `submit`.
+    ///
+    /// ```
+    /// # use active_tx::TxBuilderError;
+    /// assert_eq!(TxBuilderError::MissingField("contract").category(), "txbuild");
+    /// ```
+    pub fn category(&self) -> &'static str {
+        TxBuilderErrorHandler::get_category(self.code())
+    }
+
+    /// # Field
+    ///
+    /// Get the name of the field, section, column or path the error is about, if it has one.
+    ///
+    /// ```
+    /// # use active_tx::TxBuilderError;
+    /// assert_eq!(TxBuilderError::MissingField("contract").field(), Some("contract"));
+    /// assert_eq!(TxBuilderError::DecryptionError.field(), None);
+    /// ```
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            TxBuilderError::SectionError { section, .. } => Some(section),
+            TxBuilderError::WithField { field, .. } => Some(field),
+            TxBuilderError::MissingField(field)
+            | TxBuilderError::InvalidIdentifier { field, .. }
+            | TxBuilderError::JwkError(field) => Some(field),
+            TxBuilderError::InvalidTerritoriality { .. } | TxBuilderError::UnknownNode(_) => {
+                Some("territoriality")
             }
+            TxBuilderError::UnknownEntry(_) => Some("entry"),
+            TxBuilderError::InvalidStreamId { .. } => Some("streamid"),
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::FieldEncryptionError { path, .. } => Some(path),
+            #[cfg(feature = "csv")]
+            TxBuilderError::UnknownColumn(name)
+            | TxBuilderError::InvalidColumnValue { column: name, .. } => Some(name),
+            TxBuilderError::InvalidPath(name)
+            | TxBuilderError::ReservedField(name)
+            | TxBuilderError::MergeConflict(name) => Some(name),
+            _ => None,
+        }
+    }
+
+    /// # Context field
+    ///
+    /// Attach the name of the field the error was raised for, such as a config key or CLI
+    /// argument. The code, category and description stay those of the original error.
+    ///
+    /// ```
+    /// # use active_tx::TxBuilderError;
+    /// let error = TxBuilderError::UnknownNode("node9".to_string()).context_field("--node");
+    ///
+    /// assert_eq!(error.code(), 5010);
+    /// assert_eq!(error.field(), Some("--node"));
+    /// assert!(error.to_string().ends_with("'node9' (field '--node')"));
+    /// ```
+    pub fn context_field<F: Into<String>>(self, field: F) -> TxBuilderError {
+        TxBuilderError::WithField {
+            field: field.into(),
+            source: Box::new(self),
+        }
+    }
 
-            TxBuilderError::TxGenerateError(ref code) => {
-                let error = TxBuilderErrorHandler::get_txgenerate_error(code);
-                write!(f, " Error - {} : {}", code, error)
+    /// Write the details held by the error, following its description
+    fn fmt_details(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TxBuilderError::SectionError { source, .. } => write!(f, " -{}", source),
+            TxBuilderError::WithField { field, source } => {
+                source.fmt_details(f)?;
+                write!(f, " (field '{}')", field)
+            }
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::KeyGenerationError { source, .. } => write!(f, " - {}", source),
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::SigningError {
+                streamid, source, ..
+            } => write!(f, " for stream '{}' - {}", streamid, source),
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::PemError { key_name, source } => {
+                write!(f, " for key '{}' - {}", key_name, source)
+            }
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::FieldEncryptionError { path, source } => {
+                write!(f, " '{}' - {}", path, source)
+            }
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::FieldDecryptionError(Some(source)) => write!(f, " - {}", source),
+            #[cfg(feature = "std")]
+            TxBuilderError::KeyFileError { path, source }
+            | TxBuilderError::KeyStoreFileError { path, source }
+            | TxBuilderError::ProfileFileError { path, source } => {
+                write!(f, " '{}' - {}", path.display(), source)
+            }
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::PemParseError(source)
+            | TxBuilderError::DerParseError(source)
+            | TxBuilderError::KeyExportError(source)
+            | TxBuilderError::KeyImportError(source)
+            | TxBuilderError::SeededKeyError(source)
+            | TxBuilderError::PublicKeyParseError(source)
+            | TxBuilderError::KeyStoreCryptoError(source) => write!(f, " - {}", source),
+            TxBuilderError::SignerError(message) | TxBuilderError::InvalidMnemonic(message) => {
+                write!(f, " - {}", message)
             }
+            TxBuilderError::KeyStoreFormatError(source)
+            | TxBuilderError::ProfileFormatError(source)
+            | TxBuilderError::DescriptorError(source)
+            | TxBuilderError::ResponseError(source)
+            | TxBuilderError::TransactionParseError(source)
+            | TxBuilderError::WriteError(source) => write!(f, " - {}", source),
+            #[cfg(feature = "http")]
+            TxBuilderError::HttpError(source) => write!(f, " - {}", source),
+            #[cfg(feature = "cbor")]
+            TxBuilderError::CborError(source) => write!(f, " - {}", source),
+            #[cfg(feature = "msgpack")]
+            TxBuilderError::MsgpackError(source) => write!(f, " - {}", source),
+            #[cfg(feature = "yaml")]
+            TxBuilderError::YamlError(source) => write!(f, " - {}", source),
+            #[cfg(feature = "toml")]
+            TxBuilderError::TomlError(source) => write!(f, " - {}", source),
+            #[cfg(feature = "csv")]
+            TxBuilderError::CsvError(source) => write!(f, " - {}", source),
+            #[cfg(feature = "csv")]
+            TxBuilderError::InvalidColumnValue {
+                line,
+                column,
+                value,
+            } => write!(f, " on line {}, {} '{}'", line, column, value),
+            #[cfg(feature = "csv")]
+            TxBuilderError::UnknownColumn(name) => write!(f, " '{}'", name),
+            TxBuilderError::HttpStatusError(status) => write!(f, " - {}", status),
+            #[cfg(feature = "events")]
+            TxBuilderError::EventsError(source) => write!(f, " - {}", source),
+            #[cfg(feature = "std")]
+            TxBuilderError::RandomError(source) => write!(f, " - {}", source),
+            TxBuilderError::KeyNotFound(name)
+            | TxBuilderError::UnknownNode(name)
+            | TxBuilderError::UnexpectedSigner(name)
+            | TxBuilderError::ReservedField(name)
+            | TxBuilderError::PrivateKeyUnavailable(name)
+            | TxBuilderError::InvalidSignature(name)
+            | TxBuilderError::MissingSignature(name)
+            | TxBuilderError::UnknownEntry(name)
+            | TxBuilderError::MergeConflict(name)
+            | TxBuilderError::InexactDecimal(name)
+            | TxBuilderError::MissingVariable(name)
+            | TxBuilderError::RenderKeyCollision(name)
+            | TxBuilderError::InvalidDerivationPath(name)
+            | TxBuilderError::InvalidPath(name) => write!(f, " '{}'", name),
+            #[cfg(feature = "schema")]
+            TxBuilderError::InvalidSchema(reason) => write!(f, " - {}", reason),
+            #[cfg(feature = "schema")]
+            TxBuilderError::SchemaViolation(violations) => {
+                write!(f, " - {}", violations.join("; "))
+            }
+            TxBuilderError::ValidationFailed(problems) => {
+                let problems: Vec<String> = problems.iter().map(|e| e.to_string()).collect();
 
-            TxBuilderError::KeyError(ref code) => {
-                let error = TxBuilderErrorHandler::get_key_error(code);
-                write!(f, " Error - {} : {}", code, error)
+                write!(f, " - {}", problems.join("; "))
+            }
+            #[cfg(feature = "std")]
+            TxBuilderError::LintFailed(errors) => write!(f, " - {}", errors.join("; ")),
+            #[cfg(feature = "events")]
+            TxBuilderError::LedgerRejected(errors) => write!(f, " - {}", errors.join("; ")),
+            #[cfg(feature = "events")]
+            TxBuilderError::ConfirmationTimeout(streams) => write!(f, " - {}", streams.join(", ")),
+            #[cfg(feature = "std")]
+            TxBuilderError::InvalidState { expected, actual } => {
+                write!(f, " - expected {}, builder is {}", expected, actual)
+            }
+            TxBuilderError::ThresholdNotMet { signed, threshold } => {
+                write!(f, " - {} of {} signed", signed, threshold)
+            }
+            TxBuilderError::SizeExceeded { size, max } => {
+                write!(f, " - {} bytes, limit {} bytes", size, max)
+            }
+            TxBuilderError::InvalidTerritoriality { value, reason }
+            | TxBuilderError::InvalidStreamId { value, reason } => {
+                write!(f, " '{}' - {}", value, reason)
             }
+            TxBuilderError::InvalidIdentifier {
+                field,
+                value,
+                reason,
+            } => write!(f, " {} '{}' - {}", field, value, reason),
+            TxBuilderError::JwkError(field) => write!(f, " '{}'", field),
+            TxBuilderError::KeyTypeMismatch { expected } => {
+                write!(f, " - expected {:?}", expected)
+            }
+            _ => Ok(()),
         }
     }
 }
 
-impl Error for TxBuilderError {}
+/// The description of an error followed by its details, without the code
+struct ErrorMessage<'a>(&'a TxBuilderError);
+
+impl fmt::Display for ErrorMessage<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(TxBuilderErrorHandler::get_error(self.0.code()))?;
+
+        self.0.fmt_details(f)
+    }
+}
+
+impl fmt::Display for TxBuilderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, " Error - {} : {}", self.code(), ErrorMessage(self))
+    }
+}
+
+/// Errors serialize as an object with the `category`, `code`, `message` and `field` of the
+/// error, `field` being null when the error isn't about a field.
+///
+/// ```
+/// # use active_tx::TxBuilderError;
+/// # use serde_json::json;
+/// let error = TxBuilderError::MissingField("contract");
+///
+/// assert_eq!(
+///     serde_json::to_value(&error).unwrap(),
+///     json!({
+///         "category": "txbuild",
+///         "code": 5006,
+///         "message": "Contract not set",
+///         "field": "contract"
+///     })
+/// );
+/// ```
+impl Serialize for TxBuilderError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("TxBuilderError", 4)?;
+        state.serialize_field("category", self.category())?;
+        state.serialize_field("code", &self.code())?;
+        state.serialize_field("message", &ErrorMessage(self).to_string())?;
+        state.serialize_field("field", &self.field())?;
+        state.end()
+    }
+}
+
+/// # Error context
+///
+/// Adds [`TxBuilderError::context_field`] to results, so the field can be named where the
+/// error is returned.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// # use active_tx::{ErrorContext, TransactionBuilder};
+/// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+///
+/// let error = tx_builder
+///     .territoriality_checked("not a node")
+///     .context_field("territoriality")
+///     .unwrap_err();
+///
+/// assert_eq!(error.field(), Some("territoriality"));
+/// # }
+/// ```
+///
+/// [`TxBuilderError::context_field`]: enum.TxBuilderError.html#method.context_field
+pub trait ErrorContext<T> {
+    /// # Context field
+    ///
+    /// Attach the name of a field to the error, if there is one.
+    fn context_field<F: Into<String>>(self, field: F) -> TxBuilderResult<T>;
+}
+
+impl<T> ErrorContext<T> for TxBuilderResult<T> {
+    fn context_field<F: Into<String>>(self, field: F) -> TxBuilderResult<T> {
+        self.map_err(|e| e.context_field(field))
+    }
+}
+
+/// Errors convert into `std::io::Error`, keeping the original error as the inner error, for
+/// use in IO pipelines and CLI tools. The kind is taken from the source of file errors, and
+/// is otherwise `NotFound`, `TimedOut`, `InvalidData` for unparsable input, `InvalidInput`
+/// for transaction and packet errors, or `Other`.
+///
+/// ```
+/// # use active_tx::TxBuilderError;
+/// use std::io;
+///
+/// let error: io::Error = TxBuilderError::MissingField("contract").into();
+///
+/// assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+/// ```
+#[cfg(feature = "std")]
+impl From<TxBuilderError> for io::Error {
+    fn from(error: TxBuilderError) -> Self {
+        io::Error::new(io_error_kind(&error), error)
+    }
+}
+
+#[cfg(feature = "std")]
+fn io_error_kind(error: &TxBuilderError) -> io::ErrorKind {
+    match error {
+        TxBuilderError::WithField { source, .. } => io_error_kind(source),
+        TxBuilderError::KeyFileError { source, .. }
+        | TxBuilderError::KeyStoreFileError { source, .. }
+        | TxBuilderError::ProfileFileError { source, .. } => source.kind(),
+        TxBuilderError::KeyNotFound(_) => io::ErrorKind::NotFound,
+        #[cfg(feature = "events")]
+        TxBuilderError::ConfirmationTimeout(_) => io::ErrorKind::TimedOut,
+        TxBuilderError::KeyStoreFormatError(_)
+        | TxBuilderError::ProfileFormatError(_)
+        | TxBuilderError::DescriptorError(_)
+        | TxBuilderError::ResponseError(_)
+        | TxBuilderError::TransactionParseError(_) => io::ErrorKind::InvalidData,
+        _ => match error.category() {
+            "build" | "json" | "packet" | "txbody" | "txbuild" => io::ErrorKind::InvalidInput,
+            _ => io::ErrorKind::Other,
+        },
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for TxBuilderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TxBuilderError::SectionError { source, .. } => Some(source.as_ref()),
+            TxBuilderError::WithField { source, .. } => Some(source.as_ref()),
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::KeyGenerationError { source, .. }
+            | TxBuilderError::SigningError { source, .. }
+            | TxBuilderError::PemError { source, .. } => Some(source),
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::FieldEncryptionError { source, .. }
+            | TxBuilderError::FieldDecryptionError(Some(source)) => Some(source),
+            TxBuilderError::KeyFileError { source, .. }
+            | TxBuilderError::KeyStoreFileError { source, .. }
+            | TxBuilderError::ProfileFileError { source, .. } => Some(source),
+            TxBuilderError::KeyStoreFormatError(source)
+            | TxBuilderError::ProfileFormatError(source)
+            | TxBuilderError::DescriptorError(source)
+            | TxBuilderError::ResponseError(source)
+            | TxBuilderError::TransactionParseError(source)
+            | TxBuilderError::WriteError(source) => Some(source),
+            TxBuilderError::RandomError(source) => Some(source),
+            #[cfg(feature = "http")]
+            TxBuilderError::HttpError(source) => Some(source),
+            #[cfg(feature = "events")]
+            TxBuilderError::EventsError(source) => Some(source),
+            #[cfg(feature = "cbor")]
+            TxBuilderError::CborError(source) => Some(source),
+            #[cfg(feature = "msgpack")]
+            TxBuilderError::MsgpackError(source) => Some(source),
+            #[cfg(feature = "yaml")]
+            TxBuilderError::YamlError(source) => Some(source),
+            #[cfg(feature = "toml")]
+            TxBuilderError::TomlError(source) => Some(source),
+            #[cfg(feature = "csv")]
+            TxBuilderError::CsvError(source) => Some(source),
+            #[cfg(feature = "openssl-keys")]
+            TxBuilderError::PemParseError(source)
+            | TxBuilderError::DerParseError(source)
+            | TxBuilderError::KeyExportError(source)
+            | TxBuilderError::KeyImportError(source)
+            | TxBuilderError::SeededKeyError(source)
+            | TxBuilderError::PublicKeyParseError(source)
+            | TxBuilderError::KeyStoreCryptoError(source) => Some(source),
+            _ => None,
+        }
+    }
+}
 
 struct TxBuilderErrorHandler;
 
 impl TxBuilderErrorHandler {
-    fn get_build_error(code: &u16) -> &str {
+    fn get_error(code: u16) -> &'static str {
+        match code {
+            1000..=1999 => TxBuilderErrorHandler::get_build_error(code),
+            2000..=2999 => TxBuilderErrorHandler::get_json_error(code),
+            3000..=3999 => TxBuilderErrorHandler::get_packet_error(code),
+            4000..=4999 => TxBuilderErrorHandler::get_txbody_error(code),
+            5000..=5999 => TxBuilderErrorHandler::get_txbuild_error(code),
+            6000..=6999 => TxBuilderErrorHandler::get_txgenerate_error(code),
+            7000..=7999 => TxBuilderErrorHandler::get_key_error(code),
+            8000..=8999 => TxBuilderErrorHandler::get_keyimport_error(code),
+            9000..=9999 => TxBuilderErrorHandler::get_keystore_error(code),
+            10000..=10999 => TxBuilderErrorHandler::get_submit_error(code),
+            _ => "Unknown Error",
+        }
+    }
+
+    fn get_category(code: u16) -> &'static str {
+        match code {
+            1000..=1999 => "build",
+            2000..=2999 => "json",
+            3000..=3999 => "packet",
+            4000..=4999 => "txbody",
+            5000..=5999 => "txbuild",
+            6000..=6999 => "txgenerate",
+            7000..=7999 => "key",
+            8000..=8999 => "keyimport",
+            9000..=9999 => "keystore",
+            10000..=10999 => "submit",
+            _ => "unknown",
+        }
+    }
+
+    fn get_build_error(code: u16) -> &'static str {
         match code {
             1000 => "Error building the transaction packet",
+            1001 => "Binary data can only be added to an object",
+            1002 => "Invalid JSON schema",
+            1003 => "Packet data does not match the schema",
+            1004 => "Decimal can't be written as a JSON number without rounding",
             _ => "Unknown Error",
         }
     }
 
-    fn get_json_error(code: &u16) -> &str {
+    fn get_json_error(code: u16) -> &'static str {
         match code {
             2000 => "Error converting array to JSON",
             2001 => "Error converting object to JSON",
+            2002 => "Error parsing JSON string",
+            2003 => "Error parsing YAML",
+            2004 => "Error parsing TOML",
+            2005 => "Error reading CSV",
+            2006 => "CSV column not found",
+            2007 => "CSV value does not match the column type",
             _ => "Unknown Error",
         }
     }
 
-    fn get_packet_error(code: &u16) -> &str {
+    fn get_packet_error(code: u16) -> &'static str {
         match code {
             3000 => "Error getting string from packet data",
             3001 => "Error getting JSON from packet data",
+            3002 => "Invalid packet data path",
+            3003 => "Only objects can be merged",
+            3004 => "Invalid stream ID",
+            3005 => "Error encrypting packet field",
+            3006 => "Error decrypting packet field",
+            3007 => "Only string and binary fields can be compressed",
+            3008 => "Error compressing packet field",
+            3009 => "Error decompressing packet field",
+            3010 => "Template variable not set",
+            3011 => "Decompressed packet field is larger than the limit",
+            3012 => "Template keys render to the same key",
             _ => "Unknown Error",
         }
     }
 
-    fn get_txbody_error(code: &u16) -> &str {
+    fn get_txbody_error(code: u16) -> &'static str {
         match code {
             4000 => "No transaction body",
+            4001 => "Error serializing transaction body",
             _ => "Unknown Error",
         }
     }
 
-    fn get_txbuild_error(code: &u16) -> &str {
+    fn get_txbuild_error(code: u16) -> &'static str {
         match code {
             5000 => "No transaction data",
             5001 => "Error fetching input from PacketData",
             5002 => "Error fetching output from PacketData",
             5003 => "Error fetching readonly from PacketData",
             5004 => "No packet data to sign",
-            5005 => "Packet data not built yet",
+            5005 => "Transaction builder is not in the expected state",
             5006 => "Contract not set",
             5007 => "Namespace not set",
             5008 => "Input not set",
+            5009 => "Invalid territoriality",
+            5010 => "Territoriality does not match a known node",
+            5011 => "Error serializing transaction",
+            5012 => "Error writing transaction",
+            5013 => "Error encoding transaction as CBOR",
+            5014 => "Error encoding transaction as MessagePack",
+            5015 => "Signature is not for a required stream",
+            5016 => "Signature threshold not met",
+            5017 => "Invalid signature threshold",
+            5018 => "Transaction exceeds the maximum size",
+            5019 => "Field is managed by the builder",
+            5020 => "Error parsing signed transaction",
+            5021 => "Error generating random bytes",
+            5022 => "Entry is not allowed",
+            5023 => "Invalid identifier",
+            5024 => "Transaction is not ready to build",
+            5025 => "Merge conflicts with a value already set",
+            5026 => "Transaction failed lint checks",
+            5027 => "Error reading profile file",
+            5028 => "Invalid profile",
+            5029 => "Invalid transaction descriptor",
+            5030 => "No signees to build variants for",
             _ => "Unknown Error",
         }
     }
 
-    fn get_txgenerate_error(code: &u16) -> &str {
+    fn get_txgenerate_error(code: u16) -> &'static str {
         match code {
             6000 => "Error generating RSA key",
             6001 => "Error generating Elliptic Curve key",
@@ -140,11 +994,56 @@ impl TxBuilderErrorHandler {
         }
     }
 
-    fn get_key_error(code: &u16) -> &str {
+    fn get_key_error(code: u16) -> &'static str {
         match code {
             7000 => "Error signing data with Elliptic Curve key",
             7001 => "Error signing data with RSA key",
             7002 => "Error getting keys PEM",
+            7003 => "External signer failed",
+            7004 => "Private key is held by an external signer",
+            7005 => "Invalid signature",
+            7006 => "Missing signature",
+            _ => "Unknown Error",
+        }
+    }
+
+    fn get_keyimport_error(code: u16) -> &'static str {
+        match code {
+            8000 => "Error reading key file",
+            8001 => "Error parsing private key PEM",
+            8002 => "Key type mismatch",
+            8003 => "Unsupported key type",
+            8004 => "Error parsing private key DER",
+            8005 => "Invalid JWK member",
+            8006 => "Error exporting key",
+            8007 => "Error parsing public key PEM",
+            8008 => "Invalid mnemonic phrase",
+            8009 => "Invalid derivation path",
+            8010 => "Error importing key",
+            8011 => "Error deriving key from seed",
+            _ => "Unknown Error",
+        }
+    }
+
+    fn get_keystore_error(code: u16) -> &'static str {
+        match code {
+            9000 => "Error accessing key store file",
+            9001 => "Invalid key store file",
+            9002 => "Key not found in key store",
+            9003 => "Error encrypting key",
+            9004 => "Error decrypting key, the passphrase may be incorrect",
+            _ => "Unknown Error",
+        }
+    }
+
+    fn get_submit_error(code: u16) -> &'static str {
+        match code {
+            10000 => "Error sending transaction to node",
+            10001 => "Error parsing ledger response",
+            10002 => "Node returned an error status",
+            10003 => "Error subscribing to ledger events",
+            10004 => "Transaction was rejected by the ledger",
+            10005 => "Timed out waiting for stream confirmation",
             _ => "Unknown Error",
         }
     }
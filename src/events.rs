@@ -0,0 +1,187 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Events
+//!
+//! Submit a transaction and wait for the ledger to confirm the streams it created and updated,
+//! using the node's activity event stream through the
+//! [active_sse](https://crates.io/crates/active_sse) crate.
+//!
+//! Requires the `events` feature, which enables the `http` feature.
+//!
+//! ## Example
+//! ```no_run
+//! # use std::time::Duration;
+//! # use active_tx::{PacketBuilder, TransactionBuilder, Key, packet_data, signees};
+//! # use activeledger::key::EllipticCurve;
+//! let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+//! let input = PacketBuilder::new(packet_data!({"keyname": {"input": "data"}}))
+//!     .build()
+//!     .unwrap();
+//!
+//! let streams = TransactionBuilder::new("namespace", "contract")
+//!     .input(input)
+//!     .unwrap()
+//!     .build_submit_and_wait(
+//!         signees!(key),
+//!         "http://localhost:5260",
+//!         "http://localhost:5261",
+//!         Duration::from_secs(30),
+//!     )
+//!     .unwrap();
+//!
+//! println!("{:?}", streams.new);
+//! ```
+
+// STD
+use std::collections::BTreeSet;
+use std::time::{Duration, Instant};
+
+// External imports
+use active_sse::{ActiveSSE, Config};
+use serde::Deserialize;
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::response::StreamsCreated;
+use crate::{Signees, TransactionBuilder};
+
+impl TransactionBuilder {
+    /// # Build, submit and wait
+    ///
+    /// Build the transaction, send it to the node at `node_url` and wait until activity events
+    /// for every stream it created or updated are received from the node's API at `events_url`
+    /// (usually port 5261). The created and updated streams are returned.
+    ///
+    /// The event stream is subscribed to before the transaction is sent, so confirmations are
+    /// not missed. An error is returned if the ledger does not commit the transaction, or if any
+    /// stream is not confirmed before the timeout.
+    ///
+    /// Requires the `events` feature.
+    pub fn build_submit_and_wait(
+        &mut self,
+        signees: Signees,
+        node_url: &str,
+        events_url: &str,
+        timeout: Duration,
+    ) -> TxBuilderResult<StreamsCreated> {
+        let deadline = Instant::now() + timeout;
+
+        let events = match ActiveSSE::new(Config::activity(events_url)).subscribe() {
+            Ok(events) => events,
+            Err(e) => return Err(TxBuilderError::EventsError(e)),
+        };
+
+        let response = self.build_and_submit(signees, node_url)?;
+
+        if !response.is_ok() {
+            return Err(TxBuilderError::LedgerRejected(response.errors().to_vec()));
+        }
+
+        let mut pending: BTreeSet<String> = response
+            .new_stream_ids()
+            .into_iter()
+            .chain(response.updated_stream_ids())
+            .map(String::from)
+            .collect();
+
+        while !pending.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            let event = match events.recv_timeout(remaining) {
+                Ok(event) => event,
+                Err(_) => {
+                    return Err(TxBuilderError::ConfirmationTimeout(
+                        pending.into_iter().collect(),
+                    ))
+                }
+            };
+
+            confirm(&mut pending, &event.data);
+        }
+
+        Ok(response.streams)
+    }
+}
+
+/// An activity event, sent when a stream document is written
+#[derive(Deserialize)]
+struct Activity {
+    event: ActivityDocument,
+}
+
+/// The stream document written
+#[derive(Deserialize)]
+struct ActivityDocument {
+    #[serde(alias = "_id")]
+    id: String,
+}
+
+/// Remove the stream written by an activity event from the pending set.
+///
+/// Stream metadata and volatile documents are stored as `<streamid>:stream` and
+/// `<streamid>:volatile`, and also confirm their stream. Other events are ignored.
+fn confirm(pending: &mut BTreeSet<String>, data: &str) {
+    let activity: Activity = match serde_json::from_str(data) {
+        Ok(activity) => activity,
+        Err(_) => return,
+    };
+
+    let id = activity.event.id;
+    let streamid = id
+        .strip_suffix(":stream")
+        .or_else(|| id.strip_suffix(":volatile"))
+        .unwrap_or(&id);
+
+    pending.remove(streamid);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_confirm() {
+        let mut pending: BTreeSet<String> = vec!["stream1".to_string(), "stream2".to_string()]
+            .into_iter()
+            .collect();
+
+        confirm(&mut pending, "not json");
+        confirm(&mut pending, r#"{"event": {"id": "other"}}"#);
+        assert_eq!(pending.len(), 2);
+
+        // Stream IDs elsewhere in the event don't confirm the stream
+        confirm(
+            &mut pending,
+            r#"{"event": {"id": "other", "data": "stream1"}}"#,
+        );
+        confirm(&mut pending, r#"{"stream1": {"id": "stream1"}}"#);
+        assert_eq!(pending.len(), 2);
+
+        confirm(&mut pending, r#"{"event": {"id": "stream1", "data": [1]}}"#);
+        assert_eq!(pending.iter().collect::<Vec<_>>(), vec!["stream2"]);
+
+        confirm(&mut pending, r#"{"event": {"_id": "stream2:stream"}}"#);
+        assert!(pending.is_empty());
+    }
+}
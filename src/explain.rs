@@ -0,0 +1,263 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Transaction explain
+//!
+//! Summarise a transaction for people rather than the ledger, such as when a transaction is
+//! pasted into a support ticket. The summary lists what the transaction calls, which streams it
+//! touches, who signed it and which envelope flags are set.
+//!
+//! ## Example
+//! ```
+//! # use active_tx::explain;
+//! # use serde_json::json;
+//! let tx = json!({
+//!     "$tx": {
+//!         "$namespace": "ns",
+//!         "$contract": "contract",
+//!         "$entry": "transfer",
+//!         "$i": {"alice": {"amount": 10}},
+//!         "$o": {"bob": {}},
+//!         "$r": {"rates": "ratesstream"}
+//!     },
+//!     "$sigs": {"alice": "sig"},
+//!     "$territoriality": "node1"
+//! });
+//!
+//! let summary = explain(&tx);
+//!
+//! assert_eq!(summary.entry.as_deref(), Some("transfer"));
+//! assert_eq!(summary.inputs, vec!["alice"]);
+//! assert_eq!(summary.signers, vec!["alice"]);
+//!
+//! println!("{}", summary);
+//! ```
+
+// STD
+use std::collections::BTreeMap;
+use std::fmt;
+
+// External imports
+use serde::Serialize;
+use serde_json::Value;
+
+/// # Transaction summary
+///
+/// A structured summary of a transaction, see [`explain`](fn.explain.html).
+/// Fields missing from the transaction are `None` or empty. Encrypted transactions only show
+/// the envelope, as the packet can't be read.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct TxSummary {
+    pub namespace: Option<String>,
+    pub contract: Option<String>,
+    pub entry: Option<String>,
+    /// Stream IDs in `$i`
+    pub inputs: Vec<String>,
+    /// Stream IDs in `$o`
+    pub outputs: Vec<String>,
+    /// Stream IDs in `$r`, by label
+    pub readonly: BTreeMap<String, String>,
+    /// Stream IDs with a signature in `$sigs`, as `streamid/keyref` for streams signed by
+    /// several keys
+    pub signers: Vec<String>,
+    pub selfsign: bool,
+    pub broadcast: bool,
+    pub encrypted: bool,
+    pub territoriality: Option<String>,
+}
+
+/// # Explain
+///
+/// Summarise a transaction, given as the full envelope or as just the `$tx` packet.
+pub fn explain(tx: &Value) -> TxSummary {
+    let packet = tx.get("$tx").unwrap_or(tx);
+
+    let mut summary = TxSummary {
+        namespace: string(packet.get("$namespace")),
+        contract: string(packet.get("$contract")),
+        entry: string(packet.get("$entry")),
+        inputs: keys(packet.get("$i")),
+        outputs: keys(packet.get("$o")),
+        selfsign: flag(tx.get("$selfsign")),
+        broadcast: flag(tx.get("$broadcast")),
+        encrypted: flag(tx.get("$encrypt")),
+        territoriality: string(tx.get("$territoriality")),
+        ..TxSummary::default()
+    };
+
+    if let Some(Value::Object(readonly)) = packet.get("$r") {
+        for (label, streamid) in readonly {
+            let streamid = match streamid {
+                Value::String(streamid) => streamid.clone(),
+                other => other.to_string(),
+            };
+
+            summary.readonly.insert(label.clone(), streamid);
+        }
+    }
+
+    if let Some(Value::Object(sigs)) = tx.get("$sigs") {
+        for (streamid, sig) in sigs {
+            match sig {
+                Value::Object(signatures) => {
+                    for key_ref in signatures.keys() {
+                        summary.signers.push(format!("{}/{}", streamid, key_ref));
+                    }
+                }
+                _ => summary.signers.push(streamid.clone()),
+            }
+        }
+    }
+
+    summary
+}
+
+impl TxSummary {
+    /// # Flags
+    ///
+    /// Get the envelope flags that are set, as they are shown in the summary.
+    pub fn flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+
+        if self.selfsign {
+            flags.push(String::from("selfsign"));
+        }
+
+        if self.broadcast {
+            flags.push(String::from("broadcast"));
+        }
+
+        if self.encrypted {
+            flags.push(String::from("encrypted"));
+        }
+
+        if let Some(territoriality) = &self.territoriality {
+            flags.push(format!("territoriality={}", territoriality));
+        }
+
+        flags
+    }
+}
+
+impl fmt::Display for TxSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let readonly: Vec<String> = self
+            .readonly
+            .iter()
+            .map(|(label, streamid)| format!("{}={}", label, streamid))
+            .collect();
+
+        writeln!(f, "Namespace: {}", self.namespace.as_deref().unwrap_or("-"))?;
+        writeln!(f, "Contract:  {}", self.contract.as_deref().unwrap_or("-"))?;
+        writeln!(f, "Entry:     {}", self.entry.as_deref().unwrap_or("-"))?;
+        writeln!(f, "Inputs:    {}", list(&self.inputs))?;
+        writeln!(f, "Outputs:   {}", list(&self.outputs))?;
+        writeln!(f, "Readonly:  {}", list(&readonly))?;
+        writeln!(f, "Signers:   {}", list(&self.signers))?;
+        write!(f, "Flags:     {}", list(&self.flags()))
+    }
+}
+
+/// Get a string value, showing any other value as JSON
+fn string(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(value) => Some(value.clone()),
+        Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+/// Get the keys of an object value
+fn keys(value: Option<&Value>) -> Vec<String> {
+    match value {
+        Some(Value::Object(map)) => map.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a flag is set, flags are true when present unless set to false or null
+pub(crate) fn flag(value: Option<&Value>) -> bool {
+    !matches!(value, None | Some(Value::Null) | Some(Value::Bool(false)))
+}
+
+/// Join the items for display, showing a dash when there are none
+fn list(items: &[String]) -> String {
+    if items.is_empty() {
+        String::from("-")
+    } else {
+        items.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn explain_summary() {
+        let tx = json!({
+            "$tx": {
+                "$namespace": "ns",
+                "$contract": "contract",
+                "$i": {"alice": {}, "multi": {}},
+                "$o": {"bob": {}},
+                "$r": {"rates": "ratesstream"}
+            },
+            "$sigs": {"alice": "sig", "multi": {"admin": "sig", "owner": "sig"}},
+            "$selfsign": false,
+            "$broadcast": true,
+            "$territoriality": "node1"
+        });
+
+        let summary = explain(&tx);
+
+        assert_eq!(summary.namespace.as_deref(), Some("ns"));
+        assert_eq!(summary.entry, None);
+        assert_eq!(summary.inputs, vec!["alice", "multi"]);
+        assert_eq!(summary.outputs, vec!["bob"]);
+        assert_eq!(summary.readonly["rates"], "ratesstream");
+        assert_eq!(summary.signers, vec!["alice", "multi/admin", "multi/owner"]);
+        assert_eq!(summary.flags(), vec!["broadcast", "territoriality=node1"]);
+
+        assert_eq!(
+            summary.to_string(),
+            "Namespace: ns\n\
+             Contract:  contract\n\
+             Entry:     -\n\
+             Inputs:    alice, multi\n\
+             Outputs:   bob\n\
+             Readonly:  rates=ratesstream\n\
+             Signers:   alice, multi/admin, multi/owner\n\
+             Flags:     broadcast, territoriality=node1"
+        );
+
+        // The packet alone can be explained too
+        assert_eq!(explain(&tx["$tx"]).contract.as_deref(), Some("contract"));
+
+        let encrypted = explain(&json!({"$tx": {"key": "k"}, "$encrypt": true}));
+        assert!(encrypted.encrypted);
+        assert_eq!(encrypted.namespace, None);
+        assert!(encrypted.inputs.is_empty());
+    }
+}
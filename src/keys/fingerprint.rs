@@ -0,0 +1,131 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Fingerprints
+//!
+//! Short identifiers for keys, so tooling can record which key signed a transaction without
+//! passing PEMs around. A fingerprint is the SHA-256 of the public key DER (SubjectPublicKeyInfo),
+//! so the same key always has the same fingerprint whether it is loaded from a private key or
+//! from the public PEM given to the ledger.
+
+// External imports
+use openssl::sha::sha256;
+
+// Internal imports
+use super::{Key, PublicKey};
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Bitcoin base58 alphabet
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+impl Key {
+    /// # Fingerprint
+    ///
+    /// Get the lower case hex SHA-256 of the public key DER.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    ///
+    /// ```
+    /// # use active_tx::Key;
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let fingerprint = key.fingerprint().unwrap();
+    ///
+    /// assert_eq!(fingerprint.len(), 64);
+    /// assert_eq!(fingerprint, key.public_key().unwrap().fingerprint().unwrap());
+    /// ```
+    pub fn fingerprint(&self) -> TxBuilderResult<String> {
+        self.public_key()?.fingerprint()
+    }
+
+    /// # Fingerprint base58
+    ///
+    /// Get the SHA-256 of the public key DER, base58 encoded with the Bitcoin alphabet.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    pub fn fingerprint_base58(&self) -> TxBuilderResult<String> {
+        self.public_key()?.fingerprint_base58()
+    }
+}
+
+impl PublicKey {
+    /// # Fingerprint
+    ///
+    /// Get the lower case hex SHA-256 of the public key DER.
+    pub fn fingerprint(&self) -> TxBuilderResult<String> {
+        let digest = self.fingerprint_bytes()?;
+
+        Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    /// # Fingerprint base58
+    ///
+    /// Get the SHA-256 of the public key DER, base58 encoded with the Bitcoin alphabet.
+    pub fn fingerprint_base58(&self) -> TxBuilderResult<String> {
+        Ok(base58(&self.fingerprint_bytes()?))
+    }
+}
+
+// Private functions
+impl PublicKey {
+    fn fingerprint_bytes(&self) -> TxBuilderResult<[u8; 32]> {
+        match self.pkey.public_key_to_der() {
+            Ok(der) => Ok(sha256(&der)),
+            Err(e) => Err(TxBuilderError::KeyExportError(e)),
+        }
+    }
+}
+
+/// Base58 encode the bytes, keeping leading zero bytes as '1'
+pub(crate) fn base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+
+    // Base58 digits, least significant first
+    let mut digits: Vec<u8> = Vec::with_capacity(bytes.len() * 138 / 100 + 1);
+
+    for byte in bytes {
+        let mut carry = u32::from(*byte);
+
+        for digit in digits.iter_mut() {
+            carry += u32::from(*digit) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut encoded = "1".repeat(zeros);
+    encoded.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|digit| BASE58_ALPHABET[*digit as usize] as char),
+    );
+
+    encoded
+}
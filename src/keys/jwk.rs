@@ -0,0 +1,196 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! JSON Web Key import and export
+
+// External imports
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+use openssl::ec::{EcGroup, EcKey};
+use openssl::error::ErrorStack;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::RsaPrivateKeyBuilder;
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+// Internal imports
+use super::{Key, KeyType};
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Size in bytes of a secp256k1 coordinate or private scalar
+const EC_COORDINATE_SIZE: i32 = 32;
+
+impl Key {
+    /// # To JWK
+    ///
+    /// Export the key, including its private parts, as a JSON Web Key.
+    /// The key name is stored as the `kid`.
+    ///
+    /// ```
+    /// # use active_tx::Key;
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let jwk = key.to_jwk().unwrap();
+    /// ```
+    pub fn to_jwk(&self) -> TxBuilderResult<Value> {
+        let pkey = self.pkey()?;
+
+        let jwk = match self.key_type() {
+            KeyType::EC => pkey.ec_key().and_then(|key| Key::ec_to_jwk(&key)),
+            KeyType::RSA => pkey.rsa().map(|key| {
+                json!({
+                    "kty": "RSA",
+                    "n": encode(key.n()),
+                    "e": encode(key.e()),
+                    "d": encode(key.d()),
+                    "p": key.p().map(encode),
+                    "q": key.q().map(encode),
+                    "dp": key.dmp1().map(encode),
+                    "dq": key.dmq1().map(encode),
+                    "qi": key.iqmp().map(encode),
+                })
+            }),
+        };
+
+        let mut jwk = match jwk {
+            Ok(jwk) => jwk,
+            Err(e) => return Err(TxBuilderError::KeyExportError(e)),
+        };
+
+        jwk["kid"] = json!(self.name());
+
+        Ok(jwk)
+    }
+
+    /// # From JWK
+    ///
+    /// Import a private key from a JSON Web Key.
+    /// Supports RSA keys and EC keys on the secp256k1 curve.
+    ///
+    /// ```
+    /// # use active_tx::Key;
+    /// # use activeledger::key::EllipticCurve;
+    /// # let jwk = Key::Ec(EllipticCurve::new("keyname").unwrap()).to_jwk().unwrap();
+    /// let key = Key::from_jwk("keyname", &jwk).unwrap();
+    /// ```
+    pub fn from_jwk(name: &str, jwk: &Value) -> TxBuilderResult<Key> {
+        let pkey = match jwk["kty"].as_str() {
+            Some("EC") => {
+                if jwk["crv"].as_str() != Some("secp256k1") {
+                    return Err(TxBuilderError::UnsupportedKeyType);
+                }
+
+                let x = member(jwk, "x")?;
+                let y = member(jwk, "y")?;
+                let d = member(jwk, "d")?;
+
+                Key::ec_from_components(&x, &y, &d)
+            }
+            Some("RSA") => {
+                let n = member(jwk, "n")?;
+                let e = member(jwk, "e")?;
+                let d = member(jwk, "d")?;
+                let p = member(jwk, "p")?;
+                let q = member(jwk, "q")?;
+                let dp = member(jwk, "dp")?;
+                let dq = member(jwk, "dq")?;
+                let qi = member(jwk, "qi")?;
+
+                RsaPrivateKeyBuilder::new(n, e, d)
+                    .and_then(|builder| builder.set_factors(p, q))
+                    .and_then(|builder| builder.set_crt_params(dp, dq, qi))
+                    .and_then(|builder| PKey::from_rsa(builder.build()))
+            }
+            Some(_) => return Err(TxBuilderError::UnsupportedKeyType),
+            None => return Err(TxBuilderError::JwkError("kty")),
+        };
+
+        match pkey {
+            Ok(pkey) => Key::from_pkey(name, &pkey),
+            Err(e) => Err(TxBuilderError::KeyImportError(e)),
+        }
+    }
+}
+
+// Private functions
+impl Key {
+    /// Convert an EC key to a JWK
+    fn ec_to_jwk(key: &EcKey<Private>) -> Result<Value, ErrorStack> {
+        let mut ctx = BigNumContext::new()?;
+        let mut x = BigNum::new()?;
+        let mut y = BigNum::new()?;
+
+        key.public_key()
+            .affine_coordinates(key.group(), &mut x, &mut y, &mut ctx)?;
+
+        Ok(json!({
+            "kty": "EC",
+            "crv": "secp256k1",
+            "x": encode_padded(&x)?,
+            "y": encode_padded(&y)?,
+            "d": encode_padded(key.private_key())?,
+        }))
+    }
+
+    /// Rebuild an EC private key from its JWK components
+    fn ec_from_components(x: &BigNum, y: &BigNum, d: &BigNum) -> Result<PKey<Private>, ErrorStack> {
+        let group = EcGroup::from_curve_name(Nid::SECP256K1)?;
+
+        let public = EcKey::from_public_key_affine_coordinates(&group, x, y)?;
+        let private = EcKey::from_private_components(&group, d, public.public_key())?;
+        private.check_key()?;
+
+        PKey::from_ec_key(private)
+    }
+}
+
+/// Base64url encode a big number
+fn encode(num: &BigNumRef) -> String {
+    base64::encode_config(&*Zeroizing::new(num.to_vec()), base64::URL_SAFE_NO_PAD)
+}
+
+/// Base64url encode a big number padded to the EC coordinate size
+fn encode_padded(num: &BigNumRef) -> Result<String, ErrorStack> {
+    let bytes = Zeroizing::new(num.to_vec_padded(EC_COORDINATE_SIZE)?);
+
+    Ok(base64::encode_config(&*bytes, base64::URL_SAFE_NO_PAD))
+}
+
+/// Get a base64url encoded big number member of a JWK
+fn member(jwk: &Value, field: &'static str) -> TxBuilderResult<BigNum> {
+    let encoded = match jwk[field].as_str() {
+        Some(encoded) => encoded,
+        None => return Err(TxBuilderError::JwkError(field)),
+    };
+
+    let bytes = match base64::decode_config(encoded, base64::URL_SAFE_NO_PAD) {
+        Ok(bytes) => Zeroizing::new(bytes),
+        Err(_) => return Err(TxBuilderError::JwkError(field)),
+    };
+
+    match BigNum::from_slice(&bytes) {
+        Ok(num) => Ok(num),
+        Err(_) => Err(TxBuilderError::JwkError(field)),
+    }
+}
@@ -0,0 +1,356 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Key Store
+//!
+//! Stores keys encrypted in a JSON file so generated identities can be persisted and
+//! reloaded by name.
+//!
+//! Each key is exported as DER and encrypted with AES-256-GCM. The encryption key is derived
+//! from a passphrase using scrypt with a random salt per key.
+//!
+//! ## Example
+//! ```
+//! # use active_tx::{KeyStore, KeyType, TransactionBuilder};
+//! # let path = std::env::temp_dir().join("active_tx_keystore_doc.json");
+//! let (key, tx) = TransactionBuilder::generate_onboard_tx(KeyType::EC, "keyname").unwrap();
+//!
+//! let mut store = KeyStore::open(&path).unwrap();
+//! store.save(&key, "passphrase").unwrap();
+//!
+//! let key = store.load("keyname", "passphrase").unwrap();
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+//!
+//! ## File Structure
+//! ```json
+//! {
+//!     "version": 1,
+//!     "keys": {
+//!         "[key name]": {
+//!             "type": "ec",
+//!             "kdf": {"n": 32768, "r": 8, "p": 1, "salt": "[base64]"},
+//!             "nonce": "[base64]",
+//!             "tag": "[base64]",
+//!             "ciphertext": "[base64]"
+//!         }
+//!     }
+//! }
+//! ```
+
+// STD
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+// External imports
+use openssl::error::ErrorStack;
+use openssl::pkcs5::scrypt;
+use openssl::rand::rand_bytes;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+// Internal imports
+use super::{Key, KeyType};
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+const KEYSTORE_VERSION: u8 = 1;
+
+// scrypt parameters used for newly stored keys
+const SCRYPT_N: u64 = 1 << 15;
+const SCRYPT_R: u64 = 8;
+const SCRYPT_P: u64 = 1;
+const SCRYPT_MAXMEM: u64 = 64 * 1024 * 1024;
+
+const SALT_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+const AES_KEY_SIZE: usize = 32;
+
+/// Encrypted, file backed storage for keys
+pub struct KeyStore {
+    path: PathBuf,
+    file: KeyStoreFile,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct KeyStoreFile {
+    version: u8,
+    keys: BTreeMap<String, EncryptedKey>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncryptedKey {
+    #[serde(rename = "type")]
+    key_type: String,
+    kdf: ScryptParams,
+    nonce: String,
+    tag: String,
+    ciphertext: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ScryptParams {
+    n: u64,
+    r: u64,
+    p: u64,
+    salt: String,
+}
+
+impl KeyStore {
+    /// # Open
+    ///
+    /// Open the key store at the given path.
+    /// If the file does not exist an empty store is created, the file is written on the first save.
+    pub fn open<P: AsRef<Path>>(path: P) -> TxBuilderResult<KeyStore> {
+        let path = path.as_ref().to_path_buf();
+
+        let file = match fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str(&contents) {
+                Ok(file) => file,
+                Err(e) => return Err(TxBuilderError::KeyStoreFormatError(e)),
+            },
+            Err(ref e) if e.kind() == ErrorKind::NotFound => KeyStoreFile {
+                version: KEYSTORE_VERSION,
+                keys: BTreeMap::new(),
+            },
+            Err(source) => return Err(TxBuilderError::KeyStoreFileError { path, source }),
+        };
+
+        Ok(KeyStore { path, file })
+    }
+
+    /// # Save
+    ///
+    /// Encrypt the key with the passphrase and write it to the store under the keys name.
+    /// An existing key with the same name is replaced.
+    pub fn save(&mut self, key: &Key, passphrase: &str) -> TxBuilderResult<()> {
+        let der = Zeroizing::new(key.to_der()?);
+
+        let key_type = match key.key_type() {
+            KeyType::RSA => "rsa",
+            KeyType::EC => "ec",
+        };
+
+        let encrypted = match KeyStore::encrypt(&der, key_type, passphrase) {
+            Ok(encrypted) => encrypted,
+            Err(e) => return Err(TxBuilderError::KeyStoreCryptoError(e)),
+        };
+
+        self.file.keys.insert(key.name().to_string(), encrypted);
+
+        self.write()
+    }
+
+    /// # Load
+    ///
+    /// Decrypt and return the key with the given name.
+    pub fn load(&self, name: &str, passphrase: &str) -> TxBuilderResult<Key> {
+        let encrypted = match self.file.keys.get(name) {
+            Some(encrypted) => encrypted,
+            None => return Err(TxBuilderError::KeyNotFound(name.to_string())),
+        };
+
+        let der = Zeroizing::new(KeyStore::decrypt(encrypted, passphrase)?);
+
+        Key::from_der(name, &der)
+    }
+
+    /// # Remove
+    ///
+    /// Remove the key with the given name from the store, returning whether it was present.
+    pub fn remove(&mut self, name: &str) -> TxBuilderResult<bool> {
+        if self.file.keys.remove(name).is_none() {
+            return Ok(false);
+        }
+
+        self.write()?;
+
+        Ok(true)
+    }
+
+    /// # Names
+    ///
+    /// Get the names of the keys held in the store.
+    pub fn names(&self) -> Vec<&str> {
+        self.file.keys.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// # Contains
+    ///
+    /// Check whether a key with the given name is held in the store.
+    pub fn contains(&self, name: &str) -> bool {
+        self.file.keys.contains_key(name)
+    }
+}
+
+impl fmt::Debug for KeyStore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyStore")
+            .field("path", &self.path)
+            .field("keys", &self.names())
+            .finish()
+    }
+}
+
+// Private functions
+impl KeyStore {
+    /// Write the store to its file
+    fn write(&self) -> TxBuilderResult<()> {
+        let contents = match serde_json::to_string_pretty(&self.file) {
+            Ok(contents) => contents,
+            Err(e) => return Err(TxBuilderError::KeyStoreFormatError(e)),
+        };
+
+        match fs::write(&self.path, contents) {
+            Ok(_) => Ok(()),
+            Err(source) => Err(TxBuilderError::KeyStoreFileError {
+                path: self.path.clone(),
+                source,
+            }),
+        }
+    }
+
+    /// Encrypt data with a key derived from the passphrase
+    fn encrypt(data: &[u8], key_type: &str, passphrase: &str) -> Result<EncryptedKey, ErrorStack> {
+        let mut salt = [0; SALT_SIZE];
+        rand_bytes(&mut salt)?;
+
+        let mut nonce = [0; NONCE_SIZE];
+        rand_bytes(&mut nonce)?;
+
+        let params = ScryptParams {
+            n: SCRYPT_N,
+            r: SCRYPT_R,
+            p: SCRYPT_P,
+            salt: base64::encode(&salt),
+        };
+
+        let aes_key = KeyStore::derive_key(passphrase, &salt, &params)?;
+
+        let mut tag = [0; TAG_SIZE];
+        let ciphertext = encrypt_aead(
+            Cipher::aes_256_gcm(),
+            &*aes_key,
+            Some(&nonce),
+            &[],
+            data,
+            &mut tag,
+        )?;
+
+        Ok(EncryptedKey {
+            key_type: key_type.to_string(),
+            kdf: params,
+            nonce: base64::encode(&nonce),
+            tag: base64::encode(&tag),
+            ciphertext: base64::encode(&ciphertext),
+        })
+    }
+
+    /// Decrypt a stored key
+    fn decrypt(encrypted: &EncryptedKey, passphrase: &str) -> TxBuilderResult<Vec<u8>> {
+        let decode = |data: &str| match base64::decode(data) {
+            Ok(data) => Ok(data),
+            Err(_) => Err(TxBuilderError::DecryptionError),
+        };
+
+        let salt = decode(&encrypted.kdf.salt)?;
+        let nonce = decode(&encrypted.nonce)?;
+        let tag = decode(&encrypted.tag)?;
+        let ciphertext = decode(&encrypted.ciphertext)?;
+
+        let aes_key = match KeyStore::derive_key(passphrase, &salt, &encrypted.kdf) {
+            Ok(aes_key) => aes_key,
+            Err(e) => return Err(TxBuilderError::KeyStoreCryptoError(e)),
+        };
+
+        // A wrong passphrase results in an authentication tag mismatch
+        match decrypt_aead(
+            Cipher::aes_256_gcm(),
+            &*aes_key,
+            Some(&nonce),
+            &[],
+            &ciphertext,
+            &tag,
+        ) {
+            Ok(data) => Ok(data),
+            Err(_) => Err(TxBuilderError::DecryptionError),
+        }
+    }
+
+    /// Derive the AES key from the passphrase using scrypt
+    fn derive_key(
+        passphrase: &str,
+        salt: &[u8],
+        params: &ScryptParams,
+    ) -> Result<Zeroizing<[u8; AES_KEY_SIZE]>, ErrorStack> {
+        let mut aes_key = Zeroizing::new([0; AES_KEY_SIZE]);
+
+        scrypt(
+            passphrase.as_bytes(),
+            salt,
+            params.n,
+            params.r,
+            params.p,
+            SCRYPT_MAXMEM,
+            &mut *aes_key,
+        )?;
+
+        Ok(aes_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use activeledger::key::EllipticCurve;
+
+    #[test]
+    fn keystore_save_load() {
+        let path = std::env::temp_dir().join("active_tx_keystore_test.json");
+        let _ = fs::remove_file(&path);
+
+        let key = Key::Ec(EllipticCurve::new("stored").unwrap());
+
+        let mut store = KeyStore::open(&path).unwrap();
+        store.save(&key, "passphrase").unwrap();
+
+        // Reopen from disk
+        let store = KeyStore::open(&path).unwrap();
+        assert_eq!(store.names(), vec!["stored"]);
+
+        let loaded = store.load("stored", "passphrase").unwrap();
+        assert_eq!(loaded.to_der().unwrap(), key.to_der().unwrap());
+
+        let err = store.load("stored", "wrong").err().unwrap();
+        assert_eq!(err.code(), 9004);
+
+        let err = store.load("missing", "passphrase").err().unwrap();
+        assert_eq!(err.code(), 9002);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
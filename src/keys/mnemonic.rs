@@ -0,0 +1,423 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Mnemonic keys
+//!
+//! Derives EC keys from a BIP39 mnemonic phrase using BIP32 derivation paths, so wallet style
+//! applications can recreate their signing key from a seed phrase.
+//!
+//! A [`KeyFactory`](struct.KeyFactory.html) derives many child keys from one phrase, so a fleet
+//! of devices can be provisioned from a single secret rather than a key file per device.
+//!
+//! Requires the `mnemonic` feature.
+
+// STD
+use std::fmt;
+use std::ops::Range;
+
+// External imports
+use bip39::Mnemonic;
+use openssl::bn::{BigNum, BigNumContext, BigNumRef};
+use openssl::ec::{EcGroup, EcKey, EcPoint, PointConversionForm};
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::sign::Signer;
+use zeroize::Zeroizing;
+
+// Internal imports
+use super::{Key, KeyType};
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::{BatchMode, TransactionBuilder};
+
+/// Bytes of entropy used for generated mnemonics, giving 24 words
+const ENTROPY_SIZE: usize = 32;
+
+/// Child indexes from this value up are hardened
+const HARDENED: u32 = 0x8000_0000;
+
+/// Size in bytes of a secp256k1 private scalar
+const SCALAR_SIZE: i32 = 32;
+
+/// A private scalar and its chain code
+type ExtendedKey = (BigNum, Zeroizing<Vec<u8>>);
+
+impl Key {
+    /// # From mnemonic
+    ///
+    /// Derive a key from a BIP39 mnemonic phrase and a BIP32 derivation path such as
+    /// `m/44'/0'/0'/0/0`. Hardened indexes are marked with `'` or `h`.
+    /// The same phrase and path always give the same key.
+    ///
+    /// Only EC keys can be derived, a key type mismatch error is returned for RSA.
+    ///
+    /// ```
+    /// # use active_tx::{Key, KeyType};
+    /// let phrase = Key::generate_mnemonic().unwrap();
+    ///
+    /// let key = Key::from_mnemonic("keyname", &phrase, "m/44'/0'/0'/0/0", KeyType::EC).unwrap();
+    /// let same = Key::from_mnemonic("keyname", &phrase, "m/44'/0'/0'/0/0", KeyType::EC).unwrap();
+    ///
+    /// assert_eq!(key.to_der().unwrap(), same.to_der().unwrap());
+    /// ```
+    ///
+    /// Requires the `mnemonic` feature.
+    pub fn from_mnemonic(
+        name: &str,
+        phrase: &str,
+        derivation_path: &str,
+        key_type: KeyType,
+    ) -> TxBuilderResult<Key> {
+        if key_type != KeyType::EC {
+            return Err(TxBuilderError::KeyTypeMismatch {
+                expected: KeyType::EC,
+            });
+        }
+
+        let seed = mnemonic_seed(phrase)?;
+        let path = parse_path(derivation_path)?;
+
+        let pkey = match derive_pkey(&*seed, &path) {
+            Ok(Some(pkey)) => pkey,
+            // The path leads to an invalid key, BIP32 says to use the next index instead
+            Ok(None) => {
+                return Err(TxBuilderError::InvalidDerivationPath(
+                    derivation_path.to_string(),
+                ))
+            }
+            Err(e) => return Err(TxBuilderError::SeededKeyError(e)),
+        };
+
+        Key::from_pkey(name, &pkey)
+    }
+
+    /// # Generate mnemonic
+    ///
+    /// Generate a new random 24 word BIP39 mnemonic phrase, for use with
+    /// [`from_mnemonic`](#method.from_mnemonic).
+    ///
+    /// Requires the `mnemonic` feature.
+    pub fn generate_mnemonic() -> TxBuilderResult<String> {
+        let mut entropy = Zeroizing::new([0u8; ENTROPY_SIZE]);
+
+        if let Err(e) = getrandom::getrandom(&mut *entropy) {
+            return Err(TxBuilderError::RandomError(e));
+        }
+
+        match Mnemonic::from_entropy(&*entropy) {
+            Ok(mnemonic) => Ok(mnemonic.to_string()),
+            Err(e) => Err(TxBuilderError::InvalidMnemonic(e.to_string())),
+        }
+    }
+}
+
+/// # Key factory
+///
+/// Derives identity keys from one mnemonic phrase. Each child index gives a distinct key at
+/// the hardened path `<base path>/<index>'`, named with the prefix followed by the index,
+/// e.g. "device-0", "device-1". The same phrase, path and index always give the same key.
+///
+/// ```
+/// # use active_tx::{BatchMode, Key, KeyFactory};
+/// let phrase = Key::generate_mnemonic().unwrap();
+///
+/// let mut factory = KeyFactory::from_mnemonic(&phrase, "m/44'/0'/0'").unwrap();
+/// factory.prefix("sensor-");
+///
+/// let key = factory.derive_child(7).unwrap();
+/// assert_eq!(key.name(), "sensor-7");
+///
+/// let (keys, txs) = factory.onboard_range(0..10, BatchMode::Combined).unwrap();
+/// ```
+///
+/// Requires the `mnemonic` feature.
+#[derive(Clone)]
+pub struct KeyFactory {
+    seed: Zeroizing<[u8; 64]>,
+    base_path: Vec<u32>,
+    prefix: String,
+}
+
+impl fmt::Debug for KeyFactory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyFactory")
+            .field("base_path", &self.base_path)
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl KeyFactory {
+    /// # From mnemonic
+    ///
+    /// Create a factory deriving keys below the base path from the mnemonic phrase.
+    /// Keys are named "device-" followed by their index unless another prefix is set.
+    pub fn from_mnemonic(phrase: &str, base_path: &str) -> TxBuilderResult<KeyFactory> {
+        Ok(KeyFactory {
+            seed: mnemonic_seed(phrase)?,
+            base_path: parse_path(base_path)?,
+            prefix: String::from("device-"),
+        })
+    }
+
+    /// # Prefix
+    ///
+    /// Set the prefix of derived key names.
+    pub fn prefix(&mut self, prefix: &str) -> &mut Self {
+        self.prefix = prefix.to_string();
+
+        self
+    }
+
+    /// # Derive child
+    ///
+    /// Derive the key with the given index. Indexes must be below 2^31.
+    pub fn derive_child(&self, index: u32) -> TxBuilderResult<Key> {
+        if index >= HARDENED {
+            return Err(TxBuilderError::InvalidDerivationPath(index.to_string()));
+        }
+
+        let mut path = self.base_path.clone();
+        path.push(index | HARDENED);
+
+        let pkey = match derive_pkey(&*self.seed, &path) {
+            Ok(Some(pkey)) => pkey,
+            Ok(None) => return Err(TxBuilderError::InvalidDerivationPath(index.to_string())),
+            Err(e) => return Err(TxBuilderError::SeededKeyError(e)),
+        };
+
+        Key::from_pkey(&format!("{}{}", self.prefix, index), &pkey)
+    }
+
+    /// # Onboard range
+    ///
+    /// Derive the keys for a range of indexes and build the transactions to onboard them, as
+    /// [`TransactionBuilder::generate_onboard_batch`] does for generated keys.
+    ///
+    /// Returns the derived keys and the transactions.
+    ///
+    /// [`TransactionBuilder::generate_onboard_batch`]: struct.TransactionBuilder.html#method.generate_onboard_batch
+    pub fn onboard_range(
+        &self,
+        indexes: Range<u32>,
+        mode: BatchMode,
+    ) -> TxBuilderResult<(Vec<Key>, Vec<String>)> {
+        let mut keys = Vec::with_capacity(indexes.len());
+
+        for index in indexes {
+            keys.push(self.derive_child(index)?);
+        }
+
+        let txs = TransactionBuilder::onboard_batch(&keys, mode)?;
+
+        Ok((keys, txs))
+    }
+}
+
+/// Get the seed for a mnemonic phrase, with no passphrase
+fn mnemonic_seed(phrase: &str) -> TxBuilderResult<Zeroizing<[u8; 64]>> {
+    match Mnemonic::parse(phrase) {
+        Ok(mnemonic) => Ok(Zeroizing::new(mnemonic.to_seed(""))),
+        Err(e) => Err(TxBuilderError::InvalidMnemonic(e.to_string())),
+    }
+}
+
+/// Parse a derivation path into child indexes
+fn parse_path(path: &str) -> TxBuilderResult<Vec<u32>> {
+    let invalid = || TxBuilderError::InvalidDerivationPath(path.to_string());
+
+    let mut parts = path.split('/');
+
+    if parts.next() != Some("m") {
+        return Err(invalid());
+    }
+
+    let mut indexes = Vec::new();
+
+    for part in parts {
+        let (number, hardened) = match part.strip_suffix('\'').or_else(|| part.strip_suffix('h')) {
+            Some(number) => (number, true),
+            None => (part, false),
+        };
+
+        let index = match number.parse::<u32>() {
+            Ok(index) if index < HARDENED => index,
+            _ => return Err(invalid()),
+        };
+
+        indexes.push(if hardened { index | HARDENED } else { index });
+    }
+
+    Ok(indexes)
+}
+
+/// Derive the private key for the path from a seed, None if the path gives an invalid key
+fn derive_pkey(seed: &[u8], path: &[u32]) -> Result<Option<PKey<Private>>, ErrorStack> {
+    let group = EcGroup::from_curve_name(Nid::SECP256K1)?;
+    let mut ctx = BigNumContext::new()?;
+
+    let mut order = BigNum::new()?;
+    group.order(&mut order, &mut ctx)?;
+
+    let (mut private, mut chain) = match split_key(&hmac_sha512(b"Bitcoin seed", seed)?, &order)? {
+        Some(master) => master,
+        None => return Ok(None),
+    };
+
+    for &index in path {
+        let mut data = Zeroizing::new(Vec::with_capacity(37));
+
+        if index >= HARDENED {
+            data.push(0);
+            data.extend_from_slice(&private.to_vec_padded(SCALAR_SIZE)?);
+        } else {
+            let public = public_point(&group, &private, &mut ctx)?;
+            data.extend_from_slice(&public.to_bytes(
+                &group,
+                PointConversionForm::COMPRESSED,
+                &mut ctx,
+            )?);
+        }
+
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let (tweak, next_chain) = match split_key(&hmac_sha512(&chain, &data)?, &order)? {
+            Some(child) => child,
+            None => return Ok(None),
+        };
+
+        let mut child = BigNum::new()?;
+        child.mod_add(&tweak, &private, &order, &mut ctx)?;
+
+        if child.num_bits() == 0 {
+            return Ok(None);
+        }
+
+        private.clear();
+        private = child;
+        chain = next_chain;
+    }
+
+    let public = public_point(&group, &private, &mut ctx)?;
+    let key = EcKey::from_private_components(&group, &private, &public);
+    private.clear();
+
+    PKey::from_ec_key(key?).map(Some)
+}
+
+/// Split an HMAC result into a private scalar and chain code, None if the scalar is invalid
+fn split_key(digest: &[u8], order: &BigNumRef) -> Result<Option<ExtendedKey>, ErrorStack> {
+    let (left, right) = digest.split_at(digest.len() / 2);
+
+    let scalar = BigNum::from_slice(left)?;
+
+    if scalar.num_bits() == 0 || scalar >= *order {
+        return Ok(None);
+    }
+
+    Ok(Some((scalar, Zeroizing::new(right.to_vec()))))
+}
+
+fn public_point(
+    group: &EcGroup,
+    private: &BigNumRef,
+    ctx: &mut BigNumContext,
+) -> Result<EcPoint, ErrorStack> {
+    let mut public = EcPoint::new(group)?;
+    public.mul_generator2(group, private, ctx)?;
+
+    Ok(public)
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Result<Zeroizing<Vec<u8>>, ErrorStack> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha512(), &key)?;
+    signer.update(data)?;
+
+    Ok(Zeroizing::new(signer.sign_to_vec()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derive_hex(seed: &str, path: &str) -> String {
+        let seed: Vec<u8> = (0..seed.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&seed[i..i + 2], 16).unwrap())
+            .collect();
+
+        let pkey = derive_pkey(&seed, &parse_path(path).unwrap())
+            .unwrap()
+            .unwrap();
+        let private = pkey.ec_key().unwrap().private_key().to_vec();
+
+        private.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn mnemonic_bip32_vectors() {
+        let seed = "000102030405060708090a0b0c0d0e0f";
+
+        assert_eq!(
+            derive_hex(seed, "m"),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        assert_eq!(
+            derive_hex(seed, "m/0'"),
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"
+        );
+        assert_eq!(
+            derive_hex(seed, "m/0h/1"),
+            "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368"
+        );
+
+        assert!(parse_path("44'/0'").is_err());
+        assert!(parse_path("m/x").is_err());
+        assert!(parse_path("m/2147483648").is_err());
+
+        let err = Key::from_mnemonic("key", "not a mnemonic", "m/0", KeyType::EC).unwrap_err();
+        assert_eq!(err.code(), 8008);
+    }
+
+    #[test]
+    fn mnemonic_key_factory() {
+        let phrase = Key::generate_mnemonic().unwrap();
+        let factory = KeyFactory::from_mnemonic(&phrase, "m/44'/0'/0'").unwrap();
+
+        let child = factory.derive_child(3).unwrap();
+        let same = Key::from_mnemonic("device-3", &phrase, "m/44'/0'/0'/3'", KeyType::EC).unwrap();
+        assert_eq!(child.name(), "device-3");
+        assert_eq!(child.to_der().unwrap(), same.to_der().unwrap());
+
+        let other = factory.derive_child(4).unwrap();
+        assert_ne!(child.to_der().unwrap(), other.to_der().unwrap());
+
+        assert!(factory.derive_child(HARDENED).is_err());
+
+        let (keys, txs) = factory.onboard_range(0..3, BatchMode::Individual).unwrap();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(txs.len(), 3);
+    }
+}
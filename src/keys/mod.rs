@@ -0,0 +1,642 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Keys
+//!
+//! Wraps the activeledger key types so keys can be imported from and exported to
+//! formats used by other tools and SDKs.
+//!
+//! ## Supported formats
+//! * PEM - PKCS#8 or traditional (PKCS#1 / SEC1) private keys
+//! * DER - PKCS#8 or traditional private keys
+//! * JWK - JSON Web Keys, as used by the JS SDK and most web tooling
+//! * BIP39 mnemonic phrases, with the `mnemonic` feature
+//!
+//! Keys can be persisted encrypted using the [`KeyStore`](struct.KeyStore.html).
+//!
+//! Signatures can be checked with only the public half of a key using
+//! [`PublicKey`](struct.PublicKey.html).
+//!
+//! Keys held outside of this crate sign through the [`Signer`](trait.Signer.html) trait.
+//! With the `vault` feature, [`VaultSigner`](struct.VaultSigner.html) signs with a HashiCorp
+//! Vault transit key, and with the `pkcs11` feature [`Pkcs11Signer`](struct.Pkcs11Signer.html)
+//! signs with a key on a smartcard or HSM.
+//!
+//! Signees can be described in config files with [`SigneeSpec`](struct.SigneeSpec.html) and
+//! resolved to keys at runtime by a [`KeyResolver`](trait.KeyResolver.html).
+//!
+//! ## Key material
+//! Buffers holding private key material while it is converted, such as decoded PEMs and DER,
+//! are zeroed when they are dropped. The `Debug` output of a key only shows its name and type.
+//! The RSA and EC keys themselves are held by the activeledger crate.
+//!
+//! ## Features
+//! RSA and EC keys, and the formats above, need the `openssl-keys` feature which is enabled by
+//! default. Without it only [`Key::External`](enum.Key.html#variant.External) is available,
+//! for targets OpenSSL doesn't support such as `wasm32-unknown-unknown`.
+//!
+//! ## Example
+//! ```
+//! # #[cfg(feature = "openssl-keys")] {
+//! # use active_tx::Key;
+//! # use activeledger::key::EllipticCurve;
+//! let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+//!
+//! let jwk = key.to_jwk().unwrap();
+//!
+//! let imported = Key::from_jwk("keyname", &jwk).unwrap();
+//! # }
+//! ```
+
+// STD
+use std::fmt;
+use std::sync::Arc;
+
+// External imports
+#[cfg(feature = "openssl-keys")]
+use activeledger::key::{EllipticCurve, Pkcs8pem, RSA};
+#[cfg(feature = "openssl-keys")]
+use openssl::bn::{BigNum, BigNumContext};
+#[cfg(feature = "openssl-keys")]
+use openssl::ec::{EcGroup, EcKey, EcPoint};
+#[cfg(feature = "openssl-keys")]
+use openssl::error::ErrorStack;
+#[cfg(feature = "openssl-keys")]
+use openssl::nid::Nid;
+#[cfg(feature = "openssl-keys")]
+use openssl::pkey::{HasPublic, Id, PKey, PKeyRef, Private};
+#[cfg(feature = "openssl-keys")]
+use openssl::sha::sha256;
+#[cfg(feature = "openssl-keys")]
+use zeroize::{Zeroize, Zeroizing};
+
+// Internal imports
+#[cfg(feature = "openssl-keys")]
+use crate::error::TxBuilderError;
+use crate::error::TxBuilderResult;
+use crate::TransactionBuilder;
+
+#[cfg(feature = "openssl-keys")]
+mod fingerprint;
+#[cfg(feature = "openssl-keys")]
+mod jwk;
+#[cfg(feature = "openssl-keys")]
+mod keystore;
+#[cfg(feature = "mnemonic")]
+mod mnemonic;
+#[cfg(feature = "pkcs11")]
+mod pkcs11;
+mod resolver;
+mod sign;
+#[cfg(feature = "vault")]
+mod vault;
+#[cfg(feature = "openssl-keys")]
+mod verify;
+
+pub use crate::assembly::{KeyType, Signer};
+#[cfg(feature = "openssl-keys")]
+pub use keystore::KeyStore;
+#[cfg(feature = "mnemonic")]
+pub use mnemonic::KeyFactory;
+#[cfg(feature = "pkcs11")]
+pub use pkcs11::{Pkcs11Config, Pkcs11Signer};
+pub use resolver::{KeyResolver, SigneeSpec};
+#[cfg(feature = "openssl-keys")]
+pub use resolver::{KeyStoreResolver, PemFileResolver};
+pub use sign::{EcSignatureEncoding, HashAlgorithm, RsaPadding, SignOptions, SignatureEncoding};
+#[cfg(feature = "vault")]
+pub use vault::VaultSigner;
+#[cfg(feature = "openssl-keys")]
+pub use verify::PublicKey;
+
+/// Holds the key to use when signing the transaction packet
+#[derive(Clone)]
+pub enum Key {
+    #[cfg(feature = "openssl-keys")]
+    Rsa(RSA),
+    #[cfg(feature = "openssl-keys")]
+    Ec(EllipticCurve),
+    /// A key held outside of this crate, see [`Signer`](trait.Signer.html)
+    External(Arc<dyn Signer>),
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Key")
+            .field("name", &self.name())
+            .field("key_type", &self.key_type())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Key {
+    /// # From PEM
+    ///
+    /// Create a key from a private key PEM, detecting whether it is an RSA or EC key.
+    ///
+    /// ```
+    /// # use active_tx::Key;
+    /// # use activeledger::key::EllipticCurve;
+    /// # let pem = EllipticCurve::new("keyname").unwrap().get_pem().unwrap().private;
+    /// let key = Key::from_pem("keyname", &pem).unwrap();
+    /// ```
+    #[cfg(feature = "openssl-keys")]
+    pub fn from_pem(name: &str, pem: &str) -> TxBuilderResult<Key> {
+        let pkey = match PKey::private_key_from_pem(pem.as_bytes()) {
+            Ok(pkey) => pkey,
+            Err(e) => return Err(TxBuilderError::PemParseError(e)),
+        };
+
+        Key::from_pkey(name, &pkey)
+    }
+
+    /// # From DER
+    ///
+    /// Create a key from DER encoded private key bytes, detecting whether it is an RSA or EC key.
+    ///
+    /// ```
+    /// # use active_tx::Key;
+    /// # use activeledger::key::EllipticCurve;
+    /// # let der = Key::Ec(EllipticCurve::new("keyname").unwrap()).to_der().unwrap();
+    /// let key = Key::from_der("keyname", &der).unwrap();
+    /// ```
+    #[cfg(feature = "openssl-keys")]
+    pub fn from_der(name: &str, der: &[u8]) -> TxBuilderResult<Key> {
+        let pkey = match PKey::private_key_from_der(der) {
+            Ok(pkey) => pkey,
+            Err(e) => return Err(TxBuilderError::DerParseError(e)),
+        };
+
+        Key::from_pkey(name, &pkey)
+    }
+
+    /// # EC from seed
+    ///
+    /// Derive an EC key from a seed, the same seed always gives the same key.
+    /// This is for reproducible tests only, the seed must not be used for real keys.
+    ///
+    /// ECDSA signatures are still randomised, OpenSSL picks a random nonce for each signature
+    /// rather than deriving it from the key and message as RFC 6979 does. Check them with
+    /// [`PublicKey::verify`](struct.PublicKey.html#method.verify) rather than comparing them.
+    ///
+    /// ```
+    /// # use active_tx::Key;
+    /// let key = Key::ec_from_seed("keyname", b"test seed").unwrap();
+    /// let same = Key::ec_from_seed("keyname", b"test seed").unwrap();
+    ///
+    /// assert_eq!(key.to_der().unwrap(), same.to_der().unwrap());
+    /// ```
+    #[cfg(feature = "openssl-keys")]
+    pub fn ec_from_seed(name: &str, seed: &[u8]) -> TxBuilderResult<Key> {
+        let pkey = match Key::seeded_pkey(seed) {
+            Ok(pkey) => pkey,
+            Err(e) => return Err(TxBuilderError::SeededKeyError(e)),
+        };
+
+        Key::from_pkey(name, &pkey)
+    }
+
+    /// # To DER
+    ///
+    /// Export the private key as DER bytes.
+    /// External keys don't hold a private key and return an error.
+    #[cfg(feature = "openssl-keys")]
+    pub fn to_der(&self) -> TxBuilderResult<Vec<u8>> {
+        let pkey = self.pkey()?;
+
+        match pkey.private_key_to_der() {
+            Ok(der) => Ok(der),
+            Err(e) => Err(TxBuilderError::KeyExportError(e)),
+        }
+    }
+
+    /// # Name
+    ///
+    /// Get the name the key was created with.
+    pub fn name(&self) -> &str {
+        match self {
+            #[cfg(feature = "openssl-keys")]
+            Key::Rsa(key) => &key.name,
+            #[cfg(feature = "openssl-keys")]
+            Key::Ec(key) => &key.name,
+            Key::External(signer) => signer.name(),
+        }
+    }
+
+    /// # Key type
+    ///
+    /// Get the type of the key.
+    pub fn key_type(&self) -> KeyType {
+        match self {
+            #[cfg(feature = "openssl-keys")]
+            Key::Rsa(_) => KeyType::RSA,
+            #[cfg(feature = "openssl-keys")]
+            Key::Ec(_) => KeyType::EC,
+            Key::External(signer) => signer.key_type(),
+        }
+    }
+
+    /// # Public PEM
+    ///
+    /// Get the public key as a PEM string, as given to the ledger when the key is onboarded.
+    pub fn public_pem(&self) -> TxBuilderResult<String> {
+        TransactionBuilder::get_pem(self)
+    }
+
+    /// # Into shared
+    ///
+    /// Wrap the key in an `Arc` so it can be shared between threads and [`Signees`].
+    ///
+    /// [`Signees`]: struct.Signees.html
+    pub fn into_shared(self) -> Arc<Key> {
+        Arc::new(self)
+    }
+}
+
+/// # Into key
+///
+/// Anything that can sign a stream in the [`signees!`](macro.signees.html) macro: a [`Key`],
+/// a shared key or any [`Signer`](trait.Signer.html), including a
+/// [`FnSigner`](struct.FnSigner.html). Signers are wrapped in
+/// [`Key::External`](enum.Key.html#variant.External).
+///
+/// ```
+/// # use active_tx::{signees, FnSigner, KeyType};
+/// let signer = FnSigner::new("signer", KeyType::EC, |data: &str| {
+///     Ok(format!("signed:{}", data.len()))
+/// });
+/// let signees = signees![{"streamid" => signer}];
+///
+/// assert!(signees.contains("streamid"));
+/// ```
+pub trait IntoKey {
+    /// Convert into a shared key
+    fn into_key(self) -> Arc<Key>;
+}
+
+impl IntoKey for Key {
+    fn into_key(self) -> Arc<Key> {
+        Arc::new(self)
+    }
+}
+
+impl IntoKey for Arc<Key> {
+    fn into_key(self) -> Arc<Key> {
+        self
+    }
+}
+
+impl<S: Signer + 'static> IntoKey for S {
+    fn into_key(self) -> Arc<Key> {
+        Arc::new(Key::External(Arc::new(self)))
+    }
+}
+
+// Private functions
+impl Key {
+    /// Rename the key, external signers keep their own name
+    pub(crate) fn set_name(&mut self, name: &str) {
+        match self {
+            #[cfg(feature = "openssl-keys")]
+            Key::Rsa(key) => key.name = name.to_string(),
+            #[cfg(feature = "openssl-keys")]
+            Key::Ec(key) => key.name = name.to_string(),
+            Key::External(_) => {
+                let _ = name;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "openssl-keys")]
+impl Key {
+    /// Create a key from a parsed OpenSSL private key
+    fn from_pkey(name: &str, pkey: &PKey<Private>) -> TxBuilderResult<Key> {
+        let key_type = Key::pkey_type(pkey)?;
+
+        let private = match pkey.private_key_to_pem_pkcs8() {
+            Ok(private) => Zeroizing::new(private),
+            Err(e) => return Err(TxBuilderError::KeyExportError(e)),
+        };
+
+        let public = match pkey.public_key_to_pem() {
+            Ok(public) => public,
+            Err(e) => return Err(TxBuilderError::KeyExportError(e)),
+        };
+
+        let mut pem = Pkcs8pem::new(
+            &String::from_utf8_lossy(&private),
+            &String::from_utf8_lossy(&public),
+        );
+
+        let key = match key_type {
+            KeyType::RSA => Key::Rsa(RSA::create_from_pem(name, &pem)),
+            KeyType::EC => Key::Ec(EllipticCurve::create_from_pem(name, &pem)),
+        };
+
+        pem.private.zeroize();
+
+        Ok(key)
+    }
+
+    /// Derive a secp256k1 key, hashing the seed until it gives a valid private key
+    fn seeded_pkey(seed: &[u8]) -> Result<PKey<Private>, ErrorStack> {
+        let group = EcGroup::from_curve_name(Nid::SECP256K1)?;
+        let mut ctx = BigNumContext::new()?;
+
+        let mut order = BigNum::new()?;
+        group.order(&mut order, &mut ctx)?;
+
+        let mut digest = Zeroizing::new(sha256(seed));
+        let mut private = BigNum::from_slice(&*digest)?;
+
+        while private.num_bits() == 0 || private >= order {
+            *digest = sha256(&*digest);
+            private.clear();
+            private = BigNum::from_slice(&*digest)?;
+        }
+
+        let mut public = EcPoint::new(&group)?;
+        public.mul_generator2(&group, &private, &mut ctx)?;
+
+        let key = EcKey::from_private_components(&group, &private, &public);
+        private.clear();
+
+        PKey::from_ec_key(key?)
+    }
+
+    /// Get the key type of an OpenSSL key, erroring if Activeledger doesn't support it
+    pub(crate) fn pkey_type<T: HasPublic>(pkey: &PKeyRef<T>) -> TxBuilderResult<KeyType> {
+        match pkey.id() {
+            Id::RSA => Ok(KeyType::RSA),
+            Id::EC => {
+                // Activeledger only supports secp256k1 curve keys
+                let curve = pkey.ec_key().ok().and_then(|k| k.group().curve_name());
+
+                if curve != Some(Nid::SECP256K1) {
+                    return Err(TxBuilderError::UnsupportedKeyType);
+                }
+
+                Ok(KeyType::EC)
+            }
+            _ => Err(TxBuilderError::UnsupportedKeyType),
+        }
+    }
+
+    /// Get the key as an OpenSSL private key
+    pub(crate) fn pkey(&self) -> TxBuilderResult<PKey<Private>> {
+        let pem = match self {
+            Key::Rsa(key) => key.get_pem(),
+            Key::Ec(key) => key.get_pem(),
+            Key::External(_) => {
+                return Err(TxBuilderError::PrivateKeyUnavailable(
+                    self.name().to_string(),
+                ))
+            }
+        };
+
+        let mut pem = match pem {
+            Ok(pem) => pem,
+            Err(source) => {
+                return Err(TxBuilderError::PemError {
+                    key_name: self.name().to_string(),
+                    source,
+                })
+            }
+        };
+
+        let pkey = PKey::private_key_from_pem(pem.private.as_bytes());
+        pem.private.zeroize();
+
+        match pkey {
+            Ok(pkey) => Ok(pkey),
+            Err(e) => Err(TxBuilderError::PemParseError(e)),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "openssl-keys"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_pem_der_roundtrip() {
+        let key = Key::Ec(EllipticCurve::new("key").unwrap());
+
+        let der = key.to_der().unwrap();
+        let from_der = Key::from_der("key", &der).unwrap();
+        assert_eq!(from_der.key_type(), KeyType::EC);
+        assert_eq!(from_der.to_der().unwrap(), der);
+
+        let pem = match &key {
+            Key::Ec(key) => key.get_pem().unwrap().private,
+            _ => unreachable!(),
+        };
+        let from_pem = Key::from_pem("key", &pem).unwrap();
+        assert_eq!(from_pem.name(), "key");
+        assert_eq!(from_pem.to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn key_jwk_roundtrip() {
+        let ec = Key::Ec(EllipticCurve::new("ec").unwrap());
+        let jwk = ec.to_jwk().unwrap();
+        assert_eq!(jwk["kty"], "EC");
+        assert_eq!(jwk["crv"], "secp256k1");
+        let imported = Key::from_jwk("ec", &jwk).unwrap();
+        assert_eq!(imported.to_der().unwrap(), ec.to_der().unwrap());
+
+        // A point that is not on the curve is rejected by OpenSSL
+        let mut off_curve = jwk.clone();
+        off_curve["x"] = jwk["y"].clone();
+        let err = Key::from_jwk("ec", &off_curve).unwrap_err();
+        assert_eq!(err.code(), 8010);
+
+        let rsa = Key::Rsa(RSA::new("rsa").unwrap());
+        let jwk = rsa.to_jwk().unwrap();
+        assert_eq!(jwk["kty"], "RSA");
+        let imported = Key::from_jwk("rsa", &jwk).unwrap();
+        assert_eq!(imported.to_jwk().unwrap(), jwk);
+
+        // Imported keys are stored as PKCS#8 and must still be usable for signing
+        match imported {
+            Key::Rsa(key) => assert!(key.sign("data").is_ok()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn key_sign_options() {
+        use openssl::ecdsa::EcdsaSig;
+        use openssl::hash::MessageDigest;
+        use openssl::rsa::Padding;
+        use openssl::sign::Verifier;
+
+        let ec = Key::Ec(EllipticCurve::new("ec").unwrap());
+        let rsa = Key::Rsa(RSA::new("rsa").unwrap());
+
+        // Defaults match the activeledger crate
+        let signature = ec.sign("data", &SignOptions::new()).unwrap();
+        match &ec {
+            Key::Ec(key) => assert!(key.verify("data", &signature).unwrap()),
+            _ => unreachable!(),
+        }
+
+        let mut options = SignOptions::new();
+        options
+            .hash(HashAlgorithm::Sha512)
+            .ec_encoding(EcSignatureEncoding::Raw)
+            .rsa_padding(RsaPadding::Pss);
+
+        // Raw EC signature is r || s
+        let signature = base64::decode(&ec.sign("data", &options).unwrap()).unwrap();
+        assert_eq!(signature.len(), 64);
+
+        let r = openssl::bn::BigNum::from_slice(&signature[..32]).unwrap();
+        let s = openssl::bn::BigNum::from_slice(&signature[32..]).unwrap();
+        let der = EcdsaSig::from_private_components(r, s)
+            .unwrap()
+            .to_der()
+            .unwrap();
+
+        let pkey = ec.pkey().unwrap();
+        let mut verifier = Verifier::new(MessageDigest::sha512(), &pkey).unwrap();
+        verifier.update(b"data").unwrap();
+        assert!(verifier.verify(&der).unwrap());
+
+        // RSA with PSS padding
+        let signature = base64::decode(&rsa.sign("data", &options).unwrap()).unwrap();
+
+        let pkey = rsa.pkey().unwrap();
+        let mut verifier = Verifier::new(MessageDigest::sha512(), &pkey).unwrap();
+        verifier.set_rsa_padding(Padding::PKCS1_PSS).unwrap();
+        verifier.update(b"data").unwrap();
+        assert!(verifier.verify(&signature).unwrap());
+    }
+
+    #[test]
+    fn key_public_verify() {
+        let ec = Key::Ec(EllipticCurve::new("ec").unwrap());
+        let rsa = Key::Rsa(RSA::new("rsa").unwrap());
+
+        let mut options = SignOptions::new();
+        options
+            .hash(HashAlgorithm::Sha512)
+            .ec_encoding(EcSignatureEncoding::Raw)
+            .rsa_padding(RsaPadding::Pss)
+            .encoding(SignatureEncoding::Hex);
+
+        for key in &[ec, rsa] {
+            let public_key = key.public_key().unwrap();
+            assert_eq!(public_key.key_type(), key.key_type());
+
+            for options in &[SignOptions::new(), options.clone()] {
+                let signature = key.sign("data", options).unwrap();
+
+                assert!(public_key.verify("data", &signature, options));
+                assert!(!public_key.verify("other", &signature, options));
+                assert!(!public_key.verify("data", "not a signature", options));
+            }
+        }
+
+        assert_eq!(PublicKey::from_pem("pem").err().unwrap().code(), 8007);
+    }
+
+    #[test]
+    fn key_ec_from_seed() {
+        let key = Key::ec_from_seed("seeded", b"seed").unwrap();
+        let same = Key::ec_from_seed("seeded", b"seed").unwrap();
+        let other = Key::ec_from_seed("seeded", b"other seed").unwrap();
+
+        assert_eq!(key.key_type(), KeyType::EC);
+        assert_eq!(key.to_der().unwrap(), same.to_der().unwrap());
+        assert_ne!(key.to_der().unwrap(), other.to_der().unwrap());
+
+        let signature = key.sign("data", &SignOptions::new()).unwrap();
+        assert!(same
+            .public_key()
+            .unwrap()
+            .verify("data", &signature, &SignOptions::new()));
+    }
+
+    #[test]
+    fn key_debug_redacted() {
+        let key = Key::Rsa(RSA::new("redacted").unwrap());
+        let der = base64::encode(&key.to_der().unwrap());
+
+        let mut signees = crate::Signees::new();
+        signees.add(key.clone(), "streamid");
+
+        let onboard = crate::OnboardTxBuilder::new(key.clone());
+        let mut tx_builder = crate::TransactionBuilder::new("namespace", "contract");
+        tx_builder.with_clock(chrono::Utc::now);
+
+        for debug in &[
+            format!("{:?}", key),
+            format!("{:?}", signees),
+            format!("{:?}", onboard),
+            format!("{:?}", tx_builder),
+        ] {
+            assert!(!debug.contains("PRIVATE"));
+            assert!(!debug.contains(&der[40..80]));
+        }
+
+        assert_eq!(
+            format!("{:?}", key),
+            r#"Key { name: "redacted", key_type: RSA, .. }"#
+        );
+    }
+
+    #[test]
+    fn key_fingerprint() {
+        assert_eq!(fingerprint::base58(b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(fingerprint::base58(&[0, 0, 1]), "112");
+        assert_eq!(fingerprint::base58(&[]), "");
+
+        let key = Key::Ec(EllipticCurve::new("key").unwrap());
+        let public_key = PublicKey::from_pem(&key.public_pem().unwrap()).unwrap();
+
+        let der = key.pkey().unwrap().public_key_to_der().unwrap();
+        let hex: String = sha256(&der).iter().map(|b| format!("{:02x}", b)).collect();
+
+        assert_eq!(key.fingerprint().unwrap(), hex);
+        assert_eq!(public_key.fingerprint().unwrap(), hex);
+        assert_eq!(
+            key.fingerprint_base58().unwrap(),
+            fingerprint::base58(&sha256(&der))
+        );
+
+        let other = Key::Rsa(RSA::new("other").unwrap());
+        assert_ne!(other.fingerprint().unwrap(), hex);
+
+        let mut signees = crate::Signees::new();
+        signees
+            .add(key.clone(), "stream")
+            .add_multi(other.clone(), "multi", "admin");
+
+        let fingerprints = signees.fingerprints().unwrap();
+        assert_eq!(fingerprints.len(), 2);
+        assert_eq!(fingerprints["stream"], hex);
+        assert_eq!(fingerprints["multi/admin"], other.fingerprint().unwrap());
+    }
+}
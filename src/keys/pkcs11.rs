@@ -0,0 +1,430 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # PKCS#11 signer
+//!
+//! Signs packets with a key held on a smartcard or HSM, such as a YubiKey, through its
+//! PKCS#11 module. The private key never leaves the token.
+//!
+//! RSA keys sign using PKCS#1 v1.5 padding with SHA256. EC keys must be on the secp256k1
+//! curve used by Activeledger, the packet is hashed with SHA256 and the signature DER encoded.
+//!
+//! Requires the `pkcs11` feature.
+//!
+//! ## Example
+//! ```no_run
+//! # use active_tx::{signees, Key, Pkcs11Config, Pkcs11Signer};
+//! let mut config = Pkcs11Config::new("/usr/lib/libykcs11.so", "activeledger");
+//! config.slot(0).pin("123456");
+//!
+//! let key = Key::from(Pkcs11Signer::open(&config).unwrap());
+//!
+//! let signees = signees![{"streamid" => key}];
+//! ```
+
+// STD
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// External imports
+use cryptoki::context::{CInitializeArgs, CInitializeFlags, Pkcs11};
+use cryptoki::error::{Error as Pkcs11Error, RvError};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{
+    Attribute, AttributeType, KeyType as Pkcs11KeyType, ObjectClass, ObjectHandle,
+};
+use cryptoki::session::{Session, UserType};
+use cryptoki::slot::Slot;
+use cryptoki::types::AuthPin;
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey, EcPoint};
+use openssl::ecdsa::EcdsaSig;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use openssl::sha::sha256;
+use zeroize::Zeroizing;
+
+// Internal imports
+use super::{Key, KeyType, Signer};
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// DER encoded object identifier of the secp256k1 curve, as held in CKA_EC_PARAMS
+const SECP256K1_OID: [u8; 7] = [0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x0a];
+
+/// # PKCS#11 configuration
+///
+/// The module, slot and PIN used to open a token, and the label of the key to sign with.
+///
+/// If no slot is set the first slot holding a token is used. If no PIN is set the session is
+/// not logged in, for tokens that authenticate another way.
+#[derive(Clone)]
+pub struct Pkcs11Config {
+    module: PathBuf,
+    key_label: String,
+    slot: Option<u64>,
+    pin: Option<Zeroizing<String>>,
+}
+
+impl Pkcs11Config {
+    /// # New
+    ///
+    /// Create a configuration using the PKCS#11 module at the path and the key with the label.
+    pub fn new<P: AsRef<Path>>(module: P, key_label: &str) -> Pkcs11Config {
+        Pkcs11Config {
+            module: module.as_ref().to_path_buf(),
+            key_label: key_label.to_string(),
+            slot: None,
+            pin: None,
+        }
+    }
+
+    /// # Slot
+    ///
+    /// Set the ID of the slot holding the token.
+    pub fn slot(&mut self, slot: u64) -> &mut Self {
+        self.slot = Some(slot);
+
+        self
+    }
+
+    /// # PIN
+    ///
+    /// Set the user PIN used to log in to the token.
+    pub fn pin(&mut self, pin: &str) -> &mut Self {
+        self.pin = Some(Zeroizing::new(pin.to_string()));
+
+        self
+    }
+}
+
+/// # PKCS#11 signer
+///
+/// A [`Signer`](trait.Signer.html) backed by a key on a PKCS#11 token. Convert it into a
+/// [`Key`](enum.Key.html) to use it with `signees!` and the builders.
+///
+/// The session is held open for the life of the signer, signing from several threads is
+/// serialised.
+pub struct Pkcs11Signer {
+    session: Mutex<Session>,
+    key: ObjectHandle,
+    key_name: String,
+    key_type: KeyType,
+    public_pem: String,
+}
+
+impl Pkcs11Signer {
+    /// # Open
+    ///
+    /// Load the module, open a session on the configured slot, log in and find the private
+    /// key with the configured label.
+    ///
+    /// An error is returned if the token can't be used, the key is not found or it is not an
+    /// RSA or secp256k1 key.
+    pub fn open(config: &Pkcs11Config) -> TxBuilderResult<Pkcs11Signer> {
+        let session = open_session(config)?;
+
+        let label = Attribute::Label(config.key_label.as_bytes().to_vec());
+
+        let key = find_key(&session, ObjectClass::PRIVATE_KEY, &label)?;
+        let key = match key {
+            Some(key) => key,
+            None => return Err(TxBuilderError::KeyNotFound(config.key_label.clone())),
+        };
+
+        let key_type = read_key_type(&session, key)?;
+
+        // RSA private keys usually carry their public components, EC private keys don't
+        let public = match find_key(&session, ObjectClass::PUBLIC_KEY, &label)? {
+            Some(public) => public,
+            None => key,
+        };
+
+        let public_pem = read_public_pem(&session, public, key_type)?;
+
+        Ok(Pkcs11Signer {
+            session: Mutex::new(session),
+            key,
+            key_name: config.key_label.clone(),
+            key_type,
+            public_pem,
+        })
+    }
+}
+
+impl Signer for Pkcs11Signer {
+    fn name(&self) -> &str {
+        &self.key_name
+    }
+
+    fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    fn sign(&self, data: &str) -> TxBuilderResult<String> {
+        let session = match self.session.lock() {
+            Ok(session) => session,
+            Err(_) => return Err(pkcs11_error("session lock poisoned".to_string())),
+        };
+
+        let signature = match self.key_type {
+            KeyType::RSA => session.sign(&Mechanism::Sha256RsaPkcs, self.key, data.as_bytes()),
+            KeyType::EC => session.sign(&Mechanism::Ecdsa, self.key, &sha256(data.as_bytes())),
+        };
+
+        let signature = match signature {
+            Ok(signature) => signature,
+            Err(e) => return Err(token_error(e)),
+        };
+
+        match self.key_type {
+            KeyType::RSA => Ok(base64::encode(&signature)),
+            KeyType::EC => Ok(base64::encode(&ecdsa_to_der(&signature)?)),
+        }
+    }
+
+    fn public_pem(&self) -> TxBuilderResult<String> {
+        Ok(self.public_pem.clone())
+    }
+}
+
+impl From<Pkcs11Signer> for Key {
+    fn from(signer: Pkcs11Signer) -> Key {
+        Key::External(Arc::new(signer))
+    }
+}
+
+/// Load the module and open a logged in session on the configured slot
+fn open_session(config: &Pkcs11Config) -> TxBuilderResult<Session> {
+    let pkcs11 = match Pkcs11::new(&config.module) {
+        Ok(pkcs11) => pkcs11,
+        Err(e) => return Err(token_error(e)),
+    };
+
+    match pkcs11.initialize(CInitializeArgs::new(CInitializeFlags::OS_LOCKING_OK)) {
+        // Another signer may have initialised the module already
+        Ok(_) | Err(Pkcs11Error::Pkcs11(RvError::CryptokiAlreadyInitialized, _)) => {}
+        Err(e) => return Err(token_error(e)),
+    }
+
+    let slot = match config.slot {
+        Some(id) => match Slot::try_from(id) {
+            Ok(slot) => slot,
+            Err(e) => return Err(token_error(e)),
+        },
+        None => match pkcs11.get_slots_with_token() {
+            Ok(slots) => match slots.into_iter().next() {
+                Some(slot) => slot,
+                None => return Err(pkcs11_error("no token found".to_string())),
+            },
+            Err(e) => return Err(token_error(e)),
+        },
+    };
+
+    let session = match pkcs11.open_ro_session(slot) {
+        Ok(session) => session,
+        Err(e) => return Err(token_error(e)),
+    };
+
+    if let Some(pin) = &config.pin {
+        let pin = AuthPin::from(pin.as_str());
+
+        if let Err(e) = session.login(UserType::User, Some(&pin)) {
+            return Err(token_error(e));
+        }
+    }
+
+    Ok(session)
+}
+
+/// Find the first key of the class with the label
+fn find_key(
+    session: &Session,
+    class: ObjectClass,
+    label: &Attribute,
+) -> TxBuilderResult<Option<ObjectHandle>> {
+    match session.find_objects(&[Attribute::Class(class), label.clone()]) {
+        Ok(objects) => Ok(objects.into_iter().next()),
+        Err(e) => Err(token_error(e)),
+    }
+}
+
+/// Read the type of a key, checking EC keys are on secp256k1
+fn read_key_type(session: &Session, key: ObjectHandle) -> TxBuilderResult<KeyType> {
+    let attributes = get_attributes(
+        session,
+        key,
+        &[AttributeType::KeyType, AttributeType::EcParams],
+    )?;
+
+    let mut key_type = None;
+    let mut params = None;
+
+    for attribute in attributes {
+        match attribute {
+            Attribute::KeyType(value) => key_type = Some(value),
+            Attribute::EcParams(value) => params = Some(value),
+            _ => {}
+        }
+    }
+
+    match key_type {
+        Some(value) if value == Pkcs11KeyType::RSA => Ok(KeyType::RSA),
+        Some(value)
+            if value == Pkcs11KeyType::EC && params.as_deref() == Some(&SECP256K1_OID[..]) =>
+        {
+            Ok(KeyType::EC)
+        }
+        _ => Err(TxBuilderError::UnsupportedKeyType),
+    }
+}
+
+/// Read the public components of a key and encode them as a PEM
+fn read_public_pem(
+    session: &Session,
+    key: ObjectHandle,
+    key_type: KeyType,
+) -> TxBuilderResult<String> {
+    let types = match key_type {
+        KeyType::RSA => [AttributeType::Modulus, AttributeType::PublicExponent],
+        KeyType::EC => [AttributeType::EcPoint, AttributeType::EcParams],
+    };
+
+    let mut first = None;
+    let mut second = None;
+
+    for attribute in get_attributes(session, key, &types)? {
+        match attribute {
+            Attribute::Modulus(value) | Attribute::EcPoint(value) => first = Some(value),
+            Attribute::PublicExponent(value) | Attribute::EcParams(value) => second = Some(value),
+            _ => {}
+        }
+    }
+
+    let first = match first {
+        Some(value) => value,
+        None => return Err(pkcs11_error("public key not readable".to_string())),
+    };
+
+    let pem = match key_type {
+        KeyType::RSA => match second {
+            Some(exponent) => rsa_public_pem(&first, &exponent),
+            None => return Err(pkcs11_error("public exponent not readable".to_string())),
+        },
+        KeyType::EC => ec_public_pem(unwrap_octet_string(&first)),
+    };
+
+    match pem {
+        Ok(pem) => Ok(pem),
+        Err(e) => Err(TxBuilderError::KeyExportError(e)),
+    }
+}
+
+fn get_attributes(
+    session: &Session,
+    key: ObjectHandle,
+    types: &[AttributeType],
+) -> TxBuilderResult<Vec<Attribute>> {
+    match session.get_attributes(key, types) {
+        Ok(attributes) => Ok(attributes),
+        Err(e) => Err(token_error(e)),
+    }
+}
+
+fn rsa_public_pem(modulus: &[u8], exponent: &[u8]) -> Result<String, openssl::error::ErrorStack> {
+    let rsa =
+        Rsa::from_public_components(BigNum::from_slice(modulus)?, BigNum::from_slice(exponent)?)?;
+
+    pem_string(PKey::from_rsa(rsa)?.public_key_to_pem()?)
+}
+
+fn ec_public_pem(point: &[u8]) -> Result<String, openssl::error::ErrorStack> {
+    let group = EcGroup::from_curve_name(Nid::SECP256K1)?;
+    let mut ctx = BigNumContext::new()?;
+    let point = EcPoint::from_bytes(&group, point, &mut ctx)?;
+    let key = EcKey::from_public_key(&group, &point)?;
+
+    pem_string(PKey::from_ec_key(key)?.public_key_to_pem()?)
+}
+
+fn pem_string(pem: Vec<u8>) -> Result<String, openssl::error::ErrorStack> {
+    Ok(String::from_utf8_lossy(&pem).into_owned())
+}
+
+/// CKA_EC_POINT holds the point wrapped in a DER octet string, some tokens return it bare
+fn unwrap_octet_string(value: &[u8]) -> &[u8] {
+    match value {
+        [0x04, len, rest @ ..] if usize::from(*len) == rest.len() => rest,
+        [0x04, 0x81, len, rest @ ..] if usize::from(*len) == rest.len() => rest,
+        _ => value,
+    }
+}
+
+/// PKCS#11 returns ECDSA signatures as r and s concatenated, Activeledger expects DER
+fn ecdsa_to_der(signature: &[u8]) -> TxBuilderResult<Vec<u8>> {
+    let (r, s) = signature.split_at(signature.len() / 2);
+
+    let der = BigNum::from_slice(r)
+        .and_then(|r| BigNum::from_slice(s).map(|s| (r, s)))
+        .and_then(|(r, s)| EcdsaSig::from_private_components(r, s))
+        .and_then(|sig| sig.to_der());
+
+    match der {
+        Ok(der) => Ok(der),
+        Err(e) => Err(pkcs11_error(format!(
+            "signature could not be encoded - {}",
+            e
+        ))),
+    }
+}
+
+fn token_error(e: Pkcs11Error) -> TxBuilderError {
+    pkcs11_error(e.to_string())
+}
+
+fn pkcs11_error(message: String) -> TxBuilderError {
+    TxBuilderError::SignerError(format!("pkcs11 {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pkcs11_encoding() {
+        let point = [0x04; 65];
+        let mut wrapped = vec![0x04, 65];
+        wrapped.extend_from_slice(&point);
+        assert_eq!(unwrap_octet_string(&wrapped), &point[..]);
+        assert_eq!(unwrap_octet_string(&point), &point[..]);
+
+        let mut raw = vec![0u8; 64];
+        raw[31] = 1;
+        raw[63] = 2;
+        let der = ecdsa_to_der(&raw).unwrap();
+        let sig = EcdsaSig::from_der(&der).unwrap();
+        assert_eq!(sig.r().to_vec(), vec![1]);
+        assert_eq!(sig.s().to_vec(), vec![2]);
+    }
+}
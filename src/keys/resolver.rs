@@ -0,0 +1,311 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Key resolvers
+//!
+//! A [`KeyResolver`] looks up keys by name from whatever store the application keeps them in,
+//! such as a vault, a database or the environment. Signees and onboarding transactions can
+//! then be created from key names.
+//!
+//! Signing configuration can be kept in config files as [`SigneeSpec`]s, naming the keys to
+//! use rather than holding them, and resolved at runtime.
+//!
+//! ## Example
+//! ```
+//! # #[cfg(feature = "openssl-keys")] {
+//! # use active_tx::{Key, KeyType, SigneeSpec, Signees};
+//! # use activeledger::key::EllipticCurve;
+//! let specs: Vec<SigneeSpec> = serde_json::from_str(r#"[
+//!     {"streamid": "streamid", "key_name": "keyname", "key_type": "ec"}
+//! ]"#).unwrap();
+//!
+//! let resolver = |name: &str| Ok(Key::Ec(EllipticCurve::new(name).unwrap()));
+//!
+//! let signees = Signees::from_specs(&specs, &resolver).unwrap();
+//! # }
+//! ```
+
+// STD
+#[cfg(feature = "openssl-keys")]
+use std::fs;
+#[cfg(feature = "openssl-keys")]
+use std::io;
+#[cfg(feature = "openssl-keys")]
+use std::path::{Component, Path, PathBuf};
+
+// External imports
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "openssl-keys")]
+use zeroize::Zeroizing;
+
+// Internal imports
+#[cfg(feature = "openssl-keys")]
+use super::KeyStore;
+use super::{Key, KeyType};
+#[cfg(feature = "openssl-keys")]
+use crate::error::TxBuilderError;
+use crate::error::TxBuilderResult;
+
+/// # Signee spec
+///
+/// Names the key that signs for a stream, without holding the key itself.
+///
+/// The key is resolved by `pem_path_or_id` if it is set, such as a PEM path for
+/// [`PemFileResolver`] or the name the key is stored under for [`KeyStoreResolver`], and by the
+/// key name otherwise. If the key type is given, the resolved key must be of that type.
+///
+/// ```json
+/// {"streamid": "[streamid]", "key_name": "[key name]", "key_type": "ec", "pem_path_or_id": "[path]"}
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SigneeSpec {
+    pub streamid: String,
+    pub key_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_type: Option<KeyType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pem_path_or_id: Option<String>,
+}
+
+/// # Key resolver
+///
+/// Looks up keys by name from a store supplied by the application. Used by
+/// [`Signees::add_resolved`](struct.Signees.html#method.add_resolved),
+/// [`Signees::from_specs`](struct.Signees.html#method.from_specs) and
+/// [`OnboardTxBuilder::from_resolver`](struct.OnboardTxBuilder.html#method.from_resolver).
+///
+/// Closures taking a `&str` and returning a key can be used as resolvers.
+///
+/// ```
+/// # #[cfg(feature = "openssl-keys")] {
+/// # use std::collections::HashMap;
+/// # use active_tx::{Key, KeyResolver, TxBuilderError, TxBuilderResult};
+/// # use activeledger::key::EllipticCurve;
+/// struct MemoryKeys(HashMap<String, Key>);
+///
+/// impl KeyResolver for MemoryKeys {
+///     fn resolve(&self, name: &str) -> TxBuilderResult<Key> {
+///         match self.0.get(name) {
+///             Some(key) => Ok(key.clone()),
+///             None => Err(TxBuilderError::KeyNotFound(name.to_string())),
+///         }
+///     }
+/// }
+/// # }
+/// ```
+pub trait KeyResolver {
+    /// Get the key with the given name
+    fn resolve(&self, name: &str) -> TxBuilderResult<Key>;
+}
+
+impl<F> KeyResolver for F
+where
+    F: Fn(&str) -> TxBuilderResult<Key>,
+{
+    fn resolve(&self, name: &str) -> TxBuilderResult<Key> {
+        self(name)
+    }
+}
+
+/// # Key store resolver
+///
+/// Loads keys by name from a [`KeyStore`](struct.KeyStore.html).
+///
+/// ```
+/// # use active_tx::{KeyStore, KeyStoreResolver, KeyType, SigneeSpec, Signees, TransactionBuilder};
+/// # let path = std::env::temp_dir().join("active_tx_resolver_doc.json");
+/// # let (key, _) = TransactionBuilder::generate_onboard_tx(KeyType::EC, "keyname").unwrap();
+/// let mut store = KeyStore::open(&path).unwrap();
+/// store.save(&key, "passphrase").unwrap();
+///
+/// let spec = SigneeSpec {
+///     streamid: "streamid".to_string(),
+///     key_name: "keyname".to_string(),
+///     key_type: Some(KeyType::EC),
+///     pem_path_or_id: None,
+/// };
+///
+/// let resolver = KeyStoreResolver::new(&store, "passphrase");
+/// let signees = Signees::from_specs(&[spec], &resolver).unwrap();
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+///
+/// Requires the `openssl-keys` feature, which is enabled by default.
+#[cfg(feature = "openssl-keys")]
+pub struct KeyStoreResolver<'a> {
+    store: &'a KeyStore,
+    passphrase: &'a str,
+}
+
+#[cfg(feature = "openssl-keys")]
+impl<'a> KeyStoreResolver<'a> {
+    /// # New
+    ///
+    /// Create a resolver loading keys from the store with the passphrase.
+    pub fn new(store: &'a KeyStore, passphrase: &'a str) -> KeyStoreResolver<'a> {
+        KeyStoreResolver { store, passphrase }
+    }
+}
+
+#[cfg(feature = "openssl-keys")]
+impl KeyResolver for KeyStoreResolver<'_> {
+    fn resolve(&self, name: &str) -> TxBuilderResult<Key> {
+        self.store.load(name, self.passphrase)
+    }
+}
+
+/// # PEM file resolver
+///
+/// Reads private key PEMs from files in a directory. The name is the name of a file in the
+/// directory, `.pem` is added if it has no extension. Names with path separators, `.` or `..`
+/// components, or absolute paths are rejected, so keys outside the directory can't be read. The
+/// key is named after the file, without its extension.
+///
+/// ```no_run
+/// # use active_tx::{PemFileResolver, Signees};
+/// let resolver = PemFileResolver::new("/etc/activeledger/keys");
+///
+/// // Reads /etc/activeledger/keys/alice.pem
+/// let mut signees = Signees::new();
+/// signees.add_resolved(&resolver, "alice", "streamid").unwrap();
+/// ```
+///
+/// Requires the `openssl-keys` feature, which is enabled by default.
+#[cfg(feature = "openssl-keys")]
+#[derive(Debug, Clone)]
+pub struct PemFileResolver {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "openssl-keys")]
+impl PemFileResolver {
+    /// # New
+    ///
+    /// Create a resolver reading PEM files from the directory.
+    pub fn new<P: AsRef<Path>>(dir: P) -> PemFileResolver {
+        PemFileResolver {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+}
+
+#[cfg(feature = "openssl-keys")]
+impl KeyResolver for PemFileResolver {
+    fn resolve(&self, name: &str) -> TxBuilderResult<Key> {
+        let mut path = self.dir.join(name);
+
+        // Only files directly in the directory can be read
+        let mut components = Path::new(name).components();
+        let file_name = matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(_)), None)
+        );
+
+        if !file_name || name.contains(['/', '\\']) {
+            return Err(TxBuilderError::KeyFileError {
+                path,
+                source: io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "key name must be a file name in the resolver's directory",
+                ),
+            });
+        }
+
+        if path.extension().is_none() {
+            path.set_extension("pem");
+        }
+
+        let pem = match fs::read_to_string(&path) {
+            Ok(pem) => Zeroizing::new(pem),
+            Err(source) => return Err(TxBuilderError::KeyFileError { path, source }),
+        };
+
+        let key_name = match path.file_stem() {
+            Some(stem) => stem.to_string_lossy().into_owned(),
+            None => name.to_string(),
+        };
+
+        Key::from_pem(&key_name, &pem)
+    }
+}
+
+#[cfg(all(test, feature = "openssl-keys"))]
+mod tests {
+    use super::*;
+    use crate::Signees;
+    use activeledger::key::EllipticCurve;
+
+    #[test]
+    fn resolve_pem_file_specs() {
+        let dir = std::env::temp_dir().join("active_tx_resolver_test");
+        fs::create_dir_all(&dir).unwrap();
+        let pem = EllipticCurve::new("key")
+            .unwrap()
+            .get_pem()
+            .unwrap()
+            .private;
+        fs::write(dir.join("key.pem"), pem).unwrap();
+
+        let resolver = PemFileResolver::new(&dir);
+
+        let config = r#"[{"streamid": "stream", "key_name": "key", "key_type": "ec"}]"#;
+        let mut specs: Vec<SigneeSpec> = serde_json::from_str(config).unwrap();
+
+        let signees = Signees::from_specs(&specs, &resolver).unwrap();
+        assert!(signees.contains("stream"));
+        assert_eq!(signees.iter().next().unwrap().key.name(), "key");
+
+        specs[0].key_name = "renamed".to_string();
+        specs[0].pem_path_or_id = Some("key.pem".to_string());
+        let signees = Signees::from_specs(&specs, &resolver).unwrap();
+        assert_eq!(signees.iter().next().unwrap().key.name(), "renamed");
+
+        specs[0].key_type = Some(KeyType::RSA);
+        let err = Signees::from_specs(&specs, &resolver).unwrap_err();
+        assert_eq!(err.code(), 8002);
+
+        let err = resolver.resolve("missing").unwrap_err();
+        assert_eq!(err.code(), 8000);
+
+        // Names can't leave the directory
+        let outside = dir.parent().unwrap().join("outside.pem");
+        for name in &[
+            "../outside.pem",
+            "./key.pem",
+            "sub/key.pem",
+            "sub\\key.pem",
+            "..",
+            "",
+            outside.to_str().unwrap(),
+        ] {
+            match resolver.resolve(name).unwrap_err() {
+                TxBuilderError::KeyFileError { source, .. } => {
+                    assert_eq!(source.kind(), io::ErrorKind::InvalidInput, "{}", name)
+                }
+                other => panic!("unexpected error {}", other),
+            }
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,390 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Signing options
+//!
+//! Activeledger deployments can expect different signature algorithms and encodings.
+//! [`SignOptions`] selects them, the defaults match the activeledger crate (SHA256, DER encoded
+//! EC signatures, PKCS#1 v1.5 RSA padding and base64 encoding).
+//!
+//! External keys sign using their own choices, the options are not applied to them.
+
+// STD
+use std::borrow::Cow;
+
+// External imports
+#[cfg(feature = "openssl-keys")]
+use activeledger::key::KeyError;
+#[cfg(feature = "openssl-keys")]
+use openssl::ecdsa::EcdsaSig;
+#[cfg(feature = "openssl-keys")]
+use openssl::error::ErrorStack;
+#[cfg(feature = "openssl-keys")]
+use openssl::hash::MessageDigest;
+#[cfg(feature = "openssl-keys")]
+use openssl::pkey::{PKeyRef, Private};
+#[cfg(feature = "openssl-keys")]
+use openssl::rsa::Padding;
+#[cfg(feature = "openssl-keys")]
+use openssl::sha::{sha256, sha512};
+#[cfg(feature = "openssl-keys")]
+use openssl::sign::{RsaPssSaltlen, Signer};
+use serde::{Deserialize, Serialize};
+
+// Internal imports
+use super::Key;
+#[cfg(feature = "openssl-keys")]
+use super::KeyType;
+#[cfg(feature = "openssl-keys")]
+use crate::error::TxBuilderError;
+use crate::error::TxBuilderResult;
+
+/// Hash used when signing
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+/// How EC signatures are encoded
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EcSignatureEncoding {
+    /// ASN.1 DER sequence of r and s
+    #[default]
+    Der,
+    /// r and s as 32 byte big endian values, concatenated
+    Raw,
+}
+
+/// Padding used for RSA signatures
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RsaPadding {
+    #[default]
+    Pkcs1v15,
+    /// PSS with a salt the length of the digest
+    Pss,
+}
+
+/// How signatures are encoded into `$sigs`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignatureEncoding {
+    /// Standard base64
+    #[default]
+    Base64,
+    /// URL safe base64 without padding
+    Base64Url,
+    /// Lower case hex
+    Hex,
+}
+
+/// # Sign options
+///
+/// Options used when signing a packet. They can be set for a whole build using
+/// [`TransactionBuilder::sign_options`], or for a single key using
+/// [`Signees::add_with_options`].
+///
+/// ```
+/// # use active_tx::{EcSignatureEncoding, HashAlgorithm, SignOptions};
+/// let mut options = SignOptions::new();
+/// options
+///     .hash(HashAlgorithm::Sha512)
+///     .ec_encoding(EcSignatureEncoding::Raw);
+/// ```
+///
+/// [`TransactionBuilder::sign_options`]: struct.TransactionBuilder.html#method.sign_options
+/// [`Signees::add_with_options`]: struct.Signees.html#method.add_with_options
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct SignOptions {
+    pub hash: HashAlgorithm,
+    pub ec_encoding: EcSignatureEncoding,
+    pub rsa_padding: RsaPadding,
+    pub encoding: SignatureEncoding,
+    /// Domain tag the packet is wrapped with before signing, see [`SignOptions::context`]
+    ///
+    /// [`SignOptions::context`]: struct.SignOptions.html#method.context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+}
+
+impl SignOptions {
+    /// # New
+    ///
+    /// Create options with the defaults: SHA256, DER, PKCS#1 v1.5 and base64.
+    pub fn new() -> SignOptions {
+        SignOptions::default()
+    }
+
+    /// # Hash
+    ///
+    /// Set the hash algorithm.
+    pub fn hash(&mut self, hash: HashAlgorithm) -> &mut Self {
+        self.hash = hash;
+
+        self
+    }
+
+    /// # EC encoding
+    ///
+    /// Set how EC signatures are encoded.
+    pub fn ec_encoding(&mut self, encoding: EcSignatureEncoding) -> &mut Self {
+        self.ec_encoding = encoding;
+
+        self
+    }
+
+    /// # RSA padding
+    ///
+    /// Set the padding used for RSA signatures.
+    pub fn rsa_padding(&mut self, padding: RsaPadding) -> &mut Self {
+        self.rsa_padding = padding;
+
+        self
+    }
+
+    /// # Encoding
+    ///
+    /// Set how signatures are encoded into `$sigs`.
+    pub fn encoding(&mut self, encoding: SignatureEncoding) -> &mut Self {
+        self.encoding = encoding;
+
+        self
+    }
+
+    /// # Context
+    ///
+    /// Set a domain tag so signatures made for one application can't be replayed into another.
+    /// Instead of the packet itself, the signed data becomes
+    /// `{"$context":"<context>","$tx":<packet>}`, which contracts expecting the tag rebuild from
+    /// the transaction before checking `$sigs`. The same context must be given when verifying.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, SignOptions};
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// let public_key = key.public_key().unwrap();
+    ///
+    /// let mut options = SignOptions::new();
+    /// options.context("myapp:v2");
+    ///
+    /// let signature = key.sign("{}", &options).unwrap();
+    ///
+    /// assert!(public_key.verify("{}", &signature, &options));
+    /// assert!(!public_key.verify("{}", &signature, &SignOptions::new()));
+    /// # }
+    /// ```
+    pub fn context(&mut self, context: &str) -> &mut Self {
+        self.context = Some(context.to_string());
+
+        self
+    }
+
+    /// # Signed data
+    ///
+    /// Get the data that is actually signed for the given packet, which is the packet wrapped
+    /// in the context tag when one is set. Use this when signing with a key outside this crate.
+    ///
+    /// ```
+    /// # use active_tx::SignOptions;
+    /// let mut options = SignOptions::new();
+    /// assert_eq!(options.signed_data("{}"), "{}");
+    ///
+    /// options.context("myapp:v2");
+    /// assert_eq!(
+    ///     options.signed_data("{}"),
+    ///     r#"{"$context":"myapp:v2","$tx":{}}"#
+    /// );
+    /// ```
+    pub fn signed_data<'a>(&self, data: &'a str) -> Cow<'a, str> {
+        match self.context_wrapper() {
+            Some((prefix, suffix)) => Cow::Owned(format!("{}{}{}", prefix, data, suffix)),
+            None => Cow::Borrowed(data),
+        }
+    }
+}
+
+impl Key {
+    /// # Sign
+    ///
+    /// Sign the data using the given options, returning the encoded signature.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, SignOptions};
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let signature = key.sign("data", &SignOptions::new()).unwrap();
+    /// # }
+    /// ```
+    pub fn sign(&self, data: &str, options: &SignOptions) -> TxBuilderResult<String> {
+        self.sign_stream(data, self.name(), options)
+    }
+}
+
+// Crate functions
+impl Key {
+    /// Sign the data, reporting errors against the stream ID
+    #[cfg_attr(not(feature = "openssl-keys"), allow(unused_variables))]
+    pub(crate) fn sign_stream(
+        &self,
+        data: &str,
+        streamid: &str,
+        options: &SignOptions,
+    ) -> TxBuilderResult<String> {
+        let data = options.signed_data(data);
+
+        match self {
+            Key::External(signer) => signer.sign(&data),
+            #[cfg(feature = "openssl-keys")]
+            _ => self.sign_openssl(&data, streamid, options),
+        }
+    }
+
+    /// The longest encoded signature the key can produce
+    #[cfg_attr(not(feature = "openssl-keys"), allow(unused_variables))]
+    pub(crate) fn max_signature_len(&self, options: &SignOptions) -> TxBuilderResult<usize> {
+        match self {
+            Key::External(signer) => Ok(signer.max_signature_len()),
+            #[cfg(feature = "openssl-keys")]
+            _ => Ok(options.max_signature_len(&*self.pkey()?, self.key_type())),
+        }
+    }
+}
+
+impl SignOptions {
+    /// The text written before and after the packet when a context is set
+    pub(crate) fn context_wrapper(&self) -> Option<(String, &'static str)> {
+        self.context.as_ref().map(|context| {
+            let context = serde_json::Value::String(context.to_string());
+
+            (format!("{{\"$context\":{},\"$tx\":", context), "}")
+        })
+    }
+}
+
+// Private functions
+#[cfg(feature = "openssl-keys")]
+impl Key {
+    fn sign_openssl(
+        &self,
+        data: &str,
+        streamid: &str,
+        options: &SignOptions,
+    ) -> TxBuilderResult<String> {
+        let pkey = self.pkey()?;
+
+        let error = |code| TxBuilderError::SigningError {
+            streamid: streamid.to_string(),
+            key_type: self.key_type(),
+            source: KeyError::SigningError(code),
+        };
+
+        let mut signer = match options.signer(&pkey) {
+            Ok(signer) => signer,
+            Err(_) => return Err(error(2000)),
+        };
+
+        if signer.update(data.as_bytes()).is_err() {
+            return Err(error(2001));
+        }
+
+        match options.finish(signer, self.key_type()) {
+            Ok(signature) => Ok(options.encode(&signature)),
+            Err(_) => Err(error(2002)),
+        }
+    }
+}
+
+#[cfg(feature = "openssl-keys")]
+impl HashAlgorithm {
+    /// Hash the data
+    pub(crate) fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlgorithm::Sha256 => sha256(data).to_vec(),
+            HashAlgorithm::Sha512 => sha512(data).to_vec(),
+        }
+    }
+}
+
+#[cfg(feature = "openssl-keys")]
+impl SignOptions {
+    /// Create a signer for the key using these options
+    pub(crate) fn signer<'a>(&self, pkey: &'a PKeyRef<Private>) -> Result<Signer<'a>, ErrorStack> {
+        let digest = match self.hash {
+            HashAlgorithm::Sha256 => MessageDigest::sha256(),
+            HashAlgorithm::Sha512 => MessageDigest::sha512(),
+        };
+
+        let mut signer = Signer::new(digest, pkey)?;
+
+        if pkey.rsa().is_ok() && self.rsa_padding == RsaPadding::Pss {
+            signer.set_rsa_padding(Padding::PKCS1_PSS)?;
+            signer.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+        }
+
+        Ok(signer)
+    }
+
+    /// Produce the signature bytes in the configured encoding
+    pub(crate) fn finish(&self, signer: Signer, key_type: KeyType) -> Result<Vec<u8>, ErrorStack> {
+        let signature = signer.sign_to_vec()?;
+
+        if key_type == KeyType::EC && self.ec_encoding == EcSignatureEncoding::Raw {
+            let signature = EcdsaSig::from_der(&signature)?;
+
+            let mut raw = signature.r().to_vec_padded(32)?;
+            raw.extend(signature.s().to_vec_padded(32)?);
+
+            return Ok(raw);
+        }
+
+        Ok(signature)
+    }
+
+    /// The longest encoded signature the key can produce with these options
+    pub(crate) fn max_signature_len(&self, pkey: &PKeyRef<Private>, key_type: KeyType) -> usize {
+        let len = match key_type {
+            KeyType::EC if self.ec_encoding == EcSignatureEncoding::Raw => 64,
+            _ => pkey.size(),
+        };
+
+        match self.encoding {
+            SignatureEncoding::Base64 => len.div_ceil(3) * 4,
+            SignatureEncoding::Base64Url => (len * 4).div_ceil(3),
+            SignatureEncoding::Hex => len * 2,
+        }
+    }
+
+    /// Encode the signature bytes for `$sigs`
+    pub(crate) fn encode(&self, signature: &[u8]) -> String {
+        match self.encoding {
+            SignatureEncoding::Base64 => base64::encode(signature),
+            SignatureEncoding::Base64Url => {
+                base64::encode_config(signature, base64::URL_SAFE_NO_PAD)
+            }
+            SignatureEncoding::Hex => signature.iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+}
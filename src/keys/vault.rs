@@ -0,0 +1,248 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Vault transit signer
+//!
+//! Signs packets with a key held by the transit secrets engine of HashiCorp Vault, so the
+//! private key never enters application memory. The public key is read from Vault for
+//! onboarding.
+//!
+//! Activeledger EC keys use secp256k1, which the transit engine does not support, so the
+//! transit key must be an RSA key (`rsa-2048`, `rsa-3072` or `rsa-4096`). Packets are signed
+//! using PKCS#1 v1.5 padding with SHA256, as Activeledger expects.
+//!
+//! Requires the `vault` feature.
+//!
+//! ## Example
+//! ```no_run
+//! # use std::sync::Arc;
+//! # use active_tx::{signees, Key, TransactionBuilder, VaultSigner};
+//! let signer = VaultSigner::connect("https://vault:8200", "token", "activeledger").unwrap();
+//! let key = Key::External(Arc::new(signer));
+//!
+//! let onboard = TransactionBuilder::onboard_tx(key.clone()).unwrap();
+//! ```
+
+// External imports
+use serde_json::{json, Value};
+use zeroize::Zeroizing;
+
+// Internal imports
+use super::{KeyType, Signer};
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Mount path used by Vault for the transit engine unless configured otherwise
+const DEFAULT_MOUNT: &str = "transit";
+
+/// # Vault signer
+///
+/// A [`Signer`](trait.Signer.html) backed by a Vault transit key. Wrap it in
+/// [`Key::External`](enum.Key.html#variant.External) to sign with it.
+///
+/// The key type and public key are read once when connecting, each signature is a request to
+/// Vault. Signatures are made with the key version read when connecting, so they always match
+/// the public key even if the transit key is rotated. Reconnect to use a rotated key.
+pub struct VaultSigner {
+    client: reqwest::Client,
+    address: String,
+    token: Zeroizing<String>,
+    mount: String,
+    key_name: String,
+    key_type: KeyType,
+    key_version: u64,
+    public_pem: String,
+}
+
+impl VaultSigner {
+    /// # Connect
+    ///
+    /// Connect to Vault at the address using the token, and read the transit key with the
+    /// given name from the engine mounted at `transit`.
+    ///
+    /// An error is returned if Vault can't be reached, the key can't be read or it is not an
+    /// RSA key.
+    pub fn connect(address: &str, token: &str, key_name: &str) -> TxBuilderResult<VaultSigner> {
+        VaultSigner::connect_with_mount(address, token, DEFAULT_MOUNT, key_name)
+    }
+
+    /// # Connect with mount
+    ///
+    /// Connect to Vault as with [`connect`](#method.connect), using the transit engine mounted
+    /// at the given path.
+    pub fn connect_with_mount(
+        address: &str,
+        token: &str,
+        mount: &str,
+        key_name: &str,
+    ) -> TxBuilderResult<VaultSigner> {
+        let mut signer = VaultSigner {
+            client: reqwest::Client::new(),
+            address: address.trim_end_matches('/').to_string(),
+            token: Zeroizing::new(token.to_string()),
+            mount: mount.trim_matches('/').to_string(),
+            key_name: key_name.to_string(),
+            key_type: KeyType::RSA,
+            key_version: 0,
+            public_pem: String::new(),
+        };
+
+        let url = format!("{}/v1/{}/keys/{}", signer.address, signer.mount, key_name);
+        let body = signer.request(signer.client.get(&url))?;
+
+        let (key_type, key_version, public_pem) = parse_key_info(&body)?;
+        signer.key_type = key_type;
+        signer.key_version = key_version;
+        signer.public_pem = public_pem;
+
+        Ok(signer)
+    }
+}
+
+impl Signer for VaultSigner {
+    fn name(&self) -> &str {
+        &self.key_name
+    }
+
+    fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    fn sign(&self, data: &str) -> TxBuilderResult<String> {
+        let url = format!(
+            "{}/v1/{}/sign/{}/sha2-256",
+            self.address, self.mount, self.key_name
+        );
+
+        let request = json!({
+            "input": base64::encode(data),
+            "signature_algorithm": "pkcs1v15",
+            "key_version": self.key_version,
+        });
+
+        let body = self.request(self.client.post(&url).json(&request))?;
+
+        parse_signature(&body, self.key_version)
+    }
+
+    fn public_pem(&self) -> TxBuilderResult<String> {
+        Ok(self.public_pem.clone())
+    }
+}
+
+// Private functions
+impl VaultSigner {
+    /// Send a request with the Vault token, returning the response body
+    fn request(&self, request: reqwest::RequestBuilder) -> TxBuilderResult<Value> {
+        let mut res = match request.header("X-Vault-Token", self.token.as_str()).send() {
+            Ok(res) => res,
+            Err(e) => return Err(vault_error(format!("request failed - {}", e))),
+        };
+
+        if !res.status().is_success() {
+            return Err(vault_error(format!("returned status {}", res.status())));
+        }
+
+        match res.json() {
+            Ok(body) => Ok(body),
+            Err(e) => Err(vault_error(format!("response could not be parsed - {}", e))),
+        }
+    }
+}
+
+/// Read the key type, latest version and its public key from a transit key response
+fn parse_key_info(body: &Value) -> TxBuilderResult<(KeyType, u64, String)> {
+    let data = &body["data"];
+
+    let key_type = match data["type"].as_str() {
+        Some(key_type) if key_type.starts_with("rsa-") => KeyType::RSA,
+        Some(_) => return Err(TxBuilderError::UnsupportedKeyType),
+        None => return Err(vault_error("key response has no type".to_string())),
+    };
+
+    let version = match data["latest_version"].as_u64() {
+        Some(version) => version,
+        None => {
+            return Err(vault_error(
+                "key response has no latest version".to_string(),
+            ))
+        }
+    };
+
+    match data["keys"][&version.to_string()]["public_key"].as_str() {
+        Some(pem) => Ok((key_type, version, pem.to_string())),
+        None => Err(vault_error("key response has no public key".to_string())),
+    }
+}
+
+/// Take the base64 signature from a transit sign response, checking the vault version prefix
+/// matches the pinned key version
+fn parse_signature(body: &Value, key_version: u64) -> TxBuilderResult<String> {
+    let signature = match body["data"]["signature"].as_str() {
+        Some(signature) => signature,
+        None => return Err(vault_error("sign response has no signature".to_string())),
+    };
+
+    // Signatures are returned as "vault:v<key version>:<base64>"
+    let prefix = format!("vault:v{}:", key_version);
+    match signature.strip_prefix(prefix.as_str()) {
+        Some(encoded) if !encoded.is_empty() => Ok(encoded.to_string()),
+        _ => Err(vault_error(format!("unexpected signature '{}'", signature))),
+    }
+}
+
+fn vault_error(message: String) -> TxBuilderError {
+    TxBuilderError::SignerError(format!("vault {}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vault_parse_responses() {
+        let key = json!({"data": {
+            "type": "rsa-2048",
+            "latest_version": 2,
+            "keys": {
+                "1": {"public_key": "old"},
+                "2": {"public_key": "-----BEGIN PUBLIC KEY-----"}
+            }
+        }});
+        let (key_type, version, pem) = parse_key_info(&key).unwrap();
+        assert_eq!(key_type, KeyType::RSA);
+        assert_eq!(version, 2);
+        assert_eq!(pem, "-----BEGIN PUBLIC KEY-----");
+
+        let ec = json!({"data": {"type": "ecdsa-p256", "latest_version": 1, "keys": {}}});
+        assert_eq!(parse_key_info(&ec).unwrap_err().code(), 8003);
+
+        let signature = json!({"data": {"signature": "vault:v2:c2lnbmF0dXJl"}});
+        assert_eq!(parse_signature(&signature, 2).unwrap(), "c2lnbmF0dXJl");
+
+        // Signed with a version other than the one the public key was read for
+        assert_eq!(parse_signature(&signature, 1).unwrap_err().code(), 7003);
+
+        let missing = json!({"data": {}});
+        assert_eq!(parse_signature(&missing, 2).unwrap_err().code(), 7003);
+    }
+}
@@ -0,0 +1,170 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Verification
+//!
+//! Check packet signatures using only the public key, so services that verify transactions
+//! never need to hold private keys.
+
+// External imports
+use openssl::bn::BigNum;
+use openssl::ecdsa::EcdsaSig;
+use openssl::error::ErrorStack;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Public};
+use openssl::rsa::Padding;
+use openssl::sign::{RsaPssSaltlen, Verifier};
+
+// Internal imports
+use super::{
+    EcSignatureEncoding, HashAlgorithm, Key, KeyType, RsaPadding, SignOptions, SignatureEncoding,
+};
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::TransactionBuilder;
+
+/// # Public key
+///
+/// The public half of a key, used to verify signatures.
+///
+/// ```
+/// # use active_tx::{Key, PublicKey, SignOptions};
+/// # use activeledger::key::EllipticCurve;
+/// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+/// let signature = key.sign("data", &SignOptions::new()).unwrap();
+///
+/// let public_key = key.public_key().unwrap();
+/// assert!(public_key.verify("data", &signature, &SignOptions::new()));
+/// ```
+#[derive(Clone)]
+pub struct PublicKey {
+    pub(super) pkey: PKey<Public>,
+    key_type: KeyType,
+}
+
+impl PublicKey {
+    /// # From PEM
+    ///
+    /// Load a public key PEM, as given to the ledger when a key is onboarded.
+    pub fn from_pem(pem: &str) -> TxBuilderResult<PublicKey> {
+        let pkey = match PKey::public_key_from_pem(pem.as_bytes()) {
+            Ok(pkey) => pkey,
+            Err(e) => return Err(TxBuilderError::PublicKeyParseError(e)),
+        };
+
+        let key_type = Key::pkey_type(&pkey)?;
+
+        Ok(PublicKey { pkey, key_type })
+    }
+
+    /// # Key type
+    ///
+    /// Get the type of the key.
+    pub fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    /// # Verify
+    ///
+    /// Check an encoded signature over the data, using the options it was signed with.
+    /// Signatures that can't be decoded are not valid.
+    pub fn verify(&self, data: &str, signature: &str, options: &SignOptions) -> bool {
+        let signature = match options.decode(signature) {
+            Some(signature) => signature,
+            None => return false,
+        };
+
+        self.verify_bytes(&options.signed_data(data), &signature, options)
+            .unwrap_or(false)
+    }
+}
+
+impl Key {
+    /// # Public key
+    ///
+    /// Get the public half of the key for verifying its signatures.
+    pub fn public_key(&self) -> TxBuilderResult<PublicKey> {
+        let pem = TransactionBuilder::get_pem(self)?;
+
+        PublicKey::from_pem(&pem)
+    }
+}
+
+// Private functions
+impl PublicKey {
+    fn verify_bytes(
+        &self,
+        data: &str,
+        signature: &[u8],
+        options: &SignOptions,
+    ) -> Result<bool, ErrorStack> {
+        let digest = match options.hash {
+            HashAlgorithm::Sha256 => MessageDigest::sha256(),
+            HashAlgorithm::Sha512 => MessageDigest::sha512(),
+        };
+
+        let mut verifier = Verifier::new(digest, &self.pkey)?;
+
+        if self.key_type == KeyType::RSA && options.rsa_padding == RsaPadding::Pss {
+            verifier.set_rsa_padding(Padding::PKCS1_PSS)?;
+            verifier.set_rsa_pss_saltlen(RsaPssSaltlen::DIGEST_LENGTH)?;
+        }
+
+        verifier.update(data.as_bytes())?;
+
+        if self.key_type == KeyType::EC && options.ec_encoding == EcSignatureEncoding::Raw {
+            if signature.len() != 64 {
+                return Ok(false);
+            }
+
+            let r = BigNum::from_slice(&signature[..32])?;
+            let s = BigNum::from_slice(&signature[32..])?;
+            let der = EcdsaSig::from_private_components(r, s)?.to_der()?;
+
+            return verifier.verify(&der);
+        }
+
+        verifier.verify(signature)
+    }
+}
+
+impl SignOptions {
+    /// Decode a signature from `$sigs`
+    fn decode(&self, signature: &str) -> Option<Vec<u8>> {
+        match self.encoding {
+            SignatureEncoding::Base64 => base64::decode(signature).ok(),
+            SignatureEncoding::Base64Url => {
+                base64::decode_config(signature, base64::URL_SAFE_NO_PAD).ok()
+            }
+            SignatureEncoding::Hex => {
+                if !signature.len().is_multiple_of(2) || !signature.is_ascii() {
+                    return None;
+                }
+
+                (0..signature.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&signature[i..i + 2], 16).ok())
+                    .collect()
+            }
+        }
+    }
+}
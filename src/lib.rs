@@ -36,6 +36,61 @@
 //! This crate provides macros as well as builders that help create a transaction with the correct structure.
 //! Additionally it provides two methods of creating a complete onboarding transaction. With and without a provided key.
 //!
+//! ## WebAssembly
+//!
+//! The crate builds for `wasm32-unknown-unknown` with default features disabled, which removes
+//! the OpenSSL backed RSA and EC keys. Keys are then supplied through the
+//! [`Signer`](trait.Signer.html) trait. The `wasm` feature adds JavaScript bindings for the
+//! transaction builder, packet builder and signees, signing with a JavaScript function.
+//!
+//! ```toml
+//! active_tx = { version = "0.1", default-features = false, features = ["wasm"] }
+//! ```
+//!
+//! ## Thread safety
+//!
+//! [`Key`], [`Signees`], [`TransactionBuilder`] and [`TxTemplate`] are `Send` and `Sync`.
+//! A set of keys can be created once, held in an `Arc` and shared by worker threads or tasks
+//! without a `Mutex`, each worker cloning the [`Signees`] it needs (the keys are not copied).
+//!
+//! ```
+//! # #[cfg(feature = "openssl-keys")] {
+//! # use std::sync::Arc;
+//! # use std::thread;
+//! # use active_tx::{Key, PacketBuilder, Signees, TransactionBuilder, packet_data};
+//! # use activeledger::key::EllipticCurve;
+//! let key = Key::Ec(EllipticCurve::new("keyname").unwrap()).into_shared();
+//! let signees = Arc::new(Signees::from_shared(vec![(key, "keyname")]));
+//!
+//! let workers: Vec<_> = (0..4)
+//!     .map(|n| {
+//!         let signees = Arc::clone(&signees);
+//!
+//!         thread::spawn(move || {
+//!             let input = PacketBuilder::new(packet_data!({"keyname": {"worker": n}}))
+//!                 .build()
+//!                 .unwrap();
+//!
+//!             TransactionBuilder::new("namespace", "contract")
+//!                 .input(input)
+//!                 .unwrap()
+//!                 .build(Signees::clone(&signees))
+//!                 .unwrap()
+//!         })
+//!     })
+//!     .collect();
+//!
+//! for worker in workers {
+//!     worker.join().unwrap();
+//! }
+//! # }
+//! ```
+//!
+//! [`Key`]: enum.Key.html
+//! [`Signees`]: struct.Signees.html
+//! [`TransactionBuilder`]: struct.TransactionBuilder.html
+//! [`TxTemplate`]: struct.TxTemplate.html
+//!
 //! ## Additional Activeledger crates
 //! Adhearing to the Rust mentality of keeping things small we have created other crates that can be used in conjunction
 //! with this one to add additional functionality.
@@ -61,9 +116,9 @@
 //! ## Example usage
 //!
 //! ```
+//! # #[cfg(feature = "openssl-keys")] {
 //! # use active_tx::{PacketBuilder, TransactionBuilder, Key, packet_data, signees};
 //! # use activeledger::key::EllipticCurve;
-//! # fn main() {
 //! let input = packet_data!({"[streamid]": {"input": "data"}});
 //!
 //! let input_data = PacketBuilder::new(input).build().unwrap();
@@ -85,11 +140,123 @@
 //!
 //! For more information on the usage of this crate see the [`TransactionBuilder`] documentation.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+mod assembly;
+#[cfg(feature = "std")]
+pub mod compat;
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+mod encoding;
 mod error;
+#[cfg(feature = "events")]
+mod events;
+#[cfg(feature = "std")]
+mod explain;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+#[cfg(feature = "std")]
+mod keys;
+#[cfg(feature = "std")]
+mod lint;
+#[cfg(feature = "std")]
 mod macros;
+#[cfg(feature = "std")]
 mod packet_builder;
+#[cfg(feature = "std")]
+mod response;
+#[cfg(feature = "http")]
+mod submit;
+#[cfg(feature = "std")]
 mod transaction_builder;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+mod wasm;
 
-pub use error::{TxBuilderError, TxBuilderResult};
-pub use packet_builder::{PacketBuilder, PacketData, PacketValue};
-pub use transaction_builder::{Key, KeyType, Signees, TransactionBuilder};
+pub use crate::assembly::{constant_time_eq, FnSigner, KeyType, Signer, TxAssembler};
+/// # Packet data derive
+///
+/// Convert a struct into packet data. Requires the `derive` feature.
+///
+/// Fields can be renamed with `#[packet(rename = "name")]` and left out with `#[packet(skip)]`.
+/// `Option` fields are left out when they are `None`.
+///
+/// ```
+/// # use std::convert::TryFrom;
+/// # use active_tx::{PacketData, PacketValue};
+/// # use serde_json::json;
+/// #[derive(PacketData)]
+/// struct Reading {
+///     #[packet(rename = "sensorId")]
+///     sensor_id: String,
+///     values: Vec<i64>,
+///     note: Option<String>,
+///     #[packet(skip)]
+///     cached: bool,
+/// }
+///
+/// let reading = Reading {
+///     sensor_id: String::from("sensor-1"),
+///     values: vec![20, 21],
+///     note: None,
+///     cached: true,
+/// };
+///
+/// let input = PacketData::try_from(reading).unwrap();
+///
+/// assert_eq!(input.get().unwrap(), json!({"sensorId": "sensor-1", "values": [20, 21]}));
+/// ```
+#[cfg(feature = "derive")]
+pub use active_tx_derive::PacketData;
+#[cfg(feature = "std")]
+pub use diff::{tx_diff, Change, TxDiff};
+pub use error::{ErrorContext, TxBuilderError, TxBuilderResult};
+#[cfg(feature = "std")]
+pub use explain::{explain, TxSummary};
+#[cfg(feature = "mnemonic")]
+pub use keys::KeyFactory;
+#[cfg(feature = "vault")]
+pub use keys::VaultSigner;
+#[cfg(feature = "std")]
+pub use keys::{
+    EcSignatureEncoding, HashAlgorithm, IntoKey, Key, KeyResolver, RsaPadding, SignOptions,
+    SignatureEncoding, SigneeSpec,
+};
+#[cfg(feature = "openssl-keys")]
+pub use keys::{KeyStore, KeyStoreResolver, PemFileResolver, PublicKey};
+#[cfg(feature = "pkcs11")]
+pub use keys::{Pkcs11Config, Pkcs11Signer};
+#[cfg(feature = "std")]
+pub use lint::{LintIssue, LintRule, Severity, TxLinter};
+#[cfg(feature = "openssl-keys")]
+pub use packet_builder::decrypt_field;
+#[cfg(feature = "decimal")]
+pub use packet_builder::DecimalFormat;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use packet_builder::{
+    decompress_field, decompress_field_with_limit, Compression, MAX_DECOMPRESSED_SIZE,
+};
+#[cfg(feature = "std")]
+pub use packet_builder::{
+    BinaryFormat, OutputBuilder, PacketBuilder, PacketData, PacketMap, PacketValue,
+    ReadonlyBuilder, StreamData, StreamId, TimestampFormat,
+};
+#[cfg(feature = "csv")]
+pub use packet_builder::{ColumnType, CsvLoader, CsvPackets};
+#[cfg(feature = "std")]
+pub use response::{LedgerResponse, StreamRef, StreamsCreated, Summary};
+#[cfg(feature = "http")]
+pub use submit::submit;
+#[cfg(feature = "openssl-keys")]
+pub use transaction_builder::{
+    is_replay, signature_matches, AccessTxBuilder, IdempotentTx, Verifier, VerifierSet,
+};
+#[cfg(feature = "std")]
+pub use transaction_builder::{
+    BatchChunk, BatchChunker, BatchChunks, BatchMode, BuiltTransaction, ContractClient, Entry,
+    MultiSigCollector, NodeReferences, OnboardTxBuilder, Profile, Signee, Signees, StreamSignature,
+    Territoriality, TransactionBuilder, TxEvent, TxSnapshot, TxState, TxStatus, TxStream,
+    TxTemplate,
+};
@@ -0,0 +1,521 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Transaction linter
+//!
+//! Pre-flight checks for transactions that are well formed but unlikely to do what was meant,
+//! such as a territoriality on a broadcast transaction. The ledger would accept or reject these
+//! only after consensus, the linter finds them before submission.
+//!
+//! Each check is a [`LintRule`], the default rules can be removed and new rules added.
+//!
+//! ## Example
+//! ```
+//! # use active_tx::{Severity, TxLinter};
+//! # use serde_json::json;
+//! let tx = json!({
+//!     "$tx": {
+//!         "$namespace": "ns",
+//!         "$contract": "contract",
+//!         "$i": {"a1b2": {}},
+//!         "$o": {}
+//!     },
+//!     "$broadcast": true,
+//!     "$territoriality": "node1"
+//! });
+//!
+//! let issues = TxLinter::new().lint(&tx);
+//!
+//! assert!(issues.iter().any(|issue| issue.rule == "broadcast-territoriality"));
+//! assert!(issues.iter().any(|issue| issue.severity == Severity::Warning));
+//! ```
+//!
+//! [`LintRule`]: trait.LintRule.html
+
+// STD
+use std::collections::BTreeSet;
+use std::fmt;
+
+// External imports
+use serde::Serialize;
+use serde_json::Value;
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::explain::flag;
+use crate::packet_builder::is_ledger_id;
+
+/// How serious a lint issue is.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The transaction may work but is probably not what was meant
+    Warning,
+    /// The transaction will be rejected or behave incorrectly
+    Error,
+}
+
+/// # Lint issue
+///
+/// A problem found by a [`LintRule`](trait.LintRule.html).
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct LintIssue {
+    /// Name of the rule that found the issue
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// # Lint rule
+///
+/// A check run by the [`TxLinter`](struct.TxLinter.html). The rule is given the transaction
+/// envelope as JSON and returns the issues it finds.
+///
+/// ```
+/// # use active_tx::{LintIssue, LintRule, Severity, TxLinter};
+/// # use serde_json::{json, Value};
+/// struct RequireEntry;
+///
+/// impl LintRule for RequireEntry {
+///     fn name(&self) -> &'static str {
+///         "require-entry"
+///     }
+///
+///     fn check(&self, tx: &Value) -> Vec<LintIssue> {
+///         if tx["$tx"].get("$entry").is_some() {
+///             return Vec::new();
+///         }
+///
+///         vec![LintIssue {
+///             rule: self.name(),
+///             severity: Severity::Error,
+///             message: String::from("$entry is not set"),
+///         }]
+///     }
+/// }
+///
+/// let mut linter = TxLinter::new();
+/// linter.rule(RequireEntry);
+///
+/// assert!(linter.check(&json!({"$tx": {"$contract": "c"}})).is_err());
+/// ```
+pub trait LintRule: Send + Sync {
+    /// Name of the rule, used in issues and to remove the rule from a linter
+    fn name(&self) -> &'static str;
+
+    /// Check the transaction, returning any issues found
+    fn check(&self, tx: &Value) -> Vec<LintIssue>;
+}
+
+/// # Transaction linter
+///
+/// Runs a set of [`LintRule`]s over a transaction. [`new`](#method.new) starts with the default
+/// rules:
+///
+/// * `unused-output` - `$o` is present but empty (warning)
+/// * `selfsign-contract` - `$selfsign` is set on a transaction not calling the onboard contract (warning)
+/// * `broadcast-territoriality` - `$territoriality` is set on a broadcast transaction (error)
+/// * `stream-id-format` - a stream ID in `$i`, `$o` or `$r` is not 64 hex characters (warning),
+///   inputs of self signed transactions are key names and are not checked
+/// * `duplicate-stream` - a stream is in both `$i` and `$o` (error)
+///
+/// [`LintRule`]: trait.LintRule.html
+pub struct TxLinter {
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+impl TxLinter {
+    /// # New
+    ///
+    /// Create a linter with the default rules.
+    pub fn new() -> TxLinter {
+        let mut linter = TxLinter::empty();
+
+        linter
+            .rule(UnusedOutput)
+            .rule(SelfsignContract)
+            .rule(BroadcastTerritoriality)
+            .rule(StreamIdFormat)
+            .rule(DuplicateStream);
+
+        linter
+    }
+
+    /// # Empty
+    ///
+    /// Create a linter without any rules.
+    pub fn empty() -> TxLinter {
+        TxLinter { rules: Vec::new() }
+    }
+
+    /// # Rule
+    ///
+    /// Add a rule to the linter.
+    pub fn rule<R: LintRule + 'static>(&mut self, rule: R) -> &mut Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// # Remove
+    ///
+    /// Remove the rule with the given name, returning whether it was found.
+    ///
+    /// ```
+    /// # use active_tx::TxLinter;
+    /// # use serde_json::json;
+    /// let mut linter = TxLinter::new();
+    /// assert!(linter.remove("stream-id-format"));
+    ///
+    /// assert!(linter.lint(&json!({"$tx": {"$i": {"streamid": {}}}})).is_empty());
+    /// ```
+    pub fn remove(&mut self, name: &str) -> bool {
+        let len = self.rules.len();
+        self.rules.retain(|rule| rule.name() != name);
+
+        self.rules.len() != len
+    }
+
+    /// # Rule names
+    ///
+    /// Get the names of the rules in the order they are run.
+    pub fn rule_names(&self) -> Vec<&'static str> {
+        self.rules.iter().map(|rule| rule.name()).collect()
+    }
+
+    /// # Lint
+    ///
+    /// Run every rule over the transaction, given as the full envelope, returning all issues
+    /// found in rule order.
+    pub fn lint(&self, tx: &Value) -> Vec<LintIssue> {
+        self.rules.iter().flat_map(|rule| rule.check(tx)).collect()
+    }
+
+    /// # Check
+    ///
+    /// Run every rule over the transaction, returning the warnings when there are no errors.
+    /// If any rule finds an error `LintFailed` is returned holding each error message.
+    ///
+    /// ```
+    /// # use active_tx::TxLinter;
+    /// # use serde_json::json;
+    /// let tx = json!({"$tx": {"$namespace": "ns", "$contract": "contract", "$o": {}}});
+    ///
+    /// let warnings = TxLinter::new().check(&tx).unwrap();
+    /// assert_eq!(warnings[0].rule, "unused-output");
+    /// ```
+    pub fn check(&self, tx: &Value) -> TxBuilderResult<Vec<LintIssue>> {
+        let (errors, warnings): (Vec<LintIssue>, Vec<LintIssue>) = self
+            .lint(tx)
+            .into_iter()
+            .partition(|issue| issue.severity == Severity::Error);
+
+        if errors.is_empty() {
+            Ok(warnings)
+        } else {
+            Err(TxBuilderError::LintFailed(
+                errors.iter().map(|issue| issue.to_string()).collect(),
+            ))
+        }
+    }
+}
+
+impl Default for TxLinter {
+    fn default() -> Self {
+        TxLinter::new()
+    }
+}
+
+impl fmt::Debug for TxLinter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TxLinter")
+            .field("rules", &self.rule_names())
+            .finish()
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl fmt::Display for LintIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]: {}", self.severity, self.rule, self.message)
+    }
+}
+
+fn issue(rule: &'static str, severity: Severity, message: String) -> LintIssue {
+    LintIssue {
+        rule,
+        severity,
+        message,
+    }
+}
+
+/// Get the keys of an object in the packet
+fn section<'a>(tx: &'a Value, key: &str) -> Vec<&'a String> {
+    match tx.get("$tx").and_then(|packet| packet.get(key)) {
+        Some(Value::Object(map)) => map.keys().collect(),
+        _ => Vec::new(),
+    }
+}
+
+struct UnusedOutput;
+
+impl LintRule for UnusedOutput {
+    fn name(&self) -> &'static str {
+        "unused-output"
+    }
+
+    fn check(&self, tx: &Value) -> Vec<LintIssue> {
+        match tx.get("$tx").and_then(|packet| packet.get("$o")) {
+            Some(Value::Object(output)) if output.is_empty() => vec![issue(
+                self.name(),
+                Severity::Warning,
+                String::from("$o is present but has no streams"),
+            )],
+            _ => Vec::new(),
+        }
+    }
+}
+
+struct SelfsignContract;
+
+impl LintRule for SelfsignContract {
+    fn name(&self) -> &'static str {
+        "selfsign-contract"
+    }
+
+    fn check(&self, tx: &Value) -> Vec<LintIssue> {
+        if !flag(tx.get("$selfsign")) {
+            return Vec::new();
+        }
+
+        match tx["$tx"].get("$contract").and_then(Value::as_str) {
+            Some("onboard") => Vec::new(),
+            contract => vec![issue(
+                self.name(),
+                Severity::Warning,
+                format!(
+                    "$selfsign is set but the contract is '{}', not 'onboard'",
+                    contract.unwrap_or("")
+                ),
+            )],
+        }
+    }
+}
+
+struct BroadcastTerritoriality;
+
+impl LintRule for BroadcastTerritoriality {
+    fn name(&self) -> &'static str {
+        "broadcast-territoriality"
+    }
+
+    fn check(&self, tx: &Value) -> Vec<LintIssue> {
+        if flag(tx.get("$broadcast")) && flag(tx.get("$territoriality")) {
+            vec![issue(
+                self.name(),
+                Severity::Error,
+                String::from("$territoriality is set on a broadcast transaction"),
+            )]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+struct StreamIdFormat;
+
+impl LintRule for StreamIdFormat {
+    fn name(&self) -> &'static str {
+        "stream-id-format"
+    }
+
+    fn check(&self, tx: &Value) -> Vec<LintIssue> {
+        let mut streams: Vec<(&str, &str)> = Vec::new();
+
+        // Self signed inputs are named by the key rather than a stream ID
+        if !flag(tx.get("$selfsign")) {
+            streams.extend(section(tx, "$i").into_iter().map(|id| ("$i", id.as_str())));
+        }
+
+        streams.extend(section(tx, "$o").into_iter().map(|id| ("$o", id.as_str())));
+
+        if let Some(Value::Object(readonly)) = tx.get("$tx").and_then(|packet| packet.get("$r")) {
+            streams.extend(
+                readonly
+                    .values()
+                    .filter_map(Value::as_str)
+                    .map(|id| ("$r", id)),
+            );
+        }
+
+        streams
+            .into_iter()
+            .filter(|(_, id)| !is_ledger_id(id))
+            .map(|(section, id)| {
+                issue(
+                    self.name(),
+                    Severity::Warning,
+                    format!("'{}' in {} is not a valid stream ID", id, section),
+                )
+            })
+            .collect()
+    }
+}
+
+struct DuplicateStream;
+
+impl LintRule for DuplicateStream {
+    fn name(&self) -> &'static str {
+        "duplicate-stream"
+    }
+
+    fn check(&self, tx: &Value) -> Vec<LintIssue> {
+        let inputs: BTreeSet<&String> = section(tx, "$i").into_iter().collect();
+
+        section(tx, "$o")
+            .into_iter()
+            .filter(|id| inputs.contains(id))
+            .map(|id| {
+                issue(
+                    self.name(),
+                    Severity::Error,
+                    format!("'{}' is in both $i and $o", id),
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const STREAM_A: &str = "a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90";
+    const STREAM_B: &str = "0f1e2d3c4b5a69788796a5b4c3d2e1f00f1e2d3c4b5a69788796a5b4c3d2e1f0";
+
+    fn rules(issues: &[LintIssue]) -> Vec<&'static str> {
+        issues.iter().map(|issue| issue.rule).collect()
+    }
+
+    #[test]
+    fn lint_clean_transaction() {
+        let tx = json!({
+            "$tx": {
+                "$namespace": "ns",
+                "$contract": "contract",
+                "$i": {STREAM_A: {}},
+                "$o": {STREAM_B: {}},
+                "$r": {"rates": STREAM_B}
+            },
+            "$sigs": {STREAM_A: "sig"},
+            "$territoriality": "node1"
+        });
+
+        assert!(TxLinter::new().lint(&tx).is_empty());
+        assert!(TxLinter::new().check(&tx).unwrap().is_empty());
+
+        // Onboarding uses the key name as the input
+        let onboard = json!({
+            "$tx": {"$namespace": "default", "$contract": "onboard", "$i": {"keyname": {}}},
+            "$selfsign": true
+        });
+
+        assert!(TxLinter::new().lint(&onboard).is_empty());
+    }
+
+    #[test]
+    fn lint_default_rules() {
+        let tx = json!({
+            "$tx": {
+                "$namespace": "ns",
+                "$contract": "contract",
+                "$i": {STREAM_A: {}, "short": {}},
+                "$o": {}
+            },
+            "$selfsign": true,
+            "$broadcast": true,
+            "$territoriality": "node1"
+        });
+
+        let issues = TxLinter::new().lint(&tx);
+
+        assert_eq!(
+            rules(&issues),
+            vec![
+                "unused-output",
+                "selfsign-contract",
+                "broadcast-territoriality"
+            ]
+        );
+        assert_eq!(
+            issues[2].to_string(),
+            "error [broadcast-territoriality]: $territoriality is set on a broadcast transaction"
+        );
+
+        let tx = json!({
+            "$tx": {
+                "$i": {STREAM_A: {}, "short": {}},
+                "$o": {STREAM_A: {}},
+                "$r": {"rates": "rates"}
+            }
+        });
+
+        let issues = TxLinter::new().lint(&tx);
+
+        assert_eq!(
+            rules(&issues),
+            vec!["stream-id-format", "stream-id-format", "duplicate-stream"]
+        );
+        assert_eq!(issues[0].message, "'short' in $i is not a valid stream ID");
+        assert_eq!(issues[1].message, "'rates' in $r is not a valid stream ID");
+        assert_eq!(issues[2].severity, Severity::Error);
+
+        match TxLinter::new().check(&tx) {
+            Err(TxBuilderError::LintFailed(errors)) => assert_eq!(errors.len(), 1),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn lint_rules_removed() {
+        let tx = json!({"$tx": {"$i": {STREAM_A: {}}, "$o": {STREAM_A: {}}}});
+
+        let mut linter = TxLinter::new();
+        assert!(linter.remove("duplicate-stream"));
+        assert!(!linter.remove("duplicate-stream"));
+
+        assert!(!linter.rule_names().contains(&"duplicate-stream"));
+        assert!(linter.check(&tx).unwrap().is_empty());
+
+        assert!(TxLinter::empty()
+            .lint(&json!({"$tx": {"$o": {}}}))
+            .is_empty());
+    }
+}
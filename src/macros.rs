@@ -55,15 +55,18 @@ macro_rules! input_map {
 ///
 /// #### One key
 /// ```
+/// # #[cfg(feature = "openssl-keys")] {
 /// # use active_tx::{signees, Key};
 /// # use activeledger::key::EllipticCurve;
 /// #
 /// let key = Key::Ec(EllipticCurve::new("key").unwrap());
 /// let signees = signees![{"streamid" => key}];
+/// # }
 /// ```
 ///
 /// #### Multiple keys
 /// ```
+/// # #[cfg(feature = "openssl-keys")] {
 /// # use active_tx::{signees, Key};
 /// # use activeledger::key::EllipticCurve;
 /// #
@@ -76,16 +79,40 @@ macro_rules! input_map {
 ///     {"streamid2" => key2},
 ///     {"streamid3" => key3}
 /// ];
+/// # }
+/// ```
+///
+/// #### Signers
+/// Anything implementing [`Signer`](trait.Signer.html) can be given in place of a key, such as
+/// a [`FnSigner`](struct.FnSigner.html) for a fake signer in tests.
+/// See [`IntoKey`](trait.IntoKey.html).
+/// ```
+/// # #[cfg(feature = "openssl-keys")] {
+/// # use active_tx::{signees, FnSigner, Key, KeyType};
+/// # use activeledger::key::EllipticCurve;
+/// #
+/// let key = Key::Ec(EllipticCurve::new("key").unwrap());
+/// let fake = FnSigner::new("fake", KeyType::EC, |data: &str| {
+///     Ok(format!("signed:{}", data.len()))
+/// });
+///
+/// let signees = signees![
+///     {"streamid" => key},
+///     {"streamid2" => fake}
+/// ];
+/// # }
 /// ```
 ///
 /// #### Key for selfsign
 /// This uses the keys name as the stream id.
 /// ```
+/// # #[cfg(feature = "openssl-keys")] {
 /// # use active_tx::{signees, Key};
 /// # use activeledger::key::EllipticCurve;
 /// #
 /// let key = Key::Ec(EllipticCurve::new("key").unwrap());
 /// let signees = signees!(key);
+/// # }
 /// ```
 ///
 /// #### Empty
@@ -101,7 +128,7 @@ macro_rules! signees {
     [$({$streamid: expr => $key:expr}),+] => {{
         let mut signees = $crate::Signees::new();
 
-        $( signees.add($key, $streamid); )*
+        $( signees.add($crate::IntoKey::into_key($key), $streamid); )*
 
         signees
     }};
@@ -118,15 +145,139 @@ macro_rules! signees {
     }};
 }
 
+/// # Transaction Macro
+///
+/// This macro declares a whole transaction in one literal and expands to the [`PacketBuilder`]
+/// and [`TransactionBuilder`] calls needed to build it.
+/// It returns a `TxBuilderResult<String>` containing the transaction.
+///
+/// The following fields are supported, in any order:
+/// * `namespace` - Required
+/// * `contract` - Required
+/// * `entry`
+/// * `territoriality`
+/// * `selfsign` - A boolean
+/// * `broadcast` - A boolean
+/// * `i` - Required, the input data using the [packet_data!][packet_data] syntax
+/// * `o` - The output data
+/// * `r` - The readonly data
+/// * `sign` - The signees using the [signees!][signees] syntax
+///
+/// ## Example
+/// ```
+/// # #[cfg(feature = "openssl-keys")] {
+/// # use active_tx::{tx, Key};
+/// # use activeledger::key::EllipticCurve;
+/// let key = Key::Ec(EllipticCurve::new("key").unwrap());
+///
+/// let tx = tx! {
+///     namespace: "namespace",
+///     contract: "contract",
+///     entry: "entry",
+///     i: {"streamid": {"input": "data"}},
+///     o: {"streamid": {"output": "data"}},
+///     sign: [{"streamid" => key}]
+/// }
+/// .unwrap();
+/// # }
+/// ```
+///
+/// [`PacketBuilder`]: struct.PacketBuilder.html
+/// [`TransactionBuilder`]: struct.TransactionBuilder.html
+/// [packet_data]: macro.packet_data.html
+/// [signees]: macro.signees.html
+#[macro_export]
+macro_rules! tx {
+    ($($fields:tt)+) => {{
+        let build = || -> $crate::TxBuilderResult<String> {
+            let mut builder = $crate::TransactionBuilder::new_blank();
+            #[allow(unused_mut)]
+            let mut signees = $crate::Signees::new();
+
+            $crate::tx_internal!(builder signees $($fields)+);
+
+            builder.build(signees)
+        };
+
+        build()
+    }};
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! tx_internal {
+    // Reached the end
+    ($builder:ident $signees:ident) => {};
+
+    // Separating comma
+    ($builder:ident $signees:ident , $($tail:tt)*) => {
+        $crate::tx_internal!($builder $signees $($tail)*);
+    };
+
+    ($builder:ident $signees:ident namespace : $value:expr $(, $($tail:tt)*)?) => {
+        $builder.namespace($value);
+        $crate::tx_internal!($builder $signees $($($tail)*)?);
+    };
+
+    ($builder:ident $signees:ident contract : $value:expr $(, $($tail:tt)*)?) => {
+        $builder.contract($value);
+        $crate::tx_internal!($builder $signees $($($tail)*)?);
+    };
+
+    ($builder:ident $signees:ident entry : $value:expr $(, $($tail:tt)*)?) => {
+        $builder.entry($value);
+        $crate::tx_internal!($builder $signees $($($tail)*)?);
+    };
+
+    ($builder:ident $signees:ident territoriality : $value:expr $(, $($tail:tt)*)?) => {
+        $builder.territoriality($value);
+        $crate::tx_internal!($builder $signees $($($tail)*)?);
+    };
+
+    ($builder:ident $signees:ident selfsign : $value:expr $(, $($tail:tt)*)?) => {
+        $builder.set_selfsign($value);
+        $crate::tx_internal!($builder $signees $($($tail)*)?);
+    };
+
+    ($builder:ident $signees:ident broadcast : $value:expr $(, $($tail:tt)*)?) => {
+        $builder.set_broadcast($value);
+        $crate::tx_internal!($builder $signees $($($tail)*)?);
+    };
+
+    ($builder:ident $signees:ident i : {$($data:tt)*} $($tail:tt)*) => {
+        let input = $crate::PacketBuilder::new($crate::packet_data!({$($data)*})).build()?;
+        $builder.input(input)?;
+        $crate::tx_internal!($builder $signees $($tail)*);
+    };
+
+    ($builder:ident $signees:ident o : {$($data:tt)*} $($tail:tt)*) => {
+        let output = $crate::PacketBuilder::new($crate::packet_data!({$($data)*})).build()?;
+        $builder.output(output)?;
+        $crate::tx_internal!($builder $signees $($tail)*);
+    };
+
+    ($builder:ident $signees:ident r : {$($data:tt)*} $($tail:tt)*) => {
+        let readonly = $crate::PacketBuilder::new($crate::packet_data!({$($data)*})).build()?;
+        $builder.readonly(readonly)?;
+        $crate::tx_internal!($builder $signees $($tail)*);
+    };
+
+    ($builder:ident $signees:ident sign : [$({$streamid:expr => $key:expr}),* $(,)?] $($tail:tt)*) => {
+        $( $signees.add($key, $streamid); )*
+        $crate::tx_internal!($builder $signees $($tail)*);
+    };
+}
+
 /// # Packet data macro
 ///
 /// This macro can be used to generate [`PacketValue`]s which are consumed by the [`PacketBuilder`] to generate
 /// sections of the transaction packet.
 /// These are $i (input), $o (output), and $r (readonly).
 ///
-/// This macro is designed to be more restrictive than standard JSON. This means that it will only
-/// accept objects, string, and arrays. The `json!` macro in the [serde_json](https://crates.io/crates/serde_json) crate
-/// can be used for more flexibility and passed to the `add_json` method provided in [`PacketBuilder`].
+/// This macro accepts objects, arrays, and any expression that can be converted into a
+/// [`PacketValue`], such as strings, numbers, booleans, and variables holding a [`PacketValue`].
+/// The `json!` macro in the [serde_json](https://crates.io/crates/serde_json) crate
+/// can be used for more flexibility and passed to the `new_json` method provided in [`PacketBuilder`].
 ///
 /// ## Examples
 /// ```
@@ -141,6 +292,21 @@ macro_rules! signees {
 ///
 /// // And arrays can be used
 /// let value = packet_data!({"array": ["1", "2", "3"]});
+///
+/// // Numbers, booleans, and runtime values can be used
+/// let amount: u64 = 100;
+/// let nested = packet_data!({"nested": "object"});
+/// let value = packet_data!({"amount": amount, "confirmed": true, "data": nested});
+/// ```
+///
+/// With the `ids` feature, `@uuid` and `@ulid` generate a new UUID or ULID string each time
+/// the value is created.
+///
+/// ```
+/// # #[cfg(feature = "ids")] {
+/// # use active_tx::packet_data;
+/// let value = packet_data!({"event": {"id": @uuid, "ref": @ulid, "tags": [@uuid, "manual"]}});
+/// # }
 /// ```
 ///
 #[macro_export(local_inner_macros)]
@@ -181,6 +347,15 @@ macro_rules! packet_data_internal {
         packet_data_internal!(@object $object [$($key)+] (packet_data_internal!([$($array)*])) $($tail)*);
     };
 
+    // Next value is a generated ID
+    (@object $object:ident ($($key:tt)+) (: @uuid $($tail:tt)*) $copy:tt) => {
+        packet_data_internal!(@object $object [$($key)+] ($crate::PacketValue::uuid()) $($tail)*);
+    };
+
+    (@object $object:ident ($($key:tt)+) (: @ulid $($tail:tt)*) $copy:tt) => {
+        packet_data_internal!(@object $object [$($key)+] ($crate::PacketValue::ulid()) $($tail)*);
+    };
+
     // Next value, expression, tail comma
     (@object $object:ident ($($key:tt)+) (: $value:expr, $($tail:tt)*) $copy:tt) => {
         packet_data_internal!(@object $object [$($key)+] (packet_data_internal!($value)) , $($tail)*);
@@ -219,6 +394,15 @@ macro_rules! packet_data_internal {
         packet_data_internal_vec![$($elems),*]
     };
 
+    // Next: generated ID
+    (@array [$($elems:expr,)*] @uuid $(, $($tail:tt)*)?) => {
+        packet_data_internal!(@array [$($elems,)* $crate::PacketValue::uuid(),] $($($tail)*)?)
+    };
+
+    (@array [$($elems:expr,)*] @ulid $(, $($tail:tt)*)?) => {
+        packet_data_internal!(@array [$($elems,)* $crate::PacketValue::ulid(),] $($($tail)*)?)
+    };
+
     // Next: expression followed by comma
     (@array [$($elems:expr,)*] $next:expr, $($tail:tt)*) => {
 
@@ -246,13 +430,13 @@ macro_rules! packet_data_internal {
 
     // Empty object
     ({}) => {{
-        $crate::PacketValue::Object(std::collections::HashMap::new())
+        $crate::PacketValue::Object($crate::PacketMap::new())
     }};
 
     // Object
     ({ $($data:tt)+ }) => {
         $crate::PacketValue::Object({
-            let mut object = std::collections::HashMap::new();
+            let mut object = $crate::PacketMap::new();
             packet_data_internal!(@object object () ($($data)+) ($($data)+));
             object
         })
@@ -268,9 +452,9 @@ macro_rules! packet_data_internal {
         $crate::PacketValue::Array(packet_data_internal!(@array [] $($data)+))
     };
 
-    // String
+    // Any value that can be converted into a PacketValue
     ($other:expr) => {
-        input_str!(&$other)
+        $crate::PacketValue::from($other)
     }
 }
 
@@ -21,19 +21,57 @@
  * SOFTWARE.
  */
 
+use chrono::{DateTime, Utc};
+use serde::{ser, Serialize, Serializer};
 use serde_json::{json, Value};
 
+#[cfg(feature = "schema")]
+use jsonschema::JSONSchema;
+
 // STD
 use std::collections::HashMap;
+use std::fmt;
+#[cfg(feature = "schema")]
+use std::sync::Arc;
 
 // Internal
-use super::PacketValue;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use super::compress::{self, Compression};
+#[cfg(feature = "openssl-keys")]
+use super::encrypt::{self, Recipient};
+use super::render::render_json;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+use super::source;
+#[cfg(feature = "decimal")]
+use super::{decimal_tojson, DecimalFormat};
+use super::{stamp_entries, BinaryFormat, PacketMap, PacketValue};
 use crate::error::{TxBuilderError, TxBuilderResult};
 
 /// Provides build methods
 #[derive(Clone)]
 pub struct PacketBuilder {
     data: PacketData,
+    formats: Formats,
+    /// Field set to the build time on every stream entry
+    timestamp_field: Option<String>,
+    /// Time written to the timestamp field, the system clock if not set
+    timestamp_time: Option<DateTime<Utc>>,
+    #[cfg(feature = "schema")]
+    schema: Option<Arc<JSONSchema>>,
+    /// Fields to compress when the packet is built
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    compressed_fields: Vec<(String, Compression)>,
+    /// Fields to encrypt when the packet is built
+    #[cfg(feature = "openssl-keys")]
+    encrypted_fields: Vec<(String, Recipient)>,
+}
+
+/// How binary and decimal values are written into the packet
+#[derive(Clone, Copy, Default, Debug)]
+struct Formats {
+    binary: BinaryFormat,
+    #[cfg(feature = "decimal")]
+    decimal: DecimalFormat,
 }
 
 /// Stores the data built by PacketBuilder
@@ -43,6 +81,8 @@ pub struct PacketData {
     json: Option<Value>,
     is_json: bool,
     built: Option<String>,
+    /// Formats of the builder, used when rendering variables into the data
+    formats: Formats,
 }
 
 impl PacketBuilder {
@@ -52,7 +92,7 @@ impl PacketBuilder {
     pub fn new(data: PacketValue) -> PacketBuilder {
         let mut ior_data = PacketData::new();
 
-        // If the data provided is an object (PacketValue::Object(HashMap)) use the add_map function
+        // If the data provided is an object (PacketValue::Object(PacketMap)) use the add_map function
         // to add it.
         if let PacketValue::Object(data) = data {
             ior_data.add_map(data);
@@ -60,7 +100,18 @@ impl PacketBuilder {
             ior_data.add(data);
         }
 
-        PacketBuilder { data: ior_data }
+        PacketBuilder {
+            data: ior_data,
+            formats: Formats::default(),
+            timestamp_field: None,
+            timestamp_time: None,
+            #[cfg(feature = "schema")]
+            schema: None,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compressed_fields: Vec::new(),
+            #[cfg(feature = "openssl-keys")]
+            encrypted_fields: Vec::new(),
+        }
     }
 
     /// # New JSON
@@ -71,31 +122,379 @@ impl PacketBuilder {
 
         ior_data.add_json(data);
 
-        PacketBuilder { data: ior_data }
+        PacketBuilder {
+            data: ior_data,
+            formats: Formats::default(),
+            timestamp_field: None,
+            timestamp_time: None,
+            #[cfg(feature = "schema")]
+            schema: None,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compressed_fields: Vec::new(),
+            #[cfg(feature = "openssl-keys")]
+            encrypted_fields: Vec::new(),
+        }
+    }
+
+    /// # New YAML
+    ///
+    /// Parse a YAML document, such as a configuration file describing ledger data, and create a
+    /// new PacketBuilder from it. Mapping keys must be strings.
+    ///
+    /// Requires the `yaml` feature.
+    ///
+    /// ```
+    /// # #[cfg(feature = "yaml")] {
+    /// # use active_tx::PacketBuilder;
+    /// let input = PacketBuilder::new_yaml("streamid:\n  name: Sensor 1\n  roles: [sensor]\n")
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(input.get_path("streamid.name").unwrap(), "Sensor 1");
+    /// # }
+    /// ```
+    #[cfg(feature = "yaml")]
+    pub fn new_yaml(source: &str) -> TxBuilderResult<PacketBuilder> {
+        Ok(PacketBuilder::new(source::from_yaml(source)?))
+    }
+
+    /// # New TOML
+    ///
+    /// Parse a TOML document, such as a configuration file describing ledger data, and create a
+    /// new PacketBuilder from it. Dates and times are added as strings.
+    ///
+    /// Requires the `toml` feature.
+    ///
+    /// ```
+    /// # #[cfg(feature = "toml")] {
+    /// # use active_tx::PacketBuilder;
+    /// let input = PacketBuilder::new_toml("[streamid]\nname = \"Sensor 1\"\nroles = [\"sensor\"]\n")
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(input.get_path("streamid.name").unwrap(), "Sensor 1");
+    /// # }
+    /// ```
+    #[cfg(feature = "toml")]
+    pub fn new_toml(source: &str) -> TxBuilderResult<PacketBuilder> {
+        Ok(PacketBuilder::new(source::from_toml(source)?))
     }
 
     /// # Build
     ///
     /// Process the given data and store it in an [`PacketData`] object, return the [`PacketData`] object
+    ///
+    /// If a schema has been set the data is validated against it first, before any fields are
+    /// encrypted.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(size = tracing::field::Empty))
+    )]
     pub fn build(&mut self) -> TxBuilderResult<PacketData> {
-        if self.data.is_json() {
-            let json = self.data.get()?;
-
-            let json = json.to_owned();
+        let is_json = self.data.is_json();
 
-            self.data.set_built(json);
+        let json = if is_json {
+            self.data.get()?
         } else {
             let map = match self.data.get_map() {
                 Some(map) => map,
                 None => return Err(TxBuilderError::BuildError(1000)),
             };
 
-            let serialized = PacketBuilder::to_json(map)?;
+            PacketBuilder::to_json(map, self.formats)?
+        };
+
+        let json = match &self.timestamp_field {
+            Some(field) => stamp_entries(json, field, self.timestamp_time.unwrap_or_else(Utc::now)),
+            None => json,
+        };
+
+        #[cfg(feature = "schema")]
+        self.validate(&json)?;
+
+        // Fields are compressed first, encrypted data doesn't compress
+        // The unconverted data is only current when the packet wasn't given as JSON
+        #[cfg(any(feature = "gzip", feature = "zstd"))]
+        let json = {
+            let data = if is_json {
+                None
+            } else {
+                self.data.data.as_ref()
+            };
+            compress::compress_fields(json, data, &self.compressed_fields)?
+        };
+
+        #[cfg(feature = "openssl-keys")]
+        let json = encrypt::encrypt_fields(json, &self.encrypted_fields)?;
+
+        // The builder keeps the plain data so building again doesn't encrypt twice
+        let mut data = self.data.clone();
+        data.formats = self.formats;
+        data.set_built(json);
 
-            self.data.set_built(serialized);
+        #[cfg(feature = "tracing")]
+        if let Some(built) = &data.built {
+            tracing::Span::current().record("size", built.len());
         }
 
-        Ok(self.data.clone())
+        Ok(data)
+    }
+
+    /// # With schema
+    ///
+    /// Validate the packet data against a JSON Schema when it is built.
+    /// Requires the `schema` feature.
+    ///
+    /// An error is returned if the schema is not valid. If the packet data does not match the
+    /// schema, [`build`](#method.build) returns an error listing every violation.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketBuilder};
+    /// # use serde_json::json;
+    /// let schema = json!({
+    ///     "type": "object",
+    ///     "required": ["balance"],
+    ///     "properties": {"balance": {"type": "integer", "minimum": 0}}
+    /// });
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"balance": -1}))
+    ///     .with_schema(&schema)
+    ///     .unwrap()
+    ///     .build();
+    ///
+    /// assert!(input.is_err());
+    /// ```
+    #[cfg(feature = "schema")]
+    pub fn with_schema(&mut self, schema: &Value) -> TxBuilderResult<&mut Self> {
+        let schema = match JSONSchema::compile(schema) {
+            Ok(schema) => schema,
+            Err(e) => return Err(TxBuilderError::InvalidSchema(e.to_string())),
+        };
+
+        self.schema = Some(Arc::new(schema));
+
+        Ok(self)
+    }
+
+    /// # Encrypt field
+    ///
+    /// Encrypt the value at a dotted path to a recipient's public key when the packet is built.
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    ///
+    /// The value is replaced with a base64 string that can be decrypted with
+    /// [`decrypt_field`](fn.decrypt_field.html) and the recipient's private key. RSA keys use
+    /// RSA OAEP with AES-256-GCM, EC keys use ECIES with AES-256-GCM.
+    ///
+    /// An error is returned if the key can't be parsed, and building fails if there is no value
+    /// at the path.
+    ///
+    /// Only fields are encrypted. Encrypting the whole transaction to a node (`$encrypt`) is
+    /// left to the node's own tooling, this crate does not implement its wire format.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, Key, PacketBuilder};
+    /// # use activeledger::key::RSA;
+    /// let key = Key::Rsa(RSA::new("keyname").unwrap());
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"streamid": {"name": "Jane Doe", "age": 30}}))
+    ///     .encrypt_field("streamid.name", &key.public_pem().unwrap())
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(!input.get_string().unwrap().contains("Jane Doe"));
+    /// ```
+    #[cfg(feature = "openssl-keys")]
+    pub fn encrypt_field(&mut self, path: &str, recipient_pem: &str) -> TxBuilderResult<&mut Self> {
+        PacketData::split_path(path)?;
+
+        let recipient = Recipient::from_pem(recipient_pem)?;
+
+        self.encrypted_fields.push((path.to_string(), recipient));
+
+        Ok(self)
+    }
+
+    /// # Compress field
+    ///
+    /// Compress the string or binary value at a dotted path when the packet is built.
+    /// Requires the `gzip` or `zstd` feature.
+    ///
+    /// The value is replaced with an object recording the encoding, e.g.
+    /// `{"encoding": "gzip", "type": "binary", "data": "[base64]"}`, which can be read with
+    /// [`decompress_field`](fn.decompress_field.html). A compressed field can also be
+    /// encrypted with [`encrypt_field`](#method.encrypt_field).
+    ///
+    /// Building fails if there is no value at the path or it isn't a string or binary value.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, Compression, PacketBuilder};
+    /// # #[cfg(feature = "gzip")] {
+    /// let document: Vec<u8> = std::iter::repeat(0x20).take(4096).collect();
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"streamid": {"document": document}}))
+    ///     .compress_field("streamid.document", Compression::Gzip)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert!(input.get_string().unwrap().len() < 4096);
+    /// # }
+    /// ```
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub fn compress_field(
+        &mut self,
+        path: &str,
+        compression: Compression,
+    ) -> TxBuilderResult<&mut Self> {
+        PacketData::split_path(path)?;
+
+        self.compressed_fields.push((path.to_string(), compression));
+
+        Ok(self)
+    }
+
+    /// # Timestamp entries
+    ///
+    /// Set a field to the time the packet is built on every stream entry, replacing any value
+    /// already there. Every entry gets the same ISO 8601 time, e.g. `"2020-01-01T00:00:00.000Z"`.
+    /// Entries that aren't objects are left as they are. Use
+    /// [`timestamp_entries_at`](#method.timestamp_entries_at) to set a fixed time instead.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketBuilder};
+    /// let input = PacketBuilder::new(packet_data!({"alice": {"amount": 10}, "bob": {}}))
+    ///     .timestamp_entries("created")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     input.get_path("alice.created"),
+    ///     input.get_path("bob.created")
+    /// );
+    /// ```
+    pub fn timestamp_entries(&mut self, field: &str) -> &mut Self {
+        self.timestamp_field = Some(field.to_string());
+        self.timestamp_time = None;
+
+        self
+    }
+
+    /// # Timestamp entries at
+    ///
+    /// Set a field to the given time on every stream entry, in place of the build time used by
+    /// [`timestamp_entries`](#method.timestamp_entries), so the built packet is reproducible.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketBuilder};
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"alice": {"amount": 10}}))
+    ///     .timestamp_entries_at("created", Utc.timestamp_opt(1577836800, 0).unwrap())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     input.get_path("alice.created").unwrap(),
+    ///     "2020-01-01T00:00:00.000Z"
+    /// );
+    /// ```
+    pub fn timestamp_entries_at(&mut self, field: &str, time: DateTime<Utc>) -> &mut Self {
+        self.timestamp_field = Some(field.to_string());
+        self.timestamp_time = Some(time);
+
+        self
+    }
+
+    /// # Add binary
+    ///
+    /// Add raw bytes under the given key, for attaching documents, images and other blobs.
+    /// The bytes are base64 encoded using the builders [`BinaryFormat`].
+    ///
+    /// The packet data must be an object.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketBuilder};
+    /// let document: Vec<u8> = vec![0x25, 0x50, 0x44, 0x46];
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"name": "document.pdf"}))
+    ///     .add_binary("document", &document)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [`BinaryFormat`]: enum.BinaryFormat.html
+    pub fn add_binary(&mut self, key: &str, bytes: &[u8]) -> TxBuilderResult<&mut Self> {
+        let value = PacketValue::Binary(bytes.to_vec());
+
+        if self.data.is_json() {
+            let json = PacketBuilder::value_tojson(&value, self.formats)?;
+
+            match &mut self.data.json {
+                Some(Value::Object(map)) => map.insert(key.to_string(), json),
+                _ => return Err(TxBuilderError::BuildError(1001)),
+            };
+        } else {
+            match &mut self.data.data {
+                Some(PacketValue::Object(map)) => map.insert(key.to_string(), value),
+                _ => return Err(TxBuilderError::BuildError(1001)),
+            };
+        }
+
+        Ok(self)
+    }
+
+    /// # Binary format
+    ///
+    /// Set how binary data is encoded in the built packet, defaults to [`BinaryFormat::Base64`].
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, BinaryFormat, PacketBuilder};
+    /// let input = PacketBuilder::new(packet_data!({"data": vec![1u8, 2, 3]}))
+    ///     .binary_format(BinaryFormat::Base64Url)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [`BinaryFormat::Base64`]: enum.BinaryFormat.html#variant.Base64
+    pub fn binary_format(&mut self, format: BinaryFormat) -> &mut Self {
+        self.formats.binary = format;
+
+        self
+    }
+
+    /// # Decimal format
+    ///
+    /// Set how decimal amounts are written in the built packet, defaults to
+    /// [`DecimalFormat::String`]. Requires the `decimal` feature.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, DecimalFormat, PacketBuilder};
+    /// # use rust_decimal::Decimal;
+    /// # use serde_json::json;
+    /// let amount = Decimal::new(1050, 2);
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"amount": amount}))
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(input.get().unwrap(), json!({"amount": "10.50"}));
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"amount": amount}))
+    ///     .decimal_format(DecimalFormat::Number)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(input.get().unwrap(), json!({"amount": 10.5}));
+    /// ```
+    ///
+    /// [`DecimalFormat::String`]: enum.DecimalFormat.html#variant.String
+    #[cfg(feature = "decimal")]
+    pub fn decimal_format(&mut self, format: DecimalFormat) -> &mut Self {
+        self.formats.decimal = format;
+
+        self
     }
 
     /// # From string
@@ -107,18 +506,30 @@ impl PacketBuilder {
 
 // Private functions
 impl PacketBuilder {
+    /// Convert a value to a JSON Value
+    fn value_tojson(value: &PacketValue, format: Formats) -> TxBuilderResult<Value> {
+        let data = match value {
+            PacketValue::String(value) => json!(value),
+            PacketValue::Object(object) => PacketBuilder::object_tojson(object, format)?,
+            PacketValue::Array(_) => PacketBuilder::array_tojson(value, format)?,
+            PacketValue::Bool(value) => json!(value),
+            PacketValue::Number(value) => json!(value),
+            PacketValue::Null => Value::Null,
+            PacketValue::Binary(bytes) => PacketBuilder::binary_tojson(bytes, format.binary),
+            #[cfg(feature = "decimal")]
+            PacketValue::Decimal(value) => decimal_tojson(value, format.decimal)?,
+        };
+
+        Ok(data)
+    }
+
     /// Walk an array value and convert it to a JSON Value
-    fn array_tojson(array: &PacketValue) -> TxBuilderResult<Value> {
+    fn array_tojson(array: &PacketValue, format: Formats) -> TxBuilderResult<Value> {
         let mut holder: Vec<Value> = Vec::new();
         match array {
             PacketValue::Array(array) => {
                 for elem in array.iter() {
-                    let data = match elem {
-                        PacketValue::String(value) => json!(value),
-                        PacketValue::Object(object) => PacketBuilder::object_tojson(object)?,
-                        PacketValue::Array(_) => PacketBuilder::array_tojson(elem)?,
-                    };
-                    holder.push(data);
+                    holder.push(PacketBuilder::value_tojson(elem, format)?);
                 }
             }
             _ => return Err(TxBuilderError::JsonError(2000)),
@@ -127,21 +538,16 @@ impl PacketBuilder {
     }
 
     /// Walk an object value and convert it to a JSON Value
-    fn object_tojson(map: &HashMap<String, PacketValue>) -> TxBuilderResult<Value> {
+    fn object_tojson(map: &PacketMap, format: Formats) -> TxBuilderResult<Value> {
         let mut json = json!({});
 
         for (key, value) in map.iter() {
             let data = match value {
-                PacketValue::String(value) => json!(value),
-                PacketValue::Object(object) => {
-                    let data = match PacketBuilder::object_tojson(object) {
-                        Ok(data) => data,
-                        Err(_) => return Err(TxBuilderError::JsonError(2001)),
-                    };
-
-                    json!(data)
-                }
-                PacketValue::Array(_) => PacketBuilder::array_tojson(value)?,
+                PacketValue::Object(object) => match PacketBuilder::object_tojson(object, format) {
+                    Ok(data) => data,
+                    Err(_) => return Err(TxBuilderError::JsonError(2001)),
+                },
+                _ => PacketBuilder::value_tojson(value, format)?,
             };
 
             json[key] = data;
@@ -151,30 +557,55 @@ impl PacketBuilder {
     }
 
     /// Convert a map to JSON
-    fn to_json(map: &PacketValue) -> TxBuilderResult<Value> {
+    /// Check the data against the schema, if one is set
+    #[cfg(feature = "schema")]
+    fn validate(&self, json: &Value) -> TxBuilderResult<()> {
+        let schema = match &self.schema {
+            Some(schema) => schema,
+            None => return Ok(()),
+        };
+
+        if let Err(errors) = schema.validate(json) {
+            let violations = errors
+                .map(|e| format!("{}: {}", e.instance_path, e))
+                .collect();
+
+            return Err(TxBuilderError::SchemaViolation(violations));
+        }
+
+        Ok(())
+    }
+
+    fn to_json(map: &PacketValue, format: Formats) -> TxBuilderResult<Value> {
         let mut json = json!({});
 
         if let PacketValue::Object(map) = map {
             for (key, value) in map.iter() {
-                let data: Value = match value {
-                    PacketValue::String(value) => json!(value),
-                    PacketValue::Object(object) => PacketBuilder::object_tojson(object)?,
-                    PacketValue::Array(_) => PacketBuilder::array_tojson(value)?,
-                };
-
-                json[key] = data;
+                json[key] = PacketBuilder::value_tojson(value, format)?;
             }
         }
 
         Ok(json)
     }
+
+    /// Encode binary data as base64 in the given format
+    fn binary_tojson(bytes: &[u8], format: BinaryFormat) -> Value {
+        match format {
+            BinaryFormat::Base64 => json!(base64::encode(bytes)),
+            BinaryFormat::Base64Url => json!(base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)),
+            BinaryFormat::Object => json!({
+                "encoding": "base64",
+                "data": base64::encode(bytes)
+            }),
+        }
+    }
 }
 
 // Public
 impl PacketData {
     pub fn get_string(&self) -> TxBuilderResult<&str> {
         match &self.built {
-            Some(data) => Ok(&data),
+            Some(data) => Ok(data),
             None => Err(TxBuilderError::PacketError(3000)),
         }
     }
@@ -186,16 +617,213 @@ impl PacketData {
             Err(TxBuilderError::PacketError(3001))
         }
     }
+
+    /// # Get path
+    ///
+    /// Get the value at a dotted path, e.g. "streamid.balance".
+    /// Returns `None` if there is no value at the path.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketBuilder};
+    /// let input = PacketBuilder::new(packet_data!({"streamid": {"balance": 10}}))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(input.get_path("streamid.balance").unwrap(), 10);
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        let mut current = self.json.as_ref()?;
+
+        for part in path.split('.') {
+            current = current.get(part)?;
+        }
+
+        Some(current)
+    }
+
+    /// # Set
+    ///
+    /// Set the value at a dotted path, e.g. "streamid.metadata.timestamp".
+    /// Missing objects along the path are created.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketBuilder, PacketValue};
+    /// let mut input = PacketBuilder::new(packet_data!({"streamid": {"data": "value"}}))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// input.set("streamid.metadata.timestamp", PacketValue::from(1570000000)).unwrap();
+    /// ```
+    pub fn set(&mut self, path: &str, value: PacketValue) -> TxBuilderResult<&mut Self> {
+        let value = PacketBuilder::value_tojson(&value, Formats::default())?;
+        let (parents, last) = PacketData::split_path(path)?;
+
+        let mut current = self.json_mut()?;
+
+        for part in parents {
+            current = match current {
+                Value::Object(object) => object.entry(part).or_insert_with(|| json!({})),
+                _ => return Err(TxBuilderError::InvalidPath(path.to_string())),
+            };
+        }
+
+        match current {
+            Value::Object(object) => object.insert(last.to_string(), value),
+            _ => return Err(TxBuilderError::InvalidPath(path.to_string())),
+        };
+
+        self.refresh();
+
+        Ok(self)
+    }
+
+    /// # Remove
+    ///
+    /// Remove the value at a dotted path, returning it if it was present.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketBuilder};
+    /// let mut input = PacketBuilder::new(packet_data!({"streamid": {"secret": "value"}}))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let removed = input.remove("streamid.secret").unwrap();
+    /// ```
+    pub fn remove(&mut self, path: &str) -> TxBuilderResult<Option<Value>> {
+        let (parents, last) = PacketData::split_path(path)?;
+
+        let mut current = self.json_mut()?;
+
+        for part in parents {
+            current = match current.get_mut(part) {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+        }
+
+        let removed = match current {
+            #[cfg(not(feature = "preserve-order"))]
+            Value::Object(object) => object.remove(last),
+            // Keep the order of the remaining keys
+            #[cfg(feature = "preserve-order")]
+            Value::Object(object) => object.shift_remove(last),
+            _ => None,
+        };
+
+        if removed.is_some() {
+            self.refresh();
+        }
+
+        Ok(removed)
+    }
+
+    /// # Merge
+    ///
+    /// Merge another packet into this one. Objects are merged recursively, any other values
+    /// from the other packet replace those in this one.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketBuilder};
+    /// let mut input = PacketBuilder::new(packet_data!({"streamid": {"data": "value"}}))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let device = PacketBuilder::new(packet_data!({"streamid": {"device": "sensor-1"}}))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// input.merge(device).unwrap();
+    /// ```
+    pub fn merge(&mut self, other: PacketData) -> TxBuilderResult<&mut Self> {
+        let other = other.into_json()?;
+        let json = self.json_mut()?;
+
+        if !json.is_object() || !other.is_object() {
+            return Err(TxBuilderError::PacketError(3003));
+        }
+
+        PacketData::merge_json(json, other);
+
+        self.refresh();
+
+        Ok(self)
+    }
+
+    /// # Render
+    ///
+    /// Treat the packet as a template, returning a copy with each `{{name}}` placeholder
+    /// replaced by the variable of that name. A string that is only a placeholder takes the
+    /// value of the variable, keeping its type. Placeholders within longer strings, including
+    /// object keys such as stream IDs, are replaced with the text of the variable.
+    ///
+    /// Variables are written with the binary and decimal formats of the builder that built the
+    /// template. An error is returned if a placeholder has no variable, or
+    /// `RenderKeyCollision` if two object keys render to the same key.
+    ///
+    /// Write `\{{` for a literal `{{`.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketBuilder, PacketValue};
+    /// # use std::collections::HashMap;
+    /// # use serde_json::json;
+    /// let template = PacketBuilder::new_json(json!({
+    ///     "{{streamid}}": {"amount": "{{amount}}", "note": "Paid by {{name}}"}
+    /// }))
+    /// .build()
+    /// .unwrap();
+    ///
+    /// let mut vars = HashMap::new();
+    /// vars.insert("streamid".to_string(), PacketValue::from("alice"));
+    /// vars.insert("amount".to_string(), PacketValue::from(10));
+    /// vars.insert("name".to_string(), PacketValue::from("Alice"));
+    ///
+    /// let input = template.render(&vars).unwrap();
+    ///
+    /// assert_eq!(
+    ///     input.get().unwrap(),
+    ///     json!({"alice": {"amount": 10, "note": "Paid by Alice"}})
+    /// );
+    /// ```
+    pub fn render(&self, vars: &HashMap<String, PacketValue>) -> TxBuilderResult<PacketData> {
+        let mut json_vars = HashMap::with_capacity(vars.len());
+
+        for (name, value) in vars {
+            json_vars.insert(
+                name.clone(),
+                PacketBuilder::value_tojson(value, self.formats)?,
+            );
+        }
+
+        let json = match &self.json {
+            Some(json) => render_json(json, &json_vars)?,
+            None => return Err(TxBuilderError::PacketError(3001)),
+        };
+
+        let mut data = PacketData::new();
+        data.formats = self.formats;
+        data.set_built(json);
+
+        Ok(data)
+    }
 }
 
 // Private
 impl PacketData {
+    /// Take the built JSON without copying it
+    pub(crate) fn into_json(self) -> TxBuilderResult<Value> {
+        match self.json {
+            Some(json) => Ok(json),
+            None => Err(TxBuilderError::PacketError(3001)),
+        }
+    }
+
     fn new() -> PacketData {
         PacketData {
             data: None,
             json: None,
             is_json: false,
             built: None,
+            formats: Formats::default(),
         }
     }
 
@@ -205,7 +833,7 @@ impl PacketData {
         self
     }
 
-    fn add_map(&mut self, map: HashMap<String, PacketValue>) -> &mut Self {
+    fn add_map(&mut self, map: PacketMap) -> &mut Self {
         self.data = Some(PacketValue::Object(map));
 
         self
@@ -228,6 +856,50 @@ impl PacketData {
         }
     }
 
+    fn json_mut(&mut self) -> TxBuilderResult<&mut Value> {
+        match &mut self.json {
+            Some(json) => Ok(json),
+            None => Err(TxBuilderError::PacketError(3001)),
+        }
+    }
+
+    /// Update the built string after the JSON has changed
+    fn refresh(&mut self) {
+        self.built = self.json.as_ref().map(|json| json.to_string());
+    }
+
+    /// Split a dotted path into its parent keys and final key
+    fn split_path(path: &str) -> TxBuilderResult<(Vec<&str>, &str)> {
+        let mut parts: Vec<&str> = path.split('.').collect();
+
+        if parts.iter().any(|part| part.is_empty()) {
+            return Err(TxBuilderError::InvalidPath(path.to_string()));
+        }
+
+        let last = parts.pop().unwrap_or_default();
+
+        Ok((parts, last))
+    }
+
+    /// Recursively merge the source into the target
+    fn merge_json(target: &mut Value, source: Value) {
+        match (target, source) {
+            (Value::Object(target), Value::Object(source)) => {
+                for (key, value) in source {
+                    match target.get_mut(&key) {
+                        Some(existing) if existing.is_object() && value.is_object() => {
+                            PacketData::merge_json(existing, value)
+                        }
+                        _ => {
+                            target.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (target, source) => *target = source,
+        }
+    }
+
     fn set_built(&mut self, data: Value) -> &mut Self {
         self.json = Some(data.clone());
         self.is_json = true;
@@ -236,3 +908,23 @@ impl PacketData {
         self
     }
 }
+
+/// Writes the built JSON string
+impl fmt::Display for PacketData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.built {
+            Some(built) => f.write_str(built),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Serializes as the built JSON, so packet data can be embedded in request bodies
+impl Serialize for PacketData {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.json {
+            Some(json) => json.serialize(serializer),
+            None => Err(ser::Error::custom("packet data has not been built")),
+        }
+    }
+}
@@ -0,0 +1,233 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Field compression
+//!
+//! Compresses large string and binary fields of the packet data, such as documents being
+//! notarized, so transactions stay within the node's size limit.
+//!
+//! The field is replaced with an object recording how it was compressed and what it held:
+//!
+//! ```json
+//! {"encoding": "gzip", "type": "string", "data": "[base64]"}
+//! ```
+
+// STD
+use std::io::Read;
+#[cfg(feature = "gzip")]
+use std::io::Write;
+
+// External imports
+use serde_json::{json, Value};
+
+// Internal imports
+use super::PacketValue;
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Largest size, in bytes, [`decompress_field`] will decompress a field to
+///
+/// [`decompress_field`]: fn.decompress_field.html
+pub const MAX_DECOMPRESSED_SIZE: usize = 16 * 1024 * 1024;
+
+/// # Compression
+///
+/// The algorithm used by [`PacketBuilder::compress_field`]. Each algorithm is enabled by the
+/// feature of the same name.
+///
+/// [`PacketBuilder::compress_field`]: struct.PacketBuilder.html#method.compress_field
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Compression {
+    /// gzip, requires the `gzip` feature - `"encoding": "gzip"`
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard, requires the `zstd` feature - `"encoding": "zstd"`
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+impl Compression {
+    /// The name recorded in the field's `encoding`
+    fn name(self) -> &'static str {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => "gzip",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Compression> {
+        match name {
+            #[cfg(feature = "gzip")]
+            "gzip" => Some(Compression::Gzip),
+            #[cfg(feature = "zstd")]
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::encode_all(data, 0),
+        }
+    }
+
+    /// Decompress the data, reading at most one byte past the limit so larger data can be
+    /// rejected without decompressing all of it
+    fn decompress(self, data: &[u8], max_size: usize) -> std::io::Result<Vec<u8>> {
+        let decoder: Box<dyn Read + '_> = match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => Box::new(flate2::read::GzDecoder::new(data)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Box::new(zstd::stream::read::Decoder::new(data)?),
+        };
+
+        let mut decompressed = Vec::new();
+        decoder
+            .take(max_size as u64 + 1)
+            .read_to_end(&mut decompressed)?;
+
+        Ok(decompressed)
+    }
+}
+
+/// Replace the value at each path with its compressed form
+///
+/// Binary values are looked up in the unconverted packet data, so their raw bytes are
+/// compressed rather than the base64 text.
+pub(super) fn compress_fields(
+    mut json: Value,
+    data: Option<&PacketValue>,
+    fields: &[(String, Compression)],
+) -> TxBuilderResult<Value> {
+    for (path, compression) in fields {
+        let mut value = Some(&mut json);
+
+        for part in path.split('.') {
+            value = value.and_then(|value| value.get_mut(part));
+        }
+
+        let value = match value {
+            Some(value) => value,
+            None => return Err(TxBuilderError::InvalidPath(path.to_string())),
+        };
+
+        let (content, bytes) = match (data.and_then(|data| data.get_path(path)), &*value) {
+            (Some(PacketValue::Binary(bytes)), _) => ("binary", bytes.as_slice()),
+            (_, Value::String(string)) => ("string", string.as_bytes()),
+            _ => return Err(TxBuilderError::PacketError(3007)),
+        };
+
+        let compressed = match compression.compress(bytes) {
+            Ok(compressed) => compressed,
+            Err(_) => return Err(TxBuilderError::PacketError(3008)),
+        };
+
+        *value = json!({
+            "encoding": compression.name(),
+            "type": content,
+            "data": base64::encode(&compressed),
+        });
+    }
+
+    Ok(json)
+}
+
+/// # Decompress field
+///
+/// Read a field compressed with
+/// [`PacketBuilder::compress_field`](struct.PacketBuilder.html#method.compress_field),
+/// returning a [`PacketValue::String`] or [`PacketValue::Binary`] as the field held.
+///
+/// The feature for the field's encoding must be enabled. An error is returned if the field
+/// decompresses to more than [`MAX_DECOMPRESSED_SIZE`] bytes, use
+/// [`decompress_field_with_limit`] to allow larger fields.
+///
+/// ```
+/// # use active_tx::{decompress_field, packet_data, Compression, PacketBuilder, PacketValue};
+/// # #[cfg(feature = "gzip")] {
+/// let document = "a long document ".repeat(100);
+///
+/// let input = PacketBuilder::new(packet_data!({"streamid": {"document": document.clone()}}))
+///     .compress_field("streamid.document", Compression::Gzip)
+///     .unwrap()
+///     .build()
+///     .unwrap();
+///
+/// let field = input.get_path("streamid.document").unwrap();
+///
+/// assert_eq!(decompress_field(field).unwrap(), PacketValue::String(document));
+/// # }
+/// ```
+///
+/// [`PacketValue::String`]: enum.PacketValue.html#variant.String
+/// [`PacketValue::Binary`]: enum.PacketValue.html#variant.Binary
+/// [`MAX_DECOMPRESSED_SIZE`]: constant.MAX_DECOMPRESSED_SIZE.html
+/// [`decompress_field_with_limit`]: fn.decompress_field_with_limit.html
+pub fn decompress_field(field: &Value) -> TxBuilderResult<PacketValue> {
+    decompress_field_with_limit(field, MAX_DECOMPRESSED_SIZE)
+}
+
+/// # Decompress field with limit
+///
+/// Read a compressed field as [`decompress_field`](fn.decompress_field.html) does, returning
+/// an error if it decompresses to more than `max_size` bytes.
+pub fn decompress_field_with_limit(field: &Value, max_size: usize) -> TxBuilderResult<PacketValue> {
+    let member = |name: &str| match field.get(name).and_then(Value::as_str) {
+        Some(member) => Ok(member),
+        None => Err(TxBuilderError::PacketError(3009)),
+    };
+
+    let compression = match Compression::from_name(member("encoding")?) {
+        Some(compression) => compression,
+        None => return Err(TxBuilderError::PacketError(3009)),
+    };
+
+    let data = match base64::decode(member("data")?) {
+        Ok(data) => data,
+        Err(_) => return Err(TxBuilderError::PacketError(3009)),
+    };
+
+    let data = match compression.decompress(&data, max_size) {
+        Ok(data) if data.len() > max_size => return Err(TxBuilderError::PacketError(3011)),
+        Ok(data) => data,
+        Err(_) => return Err(TxBuilderError::PacketError(3009)),
+    };
+
+    match member("type")? {
+        "binary" => Ok(PacketValue::Binary(data)),
+        "string" => match String::from_utf8(data) {
+            Ok(string) => Ok(PacketValue::String(string)),
+            Err(_) => Err(TxBuilderError::PacketError(3009)),
+        },
+        _ => Err(TxBuilderError::PacketError(3009)),
+    }
+}
@@ -0,0 +1,104 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Decimal values
+//!
+//! Exact decimal amounts for financial payloads, so a token transfer of `0.1` isn't sent as
+//! `0.1000000000000000055511151231257827`. Requires the `decimal` feature.
+
+// STD
+use std::str::FromStr;
+
+// External imports
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Serializer;
+use serde_json::{Number, Value};
+
+// Internal imports
+use super::PacketValue;
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// # Decimal format
+///
+/// Controls how [`PacketValue::Decimal`] amounts are written into the packet.
+/// Set on the builder using [`PacketBuilder::decimal_format`].
+///
+/// [`PacketValue::Decimal`]: enum.PacketValue.html#variant.Decimal
+/// [`PacketBuilder::decimal_format`]: struct.PacketBuilder.html#method.decimal_format
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum DecimalFormat {
+    /// String holding every digit - `"10.50"`
+    #[default]
+    String,
+    /// JSON number - `10.5`
+    ///
+    /// Building fails if the amount can't be written as a number without rounding.
+    Number,
+}
+
+impl From<Decimal> for PacketValue {
+    fn from(value: Decimal) -> Self {
+        PacketValue::Decimal(value)
+    }
+}
+
+/// Convert a decimal to a JSON Value in the given format
+pub(crate) fn decimal_tojson(value: &Decimal, format: DecimalFormat) -> TxBuilderResult<Value> {
+    match format {
+        DecimalFormat::String => Ok(Value::String(value.to_string())),
+        DecimalFormat::Number => match to_number(value) {
+            Some(number) => Ok(Value::Number(number)),
+            None => Err(TxBuilderError::InexactDecimal(value.to_string())),
+        },
+    }
+}
+
+/// Convert a decimal to a JSON number, if it can be done exactly
+fn to_number(value: &Decimal) -> Option<Number> {
+    if value.fract().is_zero() {
+        if let Some(int) = value.to_i64() {
+            return Some(Number::from(int));
+        }
+
+        if let Some(int) = value.to_u64() {
+            return Some(Number::from(int));
+        }
+    }
+
+    // The shortest form of the float must read back as the same amount
+    let float = value.to_f64()?;
+
+    match Decimal::from_str(&float.to_string()) {
+        Ok(parsed) if parsed == *value => Number::from_f64(float),
+        _ => None,
+    }
+}
+
+/// Serialize a decimal as a string holding every digit
+pub(crate) fn serialize_decimal<S: Serializer>(
+    value: &Decimal,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
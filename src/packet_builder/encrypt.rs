@@ -0,0 +1,299 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Field encryption
+//!
+//! Encrypts single fields of the packet data to a recipient's public key, so personal data
+//! written to the ledger can only be read by the holder of the private key.
+//!
+//! The field's JSON value is encrypted with AES-256-GCM and replaced with a base64 string.
+//! How the AES key reaches the recipient depends on their key type:
+//!
+//! * RSA - a random AES key is encrypted using OAEP with SHA256.
+//!   The blob is `[encrypted key][iv][tag][data]`.
+//! * EC - the AES key is the SHA256 of an ECDH secret with a random key (ECIES).
+//!   The blob is `[compressed ephemeral public key][iv][tag][data]`.
+//!
+//! Transactions encrypted to a node (`$encrypt`) are not built here, only their flag is read
+//! by `explain`.
+
+// External imports
+use openssl::bn::BigNumContext;
+use openssl::derive::Deriver;
+use openssl::ec::{EcKey, EcPoint, PointConversionForm};
+use openssl::encrypt::{Decrypter, Encrypter};
+use openssl::error::ErrorStack;
+use openssl::hash::{hash, MessageDigest};
+use openssl::pkey::{HasPrivate, HasPublic, PKey, PKeyRef, Private, Public};
+use openssl::rand::rand_bytes;
+use openssl::rsa::Padding;
+use openssl::symm::{decrypt_aead, encrypt_aead, Cipher};
+use serde_json::Value;
+use zeroize::Zeroizing;
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::keys::{Key, KeyType};
+
+const AES_KEY_SIZE: usize = 32;
+const IV_SIZE: usize = 12;
+const TAG_SIZE: usize = 16;
+
+/// The IV, tag and ciphertext of AES-256-GCM encrypted data
+type Sealed = ([u8; IV_SIZE], [u8; TAG_SIZE], Vec<u8>);
+
+/// A recipient to encrypt a field to
+#[derive(Clone)]
+pub(super) struct Recipient {
+    key: PKey<Public>,
+    key_type: KeyType,
+}
+
+impl Recipient {
+    /// Parse the recipient's public key, which must be a key type Activeledger supports
+    pub(super) fn from_pem(pem: &str) -> TxBuilderResult<Recipient> {
+        let key = match PKey::public_key_from_pem(pem.as_bytes()) {
+            Ok(key) => key,
+            Err(e) => return Err(TxBuilderError::PublicKeyParseError(e)),
+        };
+
+        let key_type = Key::pkey_type(&key)?;
+
+        Ok(Recipient { key, key_type })
+    }
+}
+
+/// Replace the value at each path with its encrypted form
+pub(super) fn encrypt_fields(
+    mut json: Value,
+    fields: &[(String, Recipient)],
+) -> TxBuilderResult<Value> {
+    for (path, recipient) in fields {
+        let mut value = Some(&mut json);
+
+        for part in path.split('.') {
+            value = value.and_then(|value| value.get_mut(part));
+        }
+
+        let value = match value {
+            Some(value) => value,
+            None => return Err(TxBuilderError::InvalidPath(path.to_string())),
+        };
+
+        let plaintext = Zeroizing::new(value.to_string());
+
+        let blob = match encrypt_blob(recipient, plaintext.as_bytes()) {
+            Ok(blob) => blob,
+            Err(e) => {
+                return Err(TxBuilderError::FieldEncryptionError {
+                    path: path.to_string(),
+                    source: e,
+                })
+            }
+        };
+
+        *value = Value::String(base64::encode(&blob));
+    }
+
+    Ok(json)
+}
+
+/// # Decrypt field
+///
+/// Decrypt a field encrypted with
+/// [`PacketBuilder::encrypt_field`](struct.PacketBuilder.html#method.encrypt_field),
+/// returning its original value. Requires the `openssl-keys` feature, which is enabled by
+/// default.
+///
+/// The key must be the private half of the key the field was encrypted to.
+///
+/// ```
+/// # use active_tx::{decrypt_field, packet_data, Key, PacketBuilder};
+/// # use activeledger::key::EllipticCurve;
+/// # use serde_json::json;
+/// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+///
+/// let input = PacketBuilder::new(packet_data!({"streamid": {"email": "user@example.com"}}))
+///     .encrypt_field("streamid.email", &key.public_pem().unwrap())
+///     .unwrap()
+///     .build()
+///     .unwrap();
+///
+/// let blob = input.get_path("streamid.email").unwrap().as_str().unwrap();
+///
+/// assert_eq!(decrypt_field(blob, &key).unwrap(), json!("user@example.com"));
+/// ```
+pub fn decrypt_field(blob: &str, key: &Key) -> TxBuilderResult<Value> {
+    let blob = match base64::decode(blob) {
+        Ok(blob) => blob,
+        Err(_) => return Err(TxBuilderError::FieldDecryptionError(None)),
+    };
+
+    let pkey = key.pkey()?;
+
+    let plaintext = match key.key_type() {
+        KeyType::RSA => decrypt_rsa(&pkey, &blob),
+        KeyType::EC => decrypt_ec(&pkey, &blob),
+    };
+
+    let plaintext = match plaintext {
+        Ok(Some(plaintext)) => plaintext,
+        Ok(None) => return Err(TxBuilderError::FieldDecryptionError(None)),
+        Err(e) => return Err(TxBuilderError::FieldDecryptionError(Some(e))),
+    };
+
+    match serde_json::from_slice(&plaintext) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(TxBuilderError::FieldDecryptionError(None)),
+    }
+}
+
+/// Encrypt data with AES-256-GCM using a random IV, returning the IV, tag and ciphertext
+fn seal(key: &[u8], data: &[u8]) -> Result<Sealed, ErrorStack> {
+    let mut iv = [0u8; IV_SIZE];
+    let mut tag = [0u8; TAG_SIZE];
+
+    rand_bytes(&mut iv)?;
+
+    let data = encrypt_aead(Cipher::aes_256_gcm(), key, Some(&iv), &[], data, &mut tag)?;
+
+    Ok((iv, tag, data))
+}
+
+/// Encrypt a key to an RSA public key using OAEP with SHA256
+fn wrap_key<T: HasPublic>(rsa: &PKeyRef<T>, key: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let mut encrypter = Encrypter::new(rsa)?;
+    encrypter.set_rsa_padding(Padding::PKCS1_OAEP)?;
+    encrypter.set_rsa_oaep_md(MessageDigest::sha256())?;
+    encrypter.set_rsa_mgf1_md(MessageDigest::sha256())?;
+
+    let mut wrapped = vec![0u8; encrypter.encrypt_len(key)?];
+    let len = encrypter.encrypt(key, &mut wrapped)?;
+    wrapped.truncate(len);
+
+    Ok(wrapped)
+}
+
+fn encrypt_blob(recipient: &Recipient, data: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let (mut blob, key) = match recipient.key_type {
+        KeyType::RSA => {
+            let mut key = Zeroizing::new([0u8; AES_KEY_SIZE]);
+            rand_bytes(&mut *key)?;
+
+            (wrap_key(&recipient.key, &*key)?, key)
+        }
+        KeyType::EC => {
+            let recipient_key = recipient.key.ec_key()?;
+            let group = recipient_key.group();
+            let ephemeral = PKey::from_ec_key(EcKey::generate(group)?)?;
+
+            let mut ctx = BigNumContext::new()?;
+            let public = ephemeral.ec_key()?.public_key().to_bytes(
+                group,
+                PointConversionForm::COMPRESSED,
+                &mut ctx,
+            )?;
+
+            let key = ecdh_key(&ephemeral, &recipient.key)?;
+
+            (public, key)
+        }
+    };
+
+    let (iv, tag, data) = seal(&*key, data)?;
+
+    blob.extend_from_slice(&iv);
+    blob.extend_from_slice(&tag);
+    blob.extend_from_slice(&data);
+
+    Ok(blob)
+}
+
+/// Derive the AES key from an ECDH secret
+fn ecdh_key<T: HasPrivate, U: HasPublic>(
+    private: &PKeyRef<T>,
+    public: &PKeyRef<U>,
+) -> Result<Zeroizing<[u8; AES_KEY_SIZE]>, ErrorStack> {
+    let mut deriver = Deriver::new(private)?;
+    deriver.set_peer(public)?;
+
+    let secret = Zeroizing::new(deriver.derive_to_vec()?);
+
+    let mut key = Zeroizing::new([0u8; AES_KEY_SIZE]);
+    key.copy_from_slice(&hash(MessageDigest::sha256(), &secret)?);
+
+    Ok(key)
+}
+
+/// Decrypt an RSA blob, returning `None` if it is too short to hold the wrapped key
+fn decrypt_rsa(pkey: &PKey<Private>, blob: &[u8]) -> Result<Option<Vec<u8>>, ErrorStack> {
+    let key_size = pkey.size();
+
+    if blob.len() < key_size + IV_SIZE + TAG_SIZE {
+        return Ok(None);
+    }
+
+    let (wrapped, sealed) = blob.split_at(key_size);
+
+    let mut decrypter = Decrypter::new(pkey)?;
+    decrypter.set_rsa_padding(Padding::PKCS1_OAEP)?;
+    decrypter.set_rsa_oaep_md(MessageDigest::sha256())?;
+    decrypter.set_rsa_mgf1_md(MessageDigest::sha256())?;
+
+    let mut key = Zeroizing::new(vec![0u8; decrypter.decrypt_len(wrapped)?]);
+    let len = decrypter.decrypt(wrapped, &mut key)?;
+    key.truncate(len);
+
+    Ok(Some(open(&key, sealed)?))
+}
+
+/// Decrypt an EC blob, returning `None` if it is too short to hold the ephemeral key
+fn decrypt_ec(pkey: &PKey<Private>, blob: &[u8]) -> Result<Option<Vec<u8>>, ErrorStack> {
+    let ec_key = pkey.ec_key()?;
+    let group = ec_key.group();
+
+    // A compressed point is a prefix byte followed by the x coordinate
+    let point_size = 1 + (group.degree() as usize).div_ceil(8);
+
+    if blob.len() < point_size + IV_SIZE + TAG_SIZE {
+        return Ok(None);
+    }
+
+    let (public, sealed) = blob.split_at(point_size);
+
+    let mut ctx = BigNumContext::new()?;
+    let point = EcPoint::from_bytes(group, public, &mut ctx)?;
+    let ephemeral = PKey::from_ec_key(EcKey::from_public_key(group, &point)?)?;
+
+    let key = ecdh_key(pkey, &ephemeral)?;
+
+    Ok(Some(open(&*key, sealed)?))
+}
+
+/// Decrypt an `[iv][tag][data]` AES-256-GCM blob
+fn open(key: &[u8], sealed: &[u8]) -> Result<Vec<u8>, ErrorStack> {
+    let (iv, sealed) = sealed.split_at(IV_SIZE);
+    let (tag, data) = sealed.split_at(TAG_SIZE);
+
+    decrypt_aead(Cipher::aes_256_gcm(), key, Some(iv), &[], data, tag)
+}
@@ -0,0 +1,135 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Generated packet values
+//!
+//! Implements [`arbitrary::Arbitrary`] with the `arbitrary` feature and
+//! [`proptest::arbitrary::Arbitrary`] with the `proptest` feature for [`PacketValue`], so
+//! contracts and other code handling packets can be fuzzed with values this crate can build.
+//!
+//! Generated values are bounded so they stay a realistic size for a transaction: objects and
+//! arrays nest at most 4 levels and hold at most 8 entries, strings are at most 64 characters
+//! and binary data at most 64 bytes.
+//!
+//! [`PacketValue`]: enum.PacketValue.html
+
+// Internal imports
+use super::PacketValue;
+
+/// Deepest nesting of objects and arrays
+const MAX_DEPTH: u32 = 4;
+
+/// Most entries in an object or array
+const MAX_LEN: usize = 8;
+
+/// Longest string, in characters, or binary value, in bytes
+const MAX_BYTES: usize = 64;
+
+/// Strings of up to MAX_BYTES characters
+#[cfg(feature = "proptest")]
+const STRING_PATTERN: &str = ".{0,64}";
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for PacketValue {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        arbitrary_value(u, MAX_DEPTH)
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_value(
+    u: &mut arbitrary::Unstructured<'_>,
+    depth: u32,
+) -> arbitrary::Result<PacketValue> {
+    // Only leaf values once the depth is used up
+    let kinds = if depth == 0 { 6 } else { 8 };
+
+    let value = match u.int_in_range(0..=kinds - 1)? {
+        0 => PacketValue::Null,
+        1 => PacketValue::Bool(u.arbitrary()?),
+        2 => PacketValue::from(u.arbitrary::<i64>()?),
+        3 => PacketValue::from(u.arbitrary::<f64>()?),
+        4 => PacketValue::String(arbitrary_string(u)?),
+        5 => PacketValue::Binary(arbitrary_bytes(u)?.to_vec()),
+        6 => {
+            let len = u.int_in_range(0..=MAX_LEN)?;
+            let array = (0..len)
+                .map(|_| arbitrary_value(u, depth - 1))
+                .collect::<arbitrary::Result<Vec<_>>>()?;
+
+            PacketValue::Array(array)
+        }
+        _ => {
+            let len = u.int_in_range(0..=MAX_LEN)?;
+            let object = (0..len)
+                .map(|_| Ok((arbitrary_string(u)?, arbitrary_value(u, depth - 1)?)))
+                .collect::<arbitrary::Result<_>>()?;
+
+            PacketValue::Object(object)
+        }
+    };
+
+    Ok(value)
+}
+
+#[cfg(feature = "arbitrary")]
+fn arbitrary_string(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<String> {
+    Ok(String::from_utf8_lossy(arbitrary_bytes(u)?).into_owned())
+}
+
+/// Take up to MAX_BYTES bytes, fewer if the data is running out
+#[cfg(feature = "arbitrary")]
+fn arbitrary_bytes<'a>(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<&'a [u8]> {
+    let len = u.int_in_range(0..=MAX_BYTES)?.min(u.len());
+
+    u.bytes(len)
+}
+
+#[cfg(feature = "proptest")]
+impl proptest::arbitrary::Arbitrary for PacketValue {
+    type Parameters = ();
+    type Strategy = proptest::strategy::BoxedStrategy<PacketValue>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::collection::{hash_map, vec};
+        use proptest::prelude::*;
+
+        let leaf = prop_oneof![
+            Just(PacketValue::Null),
+            any::<bool>().prop_map(PacketValue::Bool),
+            any::<i64>().prop_map(PacketValue::from),
+            any::<f64>().prop_map(PacketValue::from),
+            STRING_PATTERN.prop_map(PacketValue::String),
+            vec(any::<u8>(), 0..=MAX_BYTES).prop_map(PacketValue::Binary),
+        ];
+
+        leaf.prop_recursive(MAX_DEPTH, 64, MAX_LEN as u32, |inner| {
+            prop_oneof![
+                vec(inner.clone(), 0..=MAX_LEN).prop_map(PacketValue::Array),
+                hash_map(STRING_PATTERN, inner, 0..=MAX_LEN)
+                    .prop_map(|object| PacketValue::Object(object.into_iter().collect())),
+            ]
+        })
+        .boxed()
+    }
+}
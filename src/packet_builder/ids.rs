@@ -0,0 +1,130 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Generated IDs
+//!
+//! UUIDs and ULIDs for packet data, such as the IDs of events being recorded, generated
+//! without the caller depending on an ID crate. Requires the `ids` feature.
+
+// External imports
+use chrono::{DateTime, Utc};
+
+// Internal imports
+use super::PacketValue;
+use crate::error::TxBuilderResult;
+use crate::transaction_builder::nonce;
+
+impl PacketValue {
+    /// # UUID
+    ///
+    /// A random (version 4) UUID string, e.g. `"9f1c2e4a-7b3d-4c5e-8f6a-1b2c3d4e5f60"`.
+    /// Also written as `@uuid` in [`packet_data!`](macro.packet_data.html).
+    ///
+    /// # Panics
+    ///
+    /// If the system random number generator fails, as the macro has no way to return the
+    /// error. Use [`try_uuid`](#method.try_uuid) to handle it.
+    ///
+    /// ```
+    /// # use active_tx::PacketValue;
+    /// let id = PacketValue::uuid();
+    /// ```
+    pub fn uuid() -> PacketValue {
+        match PacketValue::try_uuid() {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// # Try UUID
+    ///
+    /// A random (version 4) UUID string, returning `RandomError` if the system random number
+    /// generator fails.
+    ///
+    /// ```
+    /// # use active_tx::PacketValue;
+    /// let id = PacketValue::try_uuid().unwrap();
+    /// ```
+    pub fn try_uuid() -> TxBuilderResult<PacketValue> {
+        Ok(PacketValue::String(nonce::uuid()?))
+    }
+
+    /// # ULID
+    ///
+    /// A ULID string for the current time, e.g. `"01ARZ3NDEKTSV4RRFFQ69G5FAV"`, which sorts in
+    /// the order the IDs were generated. Also written as `@ulid` in
+    /// [`packet_data!`](macro.packet_data.html).
+    ///
+    /// The time comes from the system clock, use [`try_ulid_at`](#method.try_ulid_at) for
+    /// another clock.
+    ///
+    /// # Panics
+    ///
+    /// If the system random number generator fails, as the macro has no way to return the
+    /// error. Use [`try_ulid`](#method.try_ulid) to handle it.
+    ///
+    /// ```
+    /// # use active_tx::PacketValue;
+    /// let id = PacketValue::ulid();
+    /// ```
+    pub fn ulid() -> PacketValue {
+        match PacketValue::try_ulid() {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// # Try ULID
+    ///
+    /// A ULID string for the current time, returning `RandomError` if the system random number
+    /// generator fails.
+    ///
+    /// ```
+    /// # use active_tx::PacketValue;
+    /// let id = PacketValue::try_ulid().unwrap();
+    /// ```
+    pub fn try_ulid() -> TxBuilderResult<PacketValue> {
+        PacketValue::try_ulid_at(Utc::now())
+    }
+
+    /// # Try ULID at
+    ///
+    /// A ULID string for the given time, such as the time from a transaction builder's
+    /// [clock](struct.TransactionBuilder.html#method.with_clock), returning `RandomError` if
+    /// the system random number generator fails.
+    ///
+    /// ```
+    /// # use active_tx::{PacketValue, TransactionBuilder};
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.with_clock(|| Utc.timestamp_millis_opt(1469918176385).unwrap());
+    ///
+    /// let id = PacketValue::try_ulid_at(tx_builder.now()).unwrap();
+    ///
+    /// assert!(matches!(id, PacketValue::String(id) if id.starts_with("01ARYZ6S41")));
+    /// ```
+    pub fn try_ulid_at(time: DateTime<Utc>) -> TxBuilderResult<PacketValue> {
+        Ok(PacketValue::String(nonce::ulid(time)?))
+    }
+}
@@ -0,0 +1,312 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # CSV loader
+//!
+//! Map the rows of a CSV file to packet data, one packet per row, for loading existing data
+//! onto the ledger. Requires the `csv` feature.
+
+// STD
+use std::io::Read;
+
+// External imports
+use csv::{StringRecord, StringRecordsIntoIter};
+
+// Internal imports
+use super::{PacketBuilder, PacketData, PacketMap, PacketValue};
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::{Signees, TxTemplate};
+
+/// # Column type
+///
+/// How the values of a CSV column are added to the packet.
+/// Empty values in typed columns are added as `null`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColumnType {
+    /// The value as it is in the file
+    #[default]
+    String,
+    Integer,
+    Float,
+    /// `true` or `false`
+    Bool,
+}
+
+/// # CSV loader
+///
+/// Reads a CSV file with a header row and maps its columns to dotted paths in the packet.
+/// A part of the path written as `{column}` is replaced with the row's value for that column,
+/// so each row can be keyed by its own stream ID. A row whose value for such a column contains
+/// a `.` is rejected, as it would change the shape of the path.
+///
+/// ```
+/// # use active_tx::{ColumnType, CsvLoader};
+/// let csv = "id,name,balance\nacc-1,Alice,10\nacc-2,Bob,25\n";
+///
+/// let mut loader = CsvLoader::new(csv.as_bytes());
+/// loader
+///     .column("name", "{id}.name")
+///     .typed_column("balance", "{id}.balance", ColumnType::Integer);
+///
+/// let packets: Vec<_> = loader.packets().collect::<Result<_, _>>().unwrap();
+///
+/// assert_eq!(packets.len(), 2);
+/// assert_eq!(packets[1].get_path("acc-2.balance").unwrap(), 25);
+/// ```
+pub struct CsvLoader<R: Read> {
+    reader: csv::Reader<R>,
+    columns: Vec<Column>,
+}
+
+/// Iterator over the packets of a CSV file, created by [`CsvLoader::packets`].
+///
+/// [`CsvLoader::packets`]: struct.CsvLoader.html#method.packets
+pub struct CsvPackets<R: Read> {
+    records: StringRecordsIntoIter<R>,
+    headers: StringRecord,
+    // The column indexes, or the error reading the header row
+    columns: Result<Vec<(usize, Column)>, Option<TxBuilderError>>,
+}
+
+/// A column mapped to a packet path
+#[derive(Debug, Clone)]
+struct Column {
+    header: String,
+    path: String,
+    column_type: ColumnType,
+}
+
+impl<R: Read> CsvLoader<R> {
+    /// # New
+    ///
+    /// Create a loader reading CSV from the reader, such as a file.
+    /// The first row must be the column headers.
+    pub fn new(reader: R) -> CsvLoader<R> {
+        CsvLoader {
+            reader: csv::Reader::from_reader(reader),
+            columns: Vec::new(),
+        }
+    }
+
+    /// # Column
+    ///
+    /// Add the values of the column to the packet at the path, as strings.
+    pub fn column(&mut self, header: &str, path: &str) -> &mut Self {
+        self.typed_column(header, path, ColumnType::String)
+    }
+
+    /// # Typed column
+    ///
+    /// Add the values of the column to the packet at the path, converted to the column type.
+    pub fn typed_column(&mut self, header: &str, path: &str, column_type: ColumnType) -> &mut Self {
+        self.columns.push(Column {
+            header: header.to_string(),
+            path: path.to_string(),
+            column_type,
+        });
+
+        self
+    }
+
+    /// # Packets
+    ///
+    /// Read the file, returning the packet data for each row.
+    ///
+    /// An error is returned for the first row if a mapped column is not in the header row, and
+    /// for each row that can't be read or has a value that does not match its column type.
+    pub fn packets(mut self) -> CsvPackets<R> {
+        let headers = match self.reader.headers() {
+            Ok(headers) => headers.clone(),
+            Err(e) => {
+                return CsvPackets {
+                    records: self.reader.into_records(),
+                    headers: StringRecord::new(),
+                    columns: Err(Some(TxBuilderError::CsvError(e))),
+                }
+            }
+        };
+
+        let mut columns = Ok(Vec::with_capacity(self.columns.len()));
+
+        for column in self.columns {
+            let index = headers.iter().position(|header| header == column.header);
+
+            match (index, &mut columns) {
+                (Some(index), Ok(columns)) => columns.push((index, column)),
+                (None, Ok(_)) => columns = Err(Some(TxBuilderError::UnknownColumn(column.header))),
+                _ => {}
+            }
+        }
+
+        CsvPackets {
+            records: self.reader.into_records(),
+            headers,
+            columns,
+        }
+    }
+
+    /// # Transactions
+    ///
+    /// Read the file, building a transaction from the template for each row using the row's
+    /// packet data as the input. The signees for each row are given by the callback.
+    ///
+    /// ```
+    /// # use active_tx::{signees, CsvLoader, Key, TransactionBuilder};
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("importer").unwrap());
+    /// let template = TransactionBuilder::new("namespace", "import").template().unwrap();
+    ///
+    /// let csv = "id,name\nrec-1,First\nrec-2,Second\n";
+    ///
+    /// let mut loader = CsvLoader::new(csv.as_bytes());
+    /// loader.column("id", "importer.id").column("name", "importer.name");
+    ///
+    /// for tx in loader.transactions(&template, |_| Ok(signees![{"importer" => key.clone()}])) {
+    ///     let tx = tx.unwrap();
+    /// }
+    /// ```
+    pub fn transactions<'a, F>(
+        self,
+        template: &'a TxTemplate,
+        mut signees: F,
+    ) -> impl Iterator<Item = TxBuilderResult<String>> + 'a
+    where
+        R: 'a,
+        F: FnMut(&PacketData) -> TxBuilderResult<Signees> + 'a,
+    {
+        self.packets().map(move |packet| {
+            let packet = packet?;
+            let signees = signees(&packet)?;
+
+            template.instantiate(packet, signees)
+        })
+    }
+}
+
+impl<R: Read> Iterator for CsvPackets<R> {
+    type Item = TxBuilderResult<PacketData>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let columns = match &mut self.columns {
+            Ok(columns) => columns,
+            Err(e) => return e.take().map(Err),
+        };
+
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(TxBuilderError::CsvError(e))),
+        };
+
+        Some(CsvPackets::<R>::packet(&self.headers, columns, &record))
+    }
+}
+
+// Private functions
+impl<R: Read> CsvPackets<R> {
+    /// Build the packet data for a row
+    fn packet(
+        headers: &StringRecord,
+        columns: &[(usize, Column)],
+        record: &StringRecord,
+    ) -> TxBuilderResult<PacketData> {
+        let mut data = PacketBuilder::new(PacketValue::Object(PacketMap::new())).build()?;
+
+        for (index, column) in columns {
+            let value = record.get(*index).unwrap_or_default();
+            let value = CsvPackets::<R>::value(column, value, record)?;
+
+            let path = CsvPackets::<R>::path(&column.path, headers, record)?;
+
+            data.set(&path, value)?;
+        }
+
+        Ok(data)
+    }
+
+    /// Convert a value to the column type
+    fn value(column: &Column, value: &str, record: &StringRecord) -> TxBuilderResult<PacketValue> {
+        if value.is_empty() && column.column_type != ColumnType::String {
+            return Ok(PacketValue::Null);
+        }
+
+        let parsed = match column.column_type {
+            ColumnType::String => Some(PacketValue::from(value)),
+            ColumnType::Integer => value.parse::<i64>().ok().map(PacketValue::from),
+            ColumnType::Float => value.parse::<f64>().ok().map(PacketValue::from),
+            ColumnType::Bool => value.parse::<bool>().ok().map(PacketValue::from),
+        };
+
+        match parsed {
+            Some(value) => Ok(value),
+            None => Err(TxBuilderError::InvalidColumnValue {
+                line: CsvPackets::<R>::line(record),
+                column: column.header.clone(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
+    /// Line of the file the record was read from
+    fn line(record: &StringRecord) -> u64 {
+        record
+            .position()
+            .map(|position| position.line())
+            .unwrap_or_default()
+    }
+
+    /// Replace the `{column}` parts of the path with the row's values
+    fn path(path: &str, headers: &StringRecord, record: &StringRecord) -> TxBuilderResult<String> {
+        let mut parts = Vec::new();
+
+        for part in path.split('.') {
+            let header = match part
+                .strip_prefix('{')
+                .and_then(|part| part.strip_suffix('}'))
+            {
+                Some(header) => header,
+                None => {
+                    parts.push(part);
+                    continue;
+                }
+            };
+
+            let value = match headers.iter().position(|name| name == header) {
+                Some(index) => record.get(index).unwrap_or_default(),
+                None => return Err(TxBuilderError::UnknownColumn(header.to_string())),
+            };
+
+            // The value is a single key, a dot would add levels to the path
+            if value.contains('.') {
+                return Err(TxBuilderError::InvalidColumnValue {
+                    line: CsvPackets::<R>::line(record),
+                    column: header.to_string(),
+                    value: value.to_string(),
+                });
+            }
+
+            parts.push(value);
+        }
+
+        Ok(parts.join("."))
+    }
+}
@@ -22,25 +22,243 @@
  */
 
 // External
-use serde::Serialize;
+#[cfg(feature = "preserve-order")]
+use indexmap::IndexMap;
+use serde::{Serialize, Serializer};
+use serde_json::Number;
 
 // STD
 use std::collections::HashMap;
+use std::iter::FromIterator;
 
 pub type Input = PacketData;
 pub type Output = PacketData;
 pub type Readonly = PacketData;
 
+/// Map used for [`PacketValue::Object`](enum.PacketValue.html#variant.Object).
+///
+/// Keys are written in sorted order by default. With the `preserve-order` feature this is an
+/// `IndexMap` and keys are written in the order they were inserted. The feature also enables
+/// `serde_json/preserve_order`, which applies to every crate using `serde_json` in the build.
+pub type PacketMap = Map<String, PacketValue>;
+
+#[cfg(not(feature = "preserve-order"))]
+type Map<K, V> = HashMap<K, V>;
+#[cfg(feature = "preserve-order")]
+type Map<K, V> = IndexMap<K, V>;
+
 mod builder;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compress;
+#[cfg(feature = "decimal")]
+mod decimal;
+#[cfg(feature = "openssl-keys")]
+pub(crate) mod encrypt;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+mod fuzz;
+#[cfg(feature = "ids")]
+mod ids;
+#[cfg(feature = "csv")]
+mod loader;
+mod output;
+mod readonly;
+mod render;
+#[cfg(any(feature = "yaml", feature = "toml"))]
+mod source;
+mod stream_id;
+mod timestamp;
 
 pub use builder::{PacketBuilder, PacketData};
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use compress::{
+    decompress_field, decompress_field_with_limit, Compression, MAX_DECOMPRESSED_SIZE,
+};
+#[cfg(feature = "decimal")]
+pub(crate) use decimal::decimal_tojson;
+#[cfg(feature = "decimal")]
+pub use decimal::DecimalFormat;
+#[cfg(feature = "openssl-keys")]
+pub use encrypt::decrypt_field;
+#[cfg(feature = "csv")]
+pub use loader::{ColumnType, CsvLoader, CsvPackets};
+pub use output::{OutputBuilder, StreamData};
+pub use readonly::ReadonlyBuilder;
+pub use stream_id::StreamId;
+pub(crate) use stream_id::{is_ledger_id, validate_stream_id};
+pub(crate) use timestamp::stamp_entries;
+pub use timestamp::TimestampFormat;
 
 /// Holds recursive values for the $i (input), $o (output), and $r (readonly) objects of a transaction packet.
 #[derive(Serialize, PartialEq, Eq, Debug, Clone)]
+#[non_exhaustive]
 pub enum PacketValue {
     String(String),
     Array(Vec<PacketValue>),
-    Object(HashMap<String, PacketValue>),
+    Object(PacketMap),
+    Bool(bool),
+    Number(Number),
+    Null,
+    /// Raw bytes, encoded as base64 when the packet is built.
+    /// See [`BinaryFormat`](enum.BinaryFormat.html) for the supported conventions.
+    #[serde(serialize_with = "serialize_base64")]
+    Binary(Vec<u8>),
+    /// An exact decimal amount, requires the `decimal` feature.
+    /// See [`DecimalFormat`](enum.DecimalFormat.html) for how it is written.
+    #[cfg(feature = "decimal")]
+    #[serde(serialize_with = "decimal::serialize_decimal")]
+    Decimal(rust_decimal::Decimal),
+}
+
+impl PacketValue {
+    /// # Get path
+    ///
+    /// Get the value at a dotted path, e.g. "streamid.balance".
+    /// Returns `None` if there is no value at the path.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketValue};
+    /// let data = packet_data!({"streamid": {"balance": 10}});
+    ///
+    /// assert_eq!(data.get_path("streamid.balance"), Some(&PacketValue::from(10)));
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&PacketValue> {
+        let mut current = self;
+
+        for part in path.split('.') {
+            current = match current {
+                PacketValue::Object(object) => object.get(part)?,
+                _ => return None,
+            };
+        }
+
+        Some(current)
+    }
+}
+
+/// # Binary format
+///
+/// Controls how [`PacketValue::Binary`] data is written into the packet.
+/// Set on the builder using [`PacketBuilder::binary_format`].
+///
+/// [`PacketValue::Binary`]: enum.PacketValue.html#variant.Binary
+/// [`PacketBuilder::binary_format`]: struct.PacketBuilder.html#method.binary_format
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum BinaryFormat {
+    /// Standard base64 string - `"aGVsbG8="`
+    #[default]
+    Base64,
+    /// URL safe base64 string without padding - `"aGVsbG8"`
+    Base64Url,
+    /// Object naming the encoding - `{"encoding": "base64", "data": "aGVsbG8="}`
+    Object,
+}
+
+/// Serialize binary data as a standard base64 string
+fn serialize_base64<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&base64::encode(bytes))
+}
+
+impl From<&str> for PacketValue {
+    fn from(value: &str) -> Self {
+        PacketValue::String(value.to_string())
+    }
+}
+
+impl From<String> for PacketValue {
+    fn from(value: String) -> Self {
+        PacketValue::String(value)
+    }
+}
+
+impl From<&String> for PacketValue {
+    fn from(value: &String) -> Self {
+        PacketValue::String(value.clone())
+    }
+}
+
+impl From<bool> for PacketValue {
+    fn from(value: bool) -> Self {
+        PacketValue::Bool(value)
+    }
+}
+
+macro_rules! from_integer {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for PacketValue {
+                fn from(value: $ty) -> Self {
+                    PacketValue::Number(Number::from(value))
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+impl From<f64> for PacketValue {
+    /// Non-finite values can't be represented in JSON and become `Null`
+    fn from(value: f64) -> Self {
+        match Number::from_f64(value) {
+            Some(number) => PacketValue::Number(number),
+            None => PacketValue::Null,
+        }
+    }
+}
+
+impl From<f32> for PacketValue {
+    fn from(value: f32) -> Self {
+        PacketValue::from(f64::from(value))
+    }
+}
+
+impl From<Vec<u8>> for PacketValue {
+    fn from(value: Vec<u8>) -> Self {
+        PacketValue::Binary(value)
+    }
+}
+
+impl From<&[u8]> for PacketValue {
+    fn from(value: &[u8]) -> Self {
+        PacketValue::Binary(value.to_vec())
+    }
+}
+
+// Vec<u8> is binary data, so arrays are converted per element type rather than
+// through a blanket impl. Integer element types are left out so an untyped
+// `vec![1, 2]` still infers as Vec<u8>; use `collect` for integer arrays.
+macro_rules! from_vec {
+    ($($ty:ty),*) => {
+        $(
+            impl From<Vec<$ty>> for PacketValue {
+                fn from(value: Vec<$ty>) -> Self {
+                    value.into_iter().collect()
+                }
+            }
+        )*
+    };
+}
+
+from_vec!(PacketValue, String, &str, bool, f32, f64);
+
+impl<T: Into<PacketValue>> FromIterator<T> for PacketValue {
+    /// Collect values into a `PacketValue::Array`
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        PacketValue::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<PacketValue>> From<HashMap<String, T>> for PacketValue {
+    fn from(value: HashMap<String, T>) -> Self {
+        PacketValue::Object(value.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+#[cfg(feature = "preserve-order")]
+impl<T: Into<PacketValue>> From<IndexMap<String, T>> for PacketValue {
+    fn from(value: IndexMap<String, T>) -> Self {
+        PacketValue::Object(value.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
 }
 
 #[cfg(test)]
@@ -48,6 +266,7 @@ mod tests {
 
     use crate::*;
     use serde_json::json;
+    use std::collections::HashMap;
 
     #[test]
     fn input_macro() {
@@ -59,6 +278,749 @@ mod tests {
         println!("Macro: \n{}\n", input.get().unwrap());
     }
 
+    #[test]
+    fn input_display_serialize() {
+        let input = PacketBuilder::new(packet_data!({"id": {"count": 3}}))
+            .build()
+            .unwrap();
+
+        assert_eq!(input.to_string(), input.get_string().unwrap());
+        assert_eq!(
+            serde_json::to_string(&input).unwrap(),
+            input.get_string().unwrap()
+        );
+
+        // Packet data embeds as JSON, not as a string
+        let body = json!({"input": input});
+        assert_eq!(body["input"]["id"]["count"], 3);
+    }
+
+    #[test]
+    fn input_macro_values() {
+        let name = String::from("name");
+        let count: u32 = 3;
+        let nested = packet_data!({"nested": "value"});
+
+        let object = packet_data!({
+            "name": name,
+            "count": count,
+            "negative": -1,
+            "float": 1.5,
+            "flag": true,
+            "array": [1, "two", false],
+            "nested": nested
+        });
+
+        let input = PacketBuilder::new(object).build().unwrap();
+
+        assert_eq!(
+            input.get().unwrap(),
+            json!({
+                "name": "name",
+                "count": 3,
+                "negative": -1,
+                "float": 1.5,
+                "flag": true,
+                "array": [1, "two", false],
+                "nested": {"nested": "value"}
+            })
+        );
+    }
+
+    #[test]
+    fn input_binary() {
+        let bytes = vec![0xfb, 0xff, 0x00, 0x01];
+
+        let input = PacketBuilder::new(packet_data!({"doc": bytes.clone()}))
+            .add_binary("blob", b"hello")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            input.get().unwrap(),
+            json!({"doc": "+/8AAQ==", "blob": "aGVsbG8="})
+        );
+
+        let input = PacketBuilder::new(packet_data!({"doc": bytes.clone()}))
+            .binary_format(BinaryFormat::Base64Url)
+            .build()
+            .unwrap();
+
+        assert_eq!(input.get().unwrap(), json!({"doc": "-_8AAQ"}));
+
+        let input = PacketBuilder::new(packet_data!({"doc": [bytes]}))
+            .binary_format(BinaryFormat::Object)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            input.get().unwrap(),
+            json!({"doc": [{"encoding": "base64", "data": "+/8AAQ=="}]})
+        );
+
+        let input = PacketBuilder::new_json(json!({"data": "value"}))
+            .add_binary("blob", b"hello")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            input.get().unwrap(),
+            json!({"data": "value", "blob": "aGVsbG8="})
+        );
+
+        assert!(PacketBuilder::new(packet_data!("string"))
+            .add_binary("blob", b"hello")
+            .is_err());
+    }
+
+    #[test]
+    fn input_render() {
+        let template = PacketBuilder::new_json(json!({
+            "{{streamid}}": {
+                "amount": "{{ amount }}",
+                "owner": "{{owner}}",
+                "tags": ["{{tag}}", "fixed"],
+                "note": "{{amount}} to {{streamid}}, {{ unclosed",
+                "literal": "\\{{amount}} is {{amount}}",
+                "count": 1
+            }
+        }))
+        .build()
+        .unwrap();
+
+        let mut vars = HashMap::new();
+        vars.insert("streamid".to_string(), PacketValue::from("alice"));
+        vars.insert("amount".to_string(), PacketValue::from(1.5));
+        vars.insert("owner".to_string(), packet_data!({"name": "Alice"}));
+        vars.insert("tag".to_string(), PacketValue::Null);
+
+        let input = template.render(&vars).unwrap();
+
+        assert_eq!(
+            input.get().unwrap(),
+            json!({
+                "alice": {
+                    "amount": 1.5,
+                    "owner": {"name": "Alice"},
+                    "tags": [null, "fixed"],
+                    "note": "1.5 to alice, {{ unclosed",
+                    "literal": "{{amount}} is 1.5",
+                    "count": 1
+                }
+            })
+        );
+        assert_eq!(input.to_string(), input.get().unwrap().to_string());
+
+        // The template is left as it was
+        assert!(template.get_path("{{streamid}}").is_some());
+
+        vars.remove("owner");
+        match template.render(&vars) {
+            Err(TxBuilderError::MissingVariable(name)) => assert_eq!(name, "owner"),
+            _ => unreachable!(),
+        }
+
+        // Keys rendering the same would drop a value
+        let template = PacketBuilder::new_json(json!({"{{streamid}}": {}, "alice": {}}))
+            .build()
+            .unwrap();
+        match template.render(&vars) {
+            Err(TxBuilderError::RenderKeyCollision(key)) => assert_eq!(key, "alice"),
+            _ => unreachable!(),
+        }
+
+        // Variables take the formats of the builder that built the template
+        let template = PacketBuilder::new_json(json!({"alice": {"blob": "{{blob}}"}}))
+            .binary_format(BinaryFormat::Base64Url)
+            .build()
+            .unwrap();
+        let mut vars = HashMap::new();
+        vars.insert("blob".to_string(), PacketValue::Binary(b"hello".to_vec()));
+        let input = template.render(&vars).unwrap();
+        assert_eq!(input.get_path("alice.blob").unwrap(), "aGVsbG8");
+    }
+
+    #[test]
+    fn input_timestamp() {
+        use chrono::{DateTime, TimeZone, Utc};
+
+        let time = Utc.timestamp_opt(1577836800, 5_000_000).unwrap();
+
+        assert_eq!(
+            PacketValue::from_datetime(time, TimestampFormat::Iso8601),
+            PacketValue::from("2020-01-01T00:00:00.005Z")
+        );
+        assert_eq!(
+            PacketValue::from_datetime(time, TimestampFormat::UnixMillis),
+            PacketValue::from(1577836800005i64)
+        );
+
+        let input = PacketBuilder::new(packet_data!({
+            "alice": {"amount": 10, "created": "old"},
+            "bob": {},
+            "carol": "not an object"
+        }))
+        .timestamp_entries("created")
+        .build()
+        .unwrap();
+
+        let created = input.get_path("alice.created").unwrap().as_str().unwrap();
+        assert!(DateTime::parse_from_rfc3339(created).is_ok());
+        assert_eq!(input.get_path("bob.created").unwrap(), created);
+        assert_eq!(input.get_path("carol").unwrap(), "not an object");
+
+        let input = PacketBuilder::new_json(json!({"alice": {}}))
+            .timestamp_entries("created")
+            .build()
+            .unwrap();
+
+        assert!(input.get_path("alice.created").is_some());
+
+        let input = PacketBuilder::new(packet_data!({"alice": {}}))
+            .timestamp_entries_at("created", time)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            input.get_path("alice.created").unwrap(),
+            "2020-01-01T00:00:00.005Z"
+        );
+    }
+
+    #[cfg(feature = "ids")]
+    #[test]
+    fn input_ids() {
+        use chrono::{TimeZone, Utc};
+
+        let input = PacketBuilder::new(packet_data!({
+            "event": {"id": @uuid, "ref": @ulid, "tags": [@uuid, "manual", @ulid]},
+            "other": {"id": @uuid}
+        }))
+        .build()
+        .unwrap();
+
+        let id = input.get_path("event.id").unwrap().as_str().unwrap();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+        assert!("89ab".contains(&id[19..20]));
+        assert_ne!(input.get_path("other.id").unwrap(), id);
+
+        assert_eq!(
+            input.get_path("event.ref").unwrap().as_str().unwrap().len(),
+            26
+        );
+
+        let tags = input.get_path("event.tags").unwrap().as_array().unwrap();
+        assert_eq!(tags.len(), 3);
+        assert_eq!(tags[1], "manual");
+        assert_eq!(tags[2].as_str().unwrap().len(), 26);
+
+        assert!(PacketValue::try_uuid().is_ok());
+        let time = Utc.timestamp_millis_opt(1469918176385).unwrap();
+        match PacketValue::try_ulid_at(time).unwrap() {
+            PacketValue::String(id) => assert!(id.starts_with("01ARYZ6S41")),
+            _ => unreachable!(),
+        }
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn input_decimal() {
+        use rust_decimal::Decimal;
+
+        let tenth = Decimal::new(1, 1);
+        let large = Decimal::new(i64::MAX, 0) * Decimal::new(10, 0);
+        let precise = Decimal::new(1234567890123456789, 10);
+
+        let input = PacketBuilder::new(packet_data!({"amounts": [tenth, large, precise]}))
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            input.get().unwrap(),
+            json!({"amounts": ["0.1", "92233720368547758070", "123456789.0123456789"]})
+        );
+
+        let input = PacketBuilder::new(packet_data!({"amounts": [tenth, Decimal::new(-500, 2)]}))
+            .decimal_format(DecimalFormat::Number)
+            .build()
+            .unwrap();
+
+        assert_eq!(input.get().unwrap(), json!({"amounts": [0.1, -5]}));
+
+        let err = PacketBuilder::new(packet_data!({"amount": precise}))
+            .decimal_format(DecimalFormat::Number)
+            .build()
+            .unwrap_err();
+
+        assert_eq!(err.code(), 1004);
+    }
+
+    #[test]
+    fn input_mutation() {
+        let mut input = PacketBuilder::new(packet_data!({"id": {"data": "value", "secret": "x"}}))
+            .build()
+            .unwrap();
+
+        let device = PacketBuilder::new(packet_data!({"id": {"device": "sensor"}, "other": {}}))
+            .build()
+            .unwrap();
+
+        input
+            .merge(device)
+            .unwrap()
+            .set("id.meta.time", PacketValue::from(10))
+            .unwrap();
+
+        assert_eq!(input.remove("id.secret").unwrap(), Some(json!("x")));
+        assert_eq!(input.remove("id.missing.key").unwrap(), None);
+
+        let expected = json!({
+            "id": {"data": "value", "device": "sensor", "meta": {"time": 10}},
+            "other": {}
+        });
+        assert_eq!(input.get().unwrap(), expected);
+        assert_eq!(input.get_string().unwrap(), expected.to_string());
+
+        assert!(input
+            .set("id.data.nested", PacketValue::from("value"))
+            .is_err());
+        assert!(input.set("id..data", PacketValue::Null).is_err());
+    }
+
+    #[test]
+    fn input_get_path() {
+        let data = packet_data!({"id": {"balance": 10, "tags": ["a"]}});
+
+        assert_eq!(data.get_path("id.balance"), Some(&PacketValue::from(10)));
+        assert_eq!(data.get_path("id.missing"), None);
+        assert_eq!(data.get_path("id.balance.nested"), None);
+
+        let input = PacketBuilder::new(data).build().unwrap();
+
+        assert_eq!(input.get_path("id.balance"), Some(&json!(10)));
+        assert_eq!(input.get_path("id.tags"), Some(&json!(["a"])));
+        assert_eq!(input.get_path("id.none"), None);
+        assert_eq!(input.get_path(""), None);
+    }
+
+    #[cfg(feature = "openssl-keys")]
+    #[test]
+    fn input_encrypt_field() {
+        use activeledger::key::{EllipticCurve, RSA};
+
+        let rsa = Key::Rsa(RSA::new("rsa").unwrap());
+        let ec = Key::Ec(EllipticCurve::new("ec").unwrap());
+
+        let mut builder = PacketBuilder::new(packet_data!({
+            "id": {"name": "Jane Doe", "address": {"city": "London"}, "public": "value"}
+        }));
+        builder
+            .encrypt_field("id.name", &rsa.public_pem().unwrap())
+            .unwrap()
+            .encrypt_field("id.address", &ec.public_pem().unwrap())
+            .unwrap();
+
+        // Building twice must not encrypt the fields again
+        builder.build().unwrap();
+        let input = builder.build().unwrap();
+
+        assert_eq!(input.get_path("id.public"), Some(&json!("value")));
+        assert!(!input.get_string().unwrap().contains("London"));
+
+        let name = input.get_path("id.name").unwrap().as_str().unwrap();
+        let address = input.get_path("id.address").unwrap().as_str().unwrap();
+
+        assert_eq!(decrypt_field(name, &rsa).unwrap(), json!("Jane Doe"));
+        assert_eq!(
+            decrypt_field(address, &ec).unwrap(),
+            json!({"city": "London"})
+        );
+
+        assert!(matches!(
+            decrypt_field(name, &ec),
+            Err(TxBuilderError::FieldDecryptionError(Some(_)))
+        ));
+        assert_eq!(decrypt_field(address, &rsa).unwrap_err().code(), 3006);
+        assert!(matches!(
+            decrypt_field("not base64!", &rsa),
+            Err(TxBuilderError::FieldDecryptionError(None))
+        ));
+
+        assert!(PacketBuilder::new(packet_data!({"id": "value"}))
+            .encrypt_field("id.missing", &rsa.public_pem().unwrap())
+            .unwrap()
+            .build()
+            .is_err());
+        assert!(PacketBuilder::new(packet_data!({"id": "value"}))
+            .encrypt_field("id", "not a pem")
+            .is_err());
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn input_compress_field() {
+        #[cfg(feature = "gzip")]
+        let compression = Compression::Gzip;
+        #[cfg(not(feature = "gzip"))]
+        let compression = Compression::Zstd;
+
+        let text = "notarized document ".repeat(200);
+        let bytes = vec![7u8; 2048];
+
+        let mut builder = PacketBuilder::new(packet_data!({
+            "id": {"text": text.clone(), "bytes": bytes.clone(), "count": 1}
+        }));
+        builder
+            .compress_field("id.text", compression)
+            .unwrap()
+            .compress_field("id.bytes", compression)
+            .unwrap();
+
+        let input = builder.build().unwrap();
+
+        assert!(input.get_string().unwrap().len() < 1000);
+        assert_eq!(input.get_path("id.bytes.type"), Some(&json!("binary")));
+
+        let text_field = input.get_path("id.text").unwrap();
+        let bytes_field = input.get_path("id.bytes").unwrap();
+
+        assert_eq!(
+            decompress_field(text_field).unwrap(),
+            PacketValue::String(text)
+        );
+        assert_eq!(
+            decompress_field(bytes_field).unwrap(),
+            PacketValue::Binary(bytes)
+        );
+        assert!(decompress_field(&json!({"encoding": "none", "data": ""})).is_err());
+        assert_eq!(
+            decompress_field_with_limit(text_field, 1000)
+                .unwrap_err()
+                .code(),
+            3011
+        );
+
+        assert!(PacketBuilder::new(packet_data!({"id": {"count": 1}}))
+            .compress_field("id.count", compression)
+            .unwrap()
+            .build()
+            .is_err());
+
+        // Compressed JSON fields are compressed as text
+        let input = PacketBuilder::new_json(json!({"id": {"text": "value"}}))
+            .compress_field("id.text", compression)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            decompress_field(input.get_path("id.text").unwrap()).unwrap(),
+            PacketValue::from("value")
+        );
+    }
+
+    /// Levels of objects and arrays in the value
+    #[cfg(any(feature = "arbitrary", feature = "proptest"))]
+    fn depth(value: &PacketValue) -> usize {
+        match value {
+            PacketValue::Array(array) => 1 + array.iter().map(depth).max().unwrap_or(0),
+            PacketValue::Object(object) => 1 + object.values().map(depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    #[cfg(all(feature = "yaml", feature = "toml"))]
+    #[test]
+    fn input_yaml_toml() {
+        let yaml = "
+streamid:
+  name: Sensor 1
+  count: 3
+  ratio: 0.5
+  enabled: true
+  roles: [sensor, reporter]
+  parent: ~
+";
+        let toml = r#"
+[streamid]
+name = "Sensor 1"
+count = 3
+ratio = 0.5
+enabled = true
+roles = ["sensor", "reporter"]
+"#;
+
+        let from_yaml = PacketBuilder::new_yaml(yaml).unwrap().build().unwrap();
+        let from_toml = PacketBuilder::new_toml(toml).unwrap().build().unwrap();
+
+        let mut expected = json!({"streamid": {
+            "name": "Sensor 1",
+            "count": 3,
+            "ratio": 0.5,
+            "enabled": true,
+            "roles": ["sensor", "reporter"]
+        }});
+        assert_eq!(from_toml.get().unwrap(), expected);
+
+        expected["streamid"]["parent"] = json!(null);
+        assert_eq!(from_yaml.get().unwrap(), expected);
+
+        // TOML dates are kept as strings
+        let dated = PacketBuilder::new_toml("at = 2024-01-02T03:04:05Z")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(dated.get().unwrap(), json!({"at": "2024-01-02T03:04:05Z"}));
+
+        let error = PacketBuilder::new_yaml("streamid: [unclosed").err().unwrap();
+        assert_eq!(error.code(), 2003);
+        let error = PacketBuilder::new_toml("streamid = ").err().unwrap();
+        assert_eq!(error.code(), 2004);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn input_csv() {
+        let csv = "id,name,balance,active\nacc-1,Alice,10,true\nacc-2,Bob,,false\n";
+
+        let mut loader = CsvLoader::new(csv.as_bytes());
+        loader
+            .column("name", "{id}.profile.name")
+            .typed_column("balance", "{id}.balance", ColumnType::Integer)
+            .typed_column("active", "{id}.active", ColumnType::Bool);
+
+        let packets: Vec<PacketData> = loader.packets().collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(
+            packets[0].get().unwrap(),
+            json!({"acc-1": {"profile": {"name": "Alice"}, "balance": 10, "active": true}})
+        );
+        assert_eq!(
+            packets[1].get().unwrap(),
+            json!({"acc-2": {"profile": {"name": "Bob"}, "balance": null, "active": false}})
+        );
+
+        // Unknown columns fail once, before any row is read
+        let mut loader = CsvLoader::new(csv.as_bytes());
+        loader.column("missing", "id.missing");
+        let results: Vec<_> = loader.packets().collect();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].as_ref().err().unwrap().code(), 2006);
+
+        // Bad values fail their row only
+        let mut loader = CsvLoader::new(csv.as_bytes());
+        loader.typed_column("name", "{id}.name", ColumnType::Float);
+        let results: Vec<_> = loader.packets().collect();
+        assert_eq!(results.len(), 2);
+        match &results[1] {
+            Err(TxBuilderError::InvalidColumnValue {
+                line,
+                column,
+                value,
+            }) => assert_eq!((*line, column.as_str(), value.as_str()), (3, "name", "Bob")),
+            _ => panic!("expected an invalid value"),
+        }
+
+        // Values used as keys can't add levels to the path
+        let csv = "id,name
+acc-1,Alice
+acc.2,Bob
+";
+        let mut loader = CsvLoader::new(csv.as_bytes());
+        loader.column("name", "{id}.name");
+        let results: Vec<_> = loader.packets().collect();
+        assert!(results[0].is_ok());
+        match &results[1] {
+            Err(TxBuilderError::InvalidColumnValue { column, value, .. }) => {
+                assert_eq!((column.as_str(), value.as_str()), ("id", "acc.2"))
+            }
+            _ => panic!("expected an invalid value"),
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn input_arbitrary() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        for seed in 0..64u8 {
+            let data: Vec<u8> = (0..1024u32)
+                .map(|i| (i as u8).wrapping_mul(seed).wrapping_add(seed))
+                .collect();
+
+            let value = PacketValue::arbitrary(&mut Unstructured::new(&data)).unwrap();
+
+            assert!(depth(&value) <= 4);
+
+            let input = PacketBuilder::new(packet_data!({ "streamid": value }))
+                .build()
+                .unwrap();
+            assert!(input.get_path("streamid").is_some());
+        }
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest::proptest! {
+        #[test]
+        fn input_proptest(value in proptest::prelude::any::<PacketValue>()) {
+            proptest::prop_assert!(depth(&value) <= 4);
+
+            let input = PacketBuilder::new(packet_data!({ "streamid": value }))
+                .build()
+                .unwrap();
+            proptest::prop_assert!(input.get_path("streamid").is_some());
+        }
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn input_schema() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id"],
+            "properties": {
+                "id": {
+                    "type": "object",
+                    "required": ["balance"],
+                    "properties": {"balance": {"type": "integer", "minimum": 0}}
+                }
+            }
+        });
+
+        let input = PacketBuilder::new(packet_data!({"id": {"balance": 10}}))
+            .with_schema(&schema)
+            .unwrap()
+            .build();
+
+        assert!(input.is_ok());
+
+        let error = PacketBuilder::new_json(json!({"id": {"balance": -1}, "other": 1}))
+            .with_schema(&schema)
+            .unwrap()
+            .build()
+            .unwrap_err();
+
+        match &error {
+            TxBuilderError::SchemaViolation(violations) => {
+                assert_eq!(violations.len(), 1);
+                assert!(violations[0].starts_with("/id/balance: "));
+            }
+            _ => panic!("Expected schema violation, got {}", error),
+        }
+        assert_eq!(error.code(), 1003);
+
+        let error = PacketBuilder::new(packet_data!({}))
+            .with_schema(&json!({"type": 1}))
+            .err()
+            .unwrap();
+
+        assert_eq!(error.code(), 1002);
+    }
+
+    #[test]
+    fn output_builder() {
+        let mut output = OutputBuilder::new();
+        output.add_stream("receiver").unwrap().set("amount", 10);
+        output
+            .add_stream("sender")
+            .unwrap()
+            .set("amount", -10)
+            .set("memo", "payment");
+        output
+            .add_stream("receiver")
+            .unwrap()
+            .set("memo", "received");
+
+        assert_eq!(
+            output.add_stream("sender").unwrap().get("amount"),
+            Some(&PacketValue::from(-10))
+        );
+
+        assert_eq!(
+            output.build().unwrap().get().unwrap(),
+            json!({
+                "receiver": {"amount": 10, "memo": "received"},
+                "sender": {"amount": -10, "memo": "payment"}
+            })
+        );
+
+        let error = output.add_stream("").err().unwrap();
+        assert_eq!(error.code(), 3004);
+    }
+
+    #[test]
+    fn readonly_builder() {
+        let readonly = ReadonlyBuilder::new()
+            .add("config", "stream1")
+            .unwrap()
+            .add("rates", "stream2")
+            .unwrap()
+            .add("config", "stream3")
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            readonly.get().unwrap(),
+            json!({"config": "stream3", "rates": "stream2"})
+        );
+
+        for invalid in &["", "stream 1", "stream\u{0}"] {
+            let error = ReadonlyBuilder::new().add("label", invalid).err().unwrap();
+            assert_eq!(error.code(), 3004);
+        }
+    }
+
+    #[test]
+    fn stream_id_strict_and_lenient() {
+        let id = "3b1f0c6a9e2d4b8f7a5c1e0d9b8a7f6e5d4c3b2a1f0e9d8c7b6a5f4e3d2c1b0a";
+
+        let strict = StreamId::new(id).unwrap();
+        assert_eq!(strict, id);
+        assert!(strict.is_ledger_id());
+        assert_eq!(strict.to_string(), id);
+
+        for (invalid, reason) in &[
+            ("3b1f", "not 64 characters"),
+            (&"z".repeat(64)[..], "not hex"),
+        ] {
+            match invalid.parse::<StreamId>() {
+                Err(TxBuilderError::InvalidStreamId { reason: r, .. }) => assert_eq!(r, *reason),
+                _ => unreachable!(),
+            }
+        }
+
+        // Lenient conversion keeps key names, the builders still reject whitespace
+        let name = StreamId::from("keyname");
+        assert!(!name.is_ledger_id());
+
+        let mut output = OutputBuilder::new();
+        output.add_stream(&strict).unwrap().set("amount", 1);
+        output.add_stream(name).unwrap();
+        assert!(output.add_stream(StreamId::from("a b")).is_err());
+
+        // Strict builders apply the parsing rules to strings too
+        let mut strict_output = OutputBuilder::new();
+        strict_output.strict_stream_ids(true);
+        assert!(strict_output.add_stream(id).is_ok());
+        assert_eq!(
+            strict_output.add_stream("keyname").err().unwrap().code(),
+            3004
+        );
+
+        let mut strict_readonly = ReadonlyBuilder::new();
+        strict_readonly.strict_stream_ids(true);
+        assert!(strict_readonly.add("config", "keyname").is_err());
+
+        assert_eq!(
+            output.build().unwrap().get().unwrap(),
+            json!({id: {"amount": 1}, "keyname": {}})
+        );
+    }
+
     #[test]
     fn input_json() {
         let json = json!({"I am": "json", "heres": ["an", "array"], "andbool": true});
@@ -68,4 +1030,52 @@ mod tests {
 
         println!("Json: \n{}\n", input.get().unwrap());
     }
+
+    #[test]
+    fn packet_value_from_types() {
+        let mut row = HashMap::new();
+        row.insert("name".to_string(), PacketValue::from("alice"));
+        row.insert("tags".to_string(), PacketValue::from(vec!["a", "b"]));
+        row.insert("scores".to_string(), PacketValue::from(vec![1.5, 2.0]));
+        row.insert("active".to_string(), PacketValue::from(true));
+
+        let counts: PacketValue = (1..=3).collect();
+        row.insert("counts".to_string(), counts);
+
+        let input = PacketBuilder::new(PacketValue::from(row)).build().unwrap();
+
+        assert_eq!(
+            input.get().unwrap(),
+            json!({
+                "name": "alice",
+                "tags": ["a", "b"],
+                "scores": [1.5, 2.0],
+                "active": true,
+                "counts": [1, 2, 3]
+            })
+        );
+        assert_eq!(
+            PacketValue::from(vec![0u8, 1]),
+            PacketValue::Binary(vec![0, 1])
+        );
+    }
+
+    #[cfg(feature = "preserve-order")]
+    #[test]
+    fn input_preserve_order() {
+        let object = packet_data!({"zebra": 1, "apple": {"y": 1, "b": 2, "m": 3}, "mango": 3});
+        let mut input = PacketBuilder::new(object).build().unwrap();
+
+        assert_eq!(
+            input.get_string().unwrap(),
+            r#"{"zebra":1,"apple":{"y":1,"b":2,"m":3},"mango":3}"#
+        );
+
+        input.remove("apple.y").unwrap();
+
+        assert_eq!(
+            input.get_string().unwrap(),
+            r#"{"zebra":1,"apple":{"b":2,"m":3},"mango":3}"#
+        );
+    }
 }
@@ -0,0 +1,133 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Output builder
+//!
+//! The $o object is keyed by stream ID, each stream holding the data the contract writes to it.
+
+// Internal imports
+use super::{validate_stream_id, Map, Output, PacketBuilder, PacketMap, PacketValue, StreamId};
+use crate::error::TxBuilderResult;
+
+/// Builds the $o (output) object of a transaction packet
+#[derive(Debug, Clone, Default)]
+pub struct OutputBuilder {
+    streams: Map<String, StreamData>,
+    strict_stream_ids: bool,
+}
+
+/// The data for one stream in a packet object
+#[derive(Debug, Clone, Default)]
+pub struct StreamData {
+    data: PacketMap,
+}
+
+impl OutputBuilder {
+    /// # New
+    ///
+    /// Create an empty output builder.
+    pub fn new() -> OutputBuilder {
+        OutputBuilder {
+            streams: Map::new(),
+            strict_stream_ids: false,
+        }
+    }
+
+    /// # Strict stream IDs
+    ///
+    /// Only accept stream IDs created by the ledger, 64 hex characters, as
+    /// [`StreamId::new`](struct.StreamId.html#method.new) does, including IDs given as strings.
+    pub fn strict_stream_ids(&mut self, strict: bool) -> &mut Self {
+        self.strict_stream_ids = strict;
+
+        self
+    }
+
+    /// # Add stream
+    ///
+    /// Get the data for the stream, adding the stream if it hasn't been added yet.
+    ///
+    /// An error is returned if the stream ID is empty or contains whitespace or control
+    /// characters, or is not a ledger stream ID when [strict](#method.strict_stream_ids).
+    ///
+    /// ```
+    /// # use active_tx::{OutputBuilder, TransactionBuilder};
+    /// let mut output = OutputBuilder::new();
+    /// output.add_stream("receiver").unwrap().set("amount", 10);
+    /// output
+    ///     .add_stream("fees")
+    ///     .unwrap()
+    ///     .set("amount", 1)
+    ///     .set("memo", "transfer fee");
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.output(output.build().unwrap()).unwrap();
+    /// ```
+    pub fn add_stream<S: Into<StreamId>>(
+        &mut self,
+        streamid: S,
+    ) -> TxBuilderResult<&mut StreamData> {
+        let streamid = streamid.into();
+        validate_stream_id(streamid.as_str(), self.strict_stream_ids)?;
+
+        Ok(self.streams.entry(streamid.into_string()).or_default())
+    }
+
+    /// # Build
+    ///
+    /// Build the output data to pass to the transaction builder.
+    pub fn build(&self) -> TxBuilderResult<Output> {
+        let streams = self
+            .streams
+            .iter()
+            .map(|(streamid, stream)| (streamid.clone(), stream.to_value()))
+            .collect();
+
+        PacketBuilder::new(PacketValue::Object(streams)).build()
+    }
+}
+
+impl StreamData {
+    /// # Set
+    ///
+    /// Set a value for the stream, replacing any existing value for the key.
+    pub fn set<V: Into<PacketValue>>(&mut self, key: &str, value: V) -> &mut Self {
+        self.data.insert(key.to_string(), value.into());
+
+        self
+    }
+
+    /// # Get
+    ///
+    /// Get the value for the key.
+    pub fn get(&self, key: &str) -> Option<&PacketValue> {
+        self.data.get(key)
+    }
+}
+
+// Private functions
+impl StreamData {
+    fn to_value(&self) -> PacketValue {
+        PacketValue::Object(self.data.clone())
+    }
+}
@@ -0,0 +1,102 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Readonly builder
+//!
+//! The $r object maps labels to the stream IDs a contract may read. It is flat, nested objects
+//! can't be resolved by the contract.
+
+// Internal imports
+use super::{validate_stream_id, PacketBuilder, PacketMap, PacketValue, Readonly, StreamId};
+use crate::error::TxBuilderResult;
+
+/// Builds the $r (readonly) object of a transaction packet
+#[derive(Debug, Clone, Default)]
+pub struct ReadonlyBuilder {
+    streams: PacketMap,
+    strict_stream_ids: bool,
+}
+
+impl ReadonlyBuilder {
+    /// # New
+    ///
+    /// Create an empty readonly builder.
+    pub fn new() -> ReadonlyBuilder {
+        ReadonlyBuilder {
+            streams: PacketMap::new(),
+            strict_stream_ids: false,
+        }
+    }
+
+    /// # Strict stream IDs
+    ///
+    /// Only accept stream IDs created by the ledger, 64 hex characters, as
+    /// [`StreamId::new`](struct.StreamId.html#method.new) does, including IDs given as strings.
+    pub fn strict_stream_ids(&mut self, strict: bool) -> &mut Self {
+        self.strict_stream_ids = strict;
+
+        self
+    }
+
+    /// # Add
+    ///
+    /// Add a stream ID under the label the contract uses to read it.
+    /// Adding the same label again replaces its stream ID.
+    ///
+    /// An error is returned if the stream ID is empty or contains whitespace or control
+    /// characters, or is not a ledger stream ID when [strict](#method.strict_stream_ids).
+    ///
+    /// ```
+    /// # use active_tx::{ReadonlyBuilder, TransactionBuilder};
+    /// let readonly = ReadonlyBuilder::new()
+    ///     .add("config", "8fc5e9a1d4f0c7b2")
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.readonly(readonly).unwrap();
+    /// ```
+    pub fn add<S: Into<StreamId>>(
+        &mut self,
+        label: &str,
+        stream_id: S,
+    ) -> TxBuilderResult<&mut Self> {
+        let stream_id = stream_id.into();
+        validate_stream_id(stream_id.as_str(), self.strict_stream_ids)?;
+
+        self.streams.insert(
+            label.to_string(),
+            PacketValue::from(stream_id.into_string()),
+        );
+
+        Ok(self)
+    }
+
+    /// # Build
+    ///
+    /// Build the readonly data to pass to the transaction builder.
+    pub fn build(&self) -> TxBuilderResult<Readonly> {
+        PacketBuilder::new(PacketValue::Object(self.streams.clone())).build()
+    }
+}
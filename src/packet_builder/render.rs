@@ -0,0 +1,124 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Rendering
+//!
+//! Substitutes `{{placeholder}}` tokens in templated packet data, so the shape of a payload
+//! can be kept in a file while the code supplies the values. A `{{` preceded by a backslash is
+//! written as a literal `{{`.
+
+// STD
+use std::collections::HashMap;
+
+// External imports
+use serde_json::{Map, Value};
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Replace the placeholders in a value and everything it holds
+pub(crate) fn render_json(value: &Value, vars: &HashMap<String, Value>) -> TxBuilderResult<Value> {
+    let rendered = match value {
+        Value::String(text) => match placeholder(text) {
+            // A value that is only a placeholder takes the variable's type
+            Some(name) => lookup(name, vars)?.clone(),
+            None => Value::String(render_str(text, vars)?),
+        },
+        Value::Array(array) => Value::Array(
+            array
+                .iter()
+                .map(|value| render_json(value, vars))
+                .collect::<TxBuilderResult<_>>()?,
+        ),
+        Value::Object(object) => {
+            let mut rendered = Map::new();
+
+            for (key, value) in object {
+                let key = render_str(key, vars)?;
+
+                // Two keys rendering the same would silently drop one of the values
+                if rendered.contains_key(&key) {
+                    return Err(TxBuilderError::RenderKeyCollision(key));
+                }
+
+                rendered.insert(key, render_json(value, vars)?);
+            }
+
+            Value::Object(rendered)
+        }
+        other => other.clone(),
+    };
+
+    Ok(rendered)
+}
+
+/// Replace the placeholders within a string with the text of their variables
+fn render_str(text: &str, vars: &HashMap<String, Value>) -> TxBuilderResult<String> {
+    let mut rendered = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("{{") {
+        if rest[..start].ends_with('\\') {
+            rendered.push_str(&rest[..start - 1]);
+            rendered.push_str("{{");
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        let end = match rest[start..].find("}}") {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        rendered.push_str(&rest[..start]);
+
+        match lookup(rest[start + 2..end].trim(), vars)? {
+            Value::String(value) => rendered.push_str(value),
+            value => rendered.push_str(&value.to_string()),
+        }
+
+        rest = &rest[end + 2..];
+    }
+
+    rendered.push_str(rest);
+
+    Ok(rendered)
+}
+
+/// The name of the placeholder if the string is a single placeholder
+fn placeholder(text: &str) -> Option<&str> {
+    let name = text.strip_prefix("{{")?.strip_suffix("}}")?;
+
+    if name.contains("{{") || name.contains("}}") {
+        return None;
+    }
+
+    Some(name.trim())
+}
+
+fn lookup<'a>(name: &str, vars: &'a HashMap<String, Value>) -> TxBuilderResult<&'a Value> {
+    match vars.get(name) {
+        Some(value) => Ok(value),
+        None => Err(TxBuilderError::MissingVariable(name.to_string())),
+    }
+}
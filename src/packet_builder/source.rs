@@ -0,0 +1,96 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Packet sources
+//!
+//! Parse configuration style payload definitions, written in YAML or TOML, into packet values.
+
+// External imports
+#[cfg(feature = "yaml")]
+use serde_json::Value;
+
+// Internal imports
+use super::{PacketMap, PacketValue};
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Parse a YAML document into a packet value
+#[cfg(feature = "yaml")]
+pub(super) fn from_yaml(source: &str) -> TxBuilderResult<PacketValue> {
+    match serde_yaml::from_str::<Value>(source) {
+        Ok(value) => Ok(from_json(value)),
+        Err(e) => Err(TxBuilderError::YamlError(e)),
+    }
+}
+
+/// Parse a TOML document into a packet value
+#[cfg(feature = "toml")]
+pub(super) fn from_toml(source: &str) -> TxBuilderResult<PacketValue> {
+    match source.parse::<toml::Table>() {
+        Ok(table) => Ok(from_toml_value(toml::Value::Table(table))),
+        Err(e) => Err(TxBuilderError::TomlError(e)),
+    }
+}
+
+#[cfg(feature = "yaml")]
+fn from_json(value: Value) -> PacketValue {
+    match value {
+        Value::Null => PacketValue::Null,
+        Value::Bool(value) => PacketValue::Bool(value),
+        Value::Number(value) => PacketValue::Number(value),
+        Value::String(value) => PacketValue::String(value),
+        Value::Array(values) => PacketValue::Array(values.into_iter().map(from_json).collect()),
+        Value::Object(object) => {
+            let mut map = PacketMap::new();
+
+            for (key, value) in object {
+                map.insert(key, from_json(value));
+            }
+
+            PacketValue::Object(map)
+        }
+    }
+}
+
+/// Dates and times are written as their TOML string form
+#[cfg(feature = "toml")]
+fn from_toml_value(value: toml::Value) -> PacketValue {
+    match value {
+        toml::Value::String(value) => PacketValue::String(value),
+        toml::Value::Integer(value) => PacketValue::from(value),
+        toml::Value::Float(value) => PacketValue::from(value),
+        toml::Value::Boolean(value) => PacketValue::Bool(value),
+        toml::Value::Datetime(value) => PacketValue::String(value.to_string()),
+        toml::Value::Array(values) => {
+            PacketValue::Array(values.into_iter().map(from_toml_value).collect())
+        }
+        toml::Value::Table(table) => {
+            let mut map = PacketMap::new();
+
+            for (key, value) in table {
+                map.insert(key, from_toml_value(value));
+            }
+
+            PacketValue::Object(map)
+        }
+    }
+}
@@ -0,0 +1,206 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Stream IDs
+//!
+//! The stream ID type taken by the builders, signees and verifiers, and the lenient and strict
+//! checks applied to the IDs they are given.
+
+// STD
+use std::fmt;
+use std::str::FromStr;
+
+// External imports
+use serde::Serialize;
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Length of an Activeledger stream ID, the hex SHA-256 of the stream
+const STREAM_ID_LEN: usize = 64;
+
+/// # Stream ID
+///
+/// A stream ID, passed anywhere a stream is named such as [`Signees::add`],
+/// [`OutputBuilder::add_stream`] and [`VerifierSet::add`].
+///
+/// Converting from a string with `From` is lenient, keeping the value as given so key names
+/// can be used for self signed transactions. Parsing, or [`new`](#method.new), is strict and
+/// only accepts the 64 hex character IDs the ledger creates, so a mistyped ID is caught when
+/// it is created rather than rejected by consensus.
+///
+/// The builders check stream IDs given as strings leniently, rejecting only empty IDs and
+/// whitespace or control characters. Enable strict checking on the builder, such as with
+/// [`OutputBuilder::strict_stream_ids`], to apply the parsing rules to every ID it is given.
+///
+/// ```
+/// # use active_tx::StreamId;
+/// let id = "3b1f0c6a9e2d4b8f7a5c1e0d9b8a7f6e5d4c3b2a1f0e9d8c7b6a5f4e3d2c1b0a";
+///
+/// let strict: StreamId = id.parse().unwrap();
+/// assert!(strict.is_ledger_id());
+///
+/// assert!("3b1f0c6a".parse::<StreamId>().is_err());
+///
+/// // Key names are allowed when converting
+/// let name = StreamId::from("keyname");
+/// assert!(!name.is_ledger_id());
+/// ```
+///
+/// [`Signees::add`]: struct.Signees.html#method.add
+/// [`OutputBuilder::add_stream`]: struct.OutputBuilder.html#method.add_stream
+/// [`VerifierSet::add`]: struct.VerifierSet.html#method.add
+/// [`OutputBuilder::strict_stream_ids`]: struct.OutputBuilder.html#method.strict_stream_ids
+#[derive(Serialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(transparent)]
+pub struct StreamId(String);
+
+impl StreamId {
+    /// # New
+    ///
+    /// Create a stream ID, returning an error unless it is 64 hex characters.
+    pub fn new(value: &str) -> TxBuilderResult<StreamId> {
+        validate_stream_id(value, true)?;
+
+        Ok(StreamId(value.to_string()))
+    }
+
+    /// # Is ledger ID
+    ///
+    /// Whether the ID has the format of a stream ID created by the ledger, 64 hex characters.
+    pub fn is_ledger_id(&self) -> bool {
+        stream_id_problem(&self.0, true).is_none()
+    }
+
+    /// # As str
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// # Into string
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// Check a stream ID is not empty and doesn't contain whitespace or control characters.
+/// Strict checks also require the 64 hex characters of a stream ID created by the ledger.
+pub(crate) fn validate_stream_id(value: &str, strict: bool) -> TxBuilderResult<()> {
+    match stream_id_problem(value, strict) {
+        Some(reason) => Err(TxBuilderError::InvalidStreamId {
+            value: value.to_string(),
+            reason,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Whether the value has the format of a stream ID created by the ledger
+pub(crate) fn is_ledger_id(value: &str) -> bool {
+    stream_id_problem(value, true).is_none()
+}
+
+/// The reason the stream ID is not valid, if it isn't
+fn stream_id_problem(value: &str, strict: bool) -> Option<&'static str> {
+    if value.is_empty() {
+        Some("empty")
+    } else if value.chars().any(|c| c.is_whitespace()) {
+        Some("contains whitespace")
+    } else if value.chars().any(|c| c.is_control()) {
+        Some("contains control characters")
+    } else if strict && value.len() != STREAM_ID_LEN {
+        Some("not 64 characters")
+    } else if strict && !value.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some("not hex")
+    } else {
+        None
+    }
+}
+
+impl FromStr for StreamId {
+    type Err = TxBuilderError;
+
+    fn from_str(value: &str) -> TxBuilderResult<StreamId> {
+        StreamId::new(value)
+    }
+}
+
+impl From<&str> for StreamId {
+    fn from(value: &str) -> Self {
+        StreamId(value.to_string())
+    }
+}
+
+impl From<String> for StreamId {
+    fn from(value: String) -> Self {
+        StreamId(value)
+    }
+}
+
+impl From<&&str> for StreamId {
+    fn from(value: &&str) -> Self {
+        StreamId(value.to_string())
+    }
+}
+
+impl From<&String> for StreamId {
+    fn from(value: &String) -> Self {
+        StreamId(value.clone())
+    }
+}
+
+impl From<&StreamId> for StreamId {
+    fn from(value: &StreamId) -> Self {
+        value.clone()
+    }
+}
+
+impl From<StreamId> for String {
+    fn from(value: StreamId) -> Self {
+        value.0
+    }
+}
+
+impl AsRef<str> for StreamId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for StreamId {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for StreamId {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for StreamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Timestamps
+//!
+//! One way of writing the created-at time of packet data, instead of each caller formatting
+//! it slightly differently.
+
+// External imports
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+
+// Internal imports
+use super::PacketValue;
+
+/// # Timestamp format
+///
+/// How a time is written by [`PacketValue::from_datetime`].
+///
+/// [`PacketValue::from_datetime`]: enum.PacketValue.html#method.from_datetime
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum TimestampFormat {
+    /// ISO 8601 string in UTC with milliseconds - `"2020-01-01T00:00:00.000Z"`
+    #[default]
+    Iso8601,
+    /// Seconds since the Unix epoch - `1577836800`
+    UnixSeconds,
+    /// Milliseconds since the Unix epoch - `1577836800000`
+    UnixMillis,
+}
+
+impl PacketValue {
+    /// # Timestamp now
+    ///
+    /// The current time as an ISO 8601 string, e.g. `"2020-01-01T00:00:00.000Z"`. Use
+    /// [`from_datetime`](#method.from_datetime) to write a time from another clock.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, PacketValue};
+    /// let data = packet_data!({"streamid": {"created": PacketValue::timestamp_now()}});
+    /// ```
+    pub fn timestamp_now() -> PacketValue {
+        PacketValue::from_datetime(Utc::now(), TimestampFormat::Iso8601)
+    }
+
+    /// # From datetime
+    ///
+    /// Write a time in the given format.
+    ///
+    /// ```
+    /// # use active_tx::{PacketValue, TimestampFormat};
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// let time = Utc.timestamp_opt(1577836800, 0).unwrap();
+    ///
+    /// assert_eq!(
+    ///     PacketValue::from_datetime(time, TimestampFormat::Iso8601),
+    ///     PacketValue::from("2020-01-01T00:00:00.000Z")
+    /// );
+    /// assert_eq!(
+    ///     PacketValue::from_datetime(time, TimestampFormat::UnixSeconds),
+    ///     PacketValue::from(1577836800)
+    /// );
+    /// ```
+    pub fn from_datetime(time: DateTime<Utc>, format: TimestampFormat) -> PacketValue {
+        match format {
+            TimestampFormat::Iso8601 => {
+                PacketValue::String(time.to_rfc3339_opts(SecondsFormat::Millis, true))
+            }
+            TimestampFormat::UnixSeconds => PacketValue::from(time.timestamp()),
+            TimestampFormat::UnixMillis => PacketValue::from(time.timestamp_millis()),
+        }
+    }
+}
+
+/// Set the field to the timestamp on every stream entry that is an object
+pub(crate) fn stamp_entries(mut json: Value, field: &str, time: DateTime<Utc>) -> Value {
+    let timestamp = Value::String(time.to_rfc3339_opts(SecondsFormat::Millis, true));
+
+    if let Value::Object(streams) = &mut json {
+        for entry in streams.values_mut() {
+            if let Value::Object(entry) = entry {
+                entry.insert(field.to_string(), timestamp.clone());
+            }
+        }
+    }
+
+    json
+}
@@ -0,0 +1,170 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Ledger Response
+//!
+//! Types for the JSON returned by a node after a transaction has been submitted.
+//! These can be used with any HTTP client, the `http` feature uses them for its responses.
+//!
+//! ## Example
+//! ```
+//! # use active_tx::LedgerResponse;
+//! let body = r#"{
+//!     "$umid": "umid",
+//!     "$summary": {"total": 1, "vote": 1, "commit": 1},
+//!     "$streams": {"new": [{"id": "streamid", "name": "name"}], "updated": []}
+//! }"#;
+//!
+//! let response = LedgerResponse::parse(body).unwrap();
+//!
+//! assert!(response.is_ok());
+//! assert_eq!(response.new_stream_ids(), vec!["streamid"]);
+//! ```
+
+// External imports
+use serde::Deserialize;
+use serde_json::Value;
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Holds the parsed response returned by a node after submitting a transaction
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct LedgerResponse {
+    /// The unique message ID of the transaction
+    #[serde(rename = "$umid", default)]
+    pub umid: String,
+
+    #[serde(rename = "$summary", default)]
+    pub summary: Summary,
+
+    #[serde(rename = "$streams", default)]
+    pub streams: StreamsCreated,
+
+    /// Any data returned by the contract
+    #[serde(rename = "$responses", default)]
+    pub responses: Vec<Value>,
+}
+
+/// Summary of the consensus process for the transaction
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct Summary {
+    pub total: u32,
+    pub vote: u32,
+    pub commit: u32,
+    pub errors: Vec<String>,
+}
+
+/// The streams created and updated by the transaction
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct StreamsCreated {
+    pub new: Vec<StreamRef>,
+    pub updated: Vec<StreamRef>,
+}
+
+/// A stream ID and its name
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct StreamRef {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+impl LedgerResponse {
+    /// # Parse
+    ///
+    /// Parse the response body returned by a node.
+    pub fn parse(body: &str) -> TxBuilderResult<LedgerResponse> {
+        match serde_json::from_str(body) {
+            Ok(response) => Ok(response),
+            Err(e) => Err(TxBuilderError::ResponseError(e)),
+        }
+    }
+
+    /// # Is OK
+    ///
+    /// Check whether the transaction was committed without any errors being reported.
+    pub fn is_ok(&self) -> bool {
+        self.summary.errors.is_empty() && self.summary.commit > 0
+    }
+
+    /// # Errors
+    ///
+    /// Get the errors reported by the node.
+    pub fn errors(&self) -> &[String] {
+        &self.summary.errors
+    }
+
+    /// # New stream IDs
+    ///
+    /// Get the IDs of the streams created by the transaction.
+    pub fn new_stream_ids(&self) -> Vec<&str> {
+        self.streams.new.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    /// # Updated stream IDs
+    ///
+    /// Get the IDs of the streams updated by the transaction.
+    pub fn updated_stream_ids(&self) -> Vec<&str> {
+        self.streams.updated.iter().map(|s| s.id.as_str()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn response_parse() {
+        let body = r#"{
+            "$umid": "umid",
+            "$summary": {"total": 2, "vote": 2, "commit": 2},
+            "$streams": {
+                "new": [{"id": "new1", "name": "activeledger.default.onboard"}],
+                "updated": [{"id": "updated1", "name": "updated"}]
+            },
+            "$responses": [{"response": "data"}]
+        }"#;
+
+        let response = LedgerResponse::parse(body).unwrap();
+        assert!(response.is_ok());
+        assert_eq!(response.umid, "umid");
+        assert_eq!(response.new_stream_ids(), vec!["new1"]);
+        assert_eq!(response.updated_stream_ids(), vec!["updated1"]);
+        assert_eq!(response.responses.len(), 1);
+
+        let body = r#"{"$umid": "umid", "$summary": {"total": 1, "vote": 0, "commit": 0, "errors": ["Vote Failure"]}}"#;
+
+        let response = LedgerResponse::parse(body).unwrap();
+        assert!(!response.is_ok());
+        assert_eq!(response.errors(), ["Vote Failure"]);
+        assert!(response.new_stream_ids().is_empty());
+
+        assert_eq!(
+            LedgerResponse::parse("not json").err().unwrap().code(),
+            10001
+        );
+    }
+}
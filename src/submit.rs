@@ -0,0 +1,109 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Submit
+//!
+//! Send built transactions to an Activeledger node.
+//!
+//! Requires the `http` feature.
+//!
+//! ## Example
+//! ```no_run
+//! # use active_tx::{PacketBuilder, TransactionBuilder, Key, packet_data, signees};
+//! # use activeledger::key::EllipticCurve;
+//! let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+//! let input = PacketBuilder::new(packet_data!({"keyname": {"input": "data"}}))
+//!     .build()
+//!     .unwrap();
+//!
+//! let response = TransactionBuilder::new("namespace", "contract")
+//!     .input(input)
+//!     .unwrap()
+//!     .build_and_submit(signees!(key), "http://localhost:5260")
+//!     .unwrap();
+//!
+//! println!("{}", response.umid);
+//! ```
+
+// External imports
+use reqwest::header::CONTENT_TYPE;
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::response::LedgerResponse;
+use crate::{Signees, TransactionBuilder};
+
+/// # Submit
+///
+/// Send a transaction to the given node and parse its response.
+///
+/// ```no_run
+/// # use active_tx::{submit, TransactionBuilder, Key, KeyType};
+/// let (_key, tx) = TransactionBuilder::generate_onboard_tx(KeyType::EC, "keyname").unwrap();
+///
+/// let response = submit(&tx, "http://localhost:5260").unwrap();
+/// ```
+pub fn submit(tx: &str, node_url: &str) -> TxBuilderResult<LedgerResponse> {
+    let client = reqwest::Client::new();
+
+    let mut res = match client
+        .post(node_url)
+        .header(CONTENT_TYPE, "application/json")
+        .body(tx.to_string())
+        .send()
+    {
+        Ok(res) => res,
+        Err(e) => return Err(TxBuilderError::HttpError(e)),
+    };
+
+    let body = match res.text() {
+        Ok(body) => body,
+        Err(e) => return Err(TxBuilderError::HttpError(e)),
+    };
+
+    // Nodes report transaction errors in the response body, so only fail on the status
+    // if the body can't be understood
+    match LedgerResponse::parse(&body) {
+        Ok(response) => Ok(response),
+        Err(_) if !res.status().is_success() => {
+            Err(TxBuilderError::HttpStatusError(res.status().as_u16()))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+impl TransactionBuilder {
+    /// # Build and submit
+    ///
+    /// Build the transaction using the given signees and send it to the node.
+    /// Requires the `http` feature.
+    pub fn build_and_submit(
+        &mut self,
+        signees: Signees,
+        node_url: &str,
+    ) -> TxBuilderResult<LedgerResponse> {
+        let tx = self.build(signees)?;
+
+        submit(&tx, node_url)
+    }
+}
@@ -0,0 +1,162 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Stream access
+//!
+//! Builds the transactions that grant or revoke another identity's access to a stream.
+
+// Internal imports
+use super::onboard::OnboardTxBuilder;
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::{
+    Key, OutputBuilder, PacketBuilder, PacketMap, PacketValue, PublicKey, Signees,
+    TransactionBuilder,
+};
+
+/// # Access transaction builder
+///
+/// Builds transactions adding a key to, or removing it from, the authorized signers of a
+/// stream. Activeledger doesn't define a contract for this, so the namespace, contract and the
+/// entries for granting and revoking are those of the caller's own access contract. The grant
+/// and revoke entries must differ, as they are how the contract tells the two apart.
+///
+/// The transaction is signed by the owner's key under the owner's stream ID, which is the
+/// input. The stream being changed is the output, holding the key in the same form used when
+/// onboarding:
+///
+/// ```json
+/// {
+///   "$namespace": "namespace",
+///   "$contract": "contract",
+///   "$entry": "grant",
+///   "$i": {"owner streamid": {}},
+///   "$o": {"streamid": {"type": "secp256k1", "publicKey": "-----BEGIN PUBLIC KEY-----..."}}
+/// }
+/// ```
+///
+/// Requires the `openssl-keys` feature, which is enabled by default.
+///
+/// ```
+/// # use active_tx::{AccessTxBuilder, Key};
+/// # use activeledger::key::EllipticCurve;
+/// let owner = Key::Ec(EllipticCurve::new("owner").unwrap());
+/// let grantee = Key::Ec(EllipticCurve::new("grantee").unwrap());
+/// let grantee_pem = grantee.public_pem().unwrap();
+///
+/// let access =
+///     AccessTxBuilder::new("namespace", "permissions", "grant", "revoke", "owner-id", owner)
+///         .unwrap();
+///
+/// let tx = access.grant("streamid", &grantee_pem).unwrap();
+/// ```
+#[derive(Clone, Debug)]
+pub struct AccessTxBuilder {
+    namespace: String,
+    contract: String,
+    grant_entry: String,
+    revoke_entry: String,
+    owner_stream: String,
+    owner: Key,
+}
+
+impl AccessTxBuilder {
+    /// # New
+    ///
+    /// Create a builder for the access contract, signing with the owner's key for the owner's
+    /// stream ID.
+    ///
+    /// An error is returned if the grant and revoke entries are the same.
+    pub fn new(
+        namespace: &str,
+        contract: &str,
+        grant_entry: &str,
+        revoke_entry: &str,
+        owner_stream: &str,
+        owner: Key,
+    ) -> TxBuilderResult<AccessTxBuilder> {
+        if grant_entry == revoke_entry {
+            return Err(TxBuilderError::InvalidIdentifier {
+                field: "entry",
+                value: revoke_entry.to_string(),
+                reason: "the grant and revoke entries must differ",
+            });
+        }
+
+        Ok(AccessTxBuilder {
+            namespace: namespace.to_string(),
+            contract: contract.to_string(),
+            grant_entry: grant_entry.to_string(),
+            revoke_entry: revoke_entry.to_string(),
+            owner_stream: owner_stream.to_string(),
+            owner,
+        })
+    }
+
+    /// # Grant
+    ///
+    /// Build a transaction adding the key to the stream's authorized signers.
+    ///
+    /// An error is returned if the public key PEM can't be parsed.
+    pub fn grant(&self, stream: &str, grantee_pem: &str) -> TxBuilderResult<String> {
+        self.build(&self.grant_entry, stream, grantee_pem)
+    }
+
+    /// # Revoke
+    ///
+    /// Build a transaction removing the key from the stream's authorized signers.
+    ///
+    /// An error is returned if the public key PEM can't be parsed.
+    pub fn revoke(&self, stream: &str, grantee_pem: &str) -> TxBuilderResult<String> {
+        self.build(&self.revoke_entry, stream, grantee_pem)
+    }
+}
+
+// Private functions
+impl AccessTxBuilder {
+    fn build(&self, entry: &str, stream: &str, grantee_pem: &str) -> TxBuilderResult<String> {
+        let key_type = PublicKey::from_pem(grantee_pem)?.key_type();
+
+        let mut input = PacketMap::new();
+        input.insert(
+            self.owner_stream.clone(),
+            PacketValue::Object(PacketMap::new()),
+        );
+
+        let input = PacketBuilder::new(PacketValue::Object(input)).build()?;
+
+        let mut output = OutputBuilder::new();
+        output
+            .add_stream(stream)?
+            .set("type", OnboardTxBuilder::key_type_name(key_type))
+            .set("publicKey", grantee_pem);
+
+        let mut signees = Signees::new();
+        signees.add(self.owner.clone(), &self.owner_stream);
+
+        TransactionBuilder::new(&self.namespace, &self.contract)
+            .entry(entry)
+            .input(input)?
+            .output(output.build()?)?
+            .build(signees)
+    }
+}
@@ -21,75 +21,49 @@
  * SOFTWARE.
  */
 
-use serde_json::{json, Value};
+// External imports
+#[cfg(feature = "openssl-keys")]
+use serde::Deserialize;
+#[cfg(feature = "openssl-keys")]
+use serde_json::value::RawValue;
 
-use crate::error::{TxBuilderError, TxBuilderResult};
+use super::Signee;
+pub use crate::assembly::body::{
+    validate_identifier, Sig, Sigs, TransactionBody, TransactionEnvelope,
+};
 
-/// Holds the transactions data
-#[derive(Debug, Clone)]
-pub struct TransactionBody {
-    entry: Option<Value>,
-    contract: Value,
-    namespace: Value,
-    input: Value,
-    output: Option<Value>,
-    readonly: Option<Value>,
-    json: Option<Value>,
-}
+/// Fields of the packet set by the builder, which extra fields may not replace
+pub const PACKET_FIELDS: [&str; 6] = ["$contract", "$entry", "$i", "$namespace", "$o", "$r"];
 
-impl TransactionBody {
-    pub fn new(contract: Value, namespace: Value, input: Value) -> TransactionBody {
-        // Init all as None to create base
-        TransactionBody {
-            entry: None,
-            contract: contract,
-            namespace: namespace,
-            input: input,
-            output: None,
-            readonly: None,
-            json: None,
-        }
-    }
+/// Fields of the envelope set by the builder, which extra fields may not replace
+pub const ENVELOPE_FIELDS: [&str; 5] =
+    ["$broadcast", "$selfsign", "$sigs", "$territoriality", "$tx"];
 
-    pub fn add(&mut self, key: &str, data: Value) -> &mut Self {
-        match key {
-            "entry" => self.entry = Some(data),
-            "output" => self.output = Some(data),
-            "readonly" => self.readonly = Some(data),
-            _ => unreachable!(),
-        };
-
-        self
-    }
-
-    pub fn build(&mut self) -> Value {
-        let mut json = json!({});
-
-        json["$contract"] = json!(self.contract.clone());
-        json["$namespace"] = json!(self.namespace.clone());
-        json["$i"] = self.input.clone();
-
-        if let Some(entry) = &self.entry {
-            json["$entry"] = json!(entry);
-        }
-
-        if let Some(output) = &self.output {
-            json["$o"] = json!(output);
-        }
+/// A signed transaction as received, keeping the packet exactly as it was signed
+#[cfg(feature = "openssl-keys")]
+#[derive(Deserialize)]
+pub struct SignedTransaction<'a> {
+    #[serde(rename = "$tx", borrow)]
+    pub tx: &'a RawValue,
+    #[serde(rename = "$sigs", default)]
+    pub sigs: Sigs,
+}
 
-        if let Some(readonly) = &self.readonly {
-            json["$r"] = json!(readonly);
+#[cfg(feature = "openssl-keys")]
+impl<'a> SignedTransaction<'a> {
+    /// Get the signature for the stream, or for one of its keys when it was signed by several
+    pub fn signature(&self, streamid: &str, key_ref: Option<&str>) -> Option<&str> {
+        match (self.sigs.get(streamid), key_ref) {
+            (Some(Sig::Single(signature)), None) => Some(signature),
+            (Some(Sig::Multi(signatures)), Some(key_ref)) => {
+                signatures.get(key_ref).map(String::as_str)
+            }
+            _ => None,
         }
-
-        self.json = Some(json.clone());
-
-        json
     }
+}
 
-    pub fn get(&mut self) -> TxBuilderResult<Value> {
-        match &self.json {
-            Some(json) => Ok(json.clone()),
-            None => Err(TxBuilderError::TxBodyError(000)),
-        }
-    }
+/// Add the signature for the signee, nesting it under its key reference if it has one
+pub fn add_sig(sigs: &mut Sigs, signee: &Signee, signature: String) {
+    crate::assembly::body::add_sig(sigs, &signee.streamid, signee.key_ref.as_deref(), signature)
 }
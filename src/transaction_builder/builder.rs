@@ -22,36 +22,64 @@
  */
 
 // STD
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+#[cfg(feature = "openssl-keys")]
+use std::io::Write;
+use std::sync::Arc;
 
 // External imports
+#[cfg(feature = "openssl-keys")]
 use activeledger::key::{EllipticCurve, RSA};
+use chrono::{DateTime, Utc};
+use serde_json::value::RawValue;
 use serde_json::{json, Value};
 
 // Internal imports
-use super::body::TransactionBody;
+use super::body::{
+    add_sig, validate_identifier, Sig, Sigs, TransactionBody, TransactionEnvelope, ENVELOPE_FIELDS,
+    PACKET_FIELDS,
+};
+use super::built::BuiltTransaction;
+use super::collector::MultiSigCollector;
+use super::entry::Entry;
+use super::event::TxEvent;
+#[cfg(feature = "openssl-keys")]
+use super::idempotency::{self, IdempotentTx};
+use super::nonce;
+#[cfg(feature = "openssl-keys")]
+use super::onboard::BatchMode;
+use super::onboard::OnboardTxBuilder;
+use super::profile::Profile;
+use super::snapshot::TxSnapshot;
+use super::status::{TxState, TxStatus};
+#[cfg(feature = "openssl-keys")]
+use super::stream::{PacketSigner, StreamKey};
+use super::template::TxTemplate;
+use super::territoriality::{NodeReferences, Territoriality};
+#[cfg(feature = "openssl-keys")]
+use super::verifier::VerifierSet;
+use crate::compat::{self, CompatMode};
 use crate::error::{TxBuilderError, TxBuilderResult};
-use crate::packet_builder::{Input, Output, Readonly};
-use crate::Signees;
-use crate::{packet_data, signees};
+use crate::keys::Key;
+#[cfg(feature = "openssl-keys")]
+use crate::keys::{KeyType, PublicKey};
+use crate::packet_builder::{validate_stream_id, Input, Output, PacketData, Readonly, StreamId};
+#[cfg(feature = "openssl-keys")]
+use crate::HashAlgorithm;
+use crate::{PacketBuilder, PacketMap, PacketValue, SignOptions, Signee, Signees};
 
-/// Holds the key to use when signing the transaction packet
-#[derive(Clone)]
-pub enum Key {
-    Rsa(RSA),
-    Ec(EllipticCurve),
-}
+/// Time source for values the builder generates
+type Clock = Arc<dyn Fn() -> DateTime<Utc> + Send + Sync>;
 
-/// Key Type for generating a key and onboarding it
-pub enum KeyType {
-    RSA,
-    EC,
-}
+/// Callback receiving build and signing events
+type EventCallback = Arc<dyn Fn(TxEvent<'_>) + Send + Sync>;
 
 /// # Transaction builder
 ///
 /// The transaction builder is used to help build a compatible Activeledger transaction object.
-/// To read more about Activeledger transactions you can read the documentation [here.](https://github.com/activeledger/activeledger/blob/master/docs/en-gb/transactions.md)
+/// To read more about Activeledger transactions you can read the documentation
+/// [here.](https://github.com/activeledger/activeledger/blob/master/docs/en-gb/transactions.md)
 ///
 /// This section will guide you through the creation of transaction using this crate.
 ///
@@ -77,17 +105,17 @@ pub enum KeyType {
 ///
 /// }
 /// ```
-/// We won't go into much detail about all of the separate parts here as that is in documentation linked
-/// above.
+/// We won't go into much detail about all of the separate parts here as that is in documentation
+/// linked above.
 /// However, it is useful to know how that structure is broken down in terms of this helper.
 ///
 /// This helper breaks the above structure down into two sections.
 /// 1. The overall transaction - Everything in the object
 /// 2. The transaction packet - everything inside the $tx object, this gets signed
 ///
-/// When using this helper to create a transaction you must first create the packet as that is passed
-/// to the main builder. You can create three packets for the three sub objects inside of the packet:
-/// $i (input), $o (output), and $r (readonly).
+/// When using this helper to create a transaction you must first create the packet as that is
+/// passed to the main builder. You can create three packets for the three sub objects inside of
+/// the packet: $i (input), $o (output), and $r (readonly).
 ///
 /// ## Examples
 /// ### Minimal
@@ -96,6 +124,7 @@ pub enum KeyType {
 /// **Note:** This example does include some bootstrapping as we need to generate a key.
 /// You may already have a key and very likely will want to reuse it.
 /// ```
+/// # #[cfg(feature = "openssl-keys")] {
 /// use activeledger::key::EllipticCurve;
 /// use active_tx::{PacketBuilder, TransactionBuilder, Key, packet_data, signees};
 ///
@@ -149,6 +178,7 @@ pub enum KeyType {
 /// //    .unwrap()
 /// //    .build(signees)
 /// //    .unwrap();
+/// # }
 /// ```
 /// ### Additional data
 ///
@@ -162,6 +192,7 @@ pub enum KeyType {
 /// **Transaction**
 /// * Territoriality
 /// * Selfsign
+/// * Broadcast
 ///
 /// Adding in this extra data is straight forward. It goes without saying that they should be added
 /// before calling the build method.
@@ -220,8 +251,17 @@ pub enum KeyType {
 /// tx_builder.selfsign();
 /// ```
 ///
+/// ##### Broadcast
+/// Calling this function will set the broadcast value of the transaction to true
+/// ```
+/// # use active_tx::TransactionBuilder;
+/// #
+/// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+/// tx_builder.broadcast();
+/// ```
+///
 /// [`PacketBuilder`]: struct.PacketBuilder.html
-
+#[derive(Clone)]
 pub struct TransactionBuilder {
     /*
     Data for $tx object
@@ -237,13 +277,73 @@ pub struct TransactionBuilder {
     /*
     territoriality,
     selfsign,
+    broadcast,
     */
     tx_data: HashMap<String, Value>,
 
+    // Fields not managed by the builder, for $tx and the envelope
+    packet_fields: BTreeMap<String, Value>,
+    envelope_fields: BTreeMap<String, Value>,
+
+    // Client side metadata returned with the transaction, never signed or sent
+    meta: BTreeMap<String, Value>,
+
+    // Entries the builder may use, any entry if not set
+    allowed_entries: Option<BTreeSet<String>>,
+
+    // Caller supplied node references used to check territoriality
+    nodes: Option<NodeReferences>,
+
+    // Options used when signing the packet
+    sign_options: SignOptions,
+
+    // How the packet is serialized for signing
+    compat: CompatMode,
+
+    // Largest transaction, in bytes, that build will return
+    max_size: Option<usize>,
+
+    // Time source for generated values, the system clock if not set
+    clock: Option<Clock>,
+
+    // Packet field holding a generated nonce
+    nonce_field: Option<String>,
+
+    // Whether input stream IDs must be ledger stream IDs
+    strict_stream_ids: bool,
+
+    // Callback receiving build and signing events
+    events: Option<EventCallback>,
+
     // Generation and storage holders
-    packet: Option<TransactionBody>,
-    tx: Option<Value>,
-    sigs: HashMap<String, String>,
+    // The packet is held once, as the canonical string that is signed
+    packet: Option<Box<RawValue>>,
+    // Whether the packet was signed for a selfsigned transaction
+    built_selfsign: bool,
+    // Whether the last build was written by build_to_writer, which doesn't keep the packet
+    streamed: bool,
+    sigs: Sigs,
+}
+
+impl fmt::Debug for TransactionBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TransactionBuilder")
+            .field("packet_data", &self.packet_data)
+            .field("tx_data", &self.tx_data)
+            .field("packet_fields", &self.packet_fields)
+            .field("envelope_fields", &self.envelope_fields)
+            .field("meta", &self.meta)
+            .field("allowed_entries", &self.allowed_entries)
+            .field("nodes", &self.nodes)
+            .field("sign_options", &self.sign_options)
+            .field("compat", &self.compat)
+            .field("max_size", &self.max_size)
+            .field("nonce_field", &self.nonce_field)
+            .field("strict_stream_ids", &self.strict_stream_ids)
+            .field("packet", &self.packet)
+            .field("sigs", &self.sigs)
+            .finish_non_exhaustive()
+    }
 }
 
 // Public functions
@@ -277,9 +377,22 @@ impl TransactionBuilder {
         TransactionBuilder {
             packet_data,
             tx_data: HashMap::new(),
+            packet_fields: BTreeMap::new(),
+            envelope_fields: BTreeMap::new(),
+            meta: BTreeMap::new(),
+            allowed_entries: None,
+            nodes: None,
+            sign_options: SignOptions::default(),
+            compat: CompatMode::default(),
+            max_size: None,
+            clock: None,
+            nonce_field: None,
+            strict_stream_ids: false,
+            events: None,
             packet: None,
-            tx: None,
-            sigs: HashMap::new(),
+            built_selfsign: false,
+            streamed: false,
+            sigs: BTreeMap::new(),
         }
     }
 
@@ -294,7 +407,8 @@ impl TransactionBuilder {
     /// let mut tx_builder = TransactionBuilder::new_blank();
     /// ```
     ///
-    /// It is required that contract, namespace, and input data be added to the builder before it will build the transaction.
+    /// It is required that contract, namespace, and input data be added to the builder before it
+    /// will build the transaction.
     ///
     /// All data can be added by the other methods provided by the builder.
     ///
@@ -303,9 +417,22 @@ impl TransactionBuilder {
         TransactionBuilder {
             packet_data: HashMap::new(),
             tx_data: HashMap::new(),
+            packet_fields: BTreeMap::new(),
+            envelope_fields: BTreeMap::new(),
+            meta: BTreeMap::new(),
+            allowed_entries: None,
+            nodes: None,
+            sign_options: SignOptions::default(),
+            compat: CompatMode::default(),
+            max_size: None,
+            clock: None,
+            nonce_field: None,
+            strict_stream_ids: false,
+            events: None,
             packet: None,
-            tx: None,
-            sigs: HashMap::new(),
+            built_selfsign: false,
+            streamed: false,
+            sigs: BTreeMap::new(),
         }
     }
 
@@ -314,7 +441,11 @@ impl TransactionBuilder {
     /// Get the built transaction as a string.
     /// Note that the build method returns the same data.
     ///
+    /// An `InvalidState` error is returned if the transaction has not been built, see
+    /// [`TxState`](enum.TxState.html).
+    ///
     /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
     /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
     /// # use activeledger::key::EllipticCurve;
     ///
@@ -332,12 +463,69 @@ impl TransactionBuilder {
     ///     .unwrap();
     ///
     /// let tx = tx_builder.get().unwrap();
+    /// # }
     /// ```
     pub fn get(&self) -> TxBuilderResult<String> {
-        match &self.tx {
-            Some(tx) => Ok(tx.to_string()),
-            None => Err(TxBuilderError::TxBuildError(5000)),
+        let packet = match &self.packet {
+            Some(packet) => packet,
+            None => return Err(TransactionBuilder::not_built()),
+        };
+
+        self.envelope(packet, &self.sigs)
+    }
+
+    /// # Rebuild envelope
+    ///
+    /// Wrap the signed packet from the last build in a new envelope, returning the transaction
+    /// string. Changes made since the build to the envelope, the territoriality, broadcast and
+    /// [envelope fields](#method.envelope_field), are included. These are not part of the
+    /// signed packet so the signatures are kept as they are.
+    ///
+    /// Changes to the packet, such as the input or entry, are not included and need a new build
+    /// to be signed. Selfsigned transactions are signed differently, so if selfsign has been
+    /// turned on or off since the build an `InvalidState` error is returned.
+    ///
+    /// If a [max size](#method.max_size) is set and the transaction is larger an error is returned.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+    /// let key = Key::Ec(EllipticCurve::new("id").unwrap());
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input).unwrap();
+    ///
+    /// let tx = tx_builder.build(signees![{"id" => key}]).unwrap();
+    /// assert!(!tx.contains("$territoriality"));
+    ///
+    /// let tx = tx_builder.territoriality("node1").rebuild_envelope().unwrap();
+    /// assert!(tx.contains("\"$territoriality\":\"node1\""));
+    /// # }
+    /// ```
+    pub fn rebuild_envelope(&self) -> TxBuilderResult<String> {
+        let tx = self.get()?;
+
+        if self.tx_data.contains_key("selfsign") != self.built_selfsign {
+            return Err(TxBuilderError::InvalidState {
+                expected: TxState::Draft,
+                actual: self.state(),
+            });
+        }
+
+        if let Some(max) = self.max_size {
+            if tx.len() > max {
+                return Err(TxBuilderError::SizeExceeded {
+                    size: tx.len(),
+                    max,
+                });
+            }
         }
+
+        self.emit(TxEvent::EnvelopeFinalized { tx: &tx });
+
+        Ok(tx)
     }
 
     /// # Transaction JSON
@@ -345,6 +533,7 @@ impl TransactionBuilder {
     /// Get the built transaction as a Serde JSON value
     ///
     /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
     /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
     /// # use activeledger::key::EllipticCurve;
     ///
@@ -362,450 +551,2429 @@ impl TransactionBuilder {
     ///     .unwrap();
     ///     
     /// let tx = tx_builder.get_json().unwrap();
+    /// # }
     /// ```
     pub fn get_json(&self) -> TxBuilderResult<Value> {
-        match &self.tx {
-            Some(tx) => Ok(tx.clone()),
-            None => Err(TxBuilderError::TxBuildError(5000)),
+        let tx = self.get()?;
+
+        match serde_json::from_str(&tx) {
+            Ok(json) => Ok(json),
+            Err(_) => Err(TxBuilderError::TxBuildError(5011)),
         }
     }
 
-    /// # Territoriality
+    /// # Packet hash
     ///
-    /// Set the territoriality value
+    /// Get the SHA-256 hash of the signed $tx packet from the last build, to keep as a receipt
+    /// of what was signed and match against ledger activity later without keeping the payload.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
     ///
     /// ```
-    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
     /// # use activeledger::key::EllipticCurve;
-    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
-    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
-    ///
+    /// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+    /// # let key = Key::Ec(EllipticCurve::new("id").unwrap());
     /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input).unwrap();
+    /// tx_builder.build(signees![{"id" => key}]).unwrap();
     ///
-    /// tx_builder.territoriality("territory");
+    /// let receipt = tx_builder.packet_hash().unwrap();
     /// ```
-    pub fn territoriality(&mut self, territoriality: &str) -> &mut Self {
-        self.tx_data.insert(
-            String::from("territoriality"),
-            json!(territoriality.to_string()),
-        );
+    #[cfg(feature = "openssl-keys")]
+    pub fn packet_hash(&self) -> TxBuilderResult<Vec<u8>> {
+        self.packet_hash_with(HashAlgorithm::Sha256)
+    }
 
-        self
+    /// # Packet hash with
+    ///
+    /// Get the hash of the signed $tx packet from the last build using the given algorithm.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    #[cfg(feature = "openssl-keys")]
+    pub fn packet_hash_with(&self, hash: HashAlgorithm) -> TxBuilderResult<Vec<u8>> {
+        match &self.packet {
+            Some(packet) => Ok(hash.digest(packet.get().as_bytes())),
+            None => Err(TransactionBuilder::not_built()),
+        }
     }
 
-    /// # Entry
+    /// # Signed payload
+    ///
+    /// Get the $tx packet from the last build, for checking signatures made or verified by
+    /// other SDKs. When the sign options set a [context](struct.SignOptions.html#method.context)
+    /// the signers are given the packet wrapped in the context tag, use
+    /// [`SignOptions::signed_data`](struct.SignOptions.html#method.signed_data) to get the data
+    /// exactly as it was signed.
     ///
-    /// Set the entry value
+    /// The payload is compact JSON with no whitespace between tokens. The packet fields,
+    /// including any extra packet fields, are in key order: `$contract`, `$entry`, `$i`,
+    /// `$namespace`, `$o`, `$r`. The fields of objects in the input, output and readonly data are
+    /// also in key order, unless the `preserve-order` feature is enabled, in which case they keep
+    /// the order they were added in. Strings use serde_json escaping. The payload is also exactly
+    /// the `$tx` value in the transaction string. This layout is part of the API and will not
+    /// change within a major version.
     ///
     /// ```
-    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
     /// # use activeledger::key::EllipticCurve;
-    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
-    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    /// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+    /// let key = Key::Ec(EllipticCurve::new("id").unwrap());
     ///
     /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input).unwrap();
+    /// tx_builder.build(signees![{"id" => key}]).unwrap();
     ///
-    /// tx_builder.entry("entry");
+    /// assert_eq!(
+    ///     tx_builder.signed_payload().unwrap(),
+    ///     r#"{"$contract":"contract","$i":{"id":"data"},"$namespace":"namespace"}"#
+    /// );
+    /// # }
     /// ```
-    pub fn entry(&mut self, entry: &str) -> &mut Self {
-        self.packet_data
-            .insert(String::from("entry"), json!(entry.to_string()));
-
-        self
+    pub fn signed_payload(&self) -> TxBuilderResult<String> {
+        match &self.packet {
+            Some(packet) => Ok(packet.get().to_string()),
+            None => Err(TransactionBuilder::not_built()),
+        }
     }
 
-    /// # Contract
+    /// # Transaction pretty string
     ///
-    /// Set the contract value
+    /// Get the built transaction as an indented string, for logging and inspection.
+    /// The packet is always signed in its compact form, use the get or build methods for the
+    /// string that is sent to the ledger.
     ///
     /// ```
-    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
     /// # use activeledger::key::EllipticCurve;
     /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
     /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder
+    ///     .input(input)
+    ///     .unwrap()
+    ///     .build(signees![{"streamid" => key}])
+    ///     .unwrap();
     ///
-    /// let mut tx_builder = TransactionBuilder::new_blank();
-    ///
-    /// tx_builder.contract("contract");
+    /// println!("{}", tx_builder.get_pretty().unwrap());
+    /// # }
     /// ```
-    pub fn contract(&mut self, contract: &str) -> &mut Self {
-        self.packet_data
-            .insert(String::from("contract"), json!(contract.to_string()));
+    pub fn get_pretty(&self) -> TxBuilderResult<String> {
+        let tx = self.get_json()?;
 
-        self
+        match serde_json::to_string_pretty(&tx) {
+            Ok(pretty) => Ok(pretty),
+            Err(_) => Err(TxBuilderError::TxBuildError(5011)),
+        }
     }
 
-    /// # Namespace
+    /// # Territoriality
     ///
-    /// Set the namespace value
+    /// Set the territoriality value
     ///
     /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
     /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
     /// # use activeledger::key::EllipticCurve;
     /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
     /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
     ///
-    /// let mut tx_builder = TransactionBuilder::new_blank();
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
     ///
-    /// tx_builder.namespace("namespace");
+    /// tx_builder.territoriality("territory");
+    /// # }
     /// ```
-    pub fn namespace(&mut self, namespace: &str) -> &mut Self {
-        self.packet_data
-            .insert(String::from("namespace"), json!(namespace.to_string()));
+    pub fn territoriality(&mut self, territoriality: &str) -> &mut Self {
+        self.tx_data.insert(
+            String::from("territoriality"),
+            json!(territoriality.to_string()),
+        );
 
         self
     }
 
-    /// # Input
+    /// # Territoriality checked
     ///
-    /// Set the input value
+    /// Validate and set the territoriality value.
+    /// An error is returned if the value is not a valid node reference.
+    /// If node references have been provided via [`nodes`](#method.nodes) the value must match
+    /// one of them, either by name or by reference. Names are replaced by their reference.
     ///
     /// ```
-    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
-    /// # use activeledger::key::EllipticCurve;
-    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// # use active_tx::{TransactionBuilder, NodeReferences};
+    /// let mut nodes = NodeReferences::new();
+    /// nodes.add("node1", "127.0.0.1:5260");
     ///
     /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
     ///
-    /// let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
-    /// tx_builder.input(input);
+    /// tx_builder.nodes(nodes).territoriality_checked("node1").unwrap();
+    ///
+    /// assert!(tx_builder.territoriality_checked("node2").is_err());
     /// ```
-    pub fn input(&mut self, input: Input) -> TxBuilderResult<&mut Self> {
-        match input.get() {
-            Ok(data) => self.packet_data.insert("input".to_string(), data),
-            Err(_) => return Err(TxBuilderError::TxBuildError(5001)),
+    pub fn territoriality_checked(&mut self, territoriality: &str) -> TxBuilderResult<&mut Self> {
+        let territoriality = Territoriality::new(territoriality)?;
+
+        let territoriality = match &self.nodes {
+            Some(nodes) => match nodes.resolve(territoriality.as_str()) {
+                Some(reference) => reference.to_string(),
+                None => {
+                    return Err(TxBuilderError::UnknownNode(
+                        territoriality.as_str().to_string(),
+                    ))
+                }
+            },
+            None => territoriality.as_str().to_string(),
         };
 
-        Ok(self)
+        Ok(self.territoriality(&territoriality))
     }
 
-    /// # Output
-    ///
-    /// Set the input value
-    ///
-    /// ```
-    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
-    /// # use activeledger::key::EllipticCurve;
-    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// # Nodes
     ///
-    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
-    ///
-    /// let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
-    /// tx_builder.input(input);
-    /// ```
-    pub fn output(&mut self, output: Output) -> TxBuilderResult<&mut Self> {
-        match output.get() {
-            Ok(data) => self.packet_data.insert("output".to_string(), data),
-            Err(_) => return Err(TxBuilderError::TxBuildError(5002)),
-        };
+    /// Provide a table of known node references used by
+    /// [`territoriality_checked`](#method.territoriality_checked).
+    pub fn nodes(&mut self, nodes: NodeReferences) -> &mut Self {
+        self.nodes = Some(nodes);
 
-        Ok(self)
+        self
     }
 
-    /// # Readonly
+    /// # Compatibility mode
     ///
-    /// Set the input value
+    /// Set how the packet is serialized before it is signed, defaults to
+    /// [`CompatMode::Canonical`]. Use [`CompatMode::Js`] for packets matching the JS SDK, see the
+    /// [`compat`](compat/index.html) module.
     ///
     /// ```
-    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
-    /// # use activeledger::key::EllipticCurve;
-    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
-    ///
+    /// # use active_tx::compat::CompatMode;
+    /// # use active_tx::TransactionBuilder;
     /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
-    ///
-    /// let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
-    /// tx_builder.input(input);
+    /// tx_builder.compat(CompatMode::Js);
     /// ```
-    pub fn readonly(&mut self, readonly: Readonly) -> TxBuilderResult<&mut Self> {
-        match readonly.get() {
-            Ok(data) => self.packet_data.insert("readonly".to_string(), data),
-            Err(_) => return Err(TxBuilderError::TxBuildError(5003)),
-        };
+    ///
+    /// [`CompatMode::Canonical`]: compat/enum.CompatMode.html#variant.Canonical
+    /// [`CompatMode::Js`]: compat/enum.CompatMode.html#variant.Js
+    pub fn compat(&mut self, mode: CompatMode) -> &mut Self {
+        self.compat = mode;
 
-        Ok(self)
+        self
     }
 
-    /// # Selfsign
+    /// # Sign options
     ///
-    /// Set selfsign to true
+    /// Set the options used when signing the packet, such as the hash algorithm.
+    /// Signees added with their own options keep them.
     ///
     /// ```
-    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
-    /// # use activeledger::key::EllipticCurve;
-    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
-    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    /// # use active_tx::{HashAlgorithm, SignOptions, TransactionBuilder};
+    /// let mut options = SignOptions::new();
+    /// options.hash(HashAlgorithm::Sha512);
     ///
     /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
-    /// tx_builder.input(input).unwrap();
-    ///
-    /// tx_builder.selfsign();
+    /// tx_builder.sign_options(options);
     /// ```
-    pub fn selfsign(&mut self) -> &mut Self {
-        self.tx_data
-            .insert(String::from("selfsign"), json!(String::from("true")));
+    pub fn sign_options(&mut self, options: SignOptions) -> &mut Self {
+        self.sign_options = options;
 
         self
     }
 
-    /// # Sign
+    /// # With profile
     ///
-    /// Using a given key and stream ID sign the transaction data packet.
-    /// Generally this is used to add more signatures to a transaction, as it requires the build
-    /// method to be run first.
+    /// Apply the settings in the profile, replacing the namespace, territoriality and signature
+    /// encoding when they are set in the profile. The onboarding contract is used by
+    /// [`OnboardTxBuilder::with_profile`](struct.OnboardTxBuilder.html#method.with_profile),
+    /// and the encryption flag is left to the caller.
     ///
     /// ```
-    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{packet_data, signees, Key, PacketBuilder, Profile, TransactionBuilder};
     /// # use activeledger::key::EllipticCurve;
-    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
-    ///
-    /// let streamid = "id";
-    /// let streamid2 = "id2";
-    ///
-    /// let key = Key::Ec(EllipticCurve::new(streamid).unwrap());
-    /// let key2 = Key::Ec(EllipticCurve::new(streamid2).unwrap());
+    /// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+    /// # let key = Key::Ec(EllipticCurve::new("id").unwrap());
+    /// let profile = Profile::from_json(r#"{"namespace": "production"}"#).unwrap();
     ///
-    /// let signees = signees![{streamid => key}];
-    ///
-    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
-    /// tx_builder.input(input)
+    /// let tx = TransactionBuilder::new("namespace", "contract")
+    ///     .with_profile(&profile)
+    ///     .input(input)
     ///     .unwrap()
-    ///     .build(signees)
+    ///     .build(signees![{"id" => key}])
     ///     .unwrap();
     ///
-    /// let signees2 = signees![{streamid2 => key2}];
-    ///
-    /// tx_builder.sign(signees2);
+    /// assert!(tx.contains(r#""$namespace":"production""#));
+    /// # }
     /// ```
-    pub fn sign(&mut self, signees: Signees) -> TxBuilderResult<&mut Self> {
-        let signees_array = signees.get();
-
-        let packet = match self.packet.clone() {
-            Some(mut packet) => packet.get()?,
-            None => return Err(TxBuilderError::TxBuildError(5004)),
-        };
-        let packet = packet.clone();
-
-        for signee in signees_array.iter() {
-            let signature =
-                TransactionBuilder::sign_internal(&packet.to_string(), signee.key.clone())?;
-            self.sigs.insert(signee.streamid.clone(), signature);
+    pub fn with_profile(&mut self, profile: &Profile) -> &mut Self {
+        if let Some(namespace) = &profile.namespace {
+            self.namespace(namespace);
         }
 
-        let json = match &self.tx {
-            Some(json) => json,
-            None => return Err(TxBuilderError::TxBuildError(5005)),
-        };
-
-        let mut json = json.clone();
-
-        json["$sigs"] = json!(self.sigs.clone());
+        if let Some(territoriality) = &profile.territoriality {
+            self.territoriality(territoriality);
+        }
 
-        self.tx.replace(json.clone());
+        if let Some(encoding) = profile.encoding {
+            self.sign_options.encoding(encoding);
+        }
 
-        Ok(self)
+        self
     }
 
-    /// # Build
+    /// # Size estimate
     ///
-    /// Using the data provided, compile it into the correct form for a transaction.
-    /// Returns a transaction in the form of a string.
+    /// Get the size, in bytes, the transaction will be once built and signed by the signees.
+    /// This is an upper bound, DER encoded EC signatures can be a few bytes shorter.
+    ///
+    /// Use this to split large payloads into several transactions before building them.
     ///
     /// ```
-    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{packet_data, signees, Key, PacketBuilder, TransactionBuilder};
     /// # use activeledger::key::EllipticCurve;
-    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
-    ///
-    /// let streamid = "id";
-    /// let key = Key::Ec(EllipticCurve::new(streamid).unwrap());
+    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// let input = PacketBuilder::new(packet_data!({"streamid": {"data": "data"}}))
+    ///     .build()
+    ///     .unwrap();
     ///
     /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
     /// tx_builder.input(input).unwrap();
     ///
-    /// let signees = signees![{streamid => key}];
-    ///
-    /// let tx = tx_builder.build(signees).unwrap();
+    /// let signees = signees![{"streamid" => key}];
+    /// let estimate = tx_builder.size_estimate(&signees).unwrap();
     ///
+    /// assert!(tx_builder.build(signees).unwrap().len() <= estimate);
+    /// # }
     /// ```
-    pub fn build(&mut self, signees: Signees) -> TxBuilderResult<String> {
-        let mut json = json!({});
-
-        // Contract, namespace and input are all required, if any are missing throw an error
-        let contract = match self.packet_data.get("contract") {
-            Some(contract) => contract,
-            None => return Err(TxBuilderError::TxBuildError(5006)),
-        };
-
-        let namespace = match self.packet_data.get("namespace") {
-            Some(namespace) => namespace,
-            None => return Err(TxBuilderError::TxBuildError(5007)),
-        };
+    pub fn size_estimate(&self, signees: &Signees) -> TxBuilderResult<usize> {
+        let body = compat::packet(self.compat, &self.body()?)?;
 
-        let input = match self.packet_data.get("input") {
-            Some(input) => input,
-            None => return Err(TxBuilderError::TxBuildError(5008)),
-        };
+        let mut sigs = BTreeMap::new();
 
-        let mut tx = TransactionBody::new(contract.clone(), namespace.clone(), input.clone());
+        for signee in signees.iter() {
+            let options = signee.options.as_ref().unwrap_or(&self.sign_options);
 
-        let checked = ["contract", "namespace", "input"];
+            let len = signee.key.max_signature_len(options)?;
 
-        // Loop packet_data map and add additional data
-        for (key, val) in self.packet_data.iter() {
-            // Ignore if key in checked
-            if !checked.iter().any(|v| v == &key) {
-                tx.add(key, val.clone());
-            }
+            add_sig(&mut sigs, signee, "0".repeat(len));
         }
 
-        self.packet.replace(tx.clone());
-
-        let built_packet = tx.build();
-        self.packet = Some(tx);
-
-        json["$tx"] = built_packet.clone();
-
-        let signees_array = signees.get();
+        let envelope = TransactionEnvelope {
+            broadcast: self.tx_data.get("broadcast"),
+            selfsign: self.tx_data.get("selfsign"),
+            sigs: &sigs,
+            territoriality: self.tx_data.get("territoriality"),
+            tx: &body,
+            extra: &self.envelope_fields,
+        };
 
-        for signee in signees_array.iter() {
-            let signature =
-                TransactionBuilder::sign_internal(&built_packet.to_string(), signee.key.clone())?;
-            self.sigs.insert(signee.streamid.clone(), signature);
-        }
+        Ok(envelope.build()?.len())
+    }
 
-        json["$sigs"] = json!(self.sigs.clone());
+    /// # With clock
+    ///
+    /// Set the time source used for values the builder generates, in place of the system clock.
+    /// A fixed clock makes the built packet reproducible, for golden file tests.
+    ///
+    /// EC signatures are not reproducible, OpenSSL signs with a random nonce instead of the
+    /// deterministic RFC 6979 one. Golden files should compare `$tx` and check `$sigs` with
+    /// [`verify`](#method.verify), or sign with RSA keys, whose default PKCS#1 v1.5 signatures
+    /// are deterministic.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// use chrono::{TimeZone, Utc};
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.with_clock(|| Utc.timestamp_opt(0, 0).unwrap());
+    ///
+    /// assert_eq!(tx_builder.now().timestamp(), 0);
+    /// ```
+    pub fn with_clock<F>(&mut self, clock: F) -> &mut Self
+    where
+        F: Fn() -> DateTime<Utc> + Send + Sync + 'static,
+    {
+        self.clock = Some(Arc::new(clock));
 
-        for &e in &["territoriality", "selfsign"] {
-            if let Some(data) = self.tx_data.get(e) {
-                let key = format!("${}", e);
+        self
+    }
 
-                json[key] = data.clone();
-            }
+    /// # Now
+    ///
+    /// Get the current time from the builders clock.
+    pub fn now(&self) -> DateTime<Utc> {
+        match &self.clock {
+            Some(clock) => clock(),
+            None => Utc::now(),
         }
-
-        self.tx.replace(json.clone());
-
-        Ok(json.to_string())
     }
 
-    /// # Onboard transaction
+    /// # On event
     ///
-    /// Given a key, generate a transaction to onboard the key to the ledger.
+    /// Set a callback that receives the events of building and signing a transaction: the packet
+    /// string that is signed, its hash, each signature and the final transaction. This gives an
+    /// audit trail of exactly what each key signed.
+    ///
+    /// Events are emitted by [`build`](#method.build) and [`sign`](#method.sign).
+    /// [`build_to_writer`](#method.build_to_writer) never holds the packet or transaction so only
+    /// emits the signatures.
     ///
     /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{packet_data, signees, Key, PacketBuilder, TransactionBuilder, TxEvent};
     /// # use activeledger::key::EllipticCurve;
-    /// # use active_tx::{TransactionBuilder, Key};
-    ///
-    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
-    ///
-    /// let tx = TransactionBuilder::onboard_tx(key).unwrap();
+    /// # let key = Key::Ec(EllipticCurve::new("id").unwrap());
+    /// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.on_event(|event| {
+    ///     if let TxEvent::Signed { streamid, signature, .. } = event {
+    ///         println!("{} signed {}", streamid, signature);
+    ///     }
+    /// });
+    ///
+    /// tx_builder.input(input).unwrap().build(signees![{"id" => key}]).unwrap();
+    /// # }
     /// ```
-    pub fn onboard_tx(key: Key) -> TxBuilderResult<String> {
-        // Create an onboarding transaction for the given key
+    pub fn on_event<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(TxEvent<'_>) + Send + Sync + 'static,
+    {
+        self.events = Some(Arc::new(callback));
 
-        let (key_name, key_type) = match &key {
-            Key::Rsa(key) => (key.name.clone(), "rsa"),
-            Key::Ec(key) => (key.name.clone(), "secp256k1"),
-        };
+        self
+    }
+
+    /// # Envelope field
+    ///
+    /// Add a field to the transaction that the builder does not manage, alongside `$tx` and
+    /// `$sigs`. Setting the same key again replaces its value.
+    ///
+    /// An error is returned if the key is one of the fields set by the builder.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// # use serde_json::json;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.envelope_field("$deployment", json!("blue")).unwrap();
+    ///
+    /// assert!(tx_builder.envelope_field("$sigs", json!({})).is_err());
+    /// ```
+    pub fn envelope_field(&mut self, key: &str, value: Value) -> TxBuilderResult<&mut Self> {
+        if ENVELOPE_FIELDS.contains(&key) {
+            return Err(TxBuilderError::ReservedField(key.to_string()));
+        }
+
+        self.envelope_fields.insert(key.to_string(), value);
+
+        Ok(self)
+    }
+
+    /// # Packet field
+    ///
+    /// Add a field to the `$tx` packet that the builder does not manage.
+    /// The field is signed along with the rest of the packet. Setting the same key again replaces
+    /// its value.
+    ///
+    /// An error is returned if the key is one of the fields set by the builder.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// # use serde_json::json;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.packet_field("$nonce", json!(1)).unwrap();
+    ///
+    /// assert!(tx_builder.packet_field("$i", json!({})).is_err());
+    /// ```
+    pub fn packet_field(&mut self, key: &str, value: Value) -> TxBuilderResult<&mut Self> {
+        if PACKET_FIELDS.contains(&key) {
+            return Err(TxBuilderError::ReservedField(key.to_string()));
+        }
+
+        self.packet_fields.insert(key.to_string(), value);
+
+        Ok(self)
+    }
+
+    /// # Meta
+    ///
+    /// Attach client side metadata, such as a correlation ID, tenant or retry count, that is
+    /// returned with the transaction by [`build_transaction`](#method.build_transaction).
+    /// The metadata is not part of the signed payload and is not sent to the ledger.
+    /// Setting the same key again replaces its value.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// # use serde_json::json;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder
+    ///     .meta("correlation_id", json!("c-123"))
+    ///     .meta("retries", json!(0));
+    /// ```
+    pub fn meta(&mut self, key: &str, value: Value) -> &mut Self {
+        self.meta.insert(key.to_string(), value);
+
+        self
+    }
+
+    /// # With nonce
+    ///
+    /// Add a unique ID (a ULID) to the packet as `$nonce`, so a transaction that is retried can be
+    /// recognised by the contract as the same transaction.
+    ///
+    /// The ID is generated now and kept for every build until [`reset`](#method.reset), which
+    /// generates a new one for the next transaction. A value set with
+    /// [`packet_field`](#method.packet_field) is used instead of a generated one.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.with_nonce().unwrap();
+    ///
+    /// let nonce = tx_builder.nonce().unwrap();
+    /// ```
+    pub fn with_nonce(&mut self) -> TxBuilderResult<&mut Self> {
+        self.with_nonce_field("$nonce")
+    }
+
+    /// # With nonce field
+    ///
+    /// Add a unique ID to the packet under the given key, see [`with_nonce`](#method.with_nonce).
+    ///
+    /// An error is returned if the key is one of the fields set by the builder.
+    pub fn with_nonce_field(&mut self, key: &str) -> TxBuilderResult<&mut Self> {
+        if PACKET_FIELDS.contains(&key) {
+            return Err(TxBuilderError::ReservedField(key.to_string()));
+        }
+
+        if let Some(previous) = self.nonce_field.take() {
+            self.packet_fields.remove(&previous);
+        }
+
+        self.nonce_field = Some(key.to_string());
+        self.generate_nonce()?;
+
+        Ok(self)
+    }
+
+    /// # Nonce
+    ///
+    /// Get the unique ID added to the packet, to match the transaction with the ledger's response.
+    pub fn nonce(&self) -> Option<&str> {
+        let key = self.nonce_field.as_ref()?;
+
+        self.packet_fields.get(key)?.as_str()
+    }
+
+    /// # Entry
+    ///
+    /// Set the contract entry point, given as a string or any type implementing [`Entry`].
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.entry("transfer");
+    /// ```
+    ///
+    /// [`Entry`]: trait.Entry.html
+    pub fn entry<E: Entry>(&mut self, entry: E) -> &mut Self {
+        self.packet_data
+            .insert(String::from("entry"), json!(entry.entry_name()));
+
+        self
+    }
+
+    /// # Max size
+    ///
+    /// Set the largest transaction, in bytes, that the build method will return.
+    /// Building a larger transaction returns an error holding its size.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{packet_data, signees, Key, PacketBuilder, TransactionBuilder};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// let input = PacketBuilder::new(packet_data!({"streamid": {"data": "data"}}))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// let tx = tx_builder
+    ///     .max_size(64)
+    ///     .input(input)
+    ///     .unwrap()
+    ///     .build(signees![{"streamid" => key}]);
+    ///
+    /// assert!(tx.is_err());
+    /// # }
+    /// ```
+    pub fn max_size(&mut self, bytes: usize) -> &mut Self {
+        self.max_size = Some(bytes);
+
+        self
+    }
+
+    /// # Allowed entries
+    ///
+    /// Only allow the given entries. Building a transaction with any other entry returns an error,
+    /// so a mistyped entry is caught before it reaches the ledger.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{packet_data, signees, Key, PacketBuilder, TransactionBuilder};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let key = Key::Ec(EllipticCurve::new("id").unwrap());
+    /// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "token");
+    /// tx_builder
+    ///     .allowed_entries(vec!["mint", "transfer"])
+    ///     .entry("tranfser")
+    ///     .input(input)
+    ///     .unwrap();
+    ///
+    /// assert!(tx_builder.build(signees![{"id" => key}]).is_err());
+    /// # }
+    /// ```
+    pub fn allowed_entries<I, E>(&mut self, entries: I) -> &mut Self
+    where
+        I: IntoIterator<Item = E>,
+        E: Entry,
+    {
+        let entries = entries
+            .into_iter()
+            .map(|entry| entry.entry_name().to_string())
+            .collect();
+
+        self.allowed_entries = Some(entries);
+
+        self
+    }
+
+    /// # Contract
+    ///
+    /// Set the contract value
+    ///
+    /// The contract is checked when the transaction is built. It must not be empty or contain
+    /// whitespace, and may only use letters, digits, '-', '_', '.' and '@'.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    ///
+    /// let mut tx_builder = TransactionBuilder::new_blank();
+    ///
+    /// tx_builder.contract("contract");
+    /// # }
+    /// ```
+    pub fn contract(&mut self, contract: &str) -> &mut Self {
+        self.packet_data
+            .insert(String::from("contract"), json!(contract.to_string()));
+
+        self
+    }
+
+    /// # Namespace
+    ///
+    /// Set the namespace value
+    ///
+    /// The namespace is checked when the transaction is built. It must not be empty or contain
+    /// whitespace, and may only use lower case letters, digits, '-', '_', '.' and '@'.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    ///
+    /// let mut tx_builder = TransactionBuilder::new_blank();
+    ///
+    /// tx_builder.namespace("namespace");
+    /// # }
+    /// ```
+    pub fn namespace(&mut self, namespace: &str) -> &mut Self {
+        self.packet_data
+            .insert(String::from("namespace"), json!(namespace.to_string()));
+
+        self
+    }
+
+    /// # Input
+    ///
+    /// Set the input value, replacing any input already set. This is the same as
+    /// [`set_input`](TransactionBuilder::set_input); use
+    /// [`merge_input`](TransactionBuilder::merge_input) to add to the input instead.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    /// tx_builder.input(input);
+    /// # }
+    /// ```
+    pub fn input(&mut self, input: Input) -> TxBuilderResult<&mut Self> {
+        self.set_section("input", input)
+    }
+
+    /// # Strict stream IDs
+    ///
+    /// Only accept stream IDs created by the ledger, 64 hex characters, in
+    /// [`input_stream`](#method.input_stream) and for the signees of transactions that are not
+    /// selfsigned, as [`StreamId::new`](struct.StreamId.html#method.new) does, including IDs
+    /// given as strings.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, TransactionBuilder};
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.strict_stream_ids(true);
+    ///
+    /// assert!(tx_builder.input_stream("order-1", packet_data!({"quantity": 2})).is_err());
+    /// ```
+    pub fn strict_stream_ids(&mut self, strict: bool) -> &mut Self {
+        self.strict_stream_ids = strict;
+
+        self
+    }
+
+    /// # Input stream
+    ///
+    /// Add the input for one stream, merging it into the input already set. This can be called
+    /// as the data for each stream becomes available, instead of building the whole input at
+    /// once. Any input already added for the stream is replaced.
+    ///
+    /// An error is returned if the stream ID is empty or contains whitespace or control
+    /// characters, or is not a ledger stream ID when [strict](#method.strict_stream_ids), or if
+    /// the input already set is not an object.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, TransactionBuilder};
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// tx_builder
+    ///     .input_stream("order-1", packet_data!({"quantity": 2}))
+    ///     .unwrap()
+    ///     .input_stream("order-2", packet_data!({"quantity": 5}))
+    ///     .unwrap();
+    /// ```
+    pub fn input_stream<S: Into<StreamId>, V: Into<PacketValue>>(
+        &mut self,
+        streamid: S,
+        value: V,
+    ) -> TxBuilderResult<&mut Self> {
+        let streamid = streamid.into();
+        validate_stream_id(streamid.as_str(), self.strict_stream_ids)?;
+
+        let mut stream = PacketMap::new();
+        stream.insert(streamid.into_string(), value.into());
+
+        let data = match PacketBuilder::new(PacketValue::Object(stream))
+            .build()
+            .and_then(|data| data.into_json())
+        {
+            Ok(Value::Object(data)) => data,
+            Ok(_) => return Err(TxBuilderError::BuildError(1000)),
+            Err(e) => {
+                return Err(TxBuilderError::SectionError {
+                    section: "input",
+                    source: Box::new(e),
+                })
+            }
+        };
+
+        let input = self
+            .packet_data
+            .entry(String::from("input"))
+            .or_insert_with(|| json!({}));
+
+        match input {
+            Value::Object(input) => input.extend(data),
+            _ => {
+                return Err(TxBuilderError::SectionError {
+                    section: "input",
+                    source: Box::new(TxBuilderError::PacketError(3003)),
+                })
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// # Output
+    ///
+    /// Set the output value, replacing any output already set. This is the same as
+    /// [`set_output`](TransactionBuilder::set_output); use
+    /// [`merge_output`](TransactionBuilder::merge_output) to add to the output instead.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    /// tx_builder.input(input);
+    /// # }
+    /// ```
+    pub fn output(&mut self, output: Output) -> TxBuilderResult<&mut Self> {
+        self.set_section("output", output)
+    }
+
+    /// # Readonly
+    ///
+    /// Set the readonly value, replacing any readonly value already set. This is the same as
+    /// [`set_readonly`](TransactionBuilder::set_readonly); use
+    /// [`merge_readonly`](TransactionBuilder::merge_readonly) to add to the readonly value
+    /// instead.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    /// tx_builder.input(input);
+    /// # }
+    /// ```
+    pub fn readonly(&mut self, readonly: Readonly) -> TxBuilderResult<&mut Self> {
+        self.set_section("readonly", readonly)
+    }
+
+    /// # Set input
+    ///
+    /// Set the input, replacing any input already set.
+    ///
+    /// ```
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder};
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// let input = PacketBuilder::new(packet_data!({"stream": {"data": "data"}})).build().unwrap();
+    /// tx_builder.set_input(input).unwrap();
+    /// ```
+    pub fn set_input(&mut self, input: Input) -> TxBuilderResult<&mut Self> {
+        self.set_section("input", input)
+    }
+
+    /// # Merge input
+    ///
+    /// Merge into the input already set. Objects are merged key by key, all the way down.
+    /// A key that is already set to a different value is a conflict and returns an error,
+    /// leaving the input unchanged, so nothing set earlier is lost by accident.
+    ///
+    /// ```
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder};
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// let first = PacketBuilder::new(packet_data!({"stream": {"a": 1}})).build().unwrap();
+    /// let second = PacketBuilder::new(packet_data!({"stream": {"b": 2}})).build().unwrap();
+    /// tx_builder.merge_input(first).unwrap().merge_input(second).unwrap();
+    ///
+    /// let conflict = PacketBuilder::new(packet_data!({"stream": {"a": 3}})).build().unwrap();
+    /// assert!(tx_builder.merge_input(conflict).is_err());
+    /// ```
+    pub fn merge_input(&mut self, input: Input) -> TxBuilderResult<&mut Self> {
+        self.merge_section("input", input)
+    }
+
+    /// # Set output
+    ///
+    /// Set the output, replacing any output already set.
+    ///
+    /// ```
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder};
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// let output = PacketBuilder::new(packet_data!({"stream": {"data": "data"}}))
+    ///     .build()
+    ///     .unwrap();
+    /// tx_builder.set_output(output).unwrap();
+    /// ```
+    pub fn set_output(&mut self, output: Output) -> TxBuilderResult<&mut Self> {
+        self.set_section("output", output)
+    }
+
+    /// # Merge output
+    ///
+    /// Merge into the output already set. Objects are merged key by key, all the way down.
+    /// A key that is already set to a different value is a conflict and returns an error,
+    /// leaving the output unchanged, so nothing set earlier is lost by accident.
+    ///
+    /// ```
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder};
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// let first = PacketBuilder::new(packet_data!({"stream": {"a": 1}})).build().unwrap();
+    /// let second = PacketBuilder::new(packet_data!({"stream": {"b": 2}})).build().unwrap();
+    /// tx_builder.merge_output(first).unwrap().merge_output(second).unwrap();
+    ///
+    /// let conflict = PacketBuilder::new(packet_data!({"stream": {"a": 3}})).build().unwrap();
+    /// assert!(tx_builder.merge_output(conflict).is_err());
+    /// ```
+    pub fn merge_output(&mut self, output: Output) -> TxBuilderResult<&mut Self> {
+        self.merge_section("output", output)
+    }
+
+    /// # Set readonly
+    ///
+    /// Set the readonly value, replacing any readonly value already set.
+    ///
+    /// ```
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder};
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// let readonly = PacketBuilder::new(packet_data!({"stream": {"data": "data"}}))
+    ///     .build()
+    ///     .unwrap();
+    /// tx_builder.set_readonly(readonly).unwrap();
+    /// ```
+    pub fn set_readonly(&mut self, readonly: Readonly) -> TxBuilderResult<&mut Self> {
+        self.set_section("readonly", readonly)
+    }
+
+    /// # Merge readonly
+    ///
+    /// Merge into the readonly value already set. Objects are merged key by key, all the way down.
+    /// A key that is already set to a different value is a conflict and returns an error,
+    /// leaving the readonly value unchanged, so nothing set earlier is lost by accident.
+    ///
+    /// ```
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder};
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// let first = PacketBuilder::new(packet_data!({"stream": {"a": 1}})).build().unwrap();
+    /// let second = PacketBuilder::new(packet_data!({"stream": {"b": 2}})).build().unwrap();
+    /// tx_builder.merge_readonly(first).unwrap().merge_readonly(second).unwrap();
+    ///
+    /// let conflict = PacketBuilder::new(packet_data!({"stream": {"a": 3}})).build().unwrap();
+    /// assert!(tx_builder.merge_readonly(conflict).is_err());
+    /// ```
+    pub fn merge_readonly(&mut self, readonly: Readonly) -> TxBuilderResult<&mut Self> {
+        self.merge_section("readonly", readonly)
+    }
+
+    /// # Selfsign
+    ///
+    /// Set selfsign to true
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input).unwrap();
+    ///
+    /// tx_builder.selfsign();
+    /// # }
+    /// ```
+    pub fn selfsign(&mut self) -> &mut Self {
+        self.set_selfsign(true)
+    }
+
+    /// # Selfsign off
+    ///
+    /// Remove the selfsign flag, for example when reusing a builder.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// tx_builder.selfsign();
+    /// tx_builder.selfsign_off();
+    /// ```
+    pub fn selfsign_off(&mut self) -> &mut Self {
+        self.set_selfsign(false)
+    }
+
+    /// # Set selfsign
+    ///
+    /// Set or remove the selfsign flag.
+    /// When set `$selfsign` is added to the transaction as `true`, otherwise it is left out.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// let onboarding = true;
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.set_selfsign(onboarding);
+    /// ```
+    pub fn set_selfsign(&mut self, selfsign: bool) -> &mut Self {
+        if selfsign {
+            self.tx_data.insert(String::from("selfsign"), json!(true));
+        } else {
+            self.tx_data.remove("selfsign");
+        }
+
+        self
+    }
+
+    /// # Broadcast
+    ///
+    /// Set broadcast to true, for transactions using broadcast consensus.
+    /// This is independent of the territoriality.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// tx_builder.broadcast();
+    /// ```
+    pub fn broadcast(&mut self) -> &mut Self {
+        self.set_broadcast(true)
+    }
+
+    /// # Set broadcast
+    ///
+    /// Set or remove the broadcast flag.
+    /// When set `$broadcast` is added to the transaction as `true`, otherwise it is left out.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// tx_builder.set_broadcast(false);
+    /// ```
+    pub fn set_broadcast(&mut self, broadcast: bool) -> &mut Self {
+        if broadcast {
+            self.tx_data.insert(String::from("broadcast"), json!(true));
+        } else {
+            self.tx_data.remove("broadcast");
+        }
+
+        self
+    }
+
+    /// # Sign
+    ///
+    /// Using a given key and stream ID sign the transaction data packet.
+    /// Generally this is used to add more signatures to a transaction, as it requires the build
+    /// method to be run first. Signing before building returns an `InvalidState` error, see
+    /// [`TxState`](enum.TxState.html).
+    ///
+    /// Only the signatures are changed, use [`get`](#method.get) or
+    /// [`rebuild_envelope`](#method.rebuild_envelope) for the updated transaction string, which
+    /// also includes envelope changes made since the build.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    ///
+    /// let streamid = "id";
+    /// let streamid2 = "id2";
+    ///
+    /// let key = Key::Ec(EllipticCurve::new(streamid).unwrap());
+    /// let key2 = Key::Ec(EllipticCurve::new(streamid2).unwrap());
+    ///
+    /// let signees = signees![{streamid => key}];
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input)
+    ///     .unwrap()
+    ///     .build(signees)
+    ///     .unwrap();
+    ///
+    /// let signees2 = signees![{streamid2 => key2}];
+    ///
+    /// tx_builder.sign(signees2);
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                namespace = self.field("namespace"),
+                contract = self.field("contract"),
+                signees = signees.len(),
+            )
+        )
+    )]
+    pub fn sign(&mut self, signees: Signees) -> TxBuilderResult<&mut Self> {
+        self.check_signees(&signees)?;
+
+        let packet = match &self.packet {
+            Some(packet) => packet,
+            None => return Err(TransactionBuilder::not_built()),
+        };
+
+        #[cfg(feature = "openssl-keys")]
+        self.emit_hashes(packet.get(), &signees);
+
+        for signee in signees.iter() {
+            let signature =
+                TransactionBuilder::sign_internal(packet.get(), signee, &self.sign_options)?;
+            self.emit_signature(signee, &signature);
+            add_sig(&mut self.sigs, signee, signature);
+        }
+
+        Ok(self)
+    }
+
+    /// # Build
+    ///
+    /// Using the data provided, compile it into the correct form for a transaction.
+    /// Returns a transaction in the form of a string.
+    ///
+    /// If a [max size](#method.max_size) is set and the transaction is larger an error is returned.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let input = PacketBuilder::new(packet_data!({"data": "data"})).build().unwrap();
+    ///
+    /// let streamid = "id";
+    /// let key = Key::Ec(EllipticCurve::new(streamid).unwrap());
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input).unwrap();
+    ///
+    /// let signees = signees![{streamid => key}];
+    ///
+    /// let tx = tx_builder.build(signees).unwrap();
+    ///
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                namespace = self.field("namespace"),
+                contract = self.field("contract"),
+                signees = signees.len(),
+                size = tracing::field::Empty,
+            )
+        )
+    )]
+    pub fn build(&mut self, signees: Signees) -> TxBuilderResult<String> {
+        self.check_signees(&signees)?;
+        self.generate_nonce()?;
+
+        let packet = compat::packet(self.compat, &self.body()?)?;
+
+        self.emit(TxEvent::PacketBuilt {
+            packet: packet.get(),
+        });
+
+        let (tx, sigs) = match self.sign_packet(&packet, &signees) {
+            Ok(signed) => signed,
+            Err(e) => return Err(self.discard_build(e)),
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("size", tx.len());
+
+        self.built_selfsign = self.tx_data.contains_key("selfsign");
+        self.streamed = false;
+        self.packet = Some(packet);
+        self.sigs = sigs;
+
+        Ok(tx)
+    }
+
+    /// # Build variants
+    ///
+    /// Build the packet once and sign it with each set of signees, returning a transaction for
+    /// each set in the same order. The variants share the same `$tx` and only differ in `$sigs`,
+    /// such as when the same packet is published to two ledgers under different identities.
+    ///
+    /// The builder keeps the packet and the signatures of the last variant. If a
+    /// [max size](#method.max_size) is set and any variant is larger an error is returned.
+    /// At least one set of signees must be given, an empty slice returns `EmptyVariants`.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+    /// let key = Key::Ec(EllipticCurve::new("ledger-a").unwrap());
+    /// let key2 = Key::Ec(EllipticCurve::new("ledger-b").unwrap());
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input).unwrap();
+    ///
+    /// let variants = tx_builder
+    ///     .build_variants(&[signees![{"id" => key}], signees![{"id" => key2}]])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(variants.len(), 2);
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            fields(
+                namespace = self.field("namespace"),
+                contract = self.field("contract"),
+                variants = signees.len(),
+            )
+        )
+    )]
+    pub fn build_variants(&mut self, signees: &[Signees]) -> TxBuilderResult<Vec<String>> {
+        if signees.is_empty() {
+            return Err(TxBuilderError::EmptyVariants);
+        }
+
+        for signees in signees {
+            self.check_signees(signees)?;
+        }
+
+        self.generate_nonce()?;
+
+        let packet = compat::packet(self.compat, &self.body()?)?;
+
+        self.emit(TxEvent::PacketBuilt {
+            packet: packet.get(),
+        });
+
+        let mut variants = Vec::with_capacity(signees.len());
+        let mut sigs = Sigs::new();
+
+        for signees in signees {
+            let (tx, variant_sigs) = match self.sign_packet(&packet, signees) {
+                Ok(signed) => signed,
+                Err(e) => return Err(self.discard_build(e)),
+            };
+
+            variants.push(tx);
+            sigs = variant_sigs;
+        }
+
+        self.built_selfsign = self.tx_data.contains_key("selfsign");
+        self.streamed = false;
+        self.packet = Some(packet);
+        self.sigs = sigs;
+
+        Ok(variants)
+    }
+
+    /// # Build transaction
+    ///
+    /// Build the transaction, see [`build`](#method.build), returning a [`BuiltTransaction`]
+    /// holding the transaction string, the signed packet, the signatures and the metadata set
+    /// using [`meta`](#method.meta).
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// # use serde_json::json;
+    /// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+    /// let key = Key::Ec(EllipticCurve::new("id").unwrap());
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input).unwrap().meta("tenant", json!("acme"));
+    ///
+    /// let built = tx_builder.build_transaction(signees![{"id" => key}]).unwrap();
+    ///
+    /// assert_eq!(built.stream_ids(), vec!["id"]);
+    /// assert_eq!(built.get_meta("tenant"), Some(&json!("acme")));
+    /// assert!(!built.to_string().contains("acme"));
+    /// # }
+    /// ```
+    ///
+    /// [`BuiltTransaction`]: struct.BuiltTransaction.html
+    pub fn build_transaction(&mut self, signees: Signees) -> TxBuilderResult<BuiltTransaction> {
+        let tx = self.build(signees)?;
+
+        let packet = match &self.packet {
+            Some(packet) => packet.get().to_string(),
+            None => return Err(TransactionBuilder::not_built()),
+        };
+
+        Ok(BuiltTransaction::new(
+            tx,
+            packet,
+            self.sigs.clone(),
+            self.meta.clone(),
+        ))
+    }
+
+    /// # Build idempotent
+    ///
+    /// Build the transaction with a nonce (a ULID) added to each stream's input under the given
+    /// key, so the contract can recognise a retried transaction as the same transaction.
+    /// Returns the transaction with its nonce and the hash of its signed packet, to record for
+    /// [`is_replay`](fn.is_replay.html).
+    ///
+    /// The nonce is written into the builder's input and kept there, including when the build
+    /// fails, so building again gives the same nonce, packet and hash until the input is
+    /// replaced. This makes a failed build safe to retry. An error is returned if the input has
+    /// no stream objects.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    ///
+    /// ```
+    /// # use active_tx::{packet_data, signees, Key, PacketBuilder, TransactionBuilder};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let input = PacketBuilder::new(packet_data!({"id": {"amount": 1}})).build().unwrap();
+    /// let key = Key::Ec(EllipticCurve::new("id").unwrap());
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input).unwrap();
+    ///
+    /// let built = tx_builder.build_idempotent(signees![{"id" => key}], "_nonce").unwrap();
+    ///
+    /// let json = tx_builder.get_json().unwrap();
+    /// assert_eq!(json["$tx"]["$i"]["id"]["_nonce"], built.nonce.as_str());
+    /// ```
+    #[cfg(feature = "openssl-keys")]
+    pub fn build_idempotent(
+        &mut self,
+        signees: Signees,
+        key: &str,
+    ) -> TxBuilderResult<IdempotentTx> {
+        let nonce = self.input_nonce(key)?;
+
+        let tx = self.build(signees)?;
+
+        let hash = idempotency::hex(&self.packet_hash()?);
+
+        Ok(IdempotentTx { tx, nonce, hash })
+    }
+
+    /// # Build to writer
+    ///
+    /// Build the transaction and serialize it straight into the writer (a file, socket, etc.).
+    /// The packet is signed while it is serialized, so the transaction is never held in memory
+    /// as a string. This is intended for transactions carrying large payloads.
+    ///
+    /// As the packet is not kept, the get and sign methods can not be used afterwards, though
+    /// the builder is `Built` or `Signed` as after [`build`](#method.build).
+    ///
+    /// When a [compatibility mode](#method.compat) is set, the transaction is built in memory and
+    /// then written.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    ///
+    /// ```
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+    /// let key = Key::Ec(EllipticCurve::new("id").unwrap());
+    ///
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.input(input).unwrap();
+    ///
+    /// let mut writer: Vec<u8> = Vec::new();
+    /// tx_builder.build_to_writer(&mut writer, signees![{"id" => key}]).unwrap();
+    /// ```
+    #[cfg(feature = "openssl-keys")]
+    pub fn build_to_writer<W: Write>(
+        &mut self,
+        writer: &mut W,
+        signees: Signees,
+    ) -> TxBuilderResult<()> {
+        if self.compat != CompatMode::Canonical {
+            let tx = self.build(signees)?;
+
+            return match writer.write_all(tx.as_bytes()) {
+                Ok(_) => Ok(()),
+                Err(e) => Err(TxBuilderError::WriteError(serde_json::Error::io(e))),
+            };
+        }
+
+        self.check_signees(&signees)?;
+        self.generate_nonce()?;
 
-        let pem = TransactionBuilder::get_pem(key.clone())?;
+        let body = self.body()?;
+
+        let mut keys = Vec::with_capacity(signees.len());
+        for signee in signees.iter() {
+            keys.push(StreamKey::new(signee)?);
+        }
+
+        // First pass signs the packet
+        let mut signer = PacketSigner::new(&keys, &self.sign_options)?;
+
+        if let Err(e) = serde_json::to_writer(&mut signer, &body) {
+            return match signer.failure() {
+                Some(e) => Err(e),
+                None => Err(TxBuilderError::WriteError(e)),
+            };
+        }
+
+        let sigs = signer.finish()?;
+
+        for signee in signees.iter() {
+            let signature = match (sigs.get(&signee.streamid), &signee.key_ref) {
+                (Some(Sig::Single(signature)), None) => signature,
+                (Some(Sig::Multi(signatures)), Some(key_ref)) => match signatures.get(key_ref) {
+                    Some(signature) => signature,
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            self.emit_signature(signee, signature);
+        }
+
+        // Second pass writes the transaction
+        let envelope = TransactionEnvelope {
+            broadcast: self.tx_data.get("broadcast"),
+            selfsign: self.tx_data.get("selfsign"),
+            sigs: &sigs,
+            territoriality: self.tx_data.get("territoriality"),
+            tx: &body,
+            extra: &self.envelope_fields,
+        };
+
+        if let Err(e) = serde_json::to_writer(writer, &envelope) {
+            return Err(TxBuilderError::WriteError(e));
+        }
+
+        self.streamed = true;
+        self.packet = None;
+        self.sigs = sigs;
+
+        Ok(())
+    }
+
+    /// # Status
+    ///
+    /// Get which sections of the transaction have been set and how many signatures it has,
+    /// without building it. See [`TxStatus`].
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// tx_builder.entry("entry").selfsign();
+    ///
+    /// let status = tx_builder.status();
+    ///
+    /// assert!(status.entry && status.selfsign);
+    /// assert!(!status.input && !status.territoriality);
+    /// assert_eq!(status.sigs, 0);
+    /// ```
+    ///
+    /// [`TxStatus`]: struct.TxStatus.html
+    pub fn status(&self) -> TxStatus {
+        let sigs = self
+            .sigs
+            .values()
+            .map(|sig| match sig {
+                Sig::Single(_) => 1,
+                Sig::Multi(signatures) => signatures.len(),
+            })
+            .sum();
+
+        TxStatus {
+            namespace: self.packet_data.contains_key("namespace"),
+            contract: self.packet_data.contains_key("contract"),
+            entry: self.packet_data.contains_key("entry"),
+            input: self.packet_data.contains_key("input"),
+            output: self.packet_data.contains_key("output"),
+            readonly: self.packet_data.contains_key("readonly"),
+            territoriality: self.tx_data.contains_key("territoriality"),
+            selfsign: self.tx_data.contains_key("selfsign"),
+            built: self.packet.is_some(),
+            sigs,
+        }
+    }
+
+    /// # State
+    ///
+    /// Get the [`TxState`] of the builder, see its documentation for the calls allowed in each
+    /// state.
+    ///
+    /// [`TxState`]: enum.TxState.html
+    pub fn state(&self) -> TxState {
+        match (self.packet.is_some() || self.streamed, self.sigs.is_empty()) {
+            (false, _) => TxState::Draft,
+            (true, true) => TxState::Built,
+            (true, false) => TxState::Signed,
+        }
+    }
+
+    /// # Validate
+    ///
+    /// Check everything needed to build the transaction with the given signees, returning every
+    /// problem found rather than only the first. This can be called at any point, which is useful
+    /// with [`new_blank`](#method.new_blank) builders.
+    ///
+    /// The contract, namespace and input must be set, and the contract, namespace and entry must
+    /// be valid. Which input streams must be signed is up to the contract, so only the streams
+    /// given in `required` must have a signee, and each signee must be a stream in the input.
+    /// Selfsigned transactions are signed by key name rather than stream, so their signees are
+    /// not checked.
+    ///
+    /// ```
+    /// # use active_tx::{Signees, TransactionBuilder, TxBuilderError};
+    /// let tx_builder = TransactionBuilder::new_blank();
+    ///
+    /// match tx_builder.validate(&Signees::new(), &["streamid"]) {
+    ///     Err(TxBuilderError::ValidationFailed(problems)) => assert_eq!(problems.len(), 4),
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn validate(&self, signees: &Signees, required: &[&str]) -> TxBuilderResult<()> {
+        let mut problems = Vec::new();
+
+        for &field in &["contract", "namespace"] {
+            let result = match self.packet_data.get(field) {
+                Some(value) => validate_identifier(field, value.as_str().unwrap_or_default()),
+                None => Err(TxBuilderError::MissingField(field)),
+            };
+
+            if let Err(e) = result {
+                problems.push(e);
+            }
+        }
+
+        if let Err(e) = self.check_entry() {
+            problems.push(e);
+        }
+
+        let input = match self.packet_data.get("input") {
+            Some(Value::Object(input)) => Some(input),
+            Some(_) => None,
+            None => {
+                problems.push(TxBuilderError::MissingField("input"));
+                None
+            }
+        };
+
+        if !self.tx_data.contains_key("selfsign") {
+            for &streamid in required {
+                if !signees.contains(streamid) {
+                    problems.push(TxBuilderError::MissingSignature(streamid.to_string()));
+                }
+            }
+
+            if let Some(input) = input {
+                for signee in signees.iter() {
+                    if !input.contains_key(&signee.streamid) {
+                        problems.push(TxBuilderError::UnexpectedSigner(signee.streamid.clone()));
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(TxBuilderError::ValidationFailed(problems))
+        }
+    }
+
+    /// # Reset
+    ///
+    /// Clear the input data and everything generated by a previous build (packet, transaction,
+    /// signatures and nonce), along with the metadata. The namespace, contract, entry, output,
+    /// readonly, territoriality and selfsign settings are kept so the builder can be reused for
+    /// the next transaction.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let key = Key::Ec(EllipticCurve::new("id").unwrap());
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    ///
+    /// for value in 0..2 {
+    ///     let input = PacketBuilder::new(packet_data!({"id": {"value": value}})).build().unwrap();
+    ///
+    ///     tx_builder.reset();
+    ///     tx_builder.input(input).unwrap();
+    ///
+    ///     let tx = tx_builder.build(signees![{"id" => key.clone()}]).unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub fn reset(&mut self) -> &mut Self {
+        self.packet_data.remove("input");
+        self.meta.clear();
+
+        if let Some(key) = &self.nonce_field {
+            self.packet_fields.remove(key);
+        }
+
+        self.streamed = false;
+        self.packet = None;
+        self.sigs.clear();
+
+        self
+    }
+
+    /// # Clear signatures
+    ///
+    /// Remove all signatures from the built transaction.
+    /// Signatures can then be added again using the sign method.
+    pub fn clear_sigs(&mut self) -> &mut Self {
+        self.sigs.clear();
+
+        self
+    }
+
+    /// # Snapshot
+    ///
+    /// Copy everything set on the builder into a [`TxSnapshot`] that can be serialized, so a
+    /// half built transaction can be stored by a work queue and resumed after a crash.
+    /// Anything generated by a build (packet and signatures) is left out, a generated nonce is
+    /// kept so a retried transaction keeps it. Keys, the clock and the event callback are not
+    /// captured.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{TransactionBuilder, packet_data, PacketBuilder, Key, signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+    /// let input = PacketBuilder::new(packet_data!({"id": {"value": 1}})).build().unwrap();
+    /// tx_builder.input(input).unwrap();
+    ///
+    /// let snapshot = tx_builder.snapshot();
+    ///
+    /// let key = Key::Ec(EllipticCurve::new("id").unwrap());
+    /// let tx = TransactionBuilder::restore(snapshot)
+    ///     .build(signees![{"id" => key}])
+    ///     .unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`TxSnapshot`]: struct.TxSnapshot.html
+    pub fn snapshot(&self) -> TxSnapshot {
+        TxSnapshot {
+            packet_data: self.packet_data.clone(),
+            tx_data: self.tx_data.clone(),
+            packet_fields: self.packet_fields.clone(),
+            envelope_fields: self.envelope_fields.clone(),
+            meta: self.meta.clone(),
+            allowed_entries: self.allowed_entries.clone(),
+            nodes: self.nodes.clone(),
+            sign_options: self.sign_options.clone(),
+            compat: self.compat,
+            max_size: self.max_size,
+            nonce_field: self.nonce_field.clone(),
+            strict_stream_ids: self.strict_stream_ids,
+        }
+    }
+
+    /// # Restore
+    ///
+    /// Create a builder from a [`TxSnapshot`], ready to be built or changed further.
+    /// The system clock is used and no event callback is set, set them again if needed.
+    ///
+    /// [`TxSnapshot`]: struct.TxSnapshot.html
+    pub fn restore(snapshot: TxSnapshot) -> TransactionBuilder {
+        TransactionBuilder {
+            packet_data: snapshot.packet_data,
+            tx_data: snapshot.tx_data,
+            packet_fields: snapshot.packet_fields,
+            envelope_fields: snapshot.envelope_fields,
+            meta: snapshot.meta,
+            allowed_entries: snapshot.allowed_entries,
+            nodes: snapshot.nodes,
+            sign_options: snapshot.sign_options,
+            compat: snapshot.compat,
+            max_size: snapshot.max_size,
+            clock: None,
+            nonce_field: snapshot.nonce_field,
+            strict_stream_ids: snapshot.strict_stream_ids,
+            events: None,
+            packet: None,
+            built_selfsign: false,
+            streamed: false,
+            sigs: BTreeMap::new(),
+        }
+    }
+
+    /// # Template
+    ///
+    /// Create a [`TxTemplate`] from the data provided so far.
+    /// The template holds everything except the input and signatures, which are supplied
+    /// when the template is instantiated. Any input already added to the builder is ignored.
+    ///
+    /// Namespace and contract are required.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// let template = TransactionBuilder::new("namespace", "contract")
+    ///     .entry("entry")
+    ///     .template()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [`TxTemplate`]: struct.TxTemplate.html
+    pub fn template(&self) -> TxBuilderResult<TxTemplate> {
+        let (contract, namespace) = self.identifiers()?;
+
+        let mut packet = json!(self.packet_fields);
+
+        packet["$contract"] = contract.clone();
+        packet["$namespace"] = namespace.clone();
+
+        for &(field, key) in &[("entry", "$entry"), ("output", "$o"), ("readonly", "$r")] {
+            if let Some(data) = self.packet_data.get(field) {
+                packet[key] = data.clone();
+            }
+        }
+
+        Ok(TxTemplate::new(
+            packet,
+            self.envelope_values(),
+            self.sign_options.clone(),
+            self.compat,
+        ))
+    }
+
+    /// # Multi-signature
+    ///
+    /// Build the packet without signing it and return a [`MultiSigCollector`] that collects
+    /// signatures from the required stream IDs until the threshold is met.
+    ///
+    /// The threshold must be between 1 and the number of required stream IDs.
+    ///
+    /// [`MultiSigCollector`]: struct.MultiSigCollector.html
+    pub fn multisig(
+        &self,
+        required: &[&str],
+        threshold: usize,
+    ) -> TxBuilderResult<MultiSigCollector> {
+        let packet = compat::packet(self.compat, &self.body()?)?;
+
+        MultiSigCollector::new(
+            packet,
+            self.envelope_values(),
+            self.sign_options.clone(),
+            required,
+            threshold,
+        )
+    }
+
+    /// # Verify
+    ///
+    /// Check the signatures of a signed transaction against the public keys of the streams that
+    /// signed it. Every stream given must have a valid signature, signatures for other streams
+    /// are not checked.
+    ///
+    /// Keys for a stream signed by several keys are given as `streamid/keyref`. Use
+    /// [`VerifierSet`] to load the keys from PEMs or check keys with different options.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use active_tx::{
+    /// #     packet_data, signees, Key, PacketBuilder, SignOptions, TransactionBuilder,
+    /// # };
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("streamid").unwrap());
+    /// let input = PacketBuilder::new(packet_data!({"streamid": {"data": "data"}}))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// let tx = TransactionBuilder::new("namespace", "contract")
+    ///     .input(input)
+    ///     .unwrap()
+    ///     .build(signees![{"streamid" => key.clone()}])
+    ///     .unwrap();
+    ///
+    /// let mut keys = HashMap::new();
+    /// keys.insert(String::from("streamid"), key.public_key().unwrap());
+    ///
+    /// TransactionBuilder::verify(&tx, &keys, &SignOptions::new()).unwrap();
+    /// ```
+    ///
+    /// [`VerifierSet`]: struct.VerifierSet.html
+    #[cfg(feature = "openssl-keys")]
+    pub fn verify(
+        tx: &str,
+        keys: &HashMap<String, PublicKey>,
+        options: &SignOptions,
+    ) -> TxBuilderResult<()> {
+        VerifierSet::from_names(keys).verify(tx, options)
+    }
 
-        let input = packet_data!({
-            key_name.clone(): {
-                "type": key_type,
-                "publicKey": pem
-            }
-        });
+    /// # Onboard transaction
+    ///
+    /// Given a key, generate a transaction to onboard the key to the ledger.
+    /// Use [`OnboardTxBuilder`] to customise the transaction.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use activeledger::key::EllipticCurve;
+    /// # use active_tx::{TransactionBuilder, Key};
+    ///
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let tx = TransactionBuilder::onboard_tx(key).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`OnboardTxBuilder`]: struct.OnboardTxBuilder.html
+    pub fn onboard_tx(key: Key) -> TxBuilderResult<String> {
+        // Create an onboarding transaction for the given key
+        OnboardTxBuilder::new(key).build()
+    }
 
-        let mut input_builder = crate::PacketBuilder::new(input);
-        let input = input_builder.build()?;
+    /// # Onboard transaction JSON
+    ///
+    /// Given a key, generate a transaction to onboard the key to the ledger, returning a
+    /// [`BuiltTransaction`] so the transaction JSON and the stream name the key signs under can
+    /// be inspected or logged.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use activeledger::key::EllipticCurve;
+    /// # use active_tx::{TransactionBuilder, Key};
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let built = TransactionBuilder::onboard_tx_json(key).unwrap();
+    ///
+    /// assert_eq!(built.stream_ids(), vec!["keyname"]);
+    ///
+    /// let json = built.json().unwrap();
+    /// assert_eq!(json["$tx"]["$i"]["keyname"]["type"], "secp256k1");
+    /// # }
+    /// ```
+    ///
+    /// [`BuiltTransaction`]: struct.BuiltTransaction.html
+    pub fn onboard_tx_json(key: Key) -> TxBuilderResult<BuiltTransaction> {
+        OnboardTxBuilder::new(key).build_transaction()
+    }
 
-        let signees = signees!(key);
+    /// # Onboard transaction with metadata
+    ///
+    /// Generate a transaction to onboard the key, storing the fields of the metadata object
+    /// alongside the public key in the identity's input. The default onboard contract keeps
+    /// these as the identity's profile.
+    ///
+    /// An error is returned if the metadata is not an object.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use activeledger::key::EllipticCurve;
+    /// # use active_tx::{packet_data, TransactionBuilder, Key};
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let metadata = packet_data!({"name": "Sensor 1", "roles": ["sensor"]});
+    ///
+    /// let tx = TransactionBuilder::onboard_tx_with_metadata(key, metadata).unwrap();
+    /// # }
+    /// ```
+    pub fn onboard_tx_with_metadata(key: Key, metadata: PacketValue) -> TxBuilderResult<String> {
+        OnboardTxBuilder::new(key).merge_metadata(metadata)?.build()
+    }
 
-        let mut tx_builder = TransactionBuilder::new("default", "onboard");
-        let tx = tx_builder.selfsign().input(input)?.build(signees)?;
+    /// # Onboard transaction with
+    ///
+    /// Generate a transaction to onboard the key through a custom identity contract, rather than
+    /// the default "default" namespace and "onboard" contract.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use activeledger::key::EllipticCurve;
+    /// # use active_tx::{TransactionBuilder, Key};
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let tx = TransactionBuilder::onboard_tx_with(key, "identity", "register", Some("device"))
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub fn onboard_tx_with(
+        key: Key,
+        namespace: &str,
+        contract: &str,
+        entry: Option<&str>,
+    ) -> TxBuilderResult<String> {
+        let mut onboard = OnboardTxBuilder::new(key);
+        onboard.namespace(namespace).contract(contract);
+
+        if let Some(entry) = entry {
+            onboard.entry(entry);
+        }
 
-        Ok(tx.to_string())
+        onboard.build()
     }
 
     /// # Onboard transaction
     ///
-    /// Given a key type and name, generate a key and use it to build a transaction to onboard that key to the ledger.
+    /// Given a key type and name, generate a key and use it to build a transaction to onboard
+    /// that key to the ledger.
     ///
     /// Returns the generated key and the transaction
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
     /// ```
     /// # use active_tx::{TransactionBuilder, KeyType};
     ///
     /// let (key, tx) = TransactionBuilder::generate_onboard_tx(KeyType::EC, "keyname").unwrap();
     /// ```
+    #[cfg(feature = "openssl-keys")]
     pub fn generate_onboard_tx(
         key_type: KeyType,
         key_name: &str,
     ) -> TxBuilderResult<(Key, String)> {
         // Generate a key and onboard it
-
-        let key = match key_type {
-            KeyType::RSA => {
-                let key = match RSA::new(key_name) {
-                    Ok(key) => key,
-                    Err(_) => return Err(TxBuilderError::TxGenerateError(6000)),
-                };
-                Key::Rsa(key)
-            }
-            KeyType::EC => {
-                let key = match EllipticCurve::new(key_name) {
-                    Ok(key) => key,
-                    Err(_) => return Err(TxBuilderError::TxGenerateError(6001)),
-                };
-                Key::Ec(key)
-            }
-        };
+        let key = TransactionBuilder::generate_key(key_type, key_name)?;
 
         let tx = TransactionBuilder::onboard_tx(key.clone())?;
 
         Ok((key, tx))
     }
+
+    /// # Generate onboard transaction JSON
+    ///
+    /// Given a key type and name, generate a key and use it to build a transaction to onboard
+    /// that key to the ledger, see [`onboard_tx_json`](#method.onboard_tx_json).
+    ///
+    /// Returns the generated key and the built transaction.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    /// ```
+    /// # use active_tx::{TransactionBuilder, KeyType};
+    /// let (key, built) =
+    ///     TransactionBuilder::generate_onboard_tx_json(KeyType::EC, "keyname").unwrap();
+    ///
+    /// assert_eq!(built.stream_ids(), vec![key.name()]);
+    /// ```
+    #[cfg(feature = "openssl-keys")]
+    pub fn generate_onboard_tx_json(
+        key_type: KeyType,
+        key_name: &str,
+    ) -> TxBuilderResult<(Key, BuiltTransaction)> {
+        let key = TransactionBuilder::generate_key(key_type, key_name)?;
+
+        let built = TransactionBuilder::onboard_tx_json(key.clone())?;
+
+        Ok((key, built))
+    }
+
+    /// # Mnemonic onboard transaction
+    ///
+    /// Generate a new mnemonic phrase, derive an EC key from it using the derivation path and
+    /// build a transaction to onboard that key to the ledger.
+    ///
+    /// Returns the mnemonic phrase, the derived key and the transaction. The phrase is the only
+    /// way to recreate the key with [`Key::from_mnemonic`], it must be kept safe.
+    ///
+    /// ```
+    /// # use active_tx::TransactionBuilder;
+    /// let (phrase, key, tx) =
+    ///     TransactionBuilder::generate_mnemonic_onboard_tx("keyname", "m/44'/0'/0'/0/0").unwrap();
+    /// ```
+    ///
+    /// Requires the `mnemonic` feature.
+    ///
+    /// [`Key::from_mnemonic`]: enum.Key.html#method.from_mnemonic
+    #[cfg(feature = "mnemonic")]
+    pub fn generate_mnemonic_onboard_tx(
+        key_name: &str,
+        derivation_path: &str,
+    ) -> TxBuilderResult<(String, Key, String)> {
+        let phrase = Key::generate_mnemonic()?;
+
+        let key = Key::from_mnemonic(key_name, &phrase, derivation_path, KeyType::EC)?;
+
+        let tx = TransactionBuilder::onboard_tx(key.clone())?;
+
+        Ok((phrase, key, tx))
+    }
+
+    /// # Onboard batch
+    ///
+    /// Generate a number of keys and build the transactions to onboard them.
+    /// Keys are named using the prefix followed by their index, e.g. "device-0", "device-1".
+    ///
+    /// [`BatchMode::Combined`] builds a single transaction with an input and signature for
+    /// every key, [`BatchMode::Individual`] builds one transaction per key.
+    ///
+    /// Returns the generated keys and the transactions.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    /// ```
+    /// # use active_tx::{BatchMode, TransactionBuilder, KeyType};
+    /// let mode = BatchMode::Combined;
+    /// let (keys, txs) =
+    ///     TransactionBuilder::generate_onboard_batch(KeyType::EC, 10, "device-", mode).unwrap();
+    ///
+    /// assert_eq!(keys.len(), 10);
+    /// assert_eq!(txs.len(), 1);
+    /// ```
+    ///
+    /// [`BatchMode::Combined`]: enum.BatchMode.html#variant.Combined
+    /// [`BatchMode::Individual`]: enum.BatchMode.html#variant.Individual
+    #[cfg(feature = "openssl-keys")]
+    pub fn generate_onboard_batch(
+        key_type: KeyType,
+        count: usize,
+        prefix: &str,
+        mode: BatchMode,
+    ) -> TxBuilderResult<(Vec<Key>, Vec<String>)> {
+        let mut keys = Vec::with_capacity(count);
+
+        for index in 0..count {
+            let name = format!("{}{}", prefix, index);
+
+            keys.push(TransactionBuilder::generate_key(key_type, &name)?);
+        }
+
+        let txs = TransactionBuilder::onboard_batch(&keys, mode)?;
+
+        Ok((keys, txs))
+    }
+}
+
+// Crate functions
+impl TransactionBuilder {
+    /// Build the transactions onboarding the keys
+    #[cfg(feature = "openssl-keys")]
+    pub(crate) fn onboard_batch(keys: &[Key], mode: BatchMode) -> TxBuilderResult<Vec<String>> {
+        match mode {
+            BatchMode::Individual => {
+                let mut txs = Vec::with_capacity(keys.len());
+
+                for key in keys.iter() {
+                    txs.push(OnboardTxBuilder::new(key.clone()).build()?);
+                }
+
+                Ok(txs)
+            }
+            BatchMode::Combined => {
+                let mut input = PacketMap::new();
+                let mut signees = Signees::new();
+
+                for key in keys.iter() {
+                    let identity = OnboardTxBuilder::identity(key, PacketMap::new())?;
+
+                    input.insert(key.name().to_string(), identity);
+                    signees.add_selfsign(key.clone());
+                }
+
+                let input = PacketBuilder::new(PacketValue::Object(input)).build()?;
+
+                let mut tx_builder = TransactionBuilder::new("default", "onboard");
+                let tx = tx_builder.selfsign().input(input)?.build(signees)?;
+
+                Ok(vec![tx])
+            }
+        }
+    }
 }
 
 // Private functions
 impl TransactionBuilder {
-    /// Match key type then pass to signing function
-    fn sign_internal(data: &str, key: Key) -> TxBuilderResult<String> {
-        match key {
-            Key::Rsa(key) => TransactionBuilder::sign_rsa(data, key),
-            Key::Ec(key) => TransactionBuilder::sign_ec(data, key),
+    /// Replace a section of the packet data
+    fn set_section(
+        &mut self,
+        section: &'static str,
+        data: PacketData,
+    ) -> TxBuilderResult<&mut Self> {
+        match data.into_json() {
+            Ok(data) => self.packet_data.insert(section.to_string(), data),
+            Err(e) => {
+                return Err(TxBuilderError::SectionError {
+                    section,
+                    source: Box::new(e),
+                })
+            }
+        };
+
+        Ok(self)
+    }
+
+    /// Deep merge data into a section of the packet data, failing on any conflicting value
+    fn merge_section(
+        &mut self,
+        section: &'static str,
+        data: PacketData,
+    ) -> TxBuilderResult<&mut Self> {
+        let data = match data.into_json() {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(TxBuilderError::SectionError {
+                    section,
+                    source: Box::new(e),
+                })
+            }
+        };
+
+        let merged = match self.packet_data.get(section) {
+            Some(existing) => {
+                let mut merged = existing.clone();
+                merge_json(&mut merged, data, section)?;
+                merged
+            }
+            None => data,
+        };
+
+        self.packet_data.insert(section.to_string(), merged);
+
+        Ok(self)
+    }
+
+    /// Generate a key of the given type
+    #[cfg(feature = "openssl-keys")]
+    fn generate_key(key_type: KeyType, key_name: &str) -> TxBuilderResult<Key> {
+        let key = match key_type {
+            KeyType::RSA => match RSA::new(key_name) {
+                Ok(key) => Key::Rsa(key),
+                Err(source) => {
+                    return Err(TxBuilderError::KeyGenerationError {
+                        key_type: KeyType::RSA,
+                        source,
+                    })
+                }
+            },
+            KeyType::EC => match EllipticCurve::new(key_name) {
+                Ok(key) => Key::Ec(key),
+                Err(source) => {
+                    return Err(TxBuilderError::KeyGenerationError {
+                        key_type: KeyType::EC,
+                        source,
+                    })
+                }
+            },
+        };
+
+        Ok(key)
+    }
+
+    /// Get a string field of the packet data for tracing
+    #[cfg(feature = "tracing")]
+    fn field(&self, key: &str) -> &str {
+        self.packet_data
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    }
+
+    /// Pass the event to the callback, if one is set
+    fn emit(&self, event: TxEvent) {
+        if let Some(events) = &self.events {
+            events(event);
+        }
+    }
+
+    /// Emit the signature produced for the signee, with the fingerprint of its key
+    fn emit_signature(&self, signee: &Signee, signature: &str) {
+        if self.events.is_none() {
+            return;
+        }
+
+        #[cfg(feature = "openssl-keys")]
+        let fingerprint = signee.key.fingerprint().ok();
+        #[cfg(not(feature = "openssl-keys"))]
+        let fingerprint: Option<String> = None;
+
+        self.emit(TxEvent::Signed {
+            streamid: &signee.streamid,
+            key_ref: signee.key_ref.as_deref(),
+            signature,
+            fingerprint: fingerprint.as_deref(),
+        });
+    }
+
+    /// Emit the packet hash for each hash algorithm the signees use
+    #[cfg(feature = "openssl-keys")]
+    fn emit_hashes(&self, packet: &str, signees: &Signees) {
+        if self.events.is_none() {
+            return;
+        }
+
+        let mut hashes = Vec::new();
+
+        for signee in signees.iter() {
+            if let Key::External(_) = *signee.key {
+                continue;
+            }
+
+            let hash = signee.options.as_ref().unwrap_or(&self.sign_options).hash;
+
+            if !hashes.contains(&hash) {
+                hashes.push(hash);
+            }
+        }
+
+        for hash in hashes {
+            self.emit(TxEvent::PacketHashed {
+                hash,
+                digest: &hash.digest(packet.as_bytes()),
+            });
         }
     }
 
-    /// Sign data using elliptic curve
-    fn sign_ec(tx: &str, key: EllipticCurve) -> TxBuilderResult<String> {
-        let signature = match key.sign(&tx.to_string()) {
-            Ok(sig) => sig,
-            Err(_) => return Err(TxBuilderError::KeyError(7000)),
+    /// Add a nonce to each stream's input, keeping the nonce already there from an earlier build
+    #[cfg(feature = "openssl-keys")]
+    fn input_nonce(&mut self, key: &str) -> TxBuilderResult<String> {
+        let now = self.now();
+
+        let input = match self.packet_data.get_mut("input") {
+            Some(input) => input,
+            None => return Err(TxBuilderError::MissingField("input")),
+        };
+
+        let streams: Vec<&mut serde_json::Map<String, Value>> = match input {
+            Value::Object(input) => input
+                .values_mut()
+                .filter_map(Value::as_object_mut)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        if streams.is_empty() {
+            return Err(TxBuilderError::InvalidPath(key.to_string()));
+        }
+
+        let existing = streams[0]
+            .get(key)
+            .and_then(Value::as_str)
+            .map(String::from);
+
+        let nonce = match existing {
+            Some(nonce)
+                if streams
+                    .iter()
+                    .all(|stream| stream.get(key) == Some(&json!(nonce))) =>
+            {
+                nonce
+            }
+            _ => nonce::ulid(now)?,
         };
 
-        Ok(signature)
+        for stream in streams {
+            stream.insert(key.to_string(), json!(nonce));
+        }
+
+        Ok(nonce)
     }
 
-    /// Sign data using RSA
-    fn sign_rsa(tx: &str, key: RSA) -> TxBuilderResult<String> {
-        let signature = match key.sign(&tx.to_string()) {
-            Ok(sig) => sig,
-            Err(_) => return Err(TxBuilderError::KeyError(7001)),
+    /// Generate the nonce if one is wanted and not already set
+    fn generate_nonce(&mut self) -> TxBuilderResult<()> {
+        let key = match &self.nonce_field {
+            Some(key) if !self.packet_fields.contains_key(key) => key.clone(),
+            _ => return Ok(()),
         };
 
-        Ok(signature)
+        let nonce = nonce::ulid(self.now())?;
+        self.packet_fields.insert(key, Value::from(nonce));
+
+        Ok(())
     }
 
-    /// Get the keys public PEM string
-    fn get_pem(key: Key) -> TxBuilderResult<String> {
-        let pkcs8pem = match key {
-            Key::Rsa(key) => key.get_pem(),
-            Key::Ec(key) => key.get_pem(),
+    /// Collect the packet data, contract, namespace and input are all required
+    fn body(&self) -> TxBuilderResult<TransactionBody<'_>> {
+        let (contract, namespace) = self.identifiers()?;
+
+        let input = match self.packet_data.get("input") {
+            Some(input) => input,
+            None => return Err(TxBuilderError::MissingField("input")),
+        };
+
+        self.check_entry()?;
+
+        let mut body = TransactionBody::new(contract, namespace, input);
+        body.extra(&self.packet_fields);
+
+        // Add the optional data
+        for &key in &["entry", "output", "readonly"] {
+            if let Some(data) = self.packet_data.get(key) {
+                body.add(key, data);
+            }
+        }
+
+        Ok(body)
+    }
+
+    /// Get the contract and namespace, checking they are valid identifiers
+    fn identifiers(&self) -> TxBuilderResult<(&Value, &Value)> {
+        let contract = match self.packet_data.get("contract") {
+            Some(contract) => contract,
+            None => return Err(TxBuilderError::MissingField("contract")),
+        };
+
+        let namespace = match self.packet_data.get("namespace") {
+            Some(namespace) => namespace,
+            None => return Err(TxBuilderError::MissingField("namespace")),
+        };
+
+        validate_identifier("contract", contract.as_str().unwrap_or_default())?;
+        validate_identifier("namespace", namespace.as_str().unwrap_or_default())?;
+
+        Ok((contract, namespace))
+    }
+
+    /// Check the stream IDs of the signees, selfsigned transactions are signed by key name
+    fn check_signees(&self, signees: &Signees) -> TxBuilderResult<()> {
+        if self.tx_data.contains_key("selfsign") {
+            return Ok(());
+        }
+
+        signees.check_stream_ids(self.strict_stream_ids)
+    }
+
+    /// Check the entry is allowed, if the allowed entries have been set
+    fn check_entry(&self) -> TxBuilderResult<()> {
+        let (allowed, entry) = match (&self.allowed_entries, self.packet_data.get("entry")) {
+            (Some(allowed), Some(entry)) => (allowed, entry.as_str().unwrap_or_default()),
+            _ => return Ok(()),
+        };
+
+        if !allowed.contains(entry) {
+            return Err(TxBuilderError::UnknownEntry(entry.to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// # Envelope values
+    ///
+    /// The envelope fields other than `$tx` and `$sigs`: the territoriality, selfsign and
+    /// broadcast flags and any fields set with [`envelope_field`](#method.envelope_field).
+    fn envelope_values(&self) -> BTreeMap<String, Value> {
+        let mut values = self.envelope_fields.clone();
+
+        for &e in &["territoriality", "selfsign", "broadcast"] {
+            if let Some(data) = self.tx_data.get(e) {
+                values.insert(format!("${}", e), data.clone());
+            }
+        }
+
+        values
+    }
+
+    /// Sign the packet and wrap it in the envelope, returning the transaction and its
+    /// signatures. The builder is left as it is, the caller keeps the packet and signatures
+    /// together once every signature and the size check succeeded.
+    fn sign_packet(&self, packet: &RawValue, signees: &Signees) -> TxBuilderResult<(String, Sigs)> {
+        #[cfg(feature = "openssl-keys")]
+        self.emit_hashes(packet.get(), signees);
+
+        let mut sigs = Sigs::new();
+
+        for signee in signees.iter() {
+            let signature =
+                TransactionBuilder::sign_internal(packet.get(), signee, &self.sign_options)?;
+            self.emit_signature(signee, &signature);
+            add_sig(&mut sigs, signee, signature);
+        }
+
+        let tx = self.envelope(packet, &sigs)?;
+
+        if let Some(max) = self.max_size {
+            if tx.len() > max {
+                return Err(TxBuilderError::SizeExceeded {
+                    size: tx.len(),
+                    max,
+                });
+            }
+        }
+
+        self.emit(TxEvent::EnvelopeFinalized { tx: &tx });
+
+        Ok((tx, sigs))
+    }
+
+    /// Clear the packet and signatures of a previous build after a failed build, they no
+    /// longer match the transaction data
+    fn discard_build(&mut self, error: TxBuilderError) -> TxBuilderError {
+        self.streamed = false;
+        self.packet = None;
+        self.sigs.clear();
+
+        error
+    }
+
+    /// Error for calls that need the packet from a build
+    fn not_built() -> TxBuilderError {
+        TxBuilderError::InvalidState {
+            expected: TxState::Built,
+            actual: TxState::Draft,
+        }
+    }
+
+    /// # Envelope
+    ///
+    /// Wrap the packet with the signatures and the other envelope fields, returning the
+    /// transaction string.
+    fn envelope(&self, packet: &RawValue, sigs: &Sigs) -> TxBuilderResult<String> {
+        let envelope = TransactionEnvelope {
+            broadcast: self.tx_data.get("broadcast"),
+            selfsign: self.tx_data.get("selfsign"),
+            sigs,
+            territoriality: self.tx_data.get("territoriality"),
+            tx: packet,
+            extra: &self.envelope_fields,
         };
 
+        envelope.build()
+    }
+
+    /// Sign the data with the signees key, using its own options if it has them
+    pub(super) fn sign_internal(
+        data: &str,
+        signee: &Signee,
+        options: &SignOptions,
+    ) -> TxBuilderResult<String> {
+        let options = signee.options.as_ref().unwrap_or(options);
+
+        signee.key.sign_stream(data, &signee.streamid, options)
+    }
+
+    /// Get the keys public PEM string
+    pub(crate) fn get_pem(key: &Key) -> TxBuilderResult<String> {
+        match key {
+            #[cfg(feature = "openssl-keys")]
+            Key::Rsa(rsa) => TransactionBuilder::public_pem(key, rsa.get_pem()),
+            #[cfg(feature = "openssl-keys")]
+            Key::Ec(ec) => TransactionBuilder::public_pem(key, ec.get_pem()),
+            Key::External(signer) => signer.public_pem(),
+        }
+    }
+
+    /// Take the public PEM from the keys PEM pair
+    #[cfg(feature = "openssl-keys")]
+    fn public_pem(
+        key: &Key,
+        pkcs8pem: activeledger::key::KeyResult<activeledger::key::Pkcs8pem>,
+    ) -> TxBuilderResult<String> {
         match pkcs8pem {
             Ok(pem) => Ok(pem.public),
-            Err(_) => Err(TxBuilderError::KeyError(7002)),
+            Err(source) => Err(TxBuilderError::PemError {
+                key_name: key.name().to_string(),
+                source,
+            }),
+        }
+    }
+}
+
+/// Merge source into target, recursing into objects present in both. Any other value already
+/// in target must be equal to the one in source.
+fn merge_json(target: &mut Value, source: Value, path: &str) -> TxBuilderResult<()> {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                let path = format!("{}.{}", path, key);
+
+                match target.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value, &path)?,
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+
+            Ok(())
         }
+        (target, source) if *target == source => Ok(()),
+        _ => Err(TxBuilderError::MergeConflict(path.to_string())),
     }
 }
@@ -0,0 +1,235 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Built transaction
+//!
+//! A built transaction with its packet and signatures, so they can be used without parsing the
+//! transaction string again. Client side metadata that is not part of the signed payload is
+//! kept with it, so it can be queued and retried without a separate lookup table.
+
+// STD
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+// External imports
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+// Internal imports
+use super::body::{Sig, Sigs};
+use crate::error::{TxBuilderError, TxBuilderResult};
+#[cfg(feature = "openssl-keys")]
+use crate::HashAlgorithm;
+
+/// # Built transaction
+///
+/// Returned by
+/// [`TransactionBuilder::build_transaction`](struct.TransactionBuilder.html#method.build_transaction).
+/// The metadata is never sent to the ledger, submit the transaction string on its own.
+///
+/// Serializes as `{"tx": "...", "meta": {...}}`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "StoredTransaction", into = "StoredTransaction")]
+pub struct BuiltTransaction {
+    tx: String,
+    /// Metadata set with [`TransactionBuilder::meta`](struct.TransactionBuilder.html#method.meta)
+    pub meta: BTreeMap<String, Value>,
+    packet: String,
+    sigs: Sigs,
+}
+
+/// A signature in a built transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamSignature<'a> {
+    pub streamid: &'a str,
+    /// The key reference, when the stream is signed by several keys
+    pub key_ref: Option<&'a str>,
+    pub signature: &'a str,
+}
+
+/// The serialized form of a built transaction
+#[derive(Serialize, Deserialize)]
+struct StoredTransaction {
+    tx: String,
+    #[serde(default)]
+    meta: BTreeMap<String, Value>,
+}
+
+/// The parts of the transaction string held by a built transaction
+#[derive(Deserialize)]
+struct Parts {
+    #[serde(rename = "$tx")]
+    packet: Box<RawValue>,
+    #[serde(rename = "$sigs", default)]
+    sigs: Sigs,
+}
+
+impl BuiltTransaction {
+    /// # Parse
+    ///
+    /// Parse a transaction string, for example one read back from a queue.
+    pub fn parse(tx: &str) -> TxBuilderResult<BuiltTransaction> {
+        let parts: Parts = match serde_json::from_str(tx) {
+            Ok(parts) => parts,
+            Err(e) => return Err(TxBuilderError::TransactionParseError(e)),
+        };
+
+        Ok(BuiltTransaction::new(
+            tx.to_string(),
+            parts.packet.get().to_string(),
+            parts.sigs,
+            BTreeMap::new(),
+        ))
+    }
+
+    /// # Transaction
+    ///
+    /// Get the signed transaction string, to submit to the ledger.
+    pub fn tx(&self) -> &str {
+        &self.tx
+    }
+
+    /// # JSON
+    ///
+    /// Get the transaction as a serde_json Value.
+    pub fn json(&self) -> TxBuilderResult<Value> {
+        match serde_json::from_str(&self.tx) {
+            Ok(json) => Ok(json),
+            Err(_) => Err(TxBuilderError::TxBuildError(5011)),
+        }
+    }
+
+    /// # Packet
+    ///
+    /// Get the $tx packet exactly as it was signed.
+    pub fn packet(&self) -> &str {
+        &self.packet
+    }
+
+    /// # Packet hash
+    ///
+    /// Get the SHA-256 hash of the signed packet, to keep as a receipt of what was signed.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    #[cfg(feature = "openssl-keys")]
+    pub fn packet_hash(&self) -> Vec<u8> {
+        self.packet_hash_with(HashAlgorithm::Sha256)
+    }
+
+    /// # Packet hash with
+    ///
+    /// Get the hash of the signed packet using the given algorithm.
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    #[cfg(feature = "openssl-keys")]
+    pub fn packet_hash_with(&self, hash: HashAlgorithm) -> Vec<u8> {
+        hash.digest(self.packet.as_bytes())
+    }
+
+    /// # Stream IDs
+    ///
+    /// Get the IDs of the streams that signed the transaction.
+    pub fn stream_ids(&self) -> Vec<&str> {
+        self.sigs.keys().map(String::as_str).collect()
+    }
+
+    /// # Signatures
+    ///
+    /// Get every signature in the transaction, with the stream and key reference it is for.
+    pub fn signatures(&self) -> Vec<StreamSignature<'_>> {
+        let mut signatures = Vec::new();
+
+        for (streamid, sig) in &self.sigs {
+            match sig {
+                Sig::Single(signature) => signatures.push(StreamSignature {
+                    streamid,
+                    key_ref: None,
+                    signature,
+                }),
+                Sig::Multi(map) => {
+                    for (key_ref, signature) in map {
+                        signatures.push(StreamSignature {
+                            streamid,
+                            key_ref: Some(key_ref),
+                            signature,
+                        });
+                    }
+                }
+            }
+        }
+
+        signatures
+    }
+
+    /// # Get meta
+    ///
+    /// Get the metadata value for the key.
+    pub fn get_meta(&self, key: &str) -> Option<&Value> {
+        self.meta.get(key)
+    }
+}
+
+// Private functions
+impl BuiltTransaction {
+    pub(crate) fn new(
+        tx: String,
+        packet: String,
+        sigs: Sigs,
+        meta: BTreeMap<String, Value>,
+    ) -> BuiltTransaction {
+        BuiltTransaction {
+            tx,
+            meta,
+            packet,
+            sigs,
+        }
+    }
+}
+
+impl fmt::Display for BuiltTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.tx)
+    }
+}
+
+impl TryFrom<StoredTransaction> for BuiltTransaction {
+    type Error = TxBuilderError;
+
+    fn try_from(stored: StoredTransaction) -> TxBuilderResult<BuiltTransaction> {
+        let mut built = BuiltTransaction::parse(&stored.tx)?;
+        built.meta = stored.meta;
+
+        Ok(built)
+    }
+}
+
+impl From<BuiltTransaction> for StoredTransaction {
+    fn from(built: BuiltTransaction) -> StoredTransaction {
+        StoredTransaction {
+            tx: built.tx,
+            meta: built.meta,
+        }
+    }
+}
@@ -0,0 +1,218 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Batch chunks
+//!
+//! Split a batch of built transactions into chunks that fit the limits of the node they are
+//! submitted to.
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Bytes added by the brackets of the JSON array a chunk is submitted as
+const ARRAY_OVERHEAD: usize = 2;
+
+/// # Batch chunk
+///
+/// A group of transactions from a batch, created by [`BatchChunker`].
+///
+/// [`BatchChunker`]: struct.BatchChunker.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchChunk {
+    txs: Vec<String>,
+    bytes: usize,
+}
+
+/// # Batch chunker
+///
+/// Splits transactions into chunks holding at most a number of transactions and at most a
+/// number of bytes, counted as the length of the chunk submitted as a JSON array: the
+/// transaction strings with the brackets and the commas between them. Transactions keep their
+/// order. With no limits set every transaction is in one chunk.
+///
+/// ```
+/// # use active_tx::BatchChunker;
+/// let txs = vec![String::from("tx1"), String::from("tx2"), String::from("tx3")];
+///
+/// let chunks = BatchChunker::new().max_per_chunk(2).chunk(txs).unwrap();
+///
+/// assert_eq!(chunks.len(), 2);
+/// assert_eq!(chunks[0].transactions(), ["tx1", "tx2"]);
+/// assert_eq!(chunks[1].bytes(), "[tx3]".len());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BatchChunker {
+    max_per_chunk: Option<usize>,
+    max_bytes_per_chunk: Option<usize>,
+}
+
+/// Iterator over the chunks of a batch, created by [`BatchChunker::chunks`].
+///
+/// [`BatchChunker::chunks`]: struct.BatchChunker.html#method.chunks
+#[derive(Debug)]
+pub struct BatchChunks<I: Iterator<Item = String>> {
+    chunker: BatchChunker,
+    txs: std::iter::Peekable<I>,
+}
+
+impl BatchChunk {
+    /// # Transactions
+    ///
+    /// Get the transactions in the chunk.
+    pub fn transactions(&self) -> &[String] {
+        &self.txs
+    }
+
+    /// # Into transactions
+    ///
+    /// Take the transactions from the chunk.
+    pub fn into_transactions(self) -> Vec<String> {
+        self.txs
+    }
+
+    /// # Length
+    ///
+    /// Get the number of transactions in the chunk.
+    pub fn len(&self) -> usize {
+        self.txs.len()
+    }
+
+    /// # Is empty
+    ///
+    /// Whether the chunk has no transactions.
+    pub fn is_empty(&self) -> bool {
+        self.txs.is_empty()
+    }
+
+    /// # Bytes
+    ///
+    /// Get the length of the chunk as a JSON array of its transactions.
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+impl BatchChunker {
+    /// # New
+    ///
+    /// Create a chunker with no limits.
+    pub fn new() -> BatchChunker {
+        BatchChunker::default()
+    }
+
+    /// # Max per chunk
+    ///
+    /// Set the most transactions in a chunk. A limit of 0 is treated as 1.
+    pub fn max_per_chunk(&mut self, max: usize) -> &mut Self {
+        self.max_per_chunk = Some(max.max(1));
+
+        self
+    }
+
+    /// # Max bytes per chunk
+    ///
+    /// Set the most bytes in a chunk. A single transaction larger than this can't be chunked
+    /// and gives an error.
+    pub fn max_bytes_per_chunk(&mut self, max: usize) -> &mut Self {
+        self.max_bytes_per_chunk = Some(max);
+
+        self
+    }
+
+    /// # Chunk
+    ///
+    /// Split the transactions into chunks.
+    ///
+    /// An error is returned if a transaction is larger than the byte limit.
+    pub fn chunk<I: IntoIterator<Item = String>>(
+        &self,
+        txs: I,
+    ) -> TxBuilderResult<Vec<BatchChunk>> {
+        self.chunks(txs).collect()
+    }
+
+    /// # Chunks
+    ///
+    /// Split the transactions into chunks lazily, so transactions from an iterator are only
+    /// read as each chunk is needed. A [`TxStream`](struct.TxStream.html) yields results, stop
+    /// at or handle its build errors before chunking, for example with `map_while(Result::ok)`.
+    ///
+    /// An error is returned in place of a chunk if the next transaction is larger than the byte
+    /// limit, it is skipped and chunking carries on with the transaction after it.
+    pub fn chunks<I: IntoIterator<Item = String>>(&self, txs: I) -> BatchChunks<I::IntoIter> {
+        BatchChunks {
+            chunker: *self,
+            txs: txs.into_iter().peekable(),
+        }
+    }
+}
+
+impl<I: Iterator<Item = String>> Iterator for BatchChunks<I> {
+    type Item = TxBuilderResult<BatchChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut chunk = BatchChunk {
+            txs: Vec::new(),
+            bytes: ARRAY_OVERHEAD,
+        };
+
+        while let Some(tx) = self.txs.peek() {
+            if let Some(max) = self.chunker.max_per_chunk {
+                if chunk.len() >= max {
+                    break;
+                }
+            }
+
+            // A comma separates the transaction from the one before it
+            let separator = if chunk.is_empty() { 0 } else { 1 };
+
+            if let Some(max) = self.chunker.max_bytes_per_chunk {
+                if tx.len() + ARRAY_OVERHEAD > max {
+                    if !chunk.is_empty() {
+                        break;
+                    }
+
+                    let size = tx.len() + ARRAY_OVERHEAD;
+                    self.txs.next();
+
+                    return Some(Err(TxBuilderError::SizeExceeded { size, max }));
+                }
+
+                if chunk.bytes + separator + tx.len() > max {
+                    break;
+                }
+            }
+
+            if let Some(tx) = self.txs.next() {
+                chunk.bytes += separator + tx.len();
+                chunk.txs.push(tx);
+            }
+        }
+
+        if chunk.is_empty() {
+            None
+        } else {
+            Some(Ok(chunk))
+        }
+    }
+}
@@ -0,0 +1,140 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Contract client
+//!
+//! Holds the namespace, contract and signees used to call one contract, so they are set up once
+//! instead of at every call site.
+
+// STD
+use std::collections::BTreeSet;
+
+// Internal imports
+use super::entry::Entry;
+use crate::error::TxBuilderResult;
+use crate::packet_builder::Input;
+use crate::{Signees, TransactionBuilder};
+
+/// # Contract client
+///
+/// Builds transactions for a single contract, signed by a default set of signees.
+///
+/// ```
+/// # #[cfg(feature = "openssl-keys")] {
+/// # use active_tx::{ContractClient, Key, PacketBuilder, packet_data, signees};
+/// # use activeledger::key::EllipticCurve;
+/// let key = Key::Ec(EllipticCurve::new("account").unwrap());
+///
+/// let mut token = ContractClient::new("namespace", "token", signees![{"account" => key}]);
+/// token.allowed_entries(vec!["mint", "transfer"]);
+///
+/// let input = PacketBuilder::new(packet_data!({"account": {"amount": 10}}))
+///     .build()
+///     .unwrap();
+///
+/// let tx = token.call("transfer", input).unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ContractClient {
+    namespace: String,
+    contract: String,
+    signees: Signees,
+    allowed_entries: Option<BTreeSet<String>>,
+}
+
+impl ContractClient {
+    /// # New
+    ///
+    /// Create a client for the contract, signing with the given signees.
+    pub fn new(namespace: &str, contract: &str, signees: Signees) -> ContractClient {
+        ContractClient {
+            namespace: namespace.to_string(),
+            contract: contract.to_string(),
+            signees,
+            allowed_entries: None,
+        }
+    }
+
+    /// # Allowed entries
+    ///
+    /// Only allow calls to the given entries, see
+    /// [`TransactionBuilder::allowed_entries`](struct.TransactionBuilder.html#method.allowed_entries).
+    pub fn allowed_entries<I, E>(&mut self, entries: I) -> &mut Self
+    where
+        I: IntoIterator<Item = E>,
+        E: Entry,
+    {
+        let entries = entries
+            .into_iter()
+            .map(|entry| entry.entry_name().to_string())
+            .collect();
+
+        self.allowed_entries = Some(entries);
+
+        self
+    }
+
+    /// # Signees
+    ///
+    /// Get the default signees.
+    pub fn signees(&self) -> &Signees {
+        &self.signees
+    }
+
+    /// # Builder
+    ///
+    /// Get a transaction builder for the entry, with the namespace, contract and allowed entries
+    /// set, so output, readonly and other data can be added before building.
+    pub fn builder<E: Entry>(&self, entry: E) -> TransactionBuilder {
+        let mut builder = TransactionBuilder::new(&self.namespace, &self.contract);
+
+        if let Some(allowed) = &self.allowed_entries {
+            builder.allowed_entries(allowed);
+        }
+
+        builder.entry(entry);
+
+        builder
+    }
+
+    /// # Call
+    ///
+    /// Build a transaction calling the entry with the input, signed by the default signees.
+    pub fn call<E: Entry>(&self, entry: E, input: Input) -> TxBuilderResult<String> {
+        self.call_with(entry, input, self.signees.clone())
+    }
+
+    /// # Call with
+    ///
+    /// Build a transaction calling the entry with the input, signed by the given signees instead
+    /// of the default ones.
+    pub fn call_with<E: Entry>(
+        &self,
+        entry: E,
+        input: Input,
+        signees: Signees,
+    ) -> TxBuilderResult<String> {
+        self.builder(entry).input(input)?.build(signees)
+    }
+}
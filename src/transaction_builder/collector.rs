@@ -0,0 +1,227 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Signature collection
+//!
+//! Collect signatures for a packet over time, for m-of-n approval flows.
+
+// STD
+use std::collections::BTreeMap;
+
+// External imports
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+// Internal imports
+use super::body::{add_sig, Sig, Sigs, TransactionEnvelope};
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::{SignOptions, Signees, TransactionBuilder};
+
+/// # Multi-signature collector
+///
+/// Holds an unsigned packet and collects signatures from the required stream IDs one at a time.
+/// The final transaction is only released once the threshold of required streams have signed.
+///
+/// Collectors are created from a configured [`TransactionBuilder`] using its
+/// [`multisig`](struct.TransactionBuilder.html#method.multisig) method.
+///
+/// ```
+/// # #[cfg(feature = "openssl-keys")] {
+/// # use active_tx::{PacketBuilder, TransactionBuilder, Key, packet_data, signees};
+/// # use activeledger::key::EllipticCurve;
+/// let alice = Key::Ec(EllipticCurve::new("alice").unwrap());
+/// let bob = Key::Ec(EllipticCurve::new("bob").unwrap());
+///
+/// let input = PacketBuilder::new(packet_data!({"alice": {}, "bob": {}, "carol": {}}))
+///     .build()
+///     .unwrap();
+///
+/// let mut collector = TransactionBuilder::new("namespace", "contract")
+///     .input(input)
+///     .unwrap()
+///     .multisig(&["alice", "bob", "carol"], 2)
+///     .unwrap();
+///
+/// collector.sign(signees![{"alice" => alice}]).unwrap();
+/// assert!(collector.finish().is_err());
+///
+/// // The packet can also be sent elsewhere to be signed
+/// let packet = collector.packet().to_string();
+/// let signature = bob.sign(&packet, &Default::default()).unwrap();
+/// collector.attach_signature("bob", &signature).unwrap();
+///
+/// let tx = collector.finish().unwrap();
+/// # }
+/// ```
+///
+/// [`TransactionBuilder`]: struct.TransactionBuilder.html
+#[derive(Debug, Clone)]
+pub struct MultiSigCollector {
+    packet: Box<RawValue>,
+    // Envelope fields other than $tx and $sigs
+    envelope: BTreeMap<String, Value>,
+    sign_options: SignOptions,
+
+    required: Vec<String>,
+    threshold: usize,
+    sigs: Sigs,
+}
+
+impl MultiSigCollector {
+    /// Create a collector for the built packet
+    pub(super) fn new(
+        packet: Box<RawValue>,
+        envelope: BTreeMap<String, Value>,
+        sign_options: SignOptions,
+        required: &[&str],
+        threshold: usize,
+    ) -> TxBuilderResult<MultiSigCollector> {
+        if threshold == 0 || threshold > required.len() {
+            return Err(TxBuilderError::TxBuildError(5017));
+        }
+
+        Ok(MultiSigCollector {
+            packet,
+            envelope,
+            sign_options,
+            required: required.iter().map(|s| s.to_string()).collect(),
+            threshold,
+            sigs: Sigs::new(),
+        })
+    }
+
+    /// # Packet
+    ///
+    /// Get the packet string that signers must sign. When the sign options set a context,
+    /// signers outside this crate sign [`SignOptions::signed_data`] of the packet instead.
+    ///
+    /// [`SignOptions::signed_data`]: struct.SignOptions.html#method.signed_data
+    pub fn packet(&self) -> &str {
+        self.packet.get()
+    }
+
+    /// # Sign
+    ///
+    /// Sign the packet with the given signees.
+    /// Every signee must be for one of the required stream IDs.
+    pub fn sign(&mut self, signees: Signees) -> TxBuilderResult<&mut Self> {
+        for signee in signees.iter() {
+            self.check_required(&signee.streamid)?;
+
+            let signature =
+                TransactionBuilder::sign_internal(self.packet.get(), signee, &self.sign_options)?;
+            add_sig(&mut self.sigs, signee, signature);
+        }
+
+        Ok(self)
+    }
+
+    /// # Attach signature
+    ///
+    /// Add a signature that was made elsewhere for one of the required stream IDs.
+    /// The signature is not checked.
+    pub fn attach_signature(
+        &mut self,
+        streamid: &str,
+        signature: &str,
+    ) -> TxBuilderResult<&mut Self> {
+        self.check_required(streamid)?;
+
+        self.sigs
+            .insert(streamid.to_string(), Sig::Single(signature.to_string()));
+
+        Ok(self)
+    }
+
+    /// # Signed
+    ///
+    /// Get the number of required stream IDs that have signed.
+    pub fn signed(&self) -> usize {
+        self.required
+            .iter()
+            .filter(|s| self.sigs.contains_key(*s))
+            .count()
+    }
+
+    /// # Missing
+    ///
+    /// Get the required stream IDs that have not signed yet.
+    pub fn missing(&self) -> Vec<&str> {
+        self.required
+            .iter()
+            .filter(|s| !self.sigs.contains_key(*s))
+            .map(|s| s.as_str())
+            .collect()
+    }
+
+    /// # Threshold
+    ///
+    /// Get the number of signatures required.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// # Is complete
+    ///
+    /// Check whether the threshold has been met.
+    pub fn is_complete(&self) -> bool {
+        self.signed() >= self.threshold
+    }
+
+    /// # Finish
+    ///
+    /// Get the signed transaction string, once the threshold has been met.
+    pub fn finish(&self) -> TxBuilderResult<String> {
+        let signed = self.signed();
+
+        if signed < self.threshold {
+            return Err(TxBuilderError::ThresholdNotMet {
+                signed,
+                threshold: self.threshold,
+            });
+        }
+
+        let envelope = TransactionEnvelope {
+            broadcast: None,
+            selfsign: None,
+            sigs: &self.sigs,
+            territoriality: None,
+            tx: self.packet.as_ref(),
+            extra: &self.envelope,
+        };
+
+        envelope.build()
+    }
+}
+
+// Private functions
+impl MultiSigCollector {
+    /// Only the required stream IDs may sign
+    fn check_required(&self, streamid: &str) -> TxBuilderResult<()> {
+        if self.required.iter().any(|s| s == streamid) {
+            Ok(())
+        } else {
+            Err(TxBuilderError::UnexpectedSigner(streamid.to_string()))
+        }
+    }
+}
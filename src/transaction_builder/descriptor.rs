@@ -0,0 +1,170 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Transaction descriptors
+//!
+//! Declare a transaction as JSON, naming the keys that sign it rather than holding them, so
+//! transactions can be generated from configuration.
+
+// External imports
+use serde::Deserialize;
+use serde_json::Value;
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::{KeyResolver, PacketBuilder, SigneeSpec, Signees, TransactionBuilder};
+
+/// The JSON form of a descriptor
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TxDescriptor {
+    namespace: String,
+    contract: String,
+    entry: Option<String>,
+    i: Value,
+    o: Option<Value>,
+    r: Option<Value>,
+    #[serde(default)]
+    flags: Flags,
+    #[serde(default)]
+    signees: Vec<DescriptorSignee>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct Flags {
+    selfsign: bool,
+    broadcast: bool,
+    territoriality: Option<String>,
+}
+
+/// A key name signing the stream of the same name, or a full spec
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DescriptorSignee {
+    Name(String),
+    Spec(SigneeSpec),
+}
+
+impl TransactionBuilder {
+    /// # From descriptor
+    ///
+    /// Create a builder, and the signees to build it with, from a JSON descriptor:
+    ///
+    /// ```json
+    /// {
+    ///     "namespace": "[namespace]",
+    ///     "contract": "[contract]",
+    ///     "entry": "[entry]",
+    ///     "i": {"[streamid]": {}},
+    ///     "o": {"[streamid]": {}},
+    ///     "r": {"[label]": "[streamid]"},
+    ///     "flags": {"selfsign": false, "broadcast": false, "territoriality": "[node]"},
+    ///     "signees": ["[key name]", {"streamid": "[streamid]", "key_name": "[key name]"}]
+    /// }
+    /// ```
+    ///
+    /// Only the namespace, contract and input are required. A signee given as a key name signs
+    /// the stream of the same name, otherwise it is a [`SigneeSpec`]. Each key is looked up
+    /// using the resolver.
+    ///
+    /// An error is returned if the descriptor is not in this form, the packet data can't be
+    /// built or a key can't be resolved.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, TransactionBuilder};
+    /// # use activeledger::key::EllipticCurve;
+    /// # use serde_json::json;
+    /// let resolver = |name: &str| Ok(Key::Ec(EllipticCurve::new(name).unwrap()));
+    ///
+    /// let descriptor = json!({
+    ///     "namespace": "ns",
+    ///     "contract": "contract",
+    ///     "entry": "transfer",
+    ///     "i": {"alice": {"amount": 10}},
+    ///     "o": {"bob": {}},
+    ///     "signees": ["alice"]
+    /// });
+    ///
+    /// let (mut tx_builder, signees) =
+    ///     TransactionBuilder::from_descriptor(descriptor, &resolver).unwrap();
+    ///
+    /// let tx = tx_builder.build(signees).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`SigneeSpec`]: struct.SigneeSpec.html
+    pub fn from_descriptor(
+        descriptor: Value,
+        resolver: &dyn KeyResolver,
+    ) -> TxBuilderResult<(TransactionBuilder, Signees)> {
+        let descriptor: TxDescriptor = match serde_json::from_value(descriptor) {
+            Ok(descriptor) => descriptor,
+            Err(e) => return Err(TxBuilderError::DescriptorError(e)),
+        };
+
+        let mut tx_builder = TransactionBuilder::new(&descriptor.namespace, &descriptor.contract);
+
+        if let Some(entry) = descriptor.entry {
+            tx_builder.entry(entry);
+        }
+
+        tx_builder.input(PacketBuilder::new_json(descriptor.i).build()?)?;
+
+        if let Some(output) = descriptor.o {
+            tx_builder.output(PacketBuilder::new_json(output).build()?)?;
+        }
+
+        if let Some(readonly) = descriptor.r {
+            tx_builder.readonly(PacketBuilder::new_json(readonly).build()?)?;
+        }
+
+        let flags = descriptor.flags;
+        tx_builder
+            .set_selfsign(flags.selfsign)
+            .set_broadcast(flags.broadcast);
+
+        if let Some(territoriality) = flags.territoriality {
+            tx_builder.territoriality(&territoriality);
+        }
+
+        let specs: Vec<SigneeSpec> = descriptor
+            .signees
+            .into_iter()
+            .map(|signee| match signee {
+                DescriptorSignee::Name(name) => SigneeSpec {
+                    streamid: name.clone(),
+                    key_name: name,
+                    key_type: None,
+                    pem_path_or_id: None,
+                },
+                DescriptorSignee::Spec(spec) => spec,
+            })
+            .collect();
+
+        let signees = Signees::from_specs(&specs, resolver)?;
+
+        Ok((tx_builder, signees))
+    }
+}
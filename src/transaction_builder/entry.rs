@@ -0,0 +1,74 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Entry
+//!
+//! Contract entry points can be given as strings or as a caller defined type, such as an enum
+//! of the entries a contract provides, so a typo can't reach the ledger.
+
+/// # Entry
+///
+/// A contract entry point, passed to
+/// [`TransactionBuilder::entry`](struct.TransactionBuilder.html#method.entry).
+///
+/// ```
+/// # use active_tx::{Entry, TransactionBuilder};
+/// enum TokenEntry {
+///     Mint,
+///     Transfer,
+/// }
+///
+/// impl Entry for TokenEntry {
+///     fn entry_name(&self) -> &str {
+///         match self {
+///             TokenEntry::Mint => "mint",
+///             TokenEntry::Transfer => "transfer",
+///         }
+///     }
+/// }
+///
+/// let mut tx_builder = TransactionBuilder::new("namespace", "token");
+/// tx_builder.entry(TokenEntry::Transfer);
+/// ```
+pub trait Entry {
+    /// The name of the entry point in the contract
+    fn entry_name(&self) -> &str;
+}
+
+impl Entry for str {
+    fn entry_name(&self) -> &str {
+        self
+    }
+}
+
+impl Entry for String {
+    fn entry_name(&self) -> &str {
+        self
+    }
+}
+
+impl<T: Entry + ?Sized> Entry for &T {
+    fn entry_name(&self) -> &str {
+        (**self).entry_name()
+    }
+}
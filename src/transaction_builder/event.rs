@@ -0,0 +1,57 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Events
+//!
+//! Events emitted by the transaction builder while it builds and signs, for audit logs that
+//! need to record exactly what each key signed.
+
+// Internal imports
+use crate::HashAlgorithm;
+
+/// # Transaction event
+///
+/// Passed to the callback set with
+/// [`TransactionBuilder::on_event`](struct.TransactionBuilder.html#method.on_event).
+#[derive(Debug, Clone, Copy)]
+pub enum TxEvent<'a> {
+    /// The packet was built, holding the exact string that is signed
+    PacketBuilt { packet: &'a str },
+    /// The packet was hashed for signing.
+    /// Emitted once for each hash algorithm used by the builders keys, external signers hash
+    /// the packet themselves.
+    PacketHashed {
+        hash: HashAlgorithm,
+        digest: &'a [u8],
+    },
+    /// A stream signed the packet.
+    /// The fingerprint identifies the key that signed, it is `None` when it can't be found.
+    Signed {
+        streamid: &'a str,
+        key_ref: Option<&'a str>,
+        signature: &'a str,
+        fingerprint: Option<&'a str>,
+    },
+    /// The transaction was built, holding the final transaction string
+    EnvelopeFinalized { tx: &'a str },
+}
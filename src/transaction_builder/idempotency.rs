@@ -0,0 +1,85 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Idempotency
+//!
+//! Helpers for delivering transactions at least once without applying them twice. A nonce in
+//! the input lets the contract recognise a transaction it has already seen, and a hash of the
+//! signed packet lets the client recognise one it has already sent.
+
+// STD
+use std::collections::HashSet;
+
+// Internal imports
+use super::built::BuiltTransaction;
+use crate::error::TxBuilderResult;
+
+/// # Idempotent transaction
+///
+/// Returned by
+/// [`TransactionBuilder::build_idempotent`](struct.TransactionBuilder.html#method.build_idempotent).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotentTx {
+    /// The signed transaction
+    pub tx: String,
+    /// The nonce added to each stream's input
+    pub nonce: String,
+    /// Hex SHA-256 hash of the signed packet, see [`is_replay`](fn.is_replay.html)
+    pub hash: String,
+}
+
+/// # Is replay
+///
+/// Check whether the transaction's signed packet has one of the hashes already seen, such as
+/// the hashes from [`IdempotentTx`] of transactions that have been delivered.
+///
+/// Requires the `openssl-keys` feature, which is enabled by default.
+///
+/// ```
+/// # use std::collections::HashSet;
+/// # use active_tx::{is_replay, packet_data, signees, Key, PacketBuilder, TransactionBuilder};
+/// # use activeledger::key::EllipticCurve;
+/// # let input = PacketBuilder::new(packet_data!({"id": {"amount": 1}})).build().unwrap();
+/// let key = Key::Ec(EllipticCurve::new("id").unwrap());
+///
+/// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+/// tx_builder.input(input).unwrap();
+///
+/// let built = tx_builder.build_idempotent(signees![{"id" => key}], "_nonce").unwrap();
+///
+/// let mut seen = HashSet::new();
+/// assert!(!is_replay(&built.tx, &seen).unwrap());
+///
+/// seen.insert(built.hash.clone());
+/// assert!(is_replay(&built.tx, &seen).unwrap());
+/// ```
+pub fn is_replay(tx: &str, seen_hashes: &HashSet<String>) -> TxBuilderResult<bool> {
+    let tx = BuiltTransaction::parse(tx)?;
+
+    Ok(seen_hashes.contains(&hex(&tx.packet_hash())))
+}
+
+/// Hex encode a packet hash
+pub(super) fn hex(hash: &[u8]) -> String {
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
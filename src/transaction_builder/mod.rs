@@ -28,16 +28,16 @@
 //! ## Example
 //!
 //! ```
+//! # #[cfg(feature = "openssl-keys")] {
 //! # use active_tx::{PacketBuilder, TransactionBuilder, Key, packet_data, signees};
 //! # use activeledger::key::EllipticCurve;
-//! # fn main() {
 //! let input = packet_data!({"[streamid]": {"input": "data"}});
 //!
 //! let input_data = PacketBuilder::new(input).build().unwrap();
 //!  
 //! let mut tx_builder = TransactionBuilder::new("namespace", "contract");
 //! #
-//! # let streamid = "";
+//! # let streamid = "streamid";
 //! # let key = Key::Ec(EllipticCurve::new("").unwrap());
 //! #
 //!
@@ -52,17 +52,59 @@
 //! ```
 //!
 
+#[cfg(feature = "openssl-keys")]
+mod access;
 mod body;
 mod builder;
+mod built;
+mod chunk;
+mod client;
+mod collector;
+mod descriptor;
+mod entry;
+mod event;
+#[cfg(feature = "openssl-keys")]
+mod idempotency;
+pub(crate) mod nonce;
+mod onboard;
+mod profile;
 mod signee;
+mod snapshot;
+mod status;
+#[cfg(feature = "openssl-keys")]
+mod stream;
+mod template;
+mod territoriality;
+mod tx_stream;
+#[cfg(feature = "openssl-keys")]
+mod verifier;
 
-pub use builder::{Key, KeyType, TransactionBuilder};
-pub use signee::Signees;
+#[cfg(feature = "openssl-keys")]
+pub use access::AccessTxBuilder;
+pub use builder::TransactionBuilder;
+pub use built::{BuiltTransaction, StreamSignature};
+pub use chunk::{BatchChunk, BatchChunker, BatchChunks};
+pub use client::ContractClient;
+pub use collector::MultiSigCollector;
+pub use entry::Entry;
+pub use event::TxEvent;
+#[cfg(feature = "openssl-keys")]
+pub use idempotency::{is_replay, IdempotentTx};
+pub use onboard::{BatchMode, OnboardTxBuilder};
+pub use profile::Profile;
+pub use signee::{Signee, Signees};
+pub use snapshot::TxSnapshot;
+pub use status::{TxState, TxStatus};
+pub use template::TxTemplate;
+pub use territoriality::{NodeReferences, Territoriality};
+pub use tx_stream::TxStream;
+#[cfg(feature = "openssl-keys")]
+pub use verifier::{signature_matches, Verifier, VerifierSet};
 
-#[cfg(test)]
+#[cfg(all(test, feature = "openssl-keys"))]
 mod tests {
     use crate::*;
-    use activeledger::key::EllipticCurve;
+    use activeledger::key::{EllipticCurve, RSA};
 
     #[test]
     fn tx_min() {
@@ -137,4 +179,2305 @@ mod tests {
 
         println!("\nOnboard generate\n{}", tx);
     }
+
+    #[test]
+    fn tx_onboard_json() {
+        let (key, built) =
+            TransactionBuilder::generate_onboard_tx_json(KeyType::RSA, "testkey").unwrap();
+
+        assert_eq!(built.stream_ids(), vec!["testkey"]);
+
+        let json = built.json().unwrap();
+        let identity = &json["$tx"]["$i"]["testkey"];
+
+        assert_eq!(identity["type"], "rsa");
+        assert_eq!(
+            identity["publicKey"],
+            TransactionBuilder::get_pem(&key).unwrap()
+        );
+        assert_eq!(json["$selfsign"], true);
+    }
+
+    #[test]
+    fn signees_manage() {
+        let key = Key::Ec(EllipticCurve::new("key").unwrap());
+        let key2 = Key::Ec(EllipticCurve::new("key2").unwrap());
+
+        let mut signees = signees![{"streamid" => key.clone()}, {"streamid2" => key2}];
+        assert_eq!(signees.len(), 2);
+        assert!(signees.contains("streamid2"));
+
+        // Duplicate stream ids overwrite in place
+        signees.add(key, "streamid2");
+        assert_eq!(signees.len(), 2);
+
+        let removed = signees.remove("streamid").unwrap();
+        assert_eq!(removed.streamid, "streamid");
+        assert!(!signees.contains("streamid"));
+        assert!(signees.remove("streamid").is_none());
+
+        let ids: Vec<&str> = signees.iter().map(|s| s.streamid.as_str()).collect();
+        assert_eq!(ids, vec!["streamid2"]);
+    }
+
+    #[test]
+    fn signees_shared_key() {
+        let key = std::sync::Arc::new(Key::Ec(EllipticCurve::new("test").unwrap()));
+
+        let mut signees = Signees::new();
+        signees.add(std::sync::Arc::clone(&key), "test");
+        signees.add_selfsign(std::sync::Arc::clone(&key));
+        let copy = signees.clone();
+
+        // Signees and their copies share the one key
+        assert_eq!(std::sync::Arc::strong_count(&key), 3);
+        assert_eq!(copy.len(), 1);
+
+        let input = PacketBuilder::new(packet_data!({"test": {"input": "data"}}))
+            .build()
+            .unwrap();
+
+        let tx = TransactionBuilder::new("namespace", "contract")
+            .input(input)
+            .unwrap()
+            .build(copy)
+            .unwrap();
+
+        assert!(tx.contains("$sigs"));
+    }
+
+    #[test]
+    fn thread_safe_types() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<Key>();
+        assert_send_sync::<Signee>();
+        assert_send_sync::<Signees>();
+        assert_send_sync::<TransactionBuilder>();
+        assert_send_sync::<TxTemplate>();
+        assert_send_sync::<OnboardTxBuilder>();
+        assert_send_sync::<TxBuilderError>();
+    }
+
+    #[test]
+    fn signees_from_pem() {
+        let pem = EllipticCurve::new("key")
+            .unwrap()
+            .get_pem()
+            .unwrap()
+            .private;
+
+        let path = std::env::temp_dir().join("active_tx_signees_from_pem.pem");
+        std::fs::write(&path, &pem).unwrap();
+
+        let mut signees = Signees::new();
+        signees.add_from_pem_file(&path, "streamid").unwrap();
+        signees
+            .add_from_pem_str(&pem, "streamid2", KeyType::EC)
+            .unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        let err = signees
+            .add_from_pem_str(&pem, "streamid3", KeyType::RSA)
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), 8002);
+
+        let input = PacketBuilder::new(packet_data!({"streamid": {"input": "data"}}))
+            .build()
+            .unwrap();
+
+        TransactionBuilder::new("namespace", "contract")
+            .input(input)
+            .unwrap()
+            .build(signees)
+            .unwrap();
+    }
+
+    #[test]
+    fn tx_territoriality_checked() {
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+
+        assert_eq!(
+            transaction_builder
+                .territoriality_checked(" node")
+                .err()
+                .unwrap()
+                .code(),
+            5009
+        );
+        transaction_builder.territoriality_checked("node").unwrap();
+
+        let mut nodes = NodeReferences::new();
+        nodes.add("node1", "127.0.0.1:5260");
+        transaction_builder.nodes(nodes);
+
+        assert_eq!(
+            transaction_builder
+                .territoriality_checked("node")
+                .err()
+                .unwrap()
+                .code(),
+            5010
+        );
+        transaction_builder.territoriality_checked("node1").unwrap();
+
+        let input = PacketBuilder::new(packet_data!({"input": "data"}))
+            .build()
+            .unwrap();
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .build(signees!())
+            .unwrap();
+
+        let tx = transaction_builder.get_json().unwrap();
+        assert_eq!(tx["$territoriality"], "127.0.0.1:5260");
+    }
+
+    #[test]
+    fn tx_macro() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+
+        let tx = tx! {
+            contract: "contract",
+            namespace: "namespace",
+            entry: "entry",
+            selfsign: true,
+            i: {"test": {"input": "data"}},
+            r: {"label": "streamid"},
+            sign: [{"test" => key}],
+        }
+        .unwrap();
+
+        let tx: serde_json::Value = serde_json::from_str(&tx).unwrap();
+        assert_eq!(tx["$tx"]["$namespace"], "namespace");
+        assert_eq!(tx["$tx"]["$entry"], "entry");
+        assert_eq!(tx["$tx"]["$i"]["test"]["input"], "data");
+        assert_eq!(tx["$tx"]["$r"]["label"], "streamid");
+        assert!(tx["$sigs"]["test"].is_string());
+
+        let err = tx! { namespace: "namespace", i: {"test": "data"} }
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), 5006);
+    }
+
+    #[test]
+    fn tx_template() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let output = PacketBuilder::new(packet_data!({"test": {"output": "data"}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .entry("entry")
+            .territoriality("node")
+            .output(output)
+            .unwrap();
+
+        let template = transaction_builder.template().unwrap();
+
+        for value in 0..2 {
+            let input = PacketBuilder::new(packet_data!({"test": {"value": value}}))
+                .build()
+                .unwrap();
+
+            let tx = template
+                .instantiate(input.clone(), signees!(key.clone()))
+                .unwrap();
+            let tx: serde_json::Value = serde_json::from_str(&tx).unwrap();
+
+            // Must match the transaction built without the template
+            transaction_builder
+                .input(input)
+                .unwrap()
+                .build(signees!(key.clone()))
+                .unwrap();
+            let built = transaction_builder.get_json().unwrap();
+
+            assert_eq!(tx["$tx"], built["$tx"]);
+            assert_eq!(tx["$territoriality"], built["$territoriality"]);
+            assert_eq!(tx["$tx"]["$i"]["test"]["value"], value);
+            assert!(tx["$sigs"]["test"].is_string());
+        }
+    }
+
+    #[test]
+    fn tx_stream_feed() {
+        use std::cell::Cell;
+
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let template = TransactionBuilder::new("namespace", "contract")
+            .template()
+            .unwrap();
+
+        let stream = TxStream::new(template, signees!(key));
+
+        let fed = Cell::new(0);
+        let packets = (0..100).map(|value| {
+            fed.set(fed.get() + 1);
+
+            PacketBuilder::new(packet_data!({"test": {"value": value}}))
+                .build()
+                .unwrap()
+        });
+
+        let mut txs = stream.feed(packets);
+
+        // Nothing is built until the iterator is advanced
+        assert_eq!(fed.get(), 0);
+
+        for value in 0..3 {
+            let tx: serde_json::Value =
+                serde_json::from_str(&txs.next().unwrap().unwrap()).unwrap();
+
+            assert_eq!(tx["$tx"]["$i"]["test"]["value"], value);
+            assert!(tx["$sigs"]["test"].is_string());
+        }
+
+        assert_eq!(fed.get(), 3);
+    }
+
+    #[test]
+    fn tx_batch_chunks() {
+        let txs: Vec<String> = ["aaaa", "bb", "cccccc", "d", "eeeeeeeeee", "ff"]
+            .iter()
+            .map(|tx| tx.to_string())
+            .collect();
+
+        let mut chunker = BatchChunker::new();
+        chunker.max_per_chunk(2).max_bytes_per_chunk(10);
+
+        let chunks: Vec<_> = chunker.chunks(txs.clone()).collect();
+        let sizes: Vec<_> = chunks
+            .iter()
+            .map(|chunk| chunk.as_ref().map(|chunk| (chunk.len(), chunk.bytes())))
+            .map(|chunk| chunk.map_err(|e| e.code()))
+            .collect();
+
+        // Sizes include the brackets and commas, the oversized transaction is reported and
+        // skipped
+        assert_eq!(sizes, vec![Ok((2, 9)), Ok((2, 10)), Err(5018), Ok((1, 4))]);
+
+        assert_eq!(chunker.chunk(txs.clone()).unwrap_err().code(), 5018);
+
+        // Without limits everything is one chunk
+        let chunks = BatchChunker::new().chunk(txs).unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].clone().into_transactions().len(), 6);
+    }
+
+    #[test]
+    fn tx_build_idempotent() {
+        use std::collections::HashSet;
+
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let key2 = Key::Ec(EllipticCurve::new("test2").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"test": {"a": 1}, "test2": {"b": 2}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder.input(input.clone()).unwrap();
+
+        let signees = signees![{"test" => key.clone()}, {"test2" => key2.clone()}];
+        let built = transaction_builder
+            .build_idempotent(signees.clone(), "_nonce")
+            .unwrap();
+
+        let json = transaction_builder.get_json().unwrap();
+        assert_eq!(json["$tx"]["$i"]["test"]["_nonce"], built.nonce.as_str());
+        assert_eq!(json["$tx"]["$i"]["test2"]["_nonce"], built.nonce.as_str());
+        assert_eq!(built.nonce.len(), 26);
+
+        // Rebuilding keeps the nonce, so the packet and hash are the same
+        let rebuilt = transaction_builder
+            .build_idempotent(signees.clone(), "_nonce")
+            .unwrap();
+        assert_eq!(rebuilt.nonce, built.nonce);
+        assert_eq!(rebuilt.hash, built.hash);
+
+        let mut seen = HashSet::new();
+        seen.insert(built.hash.clone());
+        assert!(is_replay(&rebuilt.tx, &seen).unwrap());
+
+        // New input is a new transaction
+        transaction_builder.input(input.clone()).unwrap();
+        let next = transaction_builder
+            .build_idempotent(signees, "_nonce")
+            .unwrap();
+        assert_ne!(next.nonce, built.nonce);
+        assert!(!is_replay(&next.tx, &seen).unwrap());
+
+        // A failed build leaves its nonce in the input for the retry
+        transaction_builder.input(input).unwrap().max_size(10);
+        let signees = signees![{"test" => key}, {"test2" => key2}];
+        assert!(transaction_builder
+            .build_idempotent(signees.clone(), "_nonce")
+            .is_err());
+        let nonce = transaction_builder.snapshot().packet_data["input"]["test"]["_nonce"].clone();
+
+        let retried = transaction_builder
+            .max_size(100_000)
+            .build_idempotent(signees, "_nonce")
+            .unwrap();
+        assert_eq!(nonce, retried.nonce.as_str());
+    }
+
+    #[test]
+    fn tx_reset_reuse() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let key2 = Key::Ec(EllipticCurve::new("test2").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"test": {"input": "one"}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .build(signees![{"test" => key.clone()}])
+            .unwrap();
+
+        // Clone keeps the built state
+        let copy = transaction_builder.clone();
+        assert_eq!(copy.get().unwrap(), transaction_builder.get().unwrap());
+
+        transaction_builder.clear_sigs();
+        let json = transaction_builder.get_json().unwrap();
+        assert_eq!(json["$sigs"], serde_json::json!({}));
+
+        transaction_builder.reset();
+        assert!(transaction_builder.get().is_err());
+        assert!(transaction_builder.build(signees!(key.clone())).is_err());
+
+        let input = PacketBuilder::new(packet_data!({"test2": {"input": "two"}}))
+            .build()
+            .unwrap();
+
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .build(signees![{"test2" => key2}])
+            .unwrap();
+
+        // Only the signature for the new packet is present
+        let json = transaction_builder.get_json().unwrap();
+        assert!(json["$sigs"]["test"].is_null());
+        assert!(json["$sigs"]["test2"].is_string());
+        assert_eq!(json["$tx"]["$namespace"], "namespace");
+    }
+
+    #[test]
+    fn tx_rebuild_envelope() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"test": "data"}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+
+        // Nothing to rebuild before the first build
+        assert!(transaction_builder.rebuild_envelope().is_err());
+
+        let tx = transaction_builder
+            .input(input)
+            .unwrap()
+            .build(signees![{"test" => key}])
+            .unwrap();
+        let before: serde_json::Value = serde_json::from_str(&tx).unwrap();
+
+        transaction_builder
+            .territoriality("node1")
+            .broadcast()
+            .envelope_field("$origin", serde_json::json!("client"))
+            .unwrap();
+
+        let tx = transaction_builder.rebuild_envelope().unwrap();
+        let after: serde_json::Value = serde_json::from_str(&tx).unwrap();
+
+        // The envelope changes, the signed packet and signatures do not
+        assert_eq!(after["$territoriality"], "node1");
+        assert_eq!(after["$broadcast"], true);
+        assert_eq!(after["$origin"], "client");
+        assert_eq!(after["$tx"], before["$tx"]);
+        assert_eq!(after["$sigs"], before["$sigs"]);
+        assert_eq!(tx, transaction_builder.get().unwrap());
+
+        // The signatures were not made for a selfsigned transaction
+        transaction_builder.selfsign();
+        match transaction_builder.rebuild_envelope() {
+            Err(TxBuilderError::InvalidState { expected, actual }) => {
+                assert_eq!(expected, TxState::Draft);
+                assert_eq!(actual, TxState::Signed);
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        transaction_builder.selfsign_off();
+        assert!(transaction_builder.rebuild_envelope().is_ok());
+    }
+
+    #[test]
+    fn tx_signed_packet_canonical() {
+        let key = EllipticCurve::new("test").unwrap();
+
+        let input = PacketBuilder::new(packet_data!({"test": {"b": "2", "a": [1, true]}}))
+            .build()
+            .unwrap();
+        let readonly = PacketBuilder::new(packet_data!({"ro": "data"}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .entry("entry")
+            .readonly(readonly)
+            .unwrap()
+            .input(input)
+            .unwrap()
+            .build(signees![{"test" => Key::Ec(key.clone())}])
+            .unwrap();
+
+        // The signature must be over the packet as it appears in the transaction
+        let json = transaction_builder.get_json().unwrap();
+        let packet = json["$tx"].to_string();
+        let signature = json["$sigs"]["test"].as_str().unwrap();
+
+        assert!(key.verify(&packet, signature).unwrap());
+        assert_eq!(transaction_builder.get().unwrap(), json.to_string());
+        assert_eq!(transaction_builder.signed_payload().unwrap(), packet);
+
+        // Pretty output is only for display, the same transaction laid out over lines
+        let pretty = transaction_builder.get_pretty().unwrap();
+        assert!(pretty.contains('\n'));
+        assert!(!transaction_builder.get().unwrap().contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&pretty).unwrap(),
+            json
+        );
+    }
+
+    #[test]
+    fn tx_build_to_writer() {
+        let key = EllipticCurve::new("test").unwrap();
+        let key2 = RSA::new("test2").unwrap();
+
+        let input = PacketBuilder::new(packet_data!({"test": {"data": "value"}, "test2": {}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .entry("entry")
+            .territoriality("node")
+            .input(input)
+            .unwrap();
+
+        let signees =
+            signees![{"test" => Key::Ec(key.clone())}, {"test2" => Key::Rsa(key2.clone())}];
+
+        let mut writer: Vec<u8> = Vec::new();
+        transaction_builder
+            .build_to_writer(&mut writer, signees.clone())
+            .unwrap();
+
+        let streamed: serde_json::Value = serde_json::from_slice(&writer).unwrap();
+        assert_eq!(transaction_builder.state(), TxState::Signed);
+
+        // Same transaction as the in memory build, apart from the EC signature
+        transaction_builder.build(signees).unwrap();
+        let built = transaction_builder.get_json().unwrap();
+
+        assert_eq!(streamed["$tx"], built["$tx"]);
+        assert_eq!(streamed["$territoriality"], built["$territoriality"]);
+        assert_eq!(streamed["$sigs"]["test2"], built["$sigs"]["test2"]);
+
+        let packet = streamed["$tx"].to_string();
+        let signature = streamed["$sigs"]["test"].as_str().unwrap();
+        assert!(key.verify(&packet, signature).unwrap());
+    }
+
+    #[test]
+    fn tx_selfsign_bool() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"test": {"input": "data"}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        let tx = transaction_builder
+            .selfsign()
+            .input(input)
+            .unwrap()
+            .build(signees!(key.clone()))
+            .unwrap();
+
+        // The ledger expects a boolean, not the string "true"
+        assert!(tx.contains(r#""$selfsign":true"#));
+        let json = transaction_builder.get_json().unwrap();
+        assert_eq!(json["$selfsign"], serde_json::Value::Bool(true));
+
+        transaction_builder.selfsign_off();
+        let tx = transaction_builder.build(signees!(key)).unwrap();
+        assert!(!tx.contains("$selfsign"));
+    }
+
+    #[test]
+    fn tx_onboard_builder() {
+        let key = EllipticCurve::new("keyname").unwrap();
+        let admin = Key::Ec(EllipticCurve::new("admin").unwrap());
+
+        let tx = OnboardTxBuilder::new(Key::Ec(key.clone()))
+            .name("device")
+            .namespace("devices")
+            .contract("register")
+            .metadata("name", "Sensor")
+            .metadata("roles", packet_data!(["sensor"]))
+            .metadata("type", "overridden")
+            .signee(admin, "admin")
+            .build()
+            .unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+
+        assert_eq!(json["$selfsign"], true);
+        assert_eq!(json["$tx"]["$namespace"], "devices");
+        assert_eq!(json["$tx"]["$contract"], "register");
+
+        let identity = &json["$tx"]["$i"]["device"];
+        assert_eq!(identity["type"], "secp256k1");
+        assert_eq!(identity["name"], "Sensor");
+        assert_eq!(identity["roles"], serde_json::json!(["sensor"]));
+        assert!(identity["publicKey"].is_string());
+
+        let packet = json["$tx"].to_string();
+        let signature = json["$sigs"]["device"].as_str().unwrap();
+        assert!(key.verify(&packet, signature).unwrap());
+        assert!(json["$sigs"]["admin"].is_string());
+    }
+
+    #[test]
+    fn tx_onboard_metadata() {
+        let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+
+        let tx = TransactionBuilder::onboard_tx_with_metadata(
+            key.clone(),
+            packet_data!({
+                "name": "Jane Doe",
+                "organisation": "Activeledger",
+                "roles": ["admin"],
+                "publicKey": "replaced"
+            }),
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+
+        let identity = &json["$tx"]["$i"]["keyname"];
+        assert_eq!(identity["name"], "Jane Doe");
+        assert_eq!(identity["organisation"], "Activeledger");
+        assert_eq!(identity["roles"], serde_json::json!(["admin"]));
+        assert_eq!(identity["type"], "secp256k1");
+        assert_ne!(identity["publicKey"], "replaced");
+
+        assert!(
+            TransactionBuilder::onboard_tx_with_metadata(key, packet_data!(["admin"])).is_err()
+        );
+    }
+
+    #[test]
+    fn tx_onboard_with() {
+        let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+
+        let tx =
+            TransactionBuilder::onboard_tx_with(key.clone(), "identity", "register", None).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+
+        assert_eq!(json["$tx"]["$namespace"], "identity");
+        assert_eq!(json["$tx"]["$contract"], "register");
+        assert!(json["$tx"].get("$entry").is_none());
+        assert!(json["$tx"]["$i"]["keyname"]["publicKey"].is_string());
+
+        let tx = TransactionBuilder::onboard_tx_with(key, "identity", "register", Some("device"))
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+
+        assert_eq!(json["$tx"]["$entry"], "device");
+        assert_eq!(json["$selfsign"], true);
+    }
+
+    #[test]
+    fn tx_onboard_batch() {
+        let (keys, txs) = TransactionBuilder::generate_onboard_batch(
+            KeyType::EC,
+            3,
+            "device-",
+            BatchMode::Combined,
+        )
+        .unwrap();
+
+        assert_eq!(keys.len(), 3);
+        assert_eq!(txs.len(), 1);
+
+        let json: serde_json::Value = serde_json::from_str(&txs[0]).unwrap();
+        for key in keys.iter() {
+            assert!(json["$tx"]["$i"][key.name()]["publicKey"].is_string());
+            assert!(json["$sigs"][key.name()].is_string());
+        }
+        assert_eq!(keys[2].name(), "device-2");
+
+        let (keys, txs) = TransactionBuilder::generate_onboard_batch(
+            KeyType::EC,
+            2,
+            "device-",
+            BatchMode::Individual,
+        )
+        .unwrap();
+
+        assert_eq!(txs.len(), 2);
+        for (key, tx) in keys.iter().zip(txs.iter()) {
+            let json: serde_json::Value = serde_json::from_str(tx).unwrap();
+            assert!(json["$sigs"][key.name()].is_string());
+        }
+    }
+
+    fn hex_decode(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn tx_sign_options() {
+        let ec = EllipticCurve::new("test").unwrap();
+        let ec2 = Key::Ec(EllipticCurve::new("test2").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"test": {}, "test2": {}}))
+            .build()
+            .unwrap();
+
+        let mut options = SignOptions::new();
+        options.ec_encoding(EcSignatureEncoding::Raw);
+
+        let mut signees = signees![{"test" => Key::Ec(ec.clone())}];
+        signees.add_with_options(ec2, "test2", options);
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .build(signees.clone())
+            .unwrap();
+
+        let json = transaction_builder.get_json().unwrap();
+        let packet = json["$tx"].to_string();
+
+        // Builder options apply to the first key, the second uses its own
+        let signature = json["$sigs"]["test"].as_str().unwrap();
+        assert!(ec.verify(&packet, signature).unwrap());
+
+        let signature = json["$sigs"]["test2"].as_str().unwrap();
+        assert_eq!(base64::decode(signature).unwrap().len(), 64);
+
+        // Signatures can be hex encoded for the whole build
+        let mut options = SignOptions::new();
+        options.encoding(SignatureEncoding::Hex);
+
+        transaction_builder
+            .sign_options(options)
+            .build(signees![{"test" => Key::Ec(ec.clone())}])
+            .unwrap();
+
+        let json = transaction_builder.get_json().unwrap();
+        let signature = json["$sigs"]["test"].as_str().unwrap();
+        assert!(signature.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(ec
+            .verify(&packet, &base64::encode(&hex_decode(signature)))
+            .unwrap());
+
+        // Streaming honours the same options
+        let mut writer: Vec<u8> = Vec::new();
+        transaction_builder
+            .build_to_writer(&mut writer, signees)
+            .unwrap();
+        let streamed: serde_json::Value = serde_json::from_slice(&writer).unwrap();
+        let signature = streamed["$sigs"]["test2"].as_str().unwrap();
+        assert_eq!(base64::decode(signature).unwrap().len(), 64);
+    }
+
+    #[test]
+    fn tx_multi_key_stream() {
+        let key1 = EllipticCurve::new("key1").unwrap();
+        let key2 = EllipticCurve::new("key2").unwrap();
+        let other = Key::Ec(EllipticCurve::new("other").unwrap());
+
+        let mut signees = Signees::new();
+        signees
+            .add(other.clone(), "test")
+            .add_multi(Key::Ec(key2.clone()), "test", "key1")
+            .add_multi(Key::Ec(key1.clone()), "test", "key1")
+            .add_multi(Key::Ec(key2.clone()), "test", "key2")
+            .add(other, "test2");
+
+        // The single key is replaced, as is the first key1 entry
+        assert_eq!(signees.len(), 3);
+
+        let input = PacketBuilder::new(packet_data!({"test": {}, "test2": {}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .build(signees.clone())
+            .unwrap();
+
+        let json = transaction_builder.get_json().unwrap();
+        let packet = json["$tx"].to_string();
+
+        let sigs = &json["$sigs"]["test"];
+        assert!(key1
+            .verify(&packet, sigs["key1"].as_str().unwrap())
+            .unwrap());
+        assert!(key2
+            .verify(&packet, sigs["key2"].as_str().unwrap())
+            .unwrap());
+        assert!(json["$sigs"]["test2"].is_string());
+
+        // Each key of the multi-key stream is counted
+        let status = transaction_builder.status();
+        assert!(status.built && status.is_ready());
+        assert_eq!(status.sigs, 3);
+
+        signees.remove("test");
+        assert_eq!(signees.len(), 1);
+    }
+
+    #[test]
+    fn tx_max_size() {
+        let ec = Key::Ec(EllipticCurve::new("ec").unwrap());
+        let rsa = Key::Rsa(RSA::new("rsa").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"ec": {"a": 1}, "rsa": {"b": 2}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .territoriality("node");
+
+        let signees = signees![{"ec" => ec.clone()}, {"rsa" => rsa}];
+        let estimate = transaction_builder.size_estimate(&signees).unwrap();
+        let size = transaction_builder.build(signees.clone()).unwrap().len();
+
+        // DER encoded EC signatures vary by a few bytes
+        assert!(size <= estimate && size + 8 >= estimate);
+
+        let mut options = SignOptions::new();
+        options
+            .ec_encoding(EcSignatureEncoding::Raw)
+            .encoding(SignatureEncoding::Hex);
+        transaction_builder.sign_options(options);
+
+        let estimate = transaction_builder.size_estimate(&signees).unwrap();
+        let size = transaction_builder.build(signees.clone()).unwrap().len();
+
+        assert_eq!(size, estimate);
+
+        let error = transaction_builder
+            .max_size(size - 1)
+            .build(signees.clone())
+            .unwrap_err();
+
+        match error {
+            TxBuilderError::SizeExceeded { size: actual, max } => {
+                assert_eq!(actual, size);
+                assert_eq!(max, size - 1);
+            }
+            _ => panic!("Expected size exceeded, got {}", error),
+        }
+        assert!(transaction_builder.get().is_err());
+
+        assert!(transaction_builder.max_size(size).build(signees).is_ok());
+    }
+
+    #[test]
+    fn tx_extra_fields() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let input = PacketBuilder::new(packet_data!({"test": {"value": 1}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .packet_field("$nonce", serde_json::json!(1))
+            .unwrap()
+            .packet_field("$a", serde_json::json!("first"))
+            .unwrap()
+            .envelope_field("$deployment", serde_json::json!("blue"))
+            .unwrap()
+            .territoriality("node");
+
+        let error = transaction_builder
+            .packet_field("$namespace", serde_json::json!("other"))
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), 5019);
+        assert!(transaction_builder
+            .envelope_field("$tx", serde_json::json!({}))
+            .is_err());
+
+        let tx = transaction_builder
+            .input(input.clone())
+            .unwrap()
+            .build(signees!(key.clone()))
+            .unwrap();
+
+        // Extra fields are kept in key order with the managed fields
+        assert!(tx.starts_with(r#"{"$deployment":"blue","$sigs":"#));
+        assert!(tx.contains(
+            r#""$tx":{"$a":"first","$contract":"contract","$i":{"test":{"value":1}},"$namespace":"namespace","$nonce":1}"#
+        ));
+
+        let template = transaction_builder.template().unwrap();
+        let templated: serde_json::Value =
+            serde_json::from_str(&template.instantiate(input, signees!(key)).unwrap()).unwrap();
+        let built = transaction_builder.get_json().unwrap();
+
+        assert_eq!(templated["$tx"], built["$tx"]);
+        assert_eq!(templated["$deployment"], built["$deployment"]);
+    }
+
+    #[test]
+    fn tx_broadcast() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let input = PacketBuilder::new(packet_data!({"test": {"value": 1}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .broadcast()
+            .territoriality("node")
+            .input(input.clone())
+            .unwrap()
+            .build(signees!(key.clone()))
+            .unwrap();
+
+        let json = transaction_builder.get_json().unwrap();
+        assert_eq!(json["$broadcast"], serde_json::Value::Bool(true));
+        assert_eq!(json["$territoriality"], "node");
+
+        let mut collector = transaction_builder.multisig(&["test"], 1).unwrap();
+        let tx = collector
+            .sign(signees!(key.clone()))
+            .unwrap()
+            .finish()
+            .unwrap();
+        let tx: serde_json::Value = serde_json::from_str(&tx).unwrap();
+        assert_eq!(tx["$broadcast"], serde_json::Value::Bool(true));
+
+        let template = transaction_builder.template().unwrap();
+        let tx = template.instantiate(input, signees!(key)).unwrap();
+        let tx: serde_json::Value = serde_json::from_str(&tx).unwrap();
+        assert_eq!(tx["$broadcast"], serde_json::Value::Bool(true));
+
+        assert!(transaction_builder
+            .envelope_field("$broadcast", serde_json::json!(false))
+            .is_err());
+
+        transaction_builder
+            .set_broadcast(false)
+            .build(Signees::new())
+            .unwrap();
+        assert!(transaction_builder
+            .get_json()
+            .unwrap()
+            .get("$broadcast")
+            .is_none());
+
+        let tx = tx! {
+            namespace: "namespace",
+            contract: "contract",
+            broadcast: true,
+            i: {"test": {"value": 1}}
+        }
+        .unwrap();
+        assert!(tx.starts_with(r#"{"$broadcast":true,"#));
+    }
+
+    #[test]
+    fn tx_multisig_collector() {
+        let alice = EllipticCurve::new("alice").unwrap();
+        let bob = EllipticCurve::new("bob").unwrap();
+        let eve = Key::Ec(EllipticCurve::new("eve").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"alice": {}, "bob": {}, "carol": {}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder.input(input).unwrap();
+
+        assert!(transaction_builder.multisig(&["alice"], 2).is_err());
+
+        let mut collector = transaction_builder
+            .multisig(&["alice", "bob", "carol"], 2)
+            .unwrap();
+
+        collector
+            .sign(signees![{"alice" => Key::Ec(alice.clone())}])
+            .unwrap();
+        assert_eq!(collector.signed(), 1);
+        assert_eq!(collector.missing(), vec!["bob", "carol"]);
+
+        match collector.finish() {
+            Err(TxBuilderError::ThresholdNotMet { signed, threshold }) => {
+                assert_eq!((signed, threshold), (1, 2))
+            }
+            _ => panic!("Expected threshold error"),
+        }
+
+        match collector.sign(signees![{"eve" => eve}]) {
+            Err(TxBuilderError::UnexpectedSigner(streamid)) => assert_eq!(streamid, "eve"),
+            _ => panic!("Expected unexpected signer error"),
+        }
+
+        let signature = bob.sign(collector.packet()).unwrap();
+        collector.attach_signature("bob", &signature).unwrap();
+        assert!(collector.is_complete());
+
+        let json: serde_json::Value = serde_json::from_str(&collector.finish().unwrap()).unwrap();
+        let packet = json["$tx"].to_string();
+        assert_eq!(packet, collector.packet());
+        assert!(alice
+            .verify(&packet, json["$sigs"]["alice"].as_str().unwrap())
+            .unwrap());
+        assert!(bob
+            .verify(&packet, json["$sigs"]["bob"].as_str().unwrap())
+            .unwrap());
+    }
+
+    #[test]
+    fn tx_missing_field_error() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+
+        let mut transaction_builder = TransactionBuilder::new_blank();
+        transaction_builder.namespace("namespace");
+
+        let err = transaction_builder.build(signees!(key)).unwrap_err();
+
+        match err {
+            TxBuilderError::MissingField(field) => assert_eq!(field, "contract"),
+            _ => panic!("Expected MissingField error, got {:?}", err),
+        }
+
+        assert_eq!(err.code(), 5006);
+    }
+
+    struct ExternalKey;
+
+    impl Signer for ExternalKey {
+        fn name(&self) -> &str {
+            "external"
+        }
+
+        fn key_type(&self) -> KeyType {
+            KeyType::EC
+        }
+
+        fn sign(&self, data: &str) -> TxBuilderResult<String> {
+            Ok(format!("signed:{}", data.len()))
+        }
+
+        fn public_pem(&self) -> TxBuilderResult<String> {
+            Ok(String::from("public"))
+        }
+
+        fn max_signature_len(&self) -> usize {
+            16
+        }
+    }
+
+    #[test]
+    fn tx_assembler_matches_builder() {
+        let key = Key::External(std::sync::Arc::new(ExternalKey));
+
+        let tx = TransactionBuilder::new("namespace", "contract")
+            .entry("entry")
+            .input(
+                PacketBuilder::new(packet_data!({"external": {"value": 1}}))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .output(
+                PacketBuilder::new(packet_data!({"external": {"value": 2}}))
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap()
+            .build(signees![{"external" => key}])
+            .unwrap();
+
+        let assembled = TxAssembler::new(
+            "namespace",
+            "contract",
+            serde_json::json!({"external": {"value": 1}}),
+        )
+        .entry("entry")
+        .output(serde_json::json!({"external": {"value": 2}}))
+        .build(&[("external", &ExternalKey)])
+        .unwrap();
+
+        assert_eq!(assembled, tx);
+
+        assert!(
+            TxAssembler::new("namespace ", "contract", serde_json::json!({}))
+                .packet()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn tx_external_signer() {
+        let key = Key::External(std::sync::Arc::new(ExternalKey));
+        let input = PacketBuilder::new(packet_data!({"external": {"value": 1}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder.input(input).unwrap();
+
+        let estimate = transaction_builder
+            .size_estimate(&signees!(key.clone()))
+            .unwrap();
+
+        let mut writer: Vec<u8> = Vec::new();
+        transaction_builder
+            .build_to_writer(&mut writer, signees!(key.clone()))
+            .unwrap();
+
+        let tx = transaction_builder.build(signees!(key.clone())).unwrap();
+        assert_eq!(String::from_utf8(writer).unwrap(), tx);
+        assert!(estimate >= tx.len());
+
+        let json = transaction_builder.get_json().unwrap();
+        assert_eq!(
+            json["$sigs"]["external"],
+            format!("signed:{}", json["$tx"].to_string().len())
+        );
+
+        let tx = OnboardTxBuilder::new(key).build().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+        assert_eq!(json["$tx"]["$i"]["external"]["publicKey"], "public");
+        assert_eq!(json["$tx"]["$i"]["external"]["type"], "secp256k1");
+    }
+
+    #[test]
+    fn tx_failed_signer() {
+        let key = Key::Ec(EllipticCurve::new("key").unwrap());
+        let failing = FnSigner::new("failing", KeyType::EC, |_: &str| {
+            Err(TxBuilderError::SignerError(String::from("offline")))
+        });
+
+        let input = PacketBuilder::new(packet_data!({"a": {}, "b": {}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .build(signees![{"a" => key.clone()}])
+            .unwrap();
+        assert_eq!(transaction_builder.state(), TxState::Signed);
+
+        // A signer failing after another signed leaves neither the old packet nor the new
+        // signatures behind
+        let error = transaction_builder
+            .build(signees![{"a" => key.clone()}, {"b" => failing}])
+            .unwrap_err();
+        assert_eq!(error.code(), 7003);
+        assert_eq!(transaction_builder.state(), TxState::Draft);
+        assert_eq!(transaction_builder.status().sigs, 0);
+        assert!(transaction_builder.get().is_err());
+
+        transaction_builder.build(signees![{"a" => key}]).unwrap();
+        assert_eq!(transaction_builder.state(), TxState::Signed);
+    }
+
+    #[test]
+    fn tx_signees_macro_signers() {
+        let input = PacketBuilder::new(packet_data!({"external": {}, "function": {}}))
+            .build()
+            .unwrap();
+
+        let tx = TransactionBuilder::new("namespace", "contract")
+            .input(input)
+            .unwrap()
+            .build(signees![
+                {"external" => ExternalKey},
+                {"function" => FnSigner::new("fake", KeyType::EC, |data: &str| Ok(format!("fake:{}", data.len())))}
+            ])
+            .unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+        let len = json["$tx"].to_string().len();
+
+        assert_eq!(json["$sigs"]["external"], format!("signed:{}", len));
+        assert_eq!(json["$sigs"]["function"], format!("fake:{}", len));
+    }
+
+    #[test]
+    fn tx_build_variants() {
+        let key1 = Key::Ec(EllipticCurve::new("key1").unwrap());
+        let key2 = Key::Rsa(RSA::new("key2").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"test": {"value": 1}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .with_nonce()
+            .unwrap();
+
+        let variants = transaction_builder
+            .build_variants(&[
+                signees![{"test" => key1.clone()}],
+                signees![{"test" => key2.clone()}],
+            ])
+            .unwrap();
+
+        let a: serde_json::Value = serde_json::from_str(&variants[0]).unwrap();
+        let b: serde_json::Value = serde_json::from_str(&variants[1]).unwrap();
+
+        // Both variants sign the same packet, including its nonce
+        assert_eq!(a["$tx"], b["$tx"]);
+        assert_ne!(a["$sigs"], b["$sigs"]);
+
+        let options = SignOptions::new();
+        for (tx, key) in variants.iter().zip(&[key1, key2]) {
+            let mut keys = std::collections::HashMap::new();
+            keys.insert(String::from("test"), key.public_key().unwrap());
+            assert!(TransactionBuilder::verify(tx, &keys, &options).is_ok());
+        }
+
+        // The builder holds the last variant
+        assert_eq!(transaction_builder.get().unwrap(), variants[1]);
+        match transaction_builder.build_variants(&[]) {
+            Err(TxBuilderError::EmptyVariants) => {}
+            _ => unreachable!(),
+        }
+        assert_eq!(transaction_builder.get().unwrap(), variants[1]);
+    }
+
+    #[test]
+    fn tx_profile() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+
+        let mut profile = Profile::new();
+        profile.namespace = Some(String::from("staging"));
+        profile.territoriality = Some(String::from("node1"));
+        profile.encoding = Some(SignatureEncoding::Hex);
+        profile.encrypt = true;
+
+        let path = std::env::temp_dir().join(format!(
+            "active_tx_profile_test_{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, serde_json::to_string(&profile).unwrap()).unwrap();
+        let read = Profile::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read.unwrap(), profile);
+
+        #[cfg(not(feature = "toml"))]
+        assert!(Profile::from_file("profile.toml")
+            .unwrap_err()
+            .to_string()
+            .contains("`toml` feature"));
+
+        let input = PacketBuilder::new(packet_data!({"test": {}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .with_profile(&profile)
+            .input(input)
+            .unwrap()
+            .build(signees![{"test" => key.clone()}])
+            .unwrap();
+
+        let json = transaction_builder.get_json().unwrap();
+        assert_eq!(json["$tx"]["$namespace"], "staging");
+        assert_eq!(json["$tx"]["$contract"], "contract");
+        assert_eq!(json["$territoriality"], "node1");
+        assert!(json["$sigs"]["test"]
+            .as_str()
+            .unwrap()
+            .chars()
+            .all(|c| c.is_ascii_hexdigit()));
+
+        // An empty profile changes nothing
+        let before = transaction_builder.snapshot();
+        transaction_builder.with_profile(&Profile::new());
+        assert_eq!(transaction_builder.snapshot(), before);
+
+        let profile = Profile::from_json(r#"{"onboard_contract": "identity"}"#).unwrap();
+        let tx = OnboardTxBuilder::new(key)
+            .with_profile(&profile)
+            .build()
+            .unwrap();
+        assert!(tx.contains("\"$contract\":\"identity\""));
+        assert!(tx.contains("\"$namespace\":\"default\""));
+
+        let err = Profile::from_file(std::env::temp_dir().join("active_tx_missing_profile.json"))
+            .unwrap_err();
+        assert_eq!(err.code(), 5027);
+        assert_eq!(Profile::from_json("{").unwrap_err().code(), 5028);
+    }
+
+    #[test]
+    fn tx_from_descriptor() {
+        let resolver = |name: &str| Ok(Key::Ec(EllipticCurve::new(name).unwrap()));
+
+        let descriptor = serde_json::json!({
+            "namespace": "ns",
+            "contract": "contract",
+            "entry": "transfer",
+            "i": {"alice": {"amount": 10}, "multi": {}},
+            "o": {"bob": {}},
+            "r": {"rates": "ratesstream"},
+            "flags": {"broadcast": true, "territoriality": "node1"},
+            "signees": ["alice", {"streamid": "multi", "key_name": "owner", "key_type": "ec"}]
+        });
+
+        let (mut transaction_builder, signees) =
+            TransactionBuilder::from_descriptor(descriptor, &resolver).unwrap();
+
+        assert_eq!(signees.len(), 2);
+        assert_eq!(signees.get()[1].key.name(), "owner");
+
+        transaction_builder.build(signees).unwrap();
+
+        let json = transaction_builder.get_json().unwrap();
+        assert_eq!(json["$tx"]["$entry"], "transfer");
+        assert_eq!(json["$tx"]["$i"]["alice"]["amount"], 10);
+        assert_eq!(json["$tx"]["$o"], serde_json::json!({"bob": {}}));
+        assert_eq!(json["$tx"]["$r"]["rates"], "ratesstream");
+        assert_eq!(json["$broadcast"], true);
+        assert_eq!(json["$territoriality"], "node1");
+        assert!(json.get("$selfsign").is_none());
+        assert!(json["$sigs"]["alice"].is_string());
+        assert!(json["$sigs"]["multi"].is_string());
+
+        let missing_input = serde_json::json!({"namespace": "ns", "contract": "contract"});
+        let err = TransactionBuilder::from_descriptor(missing_input, &resolver).unwrap_err();
+        assert_eq!(err.code(), 5029);
+
+        let unknown_field = serde_json::json!({
+            "namespace": "ns", "contract": "contract", "i": {}, "signers": []
+        });
+        assert!(TransactionBuilder::from_descriptor(unknown_field, &resolver).is_err());
+
+        let failing = |name: &str| -> TxBuilderResult<Key> {
+            Err(TxBuilderError::KeyNotFound(name.to_string()))
+        };
+        let descriptor = serde_json::json!({
+            "namespace": "ns", "contract": "contract", "i": {"alice": {}}, "signees": ["alice"]
+        });
+        let err = TransactionBuilder::from_descriptor(descriptor, &failing).unwrap_err();
+        assert_eq!(err.code(), 9002);
+    }
+
+    #[test]
+    fn tx_state() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let input = PacketBuilder::new(packet_data!({"test": {}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder.input(input).unwrap();
+        assert_eq!(transaction_builder.state(), TxState::Draft);
+
+        match transaction_builder.sign(signees![{"test" => key.clone()}]) {
+            Err(TxBuilderError::InvalidState { expected, actual }) => {
+                assert_eq!(expected, TxState::Built);
+                assert_eq!(actual, TxState::Draft);
+            }
+            _ => unreachable!(),
+        }
+
+        let err = transaction_builder.get().unwrap_err();
+        assert_eq!(err.code(), 5005);
+        assert!(err.to_string().contains("expected built, builder is draft"));
+        assert!(transaction_builder.signed_payload().is_err());
+
+        transaction_builder.build(Signees::new()).unwrap();
+        assert_eq!(transaction_builder.state(), TxState::Built);
+
+        transaction_builder
+            .sign(signees![{"test" => key.clone()}])
+            .unwrap();
+        assert_eq!(transaction_builder.state(), TxState::Signed);
+
+        transaction_builder.clear_sigs();
+        assert_eq!(transaction_builder.state(), TxState::Built);
+
+        transaction_builder
+            .build(signees![{"test" => key}])
+            .unwrap();
+        assert_eq!(transaction_builder.state(), TxState::Signed);
+
+        transaction_builder.reset();
+        assert_eq!(transaction_builder.state(), TxState::Draft);
+    }
+
+    #[test]
+    fn tx_error_serialize() {
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        let err = transaction_builder.build(Signees::new()).unwrap_err();
+
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["category"], "txbuild");
+        assert_eq!(value["code"], err.code());
+        assert_eq!(value["field"], "input");
+        assert!(err
+            .to_string()
+            .ends_with(value["message"].as_str().unwrap()));
+
+        let value = serde_json::to_value(TxBuilderError::DecryptionError).unwrap();
+        assert_eq!(value["category"], "keystore");
+        assert_eq!(value["code"], 9004);
+        assert!(value["field"].is_null());
+    }
+
+    #[test]
+    fn tx_error_context() {
+        use std::io;
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        let err = transaction_builder
+            .build(Signees::new())
+            .context_field("tx.json")
+            .unwrap_err();
+
+        assert_eq!(err.code(), 5008);
+        assert_eq!(err.category(), "txbuild");
+        assert_eq!(err.field(), Some("tx.json"));
+
+        let value = serde_json::to_value(&err).unwrap();
+        assert_eq!(value["field"], "tx.json");
+
+        let io_err: io::Error = err.into();
+        assert_eq!(io_err.kind(), io::ErrorKind::InvalidInput);
+        assert!(io_err.get_ref().unwrap().is::<TxBuilderError>());
+
+        let io_err = io::Error::from(TxBuilderError::KeyNotFound("alice".to_string()));
+        assert_eq!(io_err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn tx_signature_matches() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let input = PacketBuilder::new(packet_data!({"test": {}}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        let built = transaction_builder
+            .input(input)
+            .unwrap()
+            .build_transaction(signees![{"test" => key}])
+            .unwrap();
+
+        let signature = built.signatures()[0].signature.to_string();
+        assert!(signature_matches(built.tx(), "test", None, &signature).unwrap());
+
+        // A signature differing only in its last character
+        let mut forged = signature.clone();
+        let last = if forged.pop() == Some('A') { 'B' } else { 'A' };
+        forged.push(last);
+        assert!(!signature_matches(built.tx(), "test", None, &forged).unwrap());
+
+        assert!(!signature_matches(built.tx(), "test", Some("key"), &signature).unwrap());
+        assert!(!signature_matches(built.tx(), "other", None, &signature).unwrap());
+        assert!(signature_matches("not json", "test", None, &signature).is_err());
+
+        assert!(constant_time_eq(&signature, &signature.clone()));
+        assert!(!constant_time_eq(&signature, &forged));
+        assert!(!constant_time_eq("", "a"));
+        assert!(constant_time_eq("", ""));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn tx_tracing_spans() {
+        use std::fmt;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+        use tracing_core::span::Current;
+
+        type Spans = Arc<Mutex<Vec<(String, Vec<(String, String)>)>>>;
+
+        struct Fields<'a>(&'a mut Vec<(String, String)>);
+
+        impl Visit for Fields<'_> {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.push((field.name().to_string(), value.to_string()));
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                self.0
+                    .push((field.name().to_string(), format!("{:?}", value)));
+            }
+        }
+
+        // Records each span as "target::name" with its fields, tracking the entered spans so
+        // fields recorded on the current span are kept
+        struct Recorder {
+            spans: Spans,
+            metadata: Mutex<Vec<&'static Metadata<'static>>>,
+            entered: Mutex<Vec<Id>>,
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut spans = self.spans.lock().unwrap();
+                let metadata = span.metadata();
+                self.metadata.lock().unwrap().push(metadata);
+                let mut fields = Vec::new();
+                span.record(&mut Fields(&mut fields));
+                spans.push((
+                    format!("{}::{}", metadata.target(), metadata.name()),
+                    fields,
+                ));
+
+                Id::from_u64(spans.len() as u64)
+            }
+
+            fn record(&self, span: &Id, values: &Record<'_>) {
+                let mut spans = self.spans.lock().unwrap();
+                let index = span.into_u64() as usize - 1;
+                values.record(&mut Fields(&mut spans[index].1));
+            }
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, _: &Event<'_>) {}
+
+            fn enter(&self, span: &Id) {
+                self.entered.lock().unwrap().push(span.clone());
+            }
+
+            fn exit(&self, _: &Id) {
+                self.entered.lock().unwrap().pop();
+            }
+
+            fn current_span(&self) -> Current {
+                match self.entered.lock().unwrap().last() {
+                    Some(span) => {
+                        let index = span.into_u64() as usize - 1;
+                        Current::new(span.clone(), self.metadata.lock().unwrap()[index])
+                    }
+                    None => Current::none(),
+                }
+            }
+        }
+
+        let spans: Spans = Arc::default();
+        let recorder = Recorder {
+            spans: Arc::clone(&spans),
+            metadata: Mutex::default(),
+            entered: Mutex::default(),
+        };
+
+        let tx = tracing::subscriber::with_default(recorder, || {
+            let input = PacketBuilder::new(packet_data!({"streamid": {"input": "data"}}))
+                .build()
+                .unwrap();
+
+            let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+            tx_builder.input(input).unwrap();
+
+            let key = Key::Ec(EllipticCurve::new("streamid").unwrap());
+            let tx = tx_builder.build(signees![{"streamid" => key}]).unwrap();
+
+            let other = Key::Ec(EllipticCurve::new("other").unwrap());
+            tx_builder.sign(signees![{"other" => other}]).unwrap();
+
+            tx
+        });
+
+        let spans = spans.lock().unwrap();
+        let field = |span: &str, name: &str| {
+            spans
+                .iter()
+                .find(|(recorded, _)| recorded == span)
+                .and_then(|(_, fields)| fields.iter().find(|(field, _)| field == name))
+                .map(|(_, value)| value.clone())
+        };
+
+        let packet_span = "active_tx::packet_builder::builder::build";
+        assert!(field(packet_span, "size").is_some());
+
+        let build_span = "active_tx::transaction_builder::builder::build";
+        assert_eq!(field(build_span, "namespace").unwrap(), "namespace");
+        assert_eq!(field(build_span, "contract").unwrap(), "contract");
+        assert_eq!(field(build_span, "signees").unwrap(), "1");
+        assert_eq!(field(build_span, "size").unwrap(), tx.len().to_string());
+
+        let sign_span = "active_tx::transaction_builder::builder::sign";
+        assert_eq!(field(sign_span, "namespace").unwrap(), "namespace");
+        assert_eq!(field(sign_span, "signees").unwrap(), "1");
+    }
+
+    #[test]
+    fn tx_verify() {
+        let key1 = Key::Ec(EllipticCurve::new("key1").unwrap());
+        let key2 = Key::Rsa(RSA::new("key2").unwrap());
+        let other = Key::Ec(EllipticCurve::new("other").unwrap());
+
+        let mut signees = Signees::new();
+        signees
+            .add(key1.clone(), "test")
+            .add_multi(key1.clone(), "test2", "key1")
+            .add_multi(key2.clone(), "test2", "key2");
+
+        let input = PacketBuilder::new(packet_data!({"test": {}, "test2": {}}))
+            .build()
+            .unwrap();
+
+        let tx = TransactionBuilder::new("namespace", "contract")
+            .input(input)
+            .unwrap()
+            .build(signees)
+            .unwrap();
+
+        let options = SignOptions::new();
+        let mut keys = std::collections::HashMap::new();
+        keys.insert(String::from("test"), key1.public_key().unwrap());
+        keys.insert(String::from("test2/key1"), key1.public_key().unwrap());
+        keys.insert(String::from("test2/key2"), key2.public_key().unwrap());
+        assert!(TransactionBuilder::verify(&tx, &keys, &options).is_ok());
+
+        keys.insert(String::from("test"), other.public_key().unwrap());
+        let err = TransactionBuilder::verify(&tx, &keys, &options).unwrap_err();
+        assert_eq!(err.code(), 7005);
+
+        keys.clear();
+        keys.insert(String::from("test3"), other.public_key().unwrap());
+        let err = TransactionBuilder::verify(&tx, &keys, &options).unwrap_err();
+        assert_eq!(err.code(), 7006);
+
+        let tampered = tx.replace("\"contract\"", "\"changed\"");
+        keys.clear();
+        keys.insert(String::from("test"), key1.public_key().unwrap());
+        let err = TransactionBuilder::verify(&tampered, &keys, &options).unwrap_err();
+        assert_eq!(err.code(), 7005);
+
+        let err = TransactionBuilder::verify("{}", &keys, &options).unwrap_err();
+        assert_eq!(err.code(), 5020);
+    }
+
+    #[test]
+    fn tx_clock() {
+        use chrono::{TimeZone, Utc};
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+
+        let before = Utc::now();
+        assert!(transaction_builder.now() >= before);
+
+        let fixed = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+        transaction_builder.with_clock(move || fixed);
+
+        assert_eq!(transaction_builder.now(), fixed);
+        assert_eq!(transaction_builder.clone().now(), fixed);
+    }
+
+    #[test]
+    fn tx_nonce() {
+        use chrono::{TimeZone, Utc};
+
+        let input = || {
+            PacketBuilder::new(packet_data!({"test": {"value": 1}}))
+                .build()
+                .unwrap()
+        };
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        assert!(transaction_builder.nonce().is_none());
+
+        transaction_builder
+            .with_clock(|| Utc.timestamp_millis_opt(1469918176385).unwrap())
+            .with_nonce()
+            .unwrap()
+            .input(input())
+            .unwrap();
+
+        // The time part of the ULID comes from the clock
+        let nonce = transaction_builder.nonce().unwrap().to_string();
+        assert_eq!(nonce.len(), 26);
+        assert!(nonce.starts_with("01ARYZ6S41"));
+
+        // The nonce is kept when the transaction is built again
+        for _ in 0..2 {
+            transaction_builder.build(Signees::new()).unwrap();
+            let json = transaction_builder.get_json().unwrap();
+            assert_eq!(json["$tx"]["$nonce"], nonce.as_str());
+        }
+
+        // Reset generates a new nonce for the next transaction
+        transaction_builder.reset().input(input()).unwrap();
+        assert!(transaction_builder.nonce().is_none());
+        transaction_builder.build(Signees::new()).unwrap();
+        let next = transaction_builder.nonce().unwrap().to_string();
+        assert_ne!(next, nonce);
+
+        // A value set by the caller is used instead of a generated one
+        transaction_builder
+            .with_nonce_field("$umid")
+            .unwrap()
+            .packet_field("$umid", serde_json::json!("fixed"))
+            .unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&transaction_builder.build(Signees::new()).unwrap()).unwrap();
+        assert_eq!(json["$tx"]["$umid"], "fixed");
+        assert!(json["$tx"].get("$nonce").is_none());
+        assert_eq!(transaction_builder.nonce(), Some("fixed"));
+
+        match transaction_builder.with_nonce_field("$i") {
+            Err(err) => assert_eq!(err.code(), 5019),
+            Ok(_) => panic!("reserved field accepted"),
+        }
+    }
+
+    #[test]
+    fn tx_debug() {
+        let key = Key::Ec(EllipticCurve::new("streamid").unwrap());
+        let pem = EllipticCurve::new("other").unwrap().get_pem().unwrap();
+        let input = PacketBuilder::new(packet_data!({"streamid": {"input": "data"}}))
+            .build()
+            .unwrap();
+
+        let mut signees = signees![{"streamid" => key}];
+        signees
+            .add_from_pem_str(&pem.private, "other", KeyType::EC)
+            .unwrap();
+
+        let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+        tx_builder.input(input).unwrap();
+        tx_builder.build(signees.clone()).unwrap();
+
+        // The builder is Debug, so results holding it can be unwrapped
+        let err = tx_builder.with_nonce_field("$contract").unwrap_err();
+        assert_eq!(err.code(), 5019);
+
+        let debug = format!("{:?}", tx_builder);
+        assert!(debug.starts_with("TransactionBuilder {"));
+        assert!(debug.contains("namespace"));
+
+        for debug in &[debug, format!("{:?}", signees)] {
+            assert!(!debug.contains("PRIVATE"));
+            assert!(!debug.contains(&pem.private[40..80]));
+        }
+    }
+
+    #[test]
+    fn tx_events() {
+        use std::sync::{Arc, Mutex};
+
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let key2 = Key::Rsa(RSA::new("test2").unwrap());
+        let fingerprint = key.fingerprint().unwrap();
+        let fingerprint2 = key2.fingerprint().unwrap();
+        let input = PacketBuilder::new(packet_data!({"test": {}, "test2": {}}))
+            .build()
+            .unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&events);
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        let tx = transaction_builder
+            .on_event(move |event| {
+                let entry = match event {
+                    TxEvent::PacketBuilt { packet } => format!("packet {}", packet),
+                    TxEvent::PacketHashed { hash, digest } => {
+                        format!("hash {:?} {}", hash, digest.len())
+                    }
+                    TxEvent::Signed {
+                        streamid,
+                        key_ref,
+                        signature,
+                        fingerprint,
+                    } => format!(
+                        "signed {} {:?} {} {}",
+                        streamid,
+                        key_ref,
+                        signature,
+                        fingerprint.unwrap()
+                    ),
+                    TxEvent::EnvelopeFinalized { tx } => format!("tx {}", tx),
+                };
+
+                log.lock().unwrap().push(entry);
+            })
+            .input(input)
+            .unwrap()
+            .build(signees![{"test" => key.clone()}])
+            .unwrap();
+
+        let json = transaction_builder.get_json().unwrap();
+        let packet = json["$tx"].to_string();
+        let signature = json["$sigs"]["test"].as_str().unwrap();
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                format!("packet {}", packet),
+                String::from("hash Sha256 32"),
+                format!("signed test None {} {}", signature, fingerprint),
+                format!("tx {}", tx),
+            ]
+        );
+
+        events.lock().unwrap().clear();
+        transaction_builder
+            .sign(signees![{"test2" => key2}])
+            .unwrap();
+
+        let json = transaction_builder.get_json().unwrap();
+        let signature = json["$sigs"]["test2"].as_str().unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                String::from("hash Sha256 32"),
+                format!("signed test2 None {} {}", signature, fingerprint2),
+            ]
+        );
+
+        events.lock().unwrap().clear();
+        let mut writer = Vec::new();
+        transaction_builder
+            .build_to_writer(&mut writer, signees![{"test" => key}])
+            .unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&writer).unwrap();
+        let signature = json["$sigs"]["test"].as_str().unwrap();
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![format!("signed test None {} {}", signature, fingerprint)]
+        );
+    }
+
+    #[test]
+    fn tx_built_transaction() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let input = PacketBuilder::new(packet_data!({"test": "data"}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input(input.clone())
+            .unwrap()
+            .meta("correlation_id", serde_json::json!("c-1"))
+            .meta("retries", serde_json::json!(2));
+
+        let built = transaction_builder
+            .build_transaction(signees![{"test" => key.clone()}])
+            .unwrap();
+
+        assert_eq!(built.tx(), transaction_builder.get().unwrap());
+        assert!(!built.tx().contains("correlation_id"));
+
+        let json = built.json().unwrap();
+        assert_eq!(built.packet(), json["$tx"].to_string());
+        assert_eq!(built.stream_ids(), vec!["test"]);
+        assert_eq!(
+            built.signatures(),
+            vec![StreamSignature {
+                streamid: "test",
+                key_ref: None,
+                signature: json["$sigs"]["test"].as_str().unwrap(),
+            }]
+        );
+        assert_eq!(
+            built.packet_hash(),
+            transaction_builder.packet_hash().unwrap()
+        );
+        assert_eq!(built.packet_hash().len(), 32);
+        assert_eq!(
+            transaction_builder
+                .packet_hash_with(HashAlgorithm::Sha512)
+                .unwrap(),
+            built.packet_hash_with(HashAlgorithm::Sha512)
+        );
+        assert_eq!(
+            BuiltTransaction::parse(built.tx()).unwrap().packet(),
+            built.packet()
+        );
+        assert_eq!(built.get_meta("retries"), Some(&serde_json::json!(2)));
+
+        let stored = serde_json::to_string(&built).unwrap();
+        let restored: BuiltTransaction = serde_json::from_str(&stored).unwrap();
+        assert_eq!(restored, built);
+
+        transaction_builder.reset();
+        transaction_builder.input(input).unwrap();
+
+        let built = transaction_builder
+            .build_transaction(signees![{"test" => key}])
+            .unwrap();
+        assert!(built.meta.is_empty());
+    }
+
+    #[test]
+    fn tx_access() {
+        let owner = Key::Ec(EllipticCurve::new("owner").unwrap());
+        let grantee = Key::Rsa(RSA::new("grantee").unwrap());
+        let grantee_pem = grantee.public_pem().unwrap();
+
+        let access = AccessTxBuilder::new(
+            "namespace",
+            "permissions",
+            "add",
+            "remove",
+            "owner-id",
+            owner.clone(),
+        )
+        .unwrap();
+
+        let tx = access.grant("streamid", &grantee_pem).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+
+        assert_eq!(json["$tx"]["$namespace"], "namespace");
+        assert_eq!(json["$tx"]["$contract"], "permissions");
+        assert_eq!(json["$tx"]["$entry"], "add");
+        assert_eq!(json["$tx"]["$i"], serde_json::json!({"owner-id": {}}));
+        assert_eq!(
+            json["$tx"]["$o"],
+            serde_json::json!({"streamid": {"type": "rsa", "publicKey": grantee_pem}})
+        );
+
+        let mut keys = std::collections::HashMap::new();
+        keys.insert(String::from("owner-id"), owner.public_key().unwrap());
+        TransactionBuilder::verify(&tx, &keys, &SignOptions::new()).unwrap();
+
+        let revoke = access.revoke("streamid", &grantee_pem).unwrap();
+        let revoke: serde_json::Value = serde_json::from_str(&revoke).unwrap();
+        assert_eq!(revoke["$tx"]["$entry"], "remove");
+        assert_eq!(revoke["$tx"]["$o"], json["$tx"]["$o"]);
+
+        let error = access.grant("streamid", "not a pem");
+        assert_eq!(error.unwrap_err().code(), 8007);
+
+        let error = AccessTxBuilder::new("namespace", "acl", "set", "set", "owner-id", owner);
+        assert_eq!(error.unwrap_err().code(), 5023);
+    }
+
+    #[test]
+    fn tx_entry() {
+        enum TokenEntry {
+            Mint,
+            Transfer,
+        }
+
+        impl Entry for TokenEntry {
+            fn entry_name(&self) -> &str {
+                match self {
+                    TokenEntry::Mint => "mint",
+                    TokenEntry::Transfer => "transfer",
+                }
+            }
+        }
+
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let input = PacketBuilder::new(packet_data!({"test": "data"}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "token");
+        transaction_builder
+            .allowed_entries(vec![TokenEntry::Mint, TokenEntry::Transfer])
+            .entry(TokenEntry::Transfer)
+            .input(input)
+            .unwrap();
+
+        let tx = transaction_builder
+            .build(signees![{"test" => key.clone()}])
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+        assert_eq!(json["$tx"]["$entry"], "transfer");
+
+        let entry = String::from("burn");
+        let error = transaction_builder
+            .entry(&entry)
+            .build(signees![{"test" => key}])
+            .unwrap_err();
+        assert_eq!(error.code(), 5022);
+    }
+
+    #[test]
+    fn tx_contract_client() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let input = PacketBuilder::new(packet_data!({"test": "data"}))
+            .build()
+            .unwrap();
+
+        let mut client = ContractClient::new("namespace", "token", signees![{"test" => key}]);
+        client.allowed_entries(vec!["mint", "transfer"]);
+
+        let tx = client.call("mint", input.clone()).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+
+        assert_eq!(json["$tx"]["$namespace"], "namespace");
+        assert_eq!(json["$tx"]["$contract"], "token");
+        assert_eq!(json["$tx"]["$entry"], "mint");
+        assert!(json["$sigs"]["test"].is_string());
+
+        let other = Key::Ec(EllipticCurve::new("other").unwrap());
+        let tx = client
+            .call_with("transfer", input.clone(), signees![{"other" => other}])
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+        assert!(json["$sigs"]["other"].is_string());
+        assert!(json["$sigs"]["test"].is_null());
+
+        assert_eq!(client.call("burn", input).unwrap_err().code(), 5022);
+    }
+
+    #[test]
+    fn tx_identifiers() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let input = PacketBuilder::new(packet_data!({"test": "data"}))
+            .build()
+            .unwrap();
+
+        for &(namespace, contract, field) in &[
+            ("namespace ", "contract", "namespace"),
+            ("", "contract", "namespace"),
+            (" ", "contract", "namespace"),
+            ("namespace", "contract\n", "contract"),
+            ("namespace", "\tcontract", "contract"),
+        ] {
+            let error = TransactionBuilder::new(namespace, contract)
+                .input(input.clone())
+                .unwrap()
+                .build(signees![{"test" => key.clone()}])
+                .unwrap_err();
+
+            match error {
+                TxBuilderError::InvalidIdentifier {
+                    field: f, value, ..
+                } => {
+                    assert_eq!(f, field);
+                    assert!(value == namespace || value == contract);
+                }
+                other => panic!("unexpected error {}", other),
+            }
+        }
+
+        // Naming rules are left to the ledger
+        TransactionBuilder::new("My Namespace", "contract/1.0")
+            .input(input)
+            .unwrap()
+            .build(signees![{"test" => key}])
+            .unwrap();
+    }
+
+    #[test]
+    fn tx_validate() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let input = PacketBuilder::new(packet_data!({"test": "data", "other": "data"}))
+            .build()
+            .unwrap();
+
+        let mut transaction_builder = TransactionBuilder::new_blank();
+        transaction_builder.namespace("namespace ");
+
+        let problems = match transaction_builder.validate(&Signees::new(), &[]) {
+            Err(TxBuilderError::ValidationFailed(problems)) => problems,
+            other => panic!("unexpected result {:?}", other),
+        };
+        let codes: Vec<u16> = problems.iter().map(TxBuilderError::code).collect();
+        assert_eq!(codes, vec![5006, 5023, 5008]);
+
+        transaction_builder
+            .namespace("namespace")
+            .contract("contract")
+            .input(input)
+            .unwrap();
+
+        // Input streams only need a signature when the caller requires one
+        let signees = signees![{"test" => key.clone()}];
+        transaction_builder.validate(&signees, &[]).unwrap();
+
+        let signees = signees![{"test" => key.clone()}, {"extra" => key.clone()}];
+        let error = transaction_builder
+            .validate(&signees, &["test", "other"])
+            .unwrap_err();
+        assert_eq!(error.code(), 5024);
+
+        let problems = match error {
+            TxBuilderError::ValidationFailed(problems) => problems,
+            _ => unreachable!(),
+        };
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].to_string().contains("'other'"));
+        assert!(problems[1].to_string().contains("'extra'"));
+
+        // Selfsigned transactions are signed by key name
+        let mut signees = Signees::new();
+        signees.add_selfsign(key);
+        transaction_builder.selfsign();
+        transaction_builder.validate(&signees, &["other"]).unwrap();
+    }
+
+    #[test]
+    fn tx_input_stream() {
+        let key1 = Key::Ec(EllipticCurve::new("order1").unwrap());
+        let key2 = Key::Ec(EllipticCurve::new("order2").unwrap());
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input_stream("order1", packet_data!({"quantity": 1}))
+            .unwrap()
+            .input_stream("order2", packet_data!({"quantity": 5}))
+            .unwrap()
+            .input_stream("order1", packet_data!({"quantity": 2}))
+            .unwrap();
+
+        let tx = transaction_builder
+            .build(signees![{"order1" => key1.clone()}, {"order2" => key2}])
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+        assert_eq!(
+            json["$tx"]["$i"],
+            serde_json::json!({"order1": {"quantity": 2}, "order2": {"quantity": 5}})
+        );
+
+        assert!(transaction_builder.input_stream("bad id", "x").is_err());
+
+        // Signee stream IDs are checked too, strictly when the builder is strict
+        let error = transaction_builder
+            .build(signees![{"bad id" => key1.clone()}])
+            .unwrap_err();
+        assert_eq!(error.code(), 3004);
+
+        transaction_builder.strict_stream_ids(true);
+        let error = transaction_builder
+            .build(signees![{"order1" => key1.clone()}])
+            .unwrap_err();
+        assert_eq!(error.code(), 3004);
+
+        let mut verifiers = VerifierSet::new();
+        verifiers.add(key1.public_key().unwrap(), "bad id");
+        let error = verifiers.verify(&tx, &SignOptions::new()).unwrap_err();
+        assert_eq!(error.code(), 3004);
+    }
+
+    #[test]
+    fn tx_merge_sections() {
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+
+        let first = PacketBuilder::new(packet_data!({"stream": {"a": 1, "nested": {"x": 1}}}))
+            .build()
+            .unwrap();
+        let second = PacketBuilder::new(packet_data!({"stream": {"a": 1, "nested": {"y": 2}}}))
+            .build()
+            .unwrap();
+        transaction_builder
+            .merge_output(first)
+            .unwrap()
+            .merge_output(second)
+            .unwrap();
+
+        let conflict = PacketBuilder::new(packet_data!({"stream": {"nested": {"x": 3}}}))
+            .build()
+            .unwrap();
+        let error = transaction_builder.merge_output(conflict).unwrap_err();
+        assert_eq!(error.code(), 5025);
+        assert!(error.to_string().contains("output.stream.nested.x"));
+
+        let replacement = PacketBuilder::new(packet_data!({"other": {"b": 2}}))
+            .build()
+            .unwrap();
+        transaction_builder.set_readonly(replacement).unwrap();
+
+        let key = Key::Ec(EllipticCurve::new("stream").unwrap());
+        let input = PacketBuilder::new(packet_data!({"stream": {}}))
+            .build()
+            .unwrap();
+        transaction_builder.set_input(input).unwrap();
+
+        let tx = transaction_builder
+            .build(signees![{"stream" => key}])
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&tx).unwrap();
+        assert_eq!(
+            json["$tx"]["$o"],
+            serde_json::json!({"stream": {"a": 1, "nested": {"x": 1, "y": 2}}})
+        );
+        assert_eq!(json["$tx"]["$r"], serde_json::json!({"other": {"b": 2}}));
+    }
+
+    #[test]
+    fn tx_snapshot_restore() {
+        let key = Key::Ec(EllipticCurve::new("stream").unwrap());
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .entry("create")
+            .meta("job", serde_json::json!(7))
+            .input_stream("stream", packet_data!({"value": 1}))
+            .unwrap();
+
+        let stored = serde_json::to_string(&transaction_builder.snapshot()).unwrap();
+        let snapshot: TxSnapshot = serde_json::from_str(&stored).unwrap();
+        assert_eq!(snapshot, transaction_builder.snapshot());
+
+        let mut restored = TransactionBuilder::restore(snapshot);
+
+        let original = transaction_builder
+            .build_transaction(signees![{"stream" => key.clone()}])
+            .unwrap();
+        let resumed = restored
+            .build_transaction(signees![{"stream" => key}])
+            .unwrap();
+
+        assert_eq!(original.packet(), resumed.packet());
+        assert_eq!(original.meta, resumed.meta);
+    }
+
+    #[test]
+    fn tx_sign_context() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let key2 = Key::Rsa(RSA::new("test2").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"test": {}, "test2": {}}))
+            .build()
+            .unwrap();
+
+        let mut options = SignOptions::new();
+        options.context("myapp:v2");
+
+        let mut transaction_builder = TransactionBuilder::new("namespace", "contract");
+        transaction_builder
+            .input(input)
+            .unwrap()
+            .sign_options(options.clone());
+
+        let signees = signees![{"test" => key.clone()}, {"test2" => key2.clone()}];
+
+        let tx = transaction_builder.build(signees.clone()).unwrap();
+
+        let mut writer: Vec<u8> = Vec::new();
+        transaction_builder
+            .build_to_writer(&mut writer, signees)
+            .unwrap();
+        let streamed = String::from_utf8(writer).unwrap();
+
+        let mut keys = std::collections::HashMap::new();
+        keys.insert(String::from("test"), key.public_key().unwrap());
+        keys.insert(String::from("test2"), key2.public_key().unwrap());
+
+        for tx in &[tx, streamed] {
+            TransactionBuilder::verify(tx, &keys, &options).unwrap();
+
+            match TransactionBuilder::verify(tx, &keys, &SignOptions::new()) {
+                Err(TxBuilderError::InvalidSignature(_)) => (),
+                _ => panic!("Signature without the context should not verify"),
+            }
+
+            let mut other = SignOptions::new();
+            other.context("otherapp");
+            assert!(TransactionBuilder::verify(tx, &keys, &other).is_err());
+        }
+    }
+
+    #[test]
+    fn tx_verifier_set() {
+        let key = Key::Ec(EllipticCurve::new("test").unwrap());
+        let admin = Key::Rsa(RSA::new("admin").unwrap());
+        let owner = Key::Ec(EllipticCurve::new("owner").unwrap());
+
+        let input = PacketBuilder::new(packet_data!({"test": {}, "multi": {}}))
+            .build()
+            .unwrap();
+
+        let mut options = SignOptions::new();
+        options.encoding(SignatureEncoding::Hex);
+
+        let mut signees = Signees::new();
+        signees
+            .add_with_options(key.clone(), "test", options.clone())
+            .add_multi(admin.clone(), "multi", "admin")
+            .add_multi(owner.clone(), "multi", "owner");
+
+        let tx = TransactionBuilder::new("namespace", "contract")
+            .input(input)
+            .unwrap()
+            .build(signees)
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("verifier-{}.pem", std::process::id()));
+        std::fs::write(&path, owner.public_pem().unwrap()).unwrap();
+
+        let mut verifiers = VerifierSet::new();
+        verifiers
+            .add_with_options(key.public_key().unwrap(), "test", options)
+            .add_multi(admin.public_key().unwrap(), "multi", "admin")
+            .add_from_pem_file(&path, "multi")
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // A key without a reference replaces the keys of the stream
+        assert_eq!(verifiers.len(), 2);
+        verifiers.add_multi(owner.public_key().unwrap(), "multi", "owner");
+        verifiers.add_multi(admin.public_key().unwrap(), "multi", "admin");
+        assert_eq!(verifiers.len(), 3);
+
+        verifiers.verify(&tx, &SignOptions::new()).unwrap();
+
+        verifiers.add_multi(key.public_key().unwrap(), "multi", "owner");
+        match verifiers.verify(&tx, &SignOptions::new()) {
+            Err(TxBuilderError::InvalidSignature(name)) => assert_eq!(name, "multi/owner"),
+            _ => panic!("Expected the wrong key to fail"),
+        }
+
+        verifiers.remove("multi");
+        verifiers.add(owner.public_key().unwrap(), "other");
+        match verifiers.verify(&tx, &SignOptions::new()) {
+            Err(TxBuilderError::MissingSignature(name)) => assert_eq!(name, "other"),
+            _ => panic!("Expected the missing signature to fail"),
+        }
+
+        verifiers.remove("other");
+        assert!(verifiers.contains("test"));
+        verifiers.verify(&tx, &SignOptions::new()).unwrap();
+    }
 }
@@ -0,0 +1,86 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Nonce
+//!
+//! Client generated message IDs, so a transaction that is submitted again can be recognised
+//! as the same transaction, and the IDs generated for packet data.
+
+// External imports
+use chrono::{DateTime, Utc};
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Crockford base32 alphabet used by ULIDs
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Generate a ULID for the given time
+pub fn ulid(time: DateTime<Utc>) -> TxBuilderResult<String> {
+    let mut random = [0; 10];
+
+    if let Err(e) = getrandom::getrandom(&mut random) {
+        return Err(TxBuilderError::RandomError(e));
+    }
+
+    Ok(encode(time, &random))
+}
+
+/// Generate a random (version 4) UUID
+#[cfg(feature = "ids")]
+pub fn uuid() -> TxBuilderResult<String> {
+    let mut bytes = [0; 16];
+
+    if let Err(e) = getrandom::getrandom(&mut bytes) {
+        return Err(TxBuilderError::RandomError(e));
+    }
+
+    // Version 4, RFC 4122 variant
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    ))
+}
+
+/// 48 bits of milliseconds since the epoch followed by 80 random bits, as 26 base32 characters
+fn encode(time: DateTime<Utc>, random: &[u8; 10]) -> String {
+    let millis = time.timestamp_millis().clamp(0, (1 << 48) - 1) as u128;
+
+    let value = random
+        .iter()
+        .fold(millis, |value, byte| (value << 8) | u128::from(*byte));
+
+    (0..26)
+        .rev()
+        .map(|i| ALPHABET[((value >> (i * 5)) & 31) as usize] as char)
+        .collect()
+}
@@ -0,0 +1,342 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+// Internal imports
+use super::profile::Profile;
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::{
+    BuiltTransaction, Key, KeyResolver, KeyType, PacketBuilder, PacketMap, PacketValue, Signees,
+    TransactionBuilder,
+};
+
+/// # Onboard transaction builder
+///
+/// Builds a self-signed onboarding transaction with more control than
+/// [`TransactionBuilder::onboard_tx`].
+///
+/// * The stream name in the input can differ from the key name
+/// * Additional identity fields such as "name" or "roles" can be added
+/// * The namespace and contract can be changed, they default to "default" and "onboard", and an
+///   entry can be set
+/// * Additional signees can be added
+///
+/// ```
+/// # #[cfg(feature = "openssl-keys")] {
+/// # use active_tx::{packet_data, Key, OnboardTxBuilder};
+/// # use activeledger::key::EllipticCurve;
+/// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+///
+/// let tx = OnboardTxBuilder::new(key)
+///     .name("device-0001")
+///     .metadata("name", "Temperature sensor")
+///     .metadata("roles", packet_data!(["sensor", "reporter"]))
+///     .build()
+///     .unwrap();
+/// # }
+/// ```
+///
+/// [`TransactionBuilder::onboard_tx`]: struct.TransactionBuilder.html#method.onboard_tx
+#[derive(Clone, Debug)]
+pub struct OnboardTxBuilder {
+    key: Key,
+    name: Option<String>,
+    metadata: PacketMap,
+    namespace: String,
+    contract: String,
+    entry: Option<String>,
+    signees: Signees,
+}
+
+/// # Batch mode
+///
+/// How [`TransactionBuilder::generate_onboard_batch`] onboards the generated keys.
+///
+/// [`TransactionBuilder::generate_onboard_batch`]: struct.TransactionBuilder.html#method.generate_onboard_batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    /// One transaction holding every key
+    Combined,
+    /// One transaction per key
+    Individual,
+}
+
+impl OnboardTxBuilder {
+    /// # New
+    ///
+    /// Create an onboarding builder for the given key.
+    pub fn new(key: Key) -> OnboardTxBuilder {
+        OnboardTxBuilder {
+            key,
+            name: None,
+            metadata: PacketMap::new(),
+            namespace: String::from("default"),
+            contract: String::from("onboard"),
+            entry: None,
+            signees: Signees::new(),
+        }
+    }
+
+    /// # From resolver
+    ///
+    /// Create an onboarding builder for the key with the given name, looked up using the
+    /// resolver.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, OnboardTxBuilder};
+    /// # use activeledger::key::EllipticCurve;
+    /// let resolver = |name: &str| Ok(Key::Ec(EllipticCurve::new(name).unwrap()));
+    ///
+    /// let tx = OnboardTxBuilder::from_resolver(&resolver, "keyname")
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub fn from_resolver(
+        resolver: &dyn KeyResolver,
+        name: &str,
+    ) -> TxBuilderResult<OnboardTxBuilder> {
+        let key = resolver.resolve(name)?;
+
+        Ok(OnboardTxBuilder::new(key))
+    }
+
+    /// # Name
+    ///
+    /// Set the name used for the key in the input, and for its signature.
+    /// Defaults to the key name.
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        self.name = Some(name.to_string());
+
+        self
+    }
+
+    /// # Metadata
+    ///
+    /// Add an identity field alongside the key type and public key.
+    /// The "type" and "publicKey" fields are always set from the key.
+    pub fn metadata<V: Into<PacketValue>>(&mut self, field: &str, value: V) -> &mut Self {
+        self.metadata.insert(field.to_string(), value.into());
+
+        self
+    }
+
+    /// # Merge metadata
+    ///
+    /// Add every field of an object as identity fields, such as a profile with the name,
+    /// organisation and roles of the identity. Fields already set are replaced.
+    /// The "type" and "publicKey" fields are always set from the key.
+    ///
+    /// An error is returned if the metadata is not an object.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{packet_data, Key, OnboardTxBuilder};
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let profile = packet_data!({
+    ///     "name": "Jane Doe",
+    ///     "organisation": "Activeledger",
+    ///     "roles": ["admin", "auditor"]
+    /// });
+    ///
+    /// let tx = OnboardTxBuilder::new(key)
+    ///     .merge_metadata(profile)
+    ///     .unwrap()
+    ///     .build()
+    ///     .unwrap();
+    /// # }
+    /// ```
+    pub fn merge_metadata(&mut self, metadata: PacketValue) -> TxBuilderResult<&mut Self> {
+        let metadata = match metadata {
+            PacketValue::Object(metadata) => metadata,
+            _ => return Err(TxBuilderError::PacketError(3003)),
+        };
+
+        self.metadata.extend(metadata);
+
+        Ok(self)
+    }
+
+    /// # Namespace
+    ///
+    /// Set the namespace, defaults to "default".
+    pub fn namespace(&mut self, namespace: &str) -> &mut Self {
+        self.namespace = namespace.to_string();
+
+        self
+    }
+
+    /// # Contract
+    ///
+    /// Set the contract, defaults to "onboard".
+    pub fn contract(&mut self, contract: &str) -> &mut Self {
+        self.contract = contract.to_string();
+
+        self
+    }
+
+    /// # With profile
+    ///
+    /// Use the namespace and onboarding contract in the profile, where they are set.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, OnboardTxBuilder, Profile};
+    /// # use activeledger::key::EllipticCurve;
+    /// let profile = Profile::from_json(r#"{"onboard_contract": "identity"}"#).unwrap();
+    ///
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// let tx = OnboardTxBuilder::new(key).with_profile(&profile).build().unwrap();
+    ///
+    /// assert!(tx.contains(r#""$contract":"identity""#));
+    /// # }
+    /// ```
+    pub fn with_profile(&mut self, profile: &Profile) -> &mut Self {
+        if let Some(namespace) = &profile.namespace {
+            self.namespace(namespace);
+        }
+
+        if let Some(contract) = &profile.onboard_contract {
+            self.contract(contract);
+        }
+
+        self
+    }
+
+    /// # Entry
+    ///
+    /// Set the contract entry point, none is set by default.
+    pub fn entry(&mut self, entry: &str) -> &mut Self {
+        self.entry = Some(entry.to_string());
+
+        self
+    }
+
+    /// # Signee
+    ///
+    /// Add another key to sign the transaction against the given stream ID.
+    /// The key being onboarded always signs under its own name.
+    pub fn signee(&mut self, key: Key, streamid: &str) -> &mut Self {
+        self.signees.add(key, streamid);
+
+        self
+    }
+
+    /// # Build
+    ///
+    /// Build the self-signed onboarding transaction, returning it as a string.
+    pub fn build(&self) -> TxBuilderResult<String> {
+        let (mut tx_builder, signees) = self.tx_builder()?;
+
+        tx_builder.build(signees)
+    }
+
+    /// # Build transaction
+    ///
+    /// Build the self-signed onboarding transaction, returning a [`BuiltTransaction`].
+    /// The identity is signed under its stream name, which is given by
+    /// [`stream_ids`](struct.BuiltTransaction.html#method.stream_ids), and the transaction
+    /// JSON holding the public key is given by
+    /// [`json`](struct.BuiltTransaction.html#method.json).
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, OnboardTxBuilder};
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    ///
+    /// let built = OnboardTxBuilder::new(key)
+    ///     .name("device-0001")
+    ///     .build_transaction()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(built.stream_ids(), vec!["device-0001"]);
+    ///
+    /// let json = built.json().unwrap();
+    /// assert!(json["$tx"]["$i"]["device-0001"]["publicKey"].is_string());
+    /// # }
+    /// ```
+    ///
+    /// [`BuiltTransaction`]: struct.BuiltTransaction.html
+    pub fn build_transaction(&self) -> TxBuilderResult<BuiltTransaction> {
+        let (mut tx_builder, signees) = self.tx_builder()?;
+
+        tx_builder.build_transaction(signees)
+    }
+}
+
+// Private functions
+impl OnboardTxBuilder {
+    /// Set up the transaction builder and signees for the onboarding transaction
+    fn tx_builder(&self) -> TxBuilderResult<(TransactionBuilder, Signees)> {
+        let name = match &self.name {
+            Some(name) => name.as_str(),
+            None => self.key.name(),
+        };
+
+        let identity = OnboardTxBuilder::identity(&self.key, self.metadata.clone())?;
+
+        let mut input = PacketMap::new();
+        input.insert(name.to_string(), identity);
+
+        let input = PacketBuilder::new(PacketValue::Object(input)).build()?;
+
+        let mut signees = self.signees.clone();
+        signees.add(self.key.clone(), name);
+
+        let mut tx_builder = TransactionBuilder::new(&self.namespace, &self.contract);
+
+        if let Some(entry) = &self.entry {
+            tx_builder.entry(entry.as_str());
+        }
+
+        tx_builder.selfsign().input(input)?;
+
+        Ok((tx_builder, signees))
+    }
+
+    /// Add the key type and public key to the identity fields
+    pub(super) fn identity(key: &Key, mut identity: PacketMap) -> TxBuilderResult<PacketValue> {
+        let pem = TransactionBuilder::get_pem(key)?;
+
+        // Added in key order, so the fields are in the same order with `preserve-order`
+        identity.insert(String::from("publicKey"), PacketValue::from(pem));
+        identity.insert(
+            String::from("type"),
+            PacketValue::from(OnboardTxBuilder::key_type_name(key.key_type())),
+        );
+
+        Ok(PacketValue::Object(identity))
+    }
+
+    /// The name the ledger uses for the key type
+    pub(super) fn key_type_name(key_type: KeyType) -> &'static str {
+        match key_type {
+            KeyType::RSA => "rsa",
+            KeyType::EC => "secp256k1",
+        }
+    }
+}
@@ -0,0 +1,177 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Profiles
+//!
+//! The settings that differ between environments, such as the namespace used in development
+//! and production, kept in a config file per environment instead of in constants.
+
+// STD
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// External imports
+use serde::{Deserialize, Serialize};
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::SignatureEncoding;
+
+/// # Profile
+///
+/// Per environment defaults, applied with [`TransactionBuilder::with_profile`] and
+/// [`OnboardTxBuilder::with_profile`]. Settings left out of the profile leave the builder as
+/// it is. The encryption flag is not applied by the builders, it tells the caller whether to
+/// encrypt sensitive fields with [`PacketBuilder::encrypt_field`].
+///
+/// Profiles are loaded from JSON, or from YAML and TOML with the `yaml` and `toml` features.
+///
+/// ```
+/// # use active_tx::{Profile, SignatureEncoding, TransactionBuilder};
+/// let profile = Profile::from_json(r#"{
+///     "namespace": "staging",
+///     "territoriality": "node1",
+///     "encoding": "Hex",
+///     "encrypt": true
+/// }"#).unwrap();
+///
+/// assert_eq!(profile.encoding, Some(SignatureEncoding::Hex));
+/// assert!(profile.encrypt);
+///
+/// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+/// tx_builder.with_profile(&profile);
+/// ```
+///
+/// [`TransactionBuilder::with_profile`]: struct.TransactionBuilder.html#method.with_profile
+/// [`OnboardTxBuilder::with_profile`]: struct.OnboardTxBuilder.html#method.with_profile
+/// [`PacketBuilder::encrypt_field`]: struct.PacketBuilder.html#method.encrypt_field
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct Profile {
+    /// Namespace used by transactions and onboarding
+    pub namespace: Option<String>,
+    /// Contract used for onboarding
+    pub onboard_contract: Option<String>,
+    /// Node the transaction is sent to, see `TransactionBuilder::territoriality`
+    pub territoriality: Option<String>,
+    /// How signatures are encoded into `$sigs`
+    pub encoding: Option<SignatureEncoding>,
+    /// Whether sensitive fields are encrypted in this environment, off by default
+    pub encrypt: bool,
+}
+
+impl Profile {
+    /// # New
+    ///
+    /// Create an empty profile.
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    /// # From JSON
+    ///
+    /// Parse a profile from a JSON string.
+    pub fn from_json(json: &str) -> TxBuilderResult<Profile> {
+        match serde_json::from_str(json) {
+            Ok(profile) => Ok(profile),
+            Err(e) => Err(TxBuilderError::ProfileFormatError(e)),
+        }
+    }
+
+    /// # From YAML
+    ///
+    /// Parse a profile from a YAML document. Requires the `yaml` feature.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> TxBuilderResult<Profile> {
+        match serde_yaml::from_str(yaml) {
+            Ok(profile) => Ok(profile),
+            Err(e) => Err(TxBuilderError::YamlError(e)),
+        }
+    }
+
+    /// # From TOML
+    ///
+    /// Parse a profile from a TOML document. Requires the `toml` feature.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(source: &str) -> TxBuilderResult<Profile> {
+        match toml::from_str(source) {
+            Ok(profile) => Ok(profile),
+            Err(e) => Err(TxBuilderError::TomlError(e)),
+        }
+    }
+
+    /// # From file
+    ///
+    /// Read a profile from a file. Files ending `.yaml` or `.yml` are read as YAML and files
+    /// ending `.toml` as TOML, any other file is read as JSON. Reading a YAML or TOML file
+    /// without its feature enabled returns an error naming the feature.
+    ///
+    /// ```
+    /// # use active_tx::Profile;
+    /// # let name = format!("active_tx_profile_doc_{}.json", std::process::id());
+    /// # let path = std::env::temp_dir().join(name);
+    /// # std::fs::write(&path, r#"{"namespace": "production"}"#).unwrap();
+    /// let profile = Profile::from_file(&path).unwrap();
+    ///
+    /// assert_eq!(profile.namespace.as_deref(), Some("production"));
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> TxBuilderResult<Profile> {
+        let path = path.as_ref();
+
+        let missing = match path.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") if !cfg!(feature = "yaml") => Some("yaml"),
+            Some("toml") if !cfg!(feature = "toml") => Some("toml"),
+            _ => None,
+        };
+
+        if let Some(feature) = missing {
+            return Err(TxBuilderError::ProfileFileError {
+                path: path.to_path_buf(),
+                source: io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("the `{}` feature is required to read this file", feature),
+                ),
+            });
+        }
+
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(source) => {
+                return Err(TxBuilderError::ProfileFileError {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+
+        match path.extension().and_then(|e| e.to_str()) {
+            #[cfg(feature = "yaml")]
+            Some("yaml") | Some("yml") => Profile::from_yaml(&source),
+            #[cfg(feature = "toml")]
+            Some("toml") => Profile::from_toml(&source),
+            _ => Profile::from_json(&source),
+        }
+    }
+}
@@ -21,19 +21,43 @@
  * SOFTWARE.
  */
 
-use crate::Key;
+// STD
+#[cfg(feature = "openssl-keys")]
+use std::collections::HashMap;
+#[cfg(feature = "openssl-keys")]
+use std::fs;
+#[cfg(feature = "openssl-keys")]
+use std::path::Path;
+use std::sync::Arc;
+
+// External imports
+#[cfg(feature = "openssl-keys")]
+use zeroize::Zeroizing;
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::packet_builder::validate_stream_id;
+#[cfg(feature = "openssl-keys")]
+use crate::KeyType;
+use crate::{Key, KeyResolver, SignOptions, SigneeSpec, StreamId};
 
 /// Holds an array of Signees
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct Signees {
     keys: Vec<Signee>,
 }
 
 /// Holds the key and stream ID for use when signing the transaction packet.
-#[derive(Clone)]
+///
+/// The key is reference counted so a key shared by many transactions is not copied for each one.
+#[derive(Clone, Debug)]
 pub struct Signee {
     pub streamid: String,
-    pub key: Key,
+    pub key: Arc<Key>,
+    /// Options for this key, overriding those set on the builder
+    pub options: Option<SignOptions>,
+    /// Reference for this key when a stream is signed by several keys
+    pub key_ref: Option<String>,
 }
 
 /// # Signees
@@ -55,18 +79,141 @@ impl Signees {
         Signees { keys: vec![] }
     }
 
+    /// # From shared keys
+    ///
+    /// Create Signees from keys that are already shared, paired with their stream IDs.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, Signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap()).into_shared();
+    ///
+    /// let signees = Signees::from_shared(vec![(key, "streamid")]);
+    /// # }
+    /// ```
+    pub fn from_shared<I, S>(keys: I) -> Signees
+    where
+        I: IntoIterator<Item = (Arc<Key>, S)>,
+        S: AsRef<str>,
+    {
+        let mut signees = Signees::new();
+
+        for (key, streamid) in keys {
+            signees.add(key, streamid.as_ref());
+        }
+
+        signees
+    }
+
     /// # Add
     ///
     /// This method is the general add method.
     /// It takes a key and a stream id, the stream id must match one provided in the input ($i) of
     /// the transaction packet.
-    pub fn add(&mut self, key: Key, streamid: &str) -> &mut Self {
+    ///
+    /// If the stream id has already been added its key is replaced, keeping its original position.
+    ///
+    /// The key can be given as an `Arc<Key>` to share one key between many Signees without
+    /// copying it.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use std::sync::Arc;
+    /// # use active_tx::{Key, Signees};
+    /// # use activeledger::key::RSA;
+    /// let key = Arc::new(Key::Rsa(RSA::new("keyname").unwrap()));
+    ///
+    /// for _ in 0..3 {
+    ///     let mut signees = Signees::new();
+    ///     signees.add(Arc::clone(&key), "streamid");
+    /// }
+    /// # }
+    /// ```
+    pub fn add<K: Into<Arc<Key>>, S: Into<StreamId>>(&mut self, key: K, streamid: S) -> &mut Self {
         let signee = Signee {
-            streamid: streamid.to_string(),
-            key,
+            streamid: streamid.into().into_string(),
+            key: key.into(),
+            options: None,
+            key_ref: None,
+        };
+
+        self.insert(signee);
+
+        self
+    }
+
+    /// # Add multi-key
+    ///
+    /// Add one of several keys for a stream that has a multi-key identity.
+    /// The stream's entry in `$sigs` becomes an object of key reference to signature.
+    ///
+    /// Adding the same key reference again replaces its key. Adding a key for the stream
+    /// with [`add`](#method.add) replaces all of its multi-key entries.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, Signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("key1").unwrap());
+    /// let key2 = Key::Ec(EllipticCurve::new("key2").unwrap());
+    ///
+    /// let mut signees = Signees::new();
+    /// signees
+    ///     .add_multi(key, "streamid", "key1")
+    ///     .add_multi(key2, "streamid", "key2");
+    /// # }
+    /// ```
+    pub fn add_multi<K: Into<Arc<Key>>, S: Into<StreamId>>(
+        &mut self,
+        key: K,
+        streamid: S,
+        key_ref: &str,
+    ) -> &mut Self {
+        let signee = Signee {
+            streamid: streamid.into().into_string(),
+            key: key.into(),
+            options: None,
+            key_ref: Some(key_ref.to_string()),
+        };
+
+        self.insert(signee);
+
+        self
+    }
+
+    /// # Add with options
+    ///
+    /// Add a key against the stream id, signing with the given options instead of those set on
+    /// the builder.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, RsaPadding, SignOptions, Signees};
+    /// # use activeledger::key::RSA;
+    /// let key = Key::Rsa(RSA::new("keyname").unwrap());
+    ///
+    /// let mut options = SignOptions::new();
+    /// options.rsa_padding(RsaPadding::Pss);
+    ///
+    /// let mut signees = Signees::new();
+    /// signees.add_with_options(key, "streamid", options);
+    /// # }
+    /// ```
+    pub fn add_with_options<K: Into<Arc<Key>>, S: Into<StreamId>>(
+        &mut self,
+        key: K,
+        streamid: S,
+        options: SignOptions,
+    ) -> &mut Self {
+        let signee = Signee {
+            streamid: streamid.into().into_string(),
+            key: key.into(),
+            options: Some(options),
+            key_ref: None,
         };
 
-        self.keys.push(signee);
+        self.insert(signee);
 
         self
     }
@@ -76,18 +223,20 @@ impl Signees {
     /// This method is use for transactions that will be selfsigned.
     /// Instead of storing a related stream id it will use the keys name. This must still have a
     /// corresponding match in the input of the transaction packet.
-    pub fn add_selfsign(&mut self, key: Key) -> &mut Self {
-        let name = match &key {
-            Key::Ec(key) => key.name.clone().to_string(),
-            Key::Rsa(key) => key.name.clone().to_string(),
-        };
+    ///
+    /// As with [`add`](#method.add), an existing entry for the same name is replaced.
+    pub fn add_selfsign<K: Into<Arc<Key>>>(&mut self, key: K) -> &mut Self {
+        let key = key.into();
+        let name = key.name().to_string();
 
         let signee = Signee {
             streamid: name,
             key,
+            options: None,
+            key_ref: None,
         };
 
-        self.keys.push(signee);
+        self.insert(signee);
 
         self
     }
@@ -99,4 +248,278 @@ impl Signees {
     pub fn get(&self) -> Vec<Signee> {
         self.keys.clone()
     }
+
+    /// # Add resolved
+    ///
+    /// Look up the key with the given name using the resolver and add it against the stream id.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, Signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// let resolver = |name: &str| Ok(Key::Ec(EllipticCurve::new(name).unwrap()));
+    ///
+    /// let mut signees = Signees::new();
+    /// signees.add_resolved(&resolver, "keyname", "streamid").unwrap();
+    /// # }
+    /// ```
+    pub fn add_resolved(
+        &mut self,
+        resolver: &dyn KeyResolver,
+        name: &str,
+        streamid: &str,
+    ) -> TxBuilderResult<&mut Self> {
+        let key = resolver.resolve(name)?;
+
+        Ok(self.add(key, streamid))
+    }
+
+    /// # From specs
+    ///
+    /// Create Signees from specs, such as those read from a config file, using the resolver to
+    /// find each key. Keys found by `pem_path_or_id` are named with the spec's key name, as are
+    /// all other keys apart from external signers. An error is returned if a key can't be
+    /// resolved or is not of the type given in its spec.
+    ///
+    /// ```
+    /// # #[cfg(feature = "openssl-keys")] {
+    /// # use active_tx::{Key, SigneeSpec, Signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// let spec = SigneeSpec {
+    ///     streamid: "streamid".to_string(),
+    ///     key_name: "keyname".to_string(),
+    ///     key_type: None,
+    ///     pem_path_or_id: None,
+    /// };
+    ///
+    /// let resolver = |name: &str| Ok(Key::Ec(EllipticCurve::new(name).unwrap()));
+    ///
+    /// let signees = Signees::from_specs(&[spec], &resolver).unwrap();
+    /// # }
+    /// ```
+    pub fn from_specs(
+        specs: &[SigneeSpec],
+        resolver: &dyn KeyResolver,
+    ) -> TxBuilderResult<Signees> {
+        let mut signees = Signees::new();
+
+        for spec in specs {
+            let name = spec.pem_path_or_id.as_deref().unwrap_or(&spec.key_name);
+            let mut key = resolver.resolve(name)?;
+            key.set_name(&spec.key_name);
+
+            if let Some(key_type) = spec.key_type {
+                if key.key_type() != key_type {
+                    return Err(TxBuilderError::KeyTypeMismatch { expected: key_type });
+                }
+            }
+
+            signees.add(key, &spec.streamid);
+        }
+
+        Ok(signees)
+    }
+
+    /// # Add from PEM file
+    ///
+    /// Load a PKCS#8 private key PEM from the given file and add it against the stream id.
+    /// The key type (RSA or EC) is detected from the PEM.
+    ///
+    /// ```no_run
+    /// # use active_tx::Signees;
+    /// let mut signees = Signees::new();
+    /// signees.add_from_pem_file("/path/to/key.pem", "streamid").unwrap();
+    /// ```
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    #[cfg(feature = "openssl-keys")]
+    pub fn add_from_pem_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        streamid: &str,
+    ) -> TxBuilderResult<&mut Self> {
+        let path = path.as_ref();
+
+        let pem = match fs::read_to_string(path) {
+            Ok(pem) => Zeroizing::new(pem),
+            Err(source) => {
+                return Err(TxBuilderError::KeyFileError {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+
+        let key = Key::from_pem(streamid, &pem)?;
+
+        Ok(self.add(key, streamid))
+    }
+
+    /// # Add from PEM string
+    ///
+    /// Add a PKCS#8 private key PEM against the stream id.
+    /// An error is returned if the PEM does not hold a key of the given type.
+    ///
+    /// ```
+    /// # use active_tx::{KeyType, Signees};
+    /// # use activeledger::key::EllipticCurve;
+    /// # let pem = EllipticCurve::new("key").unwrap().get_pem().unwrap().private;
+    /// let mut signees = Signees::new();
+    /// signees.add_from_pem_str(&pem, "streamid", KeyType::EC).unwrap();
+    /// ```
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    #[cfg(feature = "openssl-keys")]
+    pub fn add_from_pem_str(
+        &mut self,
+        pem: &str,
+        streamid: &str,
+        key_type: KeyType,
+    ) -> TxBuilderResult<&mut Self> {
+        let key = Key::from_pem(streamid, pem)?;
+
+        if key.key_type() != key_type {
+            return Err(TxBuilderError::KeyTypeMismatch { expected: key_type });
+        }
+
+        Ok(self.add(key, streamid))
+    }
+
+    /// # Remove
+    ///
+    /// Remove the signees for the given stream id, returning the first if any were present.
+    pub fn remove(&mut self, streamid: &str) -> Option<Signee> {
+        let index = self.keys.iter().position(|s| s.streamid == streamid)?;
+        let signee = self.keys.remove(index);
+
+        self.keys.retain(|s| s.streamid != streamid);
+
+        Some(signee)
+    }
+
+    /// # Contains
+    ///
+    /// Check whether a signee has been added for the given stream id.
+    pub fn contains(&self, streamid: &str) -> bool {
+        self.keys.iter().any(|s| s.streamid == streamid)
+    }
+
+    /// # Length
+    ///
+    /// Get the number of signees.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// # Is empty
+    ///
+    /// Check whether any signees have been added.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// # Iterate
+    ///
+    /// Iterate over the signees in the order they were added.
+    pub fn iter(&self) -> std::slice::Iter<'_, Signee> {
+        self.keys.iter()
+    }
+
+    /// # Check stream IDs
+    ///
+    /// Check the stream ID of every signee, rejecting empty IDs and IDs with whitespace or
+    /// control characters. Strict checks only accept ledger stream IDs, as
+    /// [`StreamId::new`](struct.StreamId.html#method.new) does. Transaction builders check the
+    /// signees of transactions that are not selfsigned when building and signing.
+    ///
+    /// ```
+    /// # use active_tx::{signees, FnSigner, KeyType};
+    /// # let signer = FnSigner::new("key", KeyType::EC, |_: &str| Ok(String::new()));
+    /// let signees = signees![{"order 1" => signer}];
+    ///
+    /// assert!(signees.check_stream_ids(false).is_err());
+    /// ```
+    pub fn check_stream_ids(&self, strict: bool) -> TxBuilderResult<()> {
+        for signee in &self.keys {
+            validate_stream_id(&signee.streamid, strict)?;
+        }
+
+        Ok(())
+    }
+
+    /// # Fingerprints
+    ///
+    /// Get the fingerprint of each signees key, see [`Key::fingerprint`].
+    /// Keys are given by stream ID, or as `streamid/keyref` for streams signed by several keys,
+    /// matching the keys taken by [`TransactionBuilder::verify`].
+    ///
+    /// Requires the `openssl-keys` feature, which is enabled by default.
+    ///
+    /// ```
+    /// # use active_tx::{signees, Key};
+    /// # use activeledger::key::EllipticCurve;
+    /// let key = Key::Ec(EllipticCurve::new("keyname").unwrap());
+    /// let signees = signees![{"streamid" => key.clone()}];
+    ///
+    /// let fingerprints = signees.fingerprints().unwrap();
+    ///
+    /// assert_eq!(fingerprints["streamid"], key.fingerprint().unwrap());
+    /// ```
+    ///
+    /// [`Key::fingerprint`]: enum.Key.html#method.fingerprint
+    /// [`TransactionBuilder::verify`]: struct.TransactionBuilder.html#method.verify
+    #[cfg(feature = "openssl-keys")]
+    pub fn fingerprints(&self) -> TxBuilderResult<HashMap<String, String>> {
+        let mut fingerprints = HashMap::with_capacity(self.keys.len());
+
+        for signee in &self.keys {
+            let name = match &signee.key_ref {
+                Some(key_ref) => format!("{}/{}", signee.streamid, key_ref),
+                None => signee.streamid.clone(),
+            };
+
+            fingerprints.insert(name, signee.key.fingerprint()?);
+        }
+
+        Ok(fingerprints)
+    }
+}
+
+impl Default for Signees {
+    fn default() -> Self {
+        Signees::new()
+    }
+}
+
+impl<'a> IntoIterator for &'a Signees {
+    type Item = &'a Signee;
+    type IntoIter = std::slice::Iter<'a, Signee>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// Private functions
+impl Signees {
+    /// Add the signee, replacing any existing entry for the same stream id.
+    /// Multi-key entries only replace the entry with the same key reference, and any single key.
+    fn insert(&mut self, signee: Signee) {
+        let replaces = |s: &Signee| match &signee.key_ref {
+            Some(_) => {
+                s.streamid == signee.streamid
+                    && (s.key_ref.is_none() || s.key_ref == signee.key_ref)
+            }
+            None => s.streamid == signee.streamid,
+        };
+
+        // Take the place of the first entry replaced
+        let index = self.keys.iter().position(&replaces);
+        self.keys.retain(|s| !replaces(s));
+
+        match index {
+            Some(index) => self.keys.insert(index, signee),
+            None => self.keys.push(signee),
+        }
+    }
 }
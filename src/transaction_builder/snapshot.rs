@@ -0,0 +1,87 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Transaction snapshot
+//!
+//! A serializable copy of everything set on a transaction builder before it is built, so a
+//! half built transaction can be stored and picked up again, possibly by another process.
+
+// STD
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// External imports
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Internal imports
+use super::NodeReferences;
+use crate::compat::CompatMode;
+use crate::SignOptions;
+
+/// # Transaction snapshot
+///
+/// Created by [`TransactionBuilder::snapshot`] and turned back into a builder with
+/// [`TransactionBuilder::restore`].
+///
+/// Keys are never part of a snapshot, signees are given when the restored builder is built.
+/// The clock and event callback can't be serialized either and have to be set again.
+///
+/// ```
+/// # use active_tx::{TransactionBuilder, TxSnapshot};
+/// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+/// tx_builder.entry("create");
+///
+/// let stored = serde_json::to_string(&tx_builder.snapshot()).unwrap();
+///
+/// // Later, possibly on another worker
+/// let snapshot: TxSnapshot = serde_json::from_str(&stored).unwrap();
+/// let tx_builder = TransactionBuilder::restore(snapshot);
+/// ```
+///
+/// [`TransactionBuilder::snapshot`]: struct.TransactionBuilder.html#method.snapshot
+/// [`TransactionBuilder::restore`]: struct.TransactionBuilder.html#method.restore
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TxSnapshot {
+    pub(super) packet_data: HashMap<String, Value>,
+    pub(super) tx_data: HashMap<String, Value>,
+    #[serde(default)]
+    pub(super) packet_fields: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub(super) envelope_fields: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub(super) meta: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub(super) allowed_entries: Option<BTreeSet<String>>,
+    #[serde(default)]
+    pub(super) nodes: Option<NodeReferences>,
+    #[serde(default)]
+    pub(super) sign_options: SignOptions,
+    #[serde(default)]
+    pub(super) compat: CompatMode,
+    #[serde(default)]
+    pub(super) max_size: Option<usize>,
+    #[serde(default)]
+    pub(super) nonce_field: Option<String>,
+    #[serde(default)]
+    pub(super) strict_stream_ids: bool,
+}
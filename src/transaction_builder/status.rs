@@ -0,0 +1,165 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Transaction status
+//!
+//! A summary of what has been set on a transaction builder, for showing progress while a
+//! transaction is put together, and the state of the builder.
+
+// STD
+use std::fmt;
+
+/// # Transaction status
+///
+/// Returned by [`TransactionBuilder::status`].
+///
+/// The namespace, contract and input are required, everything else is optional.
+///
+/// ```
+/// # use active_tx::{packet_data, PacketBuilder, TransactionBuilder};
+/// let mut tx_builder = TransactionBuilder::new_blank();
+/// tx_builder.namespace("namespace").contract("contract");
+///
+/// let status = tx_builder.status();
+/// assert!(!status.is_ready());
+/// assert_eq!(status.missing(), vec!["input"]);
+///
+/// let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+/// tx_builder.input(input).unwrap();
+///
+/// assert!(tx_builder.status().is_ready());
+/// ```
+///
+/// [`TransactionBuilder::status`]: struct.TransactionBuilder.html#method.status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TxStatus {
+    pub namespace: bool,
+    pub contract: bool,
+    pub entry: bool,
+    pub input: bool,
+    pub output: bool,
+    pub readonly: bool,
+    pub territoriality: bool,
+    pub selfsign: bool,
+    /// Whether the transaction has been built, changes made since are only included once it is
+    /// built again
+    pub built: bool,
+    /// The number of signatures, counting each key of a multi-signature stream
+    pub sigs: usize,
+}
+
+impl TxStatus {
+    /// # Missing
+    ///
+    /// Get the names of the required sections that are not set.
+    pub fn missing(&self) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+
+        for &(set, name) in &[
+            (self.namespace, "namespace"),
+            (self.contract, "contract"),
+            (self.input, "input"),
+        ] {
+            if !set {
+                missing.push(name);
+            }
+        }
+
+        missing
+    }
+
+    /// # Is ready
+    ///
+    /// Whether every required section is set, so the transaction can be built.
+    /// The sections are not validated, see
+    /// [`TransactionBuilder::validate`](struct.TransactionBuilder.html#method.validate).
+    pub fn is_ready(&self) -> bool {
+        self.namespace && self.contract && self.input
+    }
+}
+
+/// # Transaction state
+///
+/// Where a [`TransactionBuilder`] is between being set up and holding a signed transaction,
+/// returned by [`TransactionBuilder::state`].
+///
+/// The transitions are:
+///
+/// * `Draft` to `Signed` - [`build`], or to `Built` when there are no signees
+/// * `Built` or `Signed` to `Signed` - [`sign`]
+/// * `Signed` to `Built` - [`clear_sigs`]
+/// * Any state to `Draft` - [`reset`], or a build failing the [max size] check
+///
+/// Building again from `Built` or `Signed` signs a new packet, replacing the signatures.
+/// Changes to the packet data after a build are only included once it is built again.
+///
+/// Getting the transaction, its packet or hash and signing need the builder to be `Built` or
+/// `Signed`, in the `Draft` state they return `InvalidState`. A transaction written with
+/// [`build_to_writer`] leaves the builder `Built` or `Signed` without keeping the packet, so
+/// these calls return `InvalidState` until it is built again.
+///
+/// ```
+/// # #[cfg(feature = "openssl-keys")] {
+/// # use active_tx::{packet_data, signees, Key, PacketBuilder, Signees, TransactionBuilder, TxState};
+/// # use activeledger::key::EllipticCurve;
+/// # let input = PacketBuilder::new(packet_data!({"id": "data"})).build().unwrap();
+/// # let key = Key::Ec(EllipticCurve::new("id").unwrap());
+/// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+/// tx_builder.input(input).unwrap();
+/// assert_eq!(tx_builder.state(), TxState::Draft);
+///
+/// tx_builder.build(Signees::new()).unwrap();
+/// assert_eq!(tx_builder.state(), TxState::Built);
+///
+/// tx_builder.sign(signees![{"id" => key}]).unwrap();
+/// assert_eq!(tx_builder.state(), TxState::Signed);
+/// # }
+/// ```
+///
+/// [`TransactionBuilder`]: struct.TransactionBuilder.html
+/// [`TransactionBuilder::state`]: struct.TransactionBuilder.html#method.state
+/// [`build`]: struct.TransactionBuilder.html#method.build
+/// [`sign`]: struct.TransactionBuilder.html#method.sign
+/// [`clear_sigs`]: struct.TransactionBuilder.html#method.clear_sigs
+/// [`build_to_writer`]: struct.TransactionBuilder.html#method.build_to_writer
+/// [`reset`]: struct.TransactionBuilder.html#method.reset
+/// [max size]: struct.TransactionBuilder.html#method.max_size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxState {
+    /// Nothing has been built yet
+    Draft,
+    /// The packet is built but has no signatures
+    Built,
+    /// The packet is built and signed
+    Signed,
+}
+
+impl fmt::Display for TxState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxState::Draft => write!(f, "draft"),
+            TxState::Built => write!(f, "built"),
+            TxState::Signed => write!(f, "signed"),
+        }
+    }
+}
@@ -0,0 +1,181 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Streaming signer
+//!
+//! Signs a packet as it is serialized so large packets never need to be held as a string.
+//! External signers can only sign a whole packet, so it is buffered for them.
+
+// STD
+use std::io::{self, Write};
+
+// External imports
+use activeledger::key::KeyError;
+use openssl::pkey::{PKey, Private};
+use openssl::sign::Signer as OpensslSigner;
+
+// Internal imports
+use super::body::{add_sig, Sigs};
+use super::Signee;
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::{Key, SignOptions, Signer};
+
+/// The key for a signee, loaded before signing starts
+pub enum StreamKey<'a> {
+    Openssl(&'a Signee, PKey<Private>),
+    External(&'a Signee, &'a dyn Signer),
+}
+
+/// Signs the data written for one signee
+enum StreamSigner<'a> {
+    Openssl(OpensslSigner<'a>, SignOptions),
+    External(&'a dyn Signer, Vec<u8>, SignOptions),
+}
+
+/// Feeds everything written to it into a signer for each signee
+pub struct PacketSigner<'a> {
+    signers: Vec<(&'a Signee, StreamSigner<'a>)>,
+    failed: Option<&'a Signee>,
+}
+
+impl<'a> StreamKey<'a> {
+    pub fn new(signee: &'a Signee) -> TxBuilderResult<StreamKey<'a>> {
+        match signee.key.as_ref() {
+            Key::External(signer) => Ok(StreamKey::External(signee, signer.as_ref())),
+            key => Ok(StreamKey::Openssl(signee, key.pkey()?)),
+        }
+    }
+}
+
+impl<'a> PacketSigner<'a> {
+    pub fn new(
+        keys: &'a [StreamKey<'a>],
+        options: &SignOptions,
+    ) -> TxBuilderResult<PacketSigner<'a>> {
+        let mut signers = Vec::with_capacity(keys.len());
+
+        for key in keys {
+            let (signee, signer) = match key {
+                StreamKey::Openssl(signee, pkey) => {
+                    let options = signee.options.as_ref().unwrap_or(options).clone();
+
+                    let mut signer = match options.signer(pkey) {
+                        Ok(signer) => signer,
+                        Err(_) => return Err(PacketSigner::error(signee, 2000)),
+                    };
+
+                    // The packet is wrapped in the context tag as it is written
+                    if let Some((prefix, _)) = options.context_wrapper() {
+                        if signer.update(prefix.as_bytes()).is_err() {
+                            return Err(PacketSigner::error(signee, 2001));
+                        }
+                    }
+
+                    (*signee, StreamSigner::Openssl(signer, options))
+                }
+                StreamKey::External(signee, signer) => {
+                    let options = signee.options.as_ref().unwrap_or(options).clone();
+
+                    (
+                        *signee,
+                        StreamSigner::External(*signer, Vec::new(), options),
+                    )
+                }
+            };
+
+            signers.push((signee, signer));
+        }
+
+        Ok(PacketSigner {
+            signers,
+            failed: None,
+        })
+    }
+
+    /// The signing error for the signee that failed while data was written, if any
+    pub fn failure(&self) -> Option<TxBuilderError> {
+        self.failed.map(|signee| PacketSigner::error(signee, 2001))
+    }
+
+    /// Finish signing, returning the encoded signatures by stream ID
+    pub fn finish(self) -> TxBuilderResult<Sigs> {
+        let mut sigs = Sigs::new();
+
+        for (signee, signer) in self.signers {
+            let signature = match signer {
+                StreamSigner::Openssl(mut signer, options) => {
+                    if let Some((_, suffix)) = options.context_wrapper() {
+                        if signer.update(suffix.as_bytes()).is_err() {
+                            return Err(PacketSigner::error(signee, 2001));
+                        }
+                    }
+
+                    match options.finish(signer, signee.key.key_type()) {
+                        Ok(signature) => options.encode(&signature),
+                        Err(_) => return Err(PacketSigner::error(signee, 2002)),
+                    }
+                }
+                StreamSigner::External(signer, data, options) => {
+                    // The packet is written as JSON so is always valid UTF-8
+                    signer.sign(&options.signed_data(&String::from_utf8_lossy(&data)))?
+                }
+            };
+
+            add_sig(&mut sigs, signee, signature);
+        }
+
+        Ok(sigs)
+    }
+
+    /// Signing errors use the same codes as the activeledger key signing errors
+    fn error(signee: &Signee, code: u16) -> TxBuilderError {
+        TxBuilderError::SigningError {
+            streamid: signee.streamid.clone(),
+            key_type: signee.key.key_type(),
+            source: KeyError::SigningError(code),
+        }
+    }
+}
+
+impl<'a> Write for PacketSigner<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for (signee, signer) in self.signers.iter_mut() {
+            match signer {
+                StreamSigner::Openssl(signer, _) => {
+                    if let Err(e) = signer.update(buf) {
+                        self.failed = Some(*signee);
+
+                        return Err(io::Error::other(e));
+                    }
+                }
+                StreamSigner::External(_, data, _) => data.extend_from_slice(buf),
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Transaction Template
+//!
+//! Templates hold the static parts of a transaction so that many transactions of the same
+//! shape can be built without re-running the whole builder for each one.
+
+// STD
+use std::collections::BTreeMap;
+
+// External imports
+use serde_json::Value;
+
+// Internal imports
+use super::body::{add_sig, Sigs, TransactionEnvelope};
+use crate::compat::{self, CompatMode};
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::packet_builder::Input;
+use crate::{SignOptions, Signees, TransactionBuilder};
+
+/// # Transaction template
+///
+/// Holds a partially built transaction: namespace, contract, entry, output, readonly,
+/// territoriality and selfsign. Instances are created by supplying the input data and signees.
+///
+/// Templates are created from a configured [`TransactionBuilder`] using its
+/// [`template`](struct.TransactionBuilder.html#method.template) method.
+///
+/// ```
+/// # #[cfg(feature = "openssl-keys")] {
+/// # use active_tx::{PacketBuilder, TransactionBuilder, Key, packet_data, signees};
+/// # use activeledger::key::EllipticCurve;
+/// let key = Key::Ec(EllipticCurve::new("streamid").unwrap());
+///
+/// let template = TransactionBuilder::new("namespace", "contract")
+///     .entry("reading")
+///     .template()
+///     .unwrap();
+///
+/// for reading in 0..3 {
+///     let input = PacketBuilder::new(packet_data!({"streamid": {"reading": reading}}))
+///         .build()
+///         .unwrap();
+///
+///     let tx = template.instantiate(input, signees!(key.clone())).unwrap();
+/// }
+/// # }
+/// ```
+///
+/// [`TransactionBuilder`]: struct.TransactionBuilder.html
+#[derive(Debug, Clone)]
+pub struct TxTemplate {
+    // $tx object without $i
+    packet: Value,
+
+    // Envelope without $tx and $sigs
+    envelope: BTreeMap<String, Value>,
+
+    sign_options: SignOptions,
+
+    // How the packet is serialized for signing
+    compat: CompatMode,
+}
+
+impl TxTemplate {
+    /// Create a template from a prepared packet and envelope
+    pub(super) fn new(
+        packet: Value,
+        envelope: BTreeMap<String, Value>,
+        sign_options: SignOptions,
+        compat: CompatMode,
+    ) -> TxTemplate {
+        TxTemplate {
+            packet,
+            envelope,
+            sign_options,
+            compat,
+        }
+    }
+
+    /// # Instantiate
+    ///
+    /// Build a transaction from the template using the given input data, signed by the signees.
+    /// Returns the transaction as a string.
+    pub fn instantiate(&self, input: Input, signees: Signees) -> TxBuilderResult<String> {
+        let input = match input.into_json() {
+            Ok(input) => input,
+            Err(e) => {
+                return Err(TxBuilderError::SectionError {
+                    section: "input",
+                    source: Box::new(e),
+                })
+            }
+        };
+
+        let mut packet = self.packet.clone();
+        packet["$i"] = input;
+
+        let packet = compat::packet(self.compat, &packet)?;
+
+        let mut sigs = Sigs::new();
+
+        for signee in signees.iter() {
+            let signature =
+                TransactionBuilder::sign_internal(packet.get(), signee, &self.sign_options)?;
+            add_sig(&mut sigs, signee, signature);
+        }
+
+        let envelope = TransactionEnvelope {
+            broadcast: None,
+            selfsign: None,
+            sigs: &sigs,
+            territoriality: None,
+            tx: &packet,
+            extra: &self.envelope,
+        };
+
+        envelope.build()
+    }
+}
@@ -0,0 +1,145 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Territoriality
+//!
+//! Territoriality must reference a node in the network. These types catch obviously invalid
+//! values before the transaction reaches consensus, and optionally check the value against a
+//! table of node references supplied by the caller.
+
+// STD
+use std::collections::HashMap;
+use std::fmt;
+
+// External imports
+use serde::{Deserialize, Serialize};
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+
+/// Maximum length of a node reference
+const MAX_LENGTH: usize = 256;
+
+/// A validated territoriality value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Territoriality(String);
+
+/// # Node references
+///
+/// A lookup table of known nodes, mapping a friendly name to the node reference.
+///
+/// ```
+/// # use active_tx::NodeReferences;
+/// let mut nodes = NodeReferences::new();
+/// nodes.add("node1", "127.0.0.1:5260");
+///
+/// assert_eq!(nodes.resolve("node1"), Some("127.0.0.1:5260"));
+/// assert_eq!(nodes.resolve("127.0.0.1:5260"), Some("127.0.0.1:5260"));
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeReferences {
+    nodes: HashMap<String, String>,
+}
+
+impl Territoriality {
+    /// # New
+    ///
+    /// Validate the format of a territoriality value.
+    /// The value must not be empty, contain whitespace or control characters, or be longer
+    /// than 256 characters.
+    ///
+    /// ```
+    /// # use active_tx::Territoriality;
+    /// let territoriality = Territoriality::new("127.0.0.1:5260").unwrap();
+    ///
+    /// assert!(Territoriality::new("bad value").is_err());
+    /// ```
+    pub fn new(value: &str) -> TxBuilderResult<Territoriality> {
+        let reason = if value.is_empty() {
+            Some("empty")
+        } else if value.len() > MAX_LENGTH {
+            Some("too long")
+        } else if value.chars().any(|c| c.is_whitespace()) {
+            Some("contains whitespace")
+        } else if value.chars().any(|c| c.is_control()) {
+            Some("contains control characters")
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => Err(TxBuilderError::InvalidTerritoriality {
+                value: value.to_string(),
+                reason,
+            }),
+            None => Ok(Territoriality(value.to_string())),
+        }
+    }
+
+    /// # As string
+    ///
+    /// Get the territoriality value.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Territoriality {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl NodeReferences {
+    /// # New
+    ///
+    /// Create an empty lookup table.
+    pub fn new() -> NodeReferences {
+        NodeReferences {
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// # Add
+    ///
+    /// Add a node reference under a friendly name.
+    pub fn add(&mut self, name: &str, reference: &str) -> &mut Self {
+        self.nodes.insert(name.to_string(), reference.to_string());
+
+        self
+    }
+
+    /// # Resolve
+    ///
+    /// Get the node reference for a name, or the reference itself if it is a known reference.
+    pub fn resolve(&self, value: &str) -> Option<&str> {
+        if let Some(reference) = self.nodes.get(value) {
+            return Some(reference);
+        }
+
+        self.nodes
+            .values()
+            .find(|reference| reference.as_str() == value)
+            .map(|reference| reference.as_str())
+    }
+}
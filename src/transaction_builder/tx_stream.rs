@@ -0,0 +1,87 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Transaction stream
+//!
+//! Build transactions from a template as packet data arrives, one at a time, so a large number
+//! of transactions can be passed to a submitter without holding them all in memory.
+
+// Internal imports
+use super::template::TxTemplate;
+use crate::error::TxBuilderResult;
+use crate::{PacketData, Signees};
+
+/// # Transaction stream
+///
+/// Builds a transaction from the template for each packet fed to it, using the packet as the
+/// input and signing with the same signees. Transactions are only built as the returned
+/// iterator is advanced.
+///
+/// ```
+/// # #[cfg(feature = "openssl-keys")] {
+/// # use active_tx::{packet_data, signees, Key, PacketBuilder, TransactionBuilder, TxStream};
+/// # use activeledger::key::EllipticCurve;
+/// let key = Key::Ec(EllipticCurve::new("importer").unwrap());
+/// let template = TransactionBuilder::new("namespace", "import").template().unwrap();
+///
+/// let stream = TxStream::new(template, signees![{"importer" => key}]);
+///
+/// let packets = (0..1000).map(|index| {
+///     PacketBuilder::new(packet_data!({"importer": {"index": index}}))
+///         .build()
+///         .unwrap()
+/// });
+///
+/// for tx in stream.feed(packets).take(3) {
+///     let tx = tx.unwrap();
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TxStream {
+    template: TxTemplate,
+    signees: Signees,
+}
+
+impl TxStream {
+    /// # New
+    ///
+    /// Create a stream building transactions from the template, signed by the signees.
+    pub fn new(template: TxTemplate, signees: Signees) -> TxStream {
+        TxStream { template, signees }
+    }
+
+    /// # Feed
+    ///
+    /// Build a transaction for each packet, lazily, returning the transaction strings.
+    /// A packet that fails to build gives an error and the following packets are still built.
+    pub fn feed<'a, I>(&'a self, packets: I) -> impl Iterator<Item = TxBuilderResult<String>> + 'a
+    where
+        I: IntoIterator<Item = PacketData>,
+        I::IntoIter: 'a,
+    {
+        packets
+            .into_iter()
+            .map(move |packet| self.template.instantiate(packet, self.signees.clone()))
+    }
+}
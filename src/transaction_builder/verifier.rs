@@ -0,0 +1,381 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # Verifiers
+//!
+//! The public key counterpart to [`Signees`](struct.Signees.html), so services that only check
+//! transactions can say which key should have signed each stream without holding, or even
+//! constructing, a [`Key`](enum.Key.html).
+
+// STD
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Internal imports
+use super::body::SignedTransaction;
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::packet_builder::validate_stream_id;
+use crate::{constant_time_eq, PublicKey, SignOptions, StreamId};
+
+/// Holds the public key and stream ID used to check one signature of a transaction.
+#[derive(Clone)]
+pub struct Verifier {
+    pub streamid: String,
+    pub key: PublicKey,
+    /// Options the signature was made with, overriding those given when verifying
+    pub options: Option<SignOptions>,
+    /// Reference for this key when a stream is signed by several keys
+    pub key_ref: Option<String>,
+}
+
+/// # Verifier set
+///
+/// The public keys expected to have signed a transaction, mirroring [`Signees`].
+/// Every verifier in the set must have a valid signature, signatures for other streams are not
+/// checked.
+///
+/// Requires the `openssl-keys` feature, which is enabled by default.
+///
+/// ```
+/// # use active_tx::{packet_data, signees, Key, PacketBuilder, SignOptions, TransactionBuilder, VerifierSet};
+/// # use activeledger::key::EllipticCurve;
+/// # let key = Key::Ec(EllipticCurve::new("streamid").unwrap());
+/// # let input = PacketBuilder::new(packet_data!({"streamid": {}})).build().unwrap();
+/// # let tx = TransactionBuilder::new("namespace", "contract")
+/// #     .input(input)
+/// #     .unwrap()
+/// #     .build(signees![{"streamid" => key.clone()}])
+/// #     .unwrap();
+/// # let pem = key.public_pem().unwrap();
+/// let mut verifiers = VerifierSet::new();
+/// verifiers.add_from_pem_str(&pem, "streamid").unwrap();
+///
+/// verifiers.verify(&tx, &SignOptions::new()).unwrap();
+/// ```
+///
+/// [`Signees`]: struct.Signees.html
+#[derive(Clone, Default)]
+pub struct VerifierSet {
+    keys: Vec<Verifier>,
+}
+
+impl VerifierSet {
+    /// # New
+    ///
+    /// Create an empty verifier set.
+    pub fn new() -> VerifierSet {
+        VerifierSet { keys: vec![] }
+    }
+
+    /// # Add
+    ///
+    /// Add the public key expected to have signed the stream.
+    ///
+    /// If the stream id has already been added its key is replaced, keeping its original position.
+    pub fn add<S: Into<StreamId>>(&mut self, key: PublicKey, streamid: S) -> &mut Self {
+        self.insert(Verifier {
+            streamid: streamid.into().into_string(),
+            key,
+            options: None,
+            key_ref: None,
+        });
+
+        self
+    }
+
+    /// # Add multi
+    ///
+    /// Add one of several public keys expected to have signed the stream, under the given key
+    /// reference.
+    pub fn add_multi<S: Into<StreamId>>(
+        &mut self,
+        key: PublicKey,
+        streamid: S,
+        key_ref: &str,
+    ) -> &mut Self {
+        self.insert(Verifier {
+            streamid: streamid.into().into_string(),
+            key,
+            options: None,
+            key_ref: Some(key_ref.to_string()),
+        });
+
+        self
+    }
+
+    /// # Add with options
+    ///
+    /// Add the public key expected to have signed the stream, checking its signature with the
+    /// given options instead of those given when verifying.
+    pub fn add_with_options<S: Into<StreamId>>(
+        &mut self,
+        key: PublicKey,
+        streamid: S,
+        options: SignOptions,
+    ) -> &mut Self {
+        self.insert(Verifier {
+            streamid: streamid.into().into_string(),
+            key,
+            options: Some(options),
+            key_ref: None,
+        });
+
+        self
+    }
+
+    /// # Add from PEM string
+    ///
+    /// Add a public key PEM, as given to the ledger when the key was onboarded, against the
+    /// stream id.
+    pub fn add_from_pem_str<S: Into<StreamId>>(
+        &mut self,
+        pem: &str,
+        streamid: S,
+    ) -> TxBuilderResult<&mut Self> {
+        let key = PublicKey::from_pem(pem)?;
+
+        Ok(self.add(key, streamid))
+    }
+
+    /// # Add from PEM file
+    ///
+    /// Read a public key PEM file and add it against the stream id.
+    pub fn add_from_pem_file<P: AsRef<Path>, S: Into<StreamId>>(
+        &mut self,
+        path: P,
+        streamid: S,
+    ) -> TxBuilderResult<&mut Self> {
+        let path = path.as_ref();
+
+        let pem = match fs::read_to_string(path) {
+            Ok(pem) => pem,
+            Err(source) => {
+                return Err(TxBuilderError::KeyFileError {
+                    path: path.to_path_buf(),
+                    source,
+                })
+            }
+        };
+
+        self.add_from_pem_str(&pem, streamid)
+    }
+
+    /// # Remove
+    ///
+    /// Remove the verifiers for the given stream id, returning the first if any were present.
+    pub fn remove(&mut self, streamid: &str) -> Option<Verifier> {
+        let index = self.keys.iter().position(|v| v.streamid == streamid)?;
+        let verifier = self.keys.remove(index);
+
+        self.keys.retain(|v| v.streamid != streamid);
+
+        Some(verifier)
+    }
+
+    /// # Contains
+    ///
+    /// Check whether a verifier has been added for the given stream id.
+    pub fn contains(&self, streamid: &str) -> bool {
+        self.keys.iter().any(|v| v.streamid == streamid)
+    }
+
+    /// # Length
+    ///
+    /// Get the number of verifiers.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// # Is empty
+    ///
+    /// Check whether any verifiers have been added.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// # Iterate
+    ///
+    /// Iterate over the verifiers in the order they were added.
+    pub fn iter(&self) -> std::slice::Iter<'_, Verifier> {
+        self.keys.iter()
+    }
+
+    /// # Check stream IDs
+    ///
+    /// Check the stream ID of every verifier, as
+    /// [`Signees::check_stream_ids`](struct.Signees.html#method.check_stream_ids) does.
+    /// [`verify`](#method.verify) applies the lenient check.
+    pub fn check_stream_ids(&self, strict: bool) -> TxBuilderResult<()> {
+        for verifier in &self.keys {
+            validate_stream_id(&verifier.streamid, strict)?;
+        }
+
+        Ok(())
+    }
+
+    /// # Verify
+    ///
+    /// Check the signatures of a signed transaction. Every verifier must have a valid signature,
+    /// using its own options if it has them.
+    ///
+    /// Errors with [`MissingSignature`] or [`InvalidSignature`] naming the stream, or
+    /// `streamid/keyref` for streams signed by several keys. Stream IDs are checked first, see
+    /// [`check_stream_ids`](#method.check_stream_ids).
+    ///
+    /// [`MissingSignature`]: enum.TxBuilderError.html#variant.MissingSignature
+    /// [`InvalidSignature`]: enum.TxBuilderError.html#variant.InvalidSignature
+    pub fn verify(&self, tx: &str, options: &SignOptions) -> TxBuilderResult<()> {
+        self.check_stream_ids(false)?;
+
+        let tx: SignedTransaction = match serde_json::from_str(tx) {
+            Ok(tx) => tx,
+            Err(e) => return Err(TxBuilderError::TransactionParseError(e)),
+        };
+
+        let packet = tx.tx.get();
+
+        for verifier in &self.keys {
+            let name = match &verifier.key_ref {
+                Some(key_ref) => format!("{}/{}", verifier.streamid, key_ref),
+                None => verifier.streamid.clone(),
+            };
+
+            let signature = match tx.signature(&verifier.streamid, verifier.key_ref.as_deref()) {
+                Some(signature) => signature,
+                None => return Err(TxBuilderError::MissingSignature(name)),
+            };
+
+            let options = verifier.options.as_ref().unwrap_or(options);
+
+            if !verifier.key.verify(packet, signature, options) {
+                return Err(TxBuilderError::InvalidSignature(name));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// # Signature matches
+///
+/// Check whether a signed transaction carries exactly the given signature for a stream, or for
+/// one of its keys when it was signed by several. This is for confirming that a transaction
+/// passed back, such as by a webhook, holds the signature that was made for it. It doesn't
+/// check the signature is valid, use [`VerifierSet`] for that.
+///
+/// The signatures are compared using [`constant_time_eq`].
+///
+/// Requires the `openssl-keys` feature, which is enabled by default.
+///
+/// ```
+/// # use active_tx::{packet_data, signature_matches, signees, Key, PacketBuilder, TransactionBuilder};
+/// # use activeledger::key::EllipticCurve;
+/// # let key = Key::Ec(EllipticCurve::new("streamid").unwrap());
+/// # let input = PacketBuilder::new(packet_data!({"streamid": {}})).build().unwrap();
+/// let mut tx_builder = TransactionBuilder::new("namespace", "contract");
+/// let built = tx_builder
+///     .input(input)
+///     .unwrap()
+///     .build_transaction(signees![{"streamid" => key}])
+///     .unwrap();
+///
+/// let signature = built.signatures()[0].signature.to_string();
+///
+/// assert!(signature_matches(built.tx(), "streamid", None, &signature).unwrap());
+/// assert!(!signature_matches(built.tx(), "streamid", None, "forged").unwrap());
+/// ```
+///
+/// [`constant_time_eq`]: fn.constant_time_eq.html
+pub fn signature_matches(
+    tx: &str,
+    streamid: &str,
+    key_ref: Option<&str>,
+    signature: &str,
+) -> TxBuilderResult<bool> {
+    let tx: SignedTransaction = match serde_json::from_str(tx) {
+        Ok(tx) => tx,
+        Err(e) => return Err(TxBuilderError::TransactionParseError(e)),
+    };
+
+    match tx.signature(streamid, key_ref) {
+        Some(signed) => Ok(constant_time_eq(signed, signature)),
+        None => Ok(false),
+    }
+}
+
+// Crate functions
+impl VerifierSet {
+    /// Create the set from keys by stream ID, or by `streamid/keyref` for one of several keys
+    pub(super) fn from_names(keys: &HashMap<String, PublicKey>) -> VerifierSet {
+        let keys = keys
+            .iter()
+            .map(|(name, key)| {
+                let (streamid, key_ref) = match name.find('/') {
+                    Some(index) => (&name[..index], Some(name[index + 1..].to_string())),
+                    None => (name.as_str(), None),
+                };
+
+                Verifier {
+                    streamid: streamid.to_string(),
+                    key: key.clone(),
+                    options: None,
+                    key_ref,
+                }
+            })
+            .collect();
+
+        VerifierSet { keys }
+    }
+}
+
+// Private functions
+impl VerifierSet {
+    fn insert(&mut self, verifier: Verifier) {
+        let replaces = |v: &Verifier| match &verifier.key_ref {
+            Some(_) => {
+                v.streamid == verifier.streamid
+                    && (v.key_ref.is_none() || v.key_ref == verifier.key_ref)
+            }
+            None => v.streamid == verifier.streamid,
+        };
+
+        // Take the place of the first entry replaced
+        let index = self.keys.iter().position(&replaces);
+        self.keys.retain(|v| !replaces(v));
+
+        match index {
+            Some(index) => self.keys.insert(index, verifier),
+            None => self.keys.push(verifier),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a VerifierSet {
+    type Item = &'a Verifier;
+    type IntoIter = std::slice::Iter<'a, Verifier>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.keys.iter()
+    }
+}
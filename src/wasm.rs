@@ -0,0 +1,277 @@
+/*
+ * MIT License (MIT)
+ * Copyright (c) 2019 Activeledger
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+//! # WebAssembly bindings
+//!
+//! JavaScript bindings for the transaction builder, built with the `wasm` feature when
+//! targeting `wasm32-unknown-unknown`.
+//!
+//! Keys are held in JavaScript. Each signee is given a function that is called with the packet
+//! string and must return the encoded signature. The function is called synchronously, so keys
+//! in WebCrypto should sign through a synchronous wrapper or sign ahead of the build.
+//!
+//! ```js
+//! const input = new PacketBuilder('{"streamid": {"data": "data"}}').build();
+//!
+//! const signees = new Signees();
+//! signees.add("streamid", "keyname", "ec", publicPem, (data) => sign(data));
+//!
+//! const tx = new TransactionBuilder("namespace", "contract");
+//! tx.input(input);
+//! const json = tx.build(signees);
+//! ```
+
+// External imports
+use serde_json::Value;
+use wasm_bindgen::prelude::*;
+
+// STD
+use std::sync::Arc;
+
+// Internal imports
+use crate::error::{TxBuilderError, TxBuilderResult};
+use crate::{Key, KeyType, PacketBuilder, PacketData, Signees, Signer, TransactionBuilder};
+
+/// Transaction builder exported to JavaScript as `TransactionBuilder`
+#[wasm_bindgen(js_name = TransactionBuilder)]
+pub struct JsTransactionBuilder {
+    builder: TransactionBuilder,
+}
+
+/// Packet builder exported to JavaScript as `PacketBuilder`
+#[wasm_bindgen(js_name = PacketBuilder)]
+pub struct JsPacketBuilder {
+    builder: PacketBuilder,
+}
+
+/// Built packet data exported to JavaScript as `PacketData`
+#[wasm_bindgen(js_name = PacketData)]
+pub struct JsPacketData {
+    data: PacketData,
+}
+
+/// Signees exported to JavaScript as `Signees`
+#[wasm_bindgen(js_name = Signees)]
+pub struct JsSignees {
+    signees: Signees,
+}
+
+/// Signer that calls a JavaScript function
+struct JsSigner {
+    name: String,
+    key_type: KeyType,
+    public_pem: String,
+    sign: js_sys::Function,
+}
+
+// SAFETY: Without the atomics target feature wasm32 runs on a single thread, so the JavaScript
+// function is never shared between threads.
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Send for JsSigner {}
+#[cfg(not(target_feature = "atomics"))]
+unsafe impl Sync for JsSigner {}
+
+#[wasm_bindgen(js_class = TransactionBuilder)]
+impl JsTransactionBuilder {
+    /// Create a builder for the given namespace and contract
+    #[wasm_bindgen(constructor)]
+    pub fn new(namespace: &str, contract: &str) -> JsTransactionBuilder {
+        JsTransactionBuilder {
+            builder: TransactionBuilder::new(namespace, contract),
+        }
+    }
+
+    /// Set the contract entry point
+    pub fn entry(&mut self, entry: &str) {
+        self.builder.entry(entry);
+    }
+
+    /// Set the territoriality node
+    pub fn territoriality(&mut self, territoriality: &str) {
+        self.builder.territoriality(territoriality);
+    }
+
+    /// Set the selfsign flag
+    #[wasm_bindgen(js_name = setSelfsign)]
+    pub fn set_selfsign(&mut self, selfsign: bool) {
+        self.builder.set_selfsign(selfsign);
+    }
+
+    /// Set the broadcast flag
+    #[wasm_bindgen(js_name = setBroadcast)]
+    pub fn set_broadcast(&mut self, broadcast: bool) {
+        self.builder.set_broadcast(broadcast);
+    }
+
+    /// Set the input ($i) data
+    pub fn input(&mut self, input: &JsPacketData) -> Result<(), JsValue> {
+        into_js(self.builder.input(input.data.clone()).map(|_| ()))
+    }
+
+    /// Set the output ($o) data
+    pub fn output(&mut self, output: &JsPacketData) -> Result<(), JsValue> {
+        into_js(self.builder.output(output.data.clone()).map(|_| ()))
+    }
+
+    /// Set the readonly ($r) data
+    pub fn readonly(&mut self, readonly: &JsPacketData) -> Result<(), JsValue> {
+        into_js(self.builder.readonly(readonly.data.clone()).map(|_| ()))
+    }
+
+    /// Sign and build the transaction, returning it as a JSON string
+    pub fn build(&mut self, signees: &JsSignees) -> Result<String, JsValue> {
+        into_js(self.builder.build(signees.signees.clone()))
+    }
+}
+
+#[wasm_bindgen(js_class = PacketBuilder)]
+impl JsPacketBuilder {
+    /// Create a builder from a JSON string
+    #[wasm_bindgen(constructor)]
+    pub fn new(json: &str) -> Result<JsPacketBuilder, JsValue> {
+        let data: Value = match serde_json::from_str(json) {
+            Ok(data) => data,
+            Err(_) => return Err(js_error(TxBuilderError::JsonError(2002))),
+        };
+
+        Ok(JsPacketBuilder {
+            builder: PacketBuilder::new_json(data),
+        })
+    }
+
+    /// Add binary data under the key
+    #[wasm_bindgen(js_name = addBinary)]
+    pub fn add_binary(&mut self, key: &str, bytes: &[u8]) -> Result<(), JsValue> {
+        into_js(self.builder.add_binary(key, bytes).map(|_| ()))
+    }
+
+    /// Build the packet data
+    pub fn build(&mut self) -> Result<JsPacketData, JsValue> {
+        into_js(self.builder.build().map(|data| JsPacketData { data }))
+    }
+}
+
+#[wasm_bindgen(js_class = PacketData)]
+impl JsPacketData {
+    /// Get the built data as a JSON string
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        into_js(self.data.get_string().map(|data| data.to_string()))
+    }
+}
+
+#[wasm_bindgen(js_class = Signees)]
+impl JsSignees {
+    /// Create an empty set of signees
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> JsSignees {
+        JsSignees {
+            signees: Signees::new(),
+        }
+    }
+
+    /// Add a key held in JavaScript against the stream id.
+    ///
+    /// The key type is "rsa" or "ec". `sign` is called with the packet string and must return
+    /// the encoded signature.
+    pub fn add(
+        &mut self,
+        streamid: &str,
+        name: &str,
+        key_type: &str,
+        public_pem: &str,
+        sign: js_sys::Function,
+    ) -> Result<(), JsValue> {
+        let key_type = match key_type {
+            "rsa" => KeyType::RSA,
+            "ec" => KeyType::EC,
+            _ => {
+                return Err(js_error(TxBuilderError::SignerError(format!(
+                    "unknown key type '{}'",
+                    key_type
+                ))))
+            }
+        };
+
+        let signer = JsSigner {
+            name: name.to_string(),
+            key_type,
+            public_pem: public_pem.to_string(),
+            sign,
+        };
+
+        self.signees.add(Key::External(Arc::new(signer)), streamid);
+
+        Ok(())
+    }
+}
+
+impl Default for JsSignees {
+    fn default() -> Self {
+        JsSignees::new()
+    }
+}
+
+impl Signer for JsSigner {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn key_type(&self) -> KeyType {
+        self.key_type
+    }
+
+    fn sign(&self, data: &str) -> TxBuilderResult<String> {
+        let signature = match self.sign.call1(&JsValue::NULL, &JsValue::from_str(data)) {
+            Ok(signature) => signature,
+            Err(e) => {
+                let message = e
+                    .as_string()
+                    .unwrap_or_else(|| "signing function threw".to_string());
+
+                return Err(TxBuilderError::SignerError(message));
+            }
+        };
+
+        match signature.as_string() {
+            Some(signature) => Ok(signature),
+            None => Err(TxBuilderError::SignerError(
+                "signing function did not return a string".to_string(),
+            )),
+        }
+    }
+
+    fn public_pem(&self) -> TxBuilderResult<String> {
+        Ok(self.public_pem.clone())
+    }
+}
+
+/// Convert a builder result into a JavaScript result
+fn into_js<T>(result: TxBuilderResult<T>) -> Result<T, JsValue> {
+    result.map_err(js_error)
+}
+
+/// Convert a builder error into a JavaScript Error
+fn js_error(error: TxBuilderError) -> JsValue {
+    js_sys::Error::new(&error.to_string()).into()
+}